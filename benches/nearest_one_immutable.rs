@@ -1,21 +1,25 @@
 use az::Cast;
 use codspeed_criterion_compat::{
-    criterion_group, criterion_main, measurement::WallTime, AxisScale, BatchSize, BenchmarkGroup,
-    BenchmarkId, Criterion, PlotConfiguration, Throughput,
+    black_box, criterion_group, criterion_main, measurement::WallTime, AxisScale, BatchSize,
+    BenchmarkGroup, BenchmarkId, Criterion, PlotConfiguration, Throughput,
 };
 use kiddo::batch_benches;
 use kiddo::float::distance::SquaredEuclidean;
 use kiddo::float_leaf_slice::leaf_slice::{LeafSliceFloat, LeafSliceFloatChunk};
+use kiddo::immutable::float::builder::ImmutableKdTreeBuilder;
 use kiddo::immutable::float::kdtree::{Axis, ImmutableKdTree};
 use kiddo::test_utils::{
     build_populated_tree_and_query_points_immutable_float, process_queries_immutable_float,
 };
 use kiddo::traits::Content;
 use rand::distributions::Standard;
+use rand::{Rng, SeedableRng};
 use rand_distr::Distribution;
 
 const BUCKET_SIZE: usize = 32;
 const QUERY_POINTS_PER_LOOP: usize = 1000;
+const CLUSTER_COUNT: usize = 64;
+const CLUSTER_RADIUS: f64 = 2.0;
 
 macro_rules! bench_float {
     ($group:ident, $a:ty, $t:ty, $k:tt, $idx:tt, $size:tt, $subtype: expr) => {
@@ -50,6 +54,61 @@ pub fn nearest_one_immutable_float(c: &mut Criterion) {
     group.finish();
 }
 
+pub fn nearest_one_immutable_float_clustered(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Query Nearest 1 (clustered)");
+    group.throughput(Throughput::Elements(QUERY_POINTS_PER_LOOP as u64));
+
+    let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
+    group.plot_config(plot_config);
+
+    for size in [10_000, 100_000, 1_000_000] {
+        let (points, query_points) = clustered_points_and_queries(size, QUERY_POINTS_PER_LOOP);
+
+        let split_planes: ImmutableKdTree<f64, u32, 3, BUCKET_SIZE> =
+            ImmutableKdTree::new_from_slice(&points);
+        let leaf_bounding_boxes: ImmutableKdTree<f64, u32, 3, BUCKET_SIZE> =
+            ImmutableKdTreeBuilder::new()
+                .leaf_bounding_boxes(true)
+                .build_from_slice(&points);
+
+        for (subtype, tree) in [
+            ("split planes", &split_planes),
+            ("leaf bounding boxes", &leaf_bounding_boxes),
+        ] {
+            group.bench_with_input(BenchmarkId::new(subtype, size), &size, |b, _| {
+                b.iter(|| {
+                    query_points.iter().for_each(|point| {
+                        black_box(tree.nearest_one::<SquaredEuclidean>(point));
+                    })
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+/// Generates `size` points in small, tight clusters within a 1000 unit cube, and
+/// `query_qty` query points scattered uniformly throughout the same cube
+fn clustered_points_and_queries(size: usize, query_qty: usize) -> (Vec<[f64; 3]>, Vec<[f64; 3]>) {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(1);
+
+    let centres: Vec<[f64; 3]> = (0..CLUSTER_COUNT)
+        .map(|_| [0; 3].map(|_| rng.gen_range(0.0..1000.0)))
+        .collect();
+    let points = (0..size)
+        .map(|i| {
+            let centre = centres[i % CLUSTER_COUNT];
+            [0, 1, 2].map(|dim| centre[dim] + rng.gen_range(-CLUSTER_RADIUS..CLUSTER_RADIUS))
+        })
+        .collect();
+    let query_points = (0..query_qty)
+        .map(|_| [0; 3].map(|_| rng.gen_range(0.0..1000.0)))
+        .collect();
+
+    (points, query_points)
+}
+
 fn perform_query_immutable_float<A, T: Content + 'static, const K: usize, const B: usize>(
     kdtree: &ImmutableKdTree<A, T, K, BUCKET_SIZE>,
     point: &[A; K],
@@ -91,5 +150,9 @@ fn bench_query_nearest_one_immutable_float<A, T: Content + 'static, const K: usi
     );
 }
 
-criterion_group!(benches, nearest_one_immutable_float);
+criterion_group!(
+    benches,
+    nearest_one_immutable_float,
+    nearest_one_immutable_float_clustered
+);
 criterion_main!(benches);
@@ -26,12 +26,20 @@ const RADIUS_LARGE: f64 = 0.25;
 
 type Fxd = U16; // FixedU16<U16>;
 
+type FloatQuery<A, T, const K: usize, IDX> = fn(&KdTree<A, T, K, BUCKET_SIZE, IDX>, &[A; K], f64);
+
 macro_rules! bench_float {
     ($group:ident, $a:ty, $t:ty, $k:tt, $idx: ty, $size:tt, $radius:tt,  $subtype: expr) => {
         bench_query_float::<$a, $t, $k, $idx>(&mut $group, $size, $radius, $subtype);
     };
 }
 
+macro_rules! bench_any_within_float {
+    ($group:ident, $a:ty, $t:ty, $k:tt, $idx: ty, $size:tt, $radius:tt,  $subtype: expr) => {
+        bench_any_within_query_float::<$a, $t, $k, $idx>(&mut $group, $size, $radius, $subtype);
+    };
+}
+
 macro_rules! bench_fixed {
     ($group:ident, $a:ty, $t:ty, $k:tt, $idx:ty, $size:tt, $radius:tt, $subtype: expr) => {
         bench_query_fixed::<$a, $t, $k, $idx>(&mut $group, $size, $radius, $subtype);
@@ -87,6 +95,39 @@ fn within(c: &mut Criterion, radius: f64, radius_name: &str) {
     group.finish();
 }
 
+pub fn any_within_small(c: &mut Criterion) {
+    any_within(c, RADIUS_SMALL, "small");
+}
+
+pub fn any_within_medium(c: &mut Criterion) {
+    any_within(c, RADIUS_MEDIUM, "medium");
+}
+
+fn any_within(c: &mut Criterion, radius: f64, radius_name: &str) {
+    let mut group = c.benchmark_group(format!(
+        "Query: any_within vs within_unsorted, {} radius",
+        radius_name
+    ));
+    group.throughput(Throughput::Elements(QUERY_POINTS_PER_LOOP as u64));
+
+    let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
+    group.plot_config(plot_config);
+
+    batch_benches_parameterized!(
+        group,
+        bench_any_within_float,
+        radius,
+        [(f64, 3), (f32, 3)],
+        [
+            (1_000, u16, u16),
+            (100_000, u32, u16),
+            (1_000_000, u32, u32)
+        ]
+    );
+
+    group.finish();
+}
+
 fn perform_query_float<
     A: Axis,
     T: Content + 'static,
@@ -104,6 +145,44 @@ fn perform_query_float<
     let _res = kdtree.within::<SquaredEuclidean>(point, radius.az::<A>());
 }
 
+fn perform_any_within_query_float<
+    A: Axis,
+    T: Content + 'static,
+    const K: usize,
+    const B: usize,
+    IDX: Index<T = IDX> + 'static,
+>(
+    kdtree: &KdTree<A, T, K, BUCKET_SIZE, IDX>,
+    point: &[A; K],
+    radius: f64,
+) where
+    usize: Cast<IDX>,
+    f64: Cast<A>,
+{
+    let _res = kdtree
+        .any_within::<SquaredEuclidean>(point, radius.az::<A>())
+        .is_some();
+}
+
+fn perform_within_unsorted_is_empty_query_float<
+    A: Axis,
+    T: Content + 'static,
+    const K: usize,
+    const B: usize,
+    IDX: Index<T = IDX> + 'static,
+>(
+    kdtree: &KdTree<A, T, K, BUCKET_SIZE, IDX>,
+    point: &[A; K],
+    radius: f64,
+) where
+    usize: Cast<IDX>,
+    f64: Cast<A>,
+{
+    let _res = !kdtree
+        .within_unsorted::<SquaredEuclidean>(point, radius.az::<A>())
+        .is_empty();
+}
+
 fn perform_query_fixed<
     A: Unsigned + LeEqU16,
     T: Content + 'static,
@@ -158,6 +237,53 @@ fn bench_query_float<
     );
 }
 
+fn bench_any_within_query_float<
+    A: Axis + 'static,
+    T: Content + 'static,
+    const K: usize,
+    IDX: Index<T = IDX> + 'static,
+>(
+    group: &mut BenchmarkGroup<WallTime>,
+    initial_size: usize,
+    radius: f64,
+    subtype: &str,
+) where
+    usize: Cast<IDX>,
+    f64: Cast<A>,
+    Standard: Distribution<T>,
+    Standard: Distribution<[A; K]>,
+{
+    let queries: [(&str, FloatQuery<A, T, K, IDX>); 2] = [
+        (
+            "any_within",
+            perform_any_within_query_float::<A, T, K, BUCKET_SIZE, IDX>,
+        ),
+        (
+            "within_unsorted().is_empty()",
+            perform_within_unsorted_is_empty_query_float::<A, T, K, BUCKET_SIZE, IDX>,
+        ),
+    ];
+
+    for (query_name, query) in queries {
+        group.bench_with_input(
+            BenchmarkId::new(format!("{} {}", query_name, subtype), initial_size),
+            &initial_size,
+            |b, &size| {
+                b.iter_batched(
+                    || {
+                        build_populated_tree_and_query_points_float::<A, T, K, BUCKET_SIZE, IDX>(
+                            size,
+                            QUERY_POINTS_PER_LOOP,
+                        )
+                    },
+                    process_queries_float_parameterized(query, radius),
+                    BatchSize::SmallInput,
+                );
+            },
+        );
+    }
+}
+
 fn bench_query_fixed<
     A: Unsigned + LeEqU16,
     T: Content + 'static,
@@ -194,5 +320,12 @@ fn bench_query_fixed<
     );
 }
 
-criterion_group!(benches, within_small, within_medium, within_large);
+criterion_group!(
+    benches,
+    within_small,
+    within_medium,
+    within_large,
+    any_within_small,
+    any_within_medium
+);
 criterion_main!(benches);
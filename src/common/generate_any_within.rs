@@ -0,0 +1,34 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_any_within {
+    ($comments:tt) => {
+        doc_comment! {
+            concat!$comments,
+            #[inline]
+            pub fn any_within<D>(&self, query: &[A; K], dist: A) -> Option<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+            {
+                let mut found = None;
+
+                self.visit_leaves::<D, _>(query, &mut |points, items| {
+                    found = points
+                        .iter()
+                        .zip(items.iter())
+                        .find_map(|(entry, &item)| {
+                            let distance = D::dist(query, entry);
+
+                            (distance < dist).then_some(NearestNeighbour { distance, item })
+                        });
+
+                    match found {
+                        Some(_) => std::ops::ControlFlow::Break(()),
+                        None => std::ops::ControlFlow::Continue(dist),
+                    }
+                });
+
+                found
+            }
+        }
+    };
+}
@@ -49,6 +49,11 @@ macro_rules! generate_best_n_within {
     {
         if is_stem_index(curr_node_idx) {
             let node = self.stems.get_unchecked(curr_node_idx.az::<usize>());
+            let split_dim = if self.split_dims.is_empty() {
+                split_dim
+            } else {
+                *self.split_dims.get_unchecked(curr_node_idx.az::<usize>()) as usize
+            };
 
             let mut rd = rd;
             let old_off = off[split_dim];
@@ -0,0 +1,50 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_count_within {
+    ($count_within_comments:tt, $count_within_at_most_comments:tt) => {
+        doc_comment! {
+            concat!$count_within_comments,
+            #[inline]
+            pub fn count_within<D>(&self, query: &[A; K], dist: A) -> usize
+            where
+                D: DistanceMetric<A, K>,
+            {
+                self.count_within_at_most::<D>(query, dist, usize::MAX)
+            }
+        }
+
+        doc_comment! {
+            concat!$count_within_at_most_comments,
+            #[inline]
+            pub fn count_within_at_most<D>(&self, query: &[A; K], dist: A, limit: usize) -> usize
+            where
+                D: DistanceMetric<A, K>,
+            {
+                let mut count = 0;
+
+                if limit == 0 {
+                    return 0;
+                }
+
+                self.visit_leaves::<D, _>(query, &mut |points, _| {
+                    for entry in points {
+                        if count >= limit {
+                            return std::ops::ControlFlow::Break(());
+                        }
+                        if D::dist(query, entry) < dist {
+                            count += 1;
+                        }
+                    }
+
+                    if count >= limit {
+                        std::ops::ControlFlow::Break(())
+                    } else {
+                        std::ops::ControlFlow::Continue(dist)
+                    }
+                });
+
+                count
+            }
+        }
+    };
+}
@@ -0,0 +1,91 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_filtered {
+    ($nearest_one_comments:tt, $nearest_n_comments:tt, $within_comments:tt) => {
+        doc_comment! {
+            concat!$nearest_one_comments,
+            #[inline]
+            pub fn nearest_one_filtered<D, F>(&self, query: &[A; K], filter: F) -> Option<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+                F: Fn(T) -> bool,
+            {
+                self.filtered_stub::<D, F>(query, A::max_value(), 1, &filter).pop()
+            }
+        }
+
+        doc_comment! {
+            concat!$nearest_n_comments,
+            #[inline]
+            pub fn nearest_n_filtered<D, F>(&self, query: &[A; K], qty: usize, filter: F) -> Vec<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+                F: Fn(T) -> bool,
+            {
+                self.filtered_stub::<D, F>(query, A::max_value(), qty, &filter).into_sorted_vec()
+            }
+        }
+
+        doc_comment! {
+            concat!$within_comments,
+            #[inline]
+            pub fn within_filtered<D, F>(&self, query: &[A; K], dist: A, filter: F) -> Vec<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+                F: Fn(T) -> bool,
+            {
+                self.filtered_stub::<D, F>(query, dist, usize::MAX, &filter).into_sorted_vec()
+            }
+        }
+
+        fn filtered_stub<D, F>(
+            &self,
+            query: &[A; K],
+            radius: A,
+            max_qty: usize,
+            filter: &F,
+        ) -> BinaryHeap<NearestNeighbour<A, T>>
+        where
+            D: DistanceMetric<A, K>,
+            F: Fn(T) -> bool,
+        {
+            let mut results = BinaryHeap::new();
+
+            if max_qty == 0 {
+                return results;
+            }
+
+            self.visit_leaves::<D, _>(query, &mut |points, items| {
+                points
+                    .iter()
+                    .zip(items.iter())
+                    .for_each(|(entry, &item)| {
+                        let distance = D::dist(query, entry);
+
+                        // the filter is only consulted for items that would make it into
+                        // the results, so that rejected items never tighten the search
+                        if distance < Self::filtered_max_dist(radius, max_qty, &results) && filter(item) {
+                            if results.len() < max_qty {
+                                results.push(NearestNeighbour { distance, item });
+                            } else {
+                                *results.peek_mut().unwrap() = NearestNeighbour { distance, item };
+                            }
+                        }
+                    });
+
+                std::ops::ControlFlow::Continue(Self::filtered_max_dist(radius, max_qty, &results))
+            });
+
+            results
+        }
+
+        /// Returns the distance that an item must be closer than to be added to `results`
+        #[inline]
+        fn filtered_max_dist(radius: A, max_qty: usize, results: &BinaryHeap<NearestNeighbour<A, T>>) -> A {
+            match results.peek() {
+                Some(furthest) if results.len() >= max_qty && furthest.distance < radius => furthest.distance,
+                _ => radius,
+            }
+        }
+    };
+}
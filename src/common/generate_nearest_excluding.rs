@@ -0,0 +1,80 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_nearest_excluding {
+    ($nearest_one_comments:tt, $nearest_n_comments:tt) => {
+        doc_comment! {
+            concat!$nearest_one_comments,
+            #[inline]
+            pub fn nearest_one_excluding<D>(&self, query: &[A; K], excluded: T) -> Option<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+            {
+                let mut nearest: Option<NearestNeighbour<A, T>> = None;
+                let mut best_dist = A::max_value();
+
+                self.visit_leaves::<D, _>(query, &mut |points, items| {
+                    points
+                        .iter()
+                        .zip(items.iter())
+                        .for_each(|(entry, &item)| {
+                            let distance = D::dist(query, entry);
+
+                            // the item is only compared for points that are closer than the best so far
+                            if distance < best_dist && item != excluded {
+                                best_dist = distance;
+                                nearest = Some(NearestNeighbour { distance, item });
+                            }
+                        });
+
+                    std::ops::ControlFlow::Continue(best_dist)
+                });
+
+                nearest
+            }
+        }
+
+        doc_comment! {
+            concat!$nearest_n_comments,
+            #[inline]
+            pub fn nearest_n_excluding<D>(&self, query: &[A; K], qty: usize, excluded: T) -> Vec<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+            {
+                let mut results: BinaryHeap<NearestNeighbour<A, T>> = BinaryHeap::with_capacity(qty);
+
+                if qty == 0 {
+                    return Vec::new();
+                }
+
+                self.visit_leaves::<D, _>(query, &mut |points, items| {
+                    points
+                        .iter()
+                        .zip(items.iter())
+                        .for_each(|(entry, &item)| {
+                            let distance = D::dist(query, entry);
+
+                            // the item is only compared for points that would make it into the results
+                            if results.len() < qty {
+                                if item != excluded {
+                                    results.push(NearestNeighbour { distance, item });
+                                }
+                            } else {
+                                let mut furthest = results.peek_mut().unwrap();
+                                if distance < furthest.distance && item != excluded {
+                                    *furthest = NearestNeighbour { distance, item };
+                                }
+                            }
+                        });
+
+                    std::ops::ControlFlow::Continue(if results.len() < qty {
+                        A::max_value()
+                    } else {
+                        results.peek().unwrap().distance
+                    })
+                });
+
+                results.into_sorted_vec()
+            }
+        }
+    };
+}
@@ -40,6 +40,11 @@ macro_rules! generate_nearest_n {
     {
         if is_stem_index(curr_node_idx) {
             let node = &self.stems.get_unchecked(curr_node_idx.az::<usize>());
+            let split_dim = if self.split_dims.is_empty() {
+                split_dim
+            } else {
+                *self.split_dims.get_unchecked(curr_node_idx.az::<usize>()) as usize
+            };
 
             let mut rd = rd;
             let old_off = off[split_dim];
@@ -0,0 +1,104 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_visit_leaves {
+    () => {
+        /// Calls `visitor` with the points and items of each leaf whose side of every
+        /// stem above it is within the current bound of `query`, visiting the side of
+        /// each stem that `query` falls on first.
+        ///
+        /// The visitor returns `ControlFlow::Continue` with the distance that the remaining
+        /// subtrees must lie within to be visited, or `ControlFlow::Break` to end the traversal.
+        #[inline]
+        pub(crate) fn visit_leaves<D, V>(&self, query: &[A; K], visitor: &mut V)
+        where
+            D: $crate::traits::DistanceMetric<A, K>,
+            V: FnMut(&[[A; K]], &[T]) -> std::ops::ControlFlow<(), A>,
+        {
+            let mut off = [A::zero(); K];
+
+            let _ = unsafe {
+                self.visit_leaves_recurse::<D, V>(
+                    query,
+                    self.root_index,
+                    0,
+                    &mut off,
+                    A::zero(),
+                    visitor,
+                )
+            };
+        }
+
+        unsafe fn visit_leaves_recurse<D, V>(
+            &self,
+            query: &[A; K],
+            curr_node_idx: IDX,
+            split_dim: usize,
+            off: &mut [A; K],
+            rd: A,
+            visitor: &mut V,
+        ) -> std::ops::ControlFlow<(), A>
+        where
+            D: $crate::traits::DistanceMetric<A, K>,
+            V: FnMut(&[[A; K]], &[T]) -> std::ops::ControlFlow<(), A>,
+        {
+            if !$crate::traits::is_stem_index(curr_node_idx) {
+                let leaf_node = self
+                    .leaves
+                    .get_unchecked((curr_node_idx - IDX::leaf_offset()).az::<usize>());
+                let size = leaf_node.size.az::<usize>();
+
+                return visitor(
+                    &leaf_node.content_points[..size],
+                    &leaf_node.content_items[..size],
+                );
+            }
+
+            let node = self.stems.get_unchecked(curr_node_idx.az::<usize>());
+            let split_dim = if self.split_dims.is_empty() {
+                split_dim
+            } else {
+                *self.split_dims.get_unchecked(curr_node_idx.az::<usize>()) as usize
+            };
+
+            let mut rd = rd;
+            let old_off = off[split_dim];
+            let new_off = query[split_dim].saturating_dist(node.split_val);
+
+            let [closer_node_idx, further_node_idx] =
+                if *query.get_unchecked(split_dim) < node.split_val {
+                    [node.left, node.right]
+                } else {
+                    [node.right, node.left]
+                };
+            let next_split_dim = (split_dim + 1) % K;
+
+            let bound = self.visit_leaves_recurse::<D, V>(
+                query,
+                closer_node_idx,
+                next_split_dim,
+                off,
+                rd,
+                visitor,
+            )?;
+
+            rd = Axis::rd_update(rd, D::dist1(new_off, old_off));
+
+            if rd > bound {
+                return std::ops::ControlFlow::Continue(bound);
+            }
+
+            off[split_dim] = new_off;
+            let result = self.visit_leaves_recurse::<D, V>(
+                query,
+                further_node_idx,
+                next_split_dim,
+                off,
+                rd,
+                visitor,
+            );
+            off[split_dim] = old_off;
+
+            result
+        }
+    };
+}
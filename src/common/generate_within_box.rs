@@ -0,0 +1,134 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_within_box {
+    ($leafnode:ident, $within_box_comments:tt, $within_box_iter_comments:tt, $count_in_box_comments:tt) => {
+        $crate::generate_within_box!(
+            $within_box_comments,
+            $within_box_iter_comments,
+            $count_in_box_comments
+        );
+
+        /// Returns each leaf that could hold points in the box from `min` to `max`,
+        /// along with whether every point that it could hold is inside the box.
+        ///
+        /// Points to the left of a stem are no greater than its split value on its split
+        /// dimension, and points to its right are no less than it. `lo_inside[dim]` and
+        /// `hi_inside[dim]` record whether the bounds on `dim` implied by the stems passed
+        /// through so far lie within the box.
+        fn within_box_leaves<'a>(
+            &'a self,
+            min: &'a [A; K],
+            max: &'a [A; K],
+        ) -> impl Iterator<Item = (&'a $leafnode<A, T, K, B, IDX>, bool)> + 'a {
+            let mut stack = vec![(self.root_index, 0, [false; K], [false; K])];
+
+            std::iter::from_fn(move || {
+                while let Some((node_idx, split_dim, lo_inside, hi_inside)) = stack.pop() {
+                    if !is_stem_index(node_idx) {
+                        let leaf_node = &self.leaves[(node_idx - IDX::leaf_offset()).az::<usize>()];
+                        let is_contained = lo_inside
+                            .iter()
+                            .chain(hi_inside.iter())
+                            .all(|&inside| inside);
+
+                        return Some((leaf_node, is_contained));
+                    }
+
+                    let node = &self.stems[node_idx.az::<usize>()];
+                    let split_dim = if self.split_dims.is_empty() {
+                        split_dim
+                    } else {
+                        self.split_dims[node_idx.az::<usize>()] as usize
+                    };
+                    let next_split_dim = (split_dim + 1).rem(K);
+
+                    if max[split_dim] >= node.split_val {
+                        let mut lo_inside = lo_inside;
+                        lo_inside[split_dim] = node.split_val >= min[split_dim];
+                        stack.push((node.right, next_split_dim, lo_inside, hi_inside));
+                    }
+                    if min[split_dim] <= node.split_val {
+                        let mut hi_inside = hi_inside;
+                        hi_inside[split_dim] = node.split_val <= max[split_dim];
+                        stack.push((node.left, next_split_dim, lo_inside, hi_inside));
+                    }
+                }
+
+                None
+            })
+        }
+    };
+    ($within_box_comments:tt, $within_box_iter_comments:tt, $count_in_box_comments:tt) => {
+        doc_comment! {
+            concat!$within_box_comments,
+            #[inline]
+            pub fn within_box(&self, min: &[A; K], max: &[A; K]) -> Vec<T> {
+                let mut items = Vec::new();
+
+                for (leaf_node, is_contained) in self.within_box_leaves(min, max) {
+                    let size = leaf_node.size.az::<usize>();
+                    if is_contained {
+                        items.extend_from_slice(&leaf_node.content_items[..size]);
+                    } else {
+                        items.extend(
+                            leaf_node.content_points[..size]
+                                .iter()
+                                .zip(leaf_node.content_items[..size].iter())
+                                .filter(|(point, _)| Self::point_is_in_box(point, min, max))
+                                .map(|(_, &item)| item),
+                        );
+                    }
+                }
+
+                items
+            }
+        }
+
+        doc_comment! {
+            concat!$within_box_iter_comments,
+            #[inline]
+            pub fn within_box_iter<'a>(
+                &'a self,
+                min: &'a [A; K],
+                max: &'a [A; K],
+            ) -> impl Iterator<Item = T> + 'a {
+                self.within_box_leaves(min, max)
+                    .flat_map(move |(leaf_node, is_contained)| {
+                        let size = leaf_node.size.az::<usize>();
+                        leaf_node.content_points[..size]
+                            .iter()
+                            .zip(leaf_node.content_items[..size].iter())
+                            .filter(move |(point, _)| {
+                                is_contained || Self::point_is_in_box(point, min, max)
+                            })
+                            .map(|(_, &item)| item)
+                    })
+            }
+        }
+
+        doc_comment! {
+            concat!$count_in_box_comments,
+            #[inline]
+            pub fn count_in_box(&self, min: &[A; K], max: &[A; K]) -> usize {
+                self.within_box_leaves(min, max)
+                    .map(|(leaf_node, is_contained)| {
+                        let size = leaf_node.size.az::<usize>();
+                        if is_contained {
+                            size
+                        } else {
+                            leaf_node.content_points[..size]
+                                .iter()
+                                .filter(|point| Self::point_is_in_box(point, min, max))
+                                .count()
+                        }
+                    })
+                    .sum()
+            }
+        }
+
+        #[inline]
+        fn point_is_in_box(point: &[A; K], min: &[A; K], max: &[A; K]) -> bool {
+            (0..K).all(|dim| point[dim] >= min[dim] && point[dim] <= max[dim])
+        }
+    };
+}
@@ -1,7 +1,13 @@
+pub(crate) mod generate_any_within;
 pub(crate) mod generate_best_n_within;
+pub(crate) mod generate_count_within;
+pub(crate) mod generate_filtered;
+pub(crate) mod generate_nearest_excluding;
 pub(crate) mod generate_nearest_n;
 pub(crate) mod generate_nearest_n_within_unsorted;
 pub(crate) mod generate_nearest_one;
+pub(crate) mod generate_visit_leaves;
 pub(crate) mod generate_within;
+pub(crate) mod generate_within_box;
 pub(crate) mod generate_within_unsorted;
 pub(crate) mod generate_within_unsorted_iter;
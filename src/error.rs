@@ -0,0 +1,39 @@
+//! Errors returned by the fallible construction methods of the mutable trees,
+//! such as `try_add` and `try_with_capacity`.
+use std::fmt;
+
+/// The reasons that a tree can fail to be created or to store an item
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KiddoError {
+    /// the requested capacity is more than the tree's index type can address
+    /// with its bucket size
+    CapacityExceeded {
+        /// number of items that capacity was requested for
+        capacity: usize,
+        /// maximum number of items supported by the index type and bucket size
+        max_capacity: usize,
+    },
+    /// a full leaf could not be split, since the new stem or leaf would have an index
+    /// that doesn't fit in the tree's index type
+    IndexOverflow,
+    /// a full leaf could not be split, since all of the items in it share the same
+    /// value on the axis that it would be split along. Only returned by the
+    /// [hybrid](crate::hybrid::kdtree::KdTree) tree: the float and fixed point trees
+    /// spread such items over more than one leaf instead
+    UnsplittableBucket,
+}
+
+impl fmt::Display for KiddoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KiddoError::CapacityExceeded {
+                capacity,
+                max_capacity,
+            } => write!(f, "Requested capacity of {capacity} items exceeds the maximum of {max_capacity} for this index type and bucket size."),
+            KiddoError::IndexOverflow => write!(f, "Too many leaves or stems for the index type. A larger index type must be used."),
+            KiddoError::UnsplittableBucket => write!(f, "Too many items with the same position on one axis. Bucket size must be increased to at least 1 more than the number of items with the same position on one axis."),
+        }
+    }
+}
+
+impl std::error::Error for KiddoError {}
@@ -8,11 +8,17 @@ use divrem::DivCeil;
 use fixed::traits::Fixed;
 use std::cmp::PartialEq;
 use std::fmt::Debug;
+use std::mem::size_of;
 
+use crate::error::KiddoError;
+use crate::float::kdtree::RebalancePolicy;
+use crate::generate_visit_leaves;
 use crate::iter::TreeIter;
+use crate::split_axis::SplitAxis;
+use crate::stats::{MemoryUsage, TreeStats, TreeStatsBuilder};
 use crate::{
     iter::IterableTreeData,
-    traits::{Content, Index},
+    traits::{is_stem_index, Content, Index, Unbounded},
 };
 
 #[cfg(feature = "serde")]
@@ -22,11 +28,11 @@ use serde::{Deserialize, Serialize};
 /// by the type that is used as the first generic parameter, `A`,
 /// on [`FixedKdTree`](crate::fixed::kdtree::KdTree). A type from the [`Fixed`](https://docs.rs/fixed/1.21.0/fixed) crate will implement
 /// all of the traits required by Axis. For example [`FixedU16<U14>`](https://docs.rs/fixed/1.21.0/fixed/struct.FixedU16.html).
-pub trait Axis: Fixed + Default + Debug + Copy + Sync + Send {
+pub trait Axis: Fixed + Default + Debug + Copy + Sync + Send + Unbounded {
     /// used in query methods to update the rd value. Basically a saturating add for Fixed and an add for Float
     fn rd_update(rd: Self, delta: Self) -> Self;
 }
-impl<T: Fixed + Default + Debug + Copy + Sync + Send> Axis for T {
+impl<T: Fixed + Default + Debug + Copy + Sync + Send + Unbounded> Axis for T {
     #[inline]
     fn rd_update(rd: Self, delta: Self) -> Self {
         rd.saturating_add(delta)
@@ -62,6 +68,12 @@ pub struct KdTreeRK<
     pub(crate) stems: Vec<StemNodeRK<A, K, IDX>>,
     pub(crate) root_index: IDX,
     pub(crate) size: T,
+    pub(crate) split_dims: Vec<u8>,
+    pub(crate) split_axis: SplitAxis,
+    pub(crate) empty_leaves: usize,
+    pub(crate) rebalance_policy: Option<RebalancePolicy>,
+    pub(crate) free_leaves: Vec<IDX>,
+    pub(crate) free_stems: Vec<IDX>,
 }
 
 /// Fixed point k-d tree
@@ -77,6 +89,28 @@ pub struct KdTree<A: Copy + Default, T: Copy + Default, const K: usize, const B:
     pub(crate) stems: Vec<StemNode<A, K, IDX>>,
     pub(crate) root_index: IDX,
     pub(crate) size: T,
+
+    /// split dimension of each stem, indexed in the same way as `stems`. Empty unless the
+    /// tree was created with [`SplitAxis::WidestSpread`], in which case the split dimensions
+    /// don't simply cycle through the axes level by level
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) split_dims: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) split_axis: SplitAxis,
+
+    /// number of leaves that hold no items. Only kept up to date while a
+    /// [`RebalancePolicy`] is set
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) empty_leaves: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) rebalance_policy: Option<RebalancePolicy>,
+
+    /// indices of leaves and stems that are no longer part of the tree, since their
+    /// contents were merged into a sibling, and which can be re-used by later splits
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) free_leaves: Vec<IDX>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) free_stems: Vec<IDX>,
 }
 
 #[doc(hidden)]
@@ -223,12 +257,79 @@ where
     /// ```
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
+        KdTree::with_capacity_and_split_axis(capacity, SplitAxis::RoundRobin)
+    }
+
+    /// Creates a new fixed-point/integer KdTree and reserves capacity for a specific number of items,
+    /// returning [`KiddoError::CapacityExceeded`] rather than panicking if `IDX` can't
+    /// address that many items with a bucket size of `B`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U14;
+    /// use kiddo::fixed::kdtree::KdTree;
+    /// use kiddo::KiddoError;
+    ///
+    /// let tree = KdTree::<FixedU16<U14>, u32, 3, 32, u16>::try_with_capacity(1_000).unwrap();
+    /// assert_eq!(tree.size(), 0);
+    ///
+    /// assert_eq!(
+    ///     KdTree::<FixedU16<U14>, u32, 3, 32, u16>::try_with_capacity(10_000_000).unwrap_err(),
+    ///     KiddoError::CapacityExceeded {
+    ///         capacity: 10_000_000,
+    ///         max_capacity: 1_048_576,
+    ///     }
+    /// );
+    /// ```
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, KiddoError> {
+        let max_capacity = <IDX as Index>::capacity_with_bucket_size(B);
+        if capacity > max_capacity {
+            return Err(KiddoError::CapacityExceeded {
+                capacity,
+                max_capacity,
+            });
+        }
+
+        Ok(KdTree::with_capacity(capacity))
+    }
+
+    /// Creates a new fixed-point/integer KdTree, reserving capacity for a specific number of
+    /// items, and choosing the axis that each leaf is split along when it fills up using `split_axis`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U14;
+    /// use kiddo::fixed::kdtree::KdTree;
+    /// use kiddo::SplitAxis;
+    ///
+    /// let mut tree: KdTree<FixedU16<U14>, u32, 3, 32, u32> =
+    ///     KdTree::with_capacity_and_split_axis(1_000_000, SplitAxis::WidestSpread);
+    ///
+    /// assert_eq!(tree.split_axis(), SplitAxis::WidestSpread);
+    /// ```
+    #[inline]
+    pub fn with_capacity_and_split_axis(capacity: usize, split_axis: SplitAxis) -> Self {
         assert!(capacity <= <IDX as Index>::capacity_with_bucket_size(B));
+        assert!(
+            split_axis == SplitAxis::RoundRobin || K <= u8::MAX as usize + 1,
+            "SplitAxis::WidestSpread supports at most 256 dimensions"
+        );
         let mut tree = Self {
             size: T::zero(),
             stems: Vec::with_capacity(capacity.max(1).ilog2() as usize),
             leaves: Vec::with_capacity(DivCeil::div_ceil(capacity, B.az::<usize>())),
             root_index: <IDX as Index>::leaf_offset(),
+            split_dims: Vec::new(),
+            split_axis,
+            empty_leaves: 0,
+            rebalance_policy: None,
+            free_leaves: Vec::new(),
+            free_stems: Vec::new(),
         };
 
         tree.leaves.push(LeafNode::new());
@@ -236,6 +337,18 @@ where
         tree
     }
 
+    /// Returns the strategy used to choose the axis that each leaf is split along
+    #[inline]
+    pub fn split_axis(&self) -> SplitAxis {
+        self.split_axis
+    }
+
+    /// Returns the policy that determines when the tree rebalances itself, if one is set
+    #[inline]
+    pub fn rebalance_policy(&self) -> Option<RebalancePolicy> {
+        self.rebalance_policy
+    }
+
     /// Returns the current number of elements stored in the tree
     ///
     /// # Examples
@@ -279,6 +392,74 @@ where
     pub fn iter(&self) -> impl Iterator<Item = (T, [A; K])> + '_ {
         TreeIter::new(self, B)
     }
+
+    /// Returns statistics describing the shape, fill level and memory usage of the tree,
+    /// which can help with choosing suitable values for `B` and `IDX`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U0;
+    /// use kiddo::fixed::kdtree::KdTree;
+    ///
+    /// type Fxd = FixedU16<U0>;
+    ///
+    /// let mut tree: KdTree<Fxd, u32, 2, 32, u32> = KdTree::new();
+    /// for i in 0..100 {
+    ///     tree.add(&[Fxd::from_num(i), Fxd::from_num(i % 10)], i);
+    /// }
+    ///
+    /// let stats = tree.stats();
+    ///
+    /// assert_eq!(stats.size, 100);
+    /// assert_eq!(stats.leaf_count, stats.stem_count + 1);
+    /// assert_eq!(stats.leaf_occupancy.iter().sum::<usize>(), stats.leaf_count);
+    /// ```
+    pub fn stats(&self) -> TreeStats {
+        let mut builder = TreeStatsBuilder::new();
+        let mut to_visit = vec![(self.root_index, 0)];
+        while let Some((node_idx, depth)) = to_visit.pop() {
+            if is_stem_index(node_idx) {
+                let stem = &self.stems[node_idx.az::<usize>()];
+                to_visit.push((stem.right, depth + 1));
+                to_visit.push((stem.left, depth + 1));
+            } else {
+                let leaf = &self.leaves[(node_idx - <IDX as Index>::leaf_offset()).az::<usize>()];
+                builder.add_leaf(
+                    depth,
+                    leaf.content_points[..leaf.size.az::<usize>()]
+                        .iter()
+                        .copied(),
+                );
+            }
+        }
+
+        let leaf_points = size_of::<[[A; K]; B]>();
+        let leaf_items = size_of::<[T; B]>();
+        let leaf_other = size_of::<LeafNode<A, T, K, B, IDX>>() - leaf_points - leaf_items;
+        builder.finish(
+            self.stems.len() - self.free_stems.len(),
+            MemoryUsage {
+                stems: self.stems.len() * size_of::<StemNode<A, K, IDX>>(),
+                leaf_points: self.leaves.len() * leaf_points,
+                leaf_items: self.leaves.len() * leaf_items,
+                other: self.leaves.len() * leaf_other
+                    + self.split_dims.len()
+                    + (self.free_leaves.len() + self.free_stems.len()) * size_of::<IDX>(),
+            },
+        )
+    }
+}
+
+impl<A, T, const K: usize, const B: usize, IDX> KdTree<A, T, K, B, IDX>
+where
+    A: Axis,
+    T: Content,
+    IDX: Index<T = IDX>,
+    usize: Cast<IDX>,
+{
+    generate_visit_leaves!();
 }
 
 impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
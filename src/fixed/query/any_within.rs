@@ -0,0 +1,91 @@
+use az::Cast;
+
+use crate::fixed::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_any_within;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_any_within!(
+        (r#"Finds an element within `dist` of `query`, using the specified
+distance metric function, or returns `None` if there are none.
+
+Stops searching as soon as an element is found, so the element returned is not
+necessarily the nearest one. Faster than checking whether `within_unsorted` is empty.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::fixed::kdtree::KdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let mut tree: KdTree<Fxd, u32, 3, 32, u32> = KdTree::new();
+
+    tree.add(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100);
+    tree.add(&[Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)], 101);
+    tree.add(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)], 102);
+
+    let found = tree.any_within::<SquaredEuclidean>(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(61)], Fxd::from_num(10));
+
+    assert_eq!(found.unwrap().item, 102);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::fixed::kdtree::KdTree;
+    use crate::test_utils::rand_data_fixed_u16_point;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn can_find_any_item_within_radius() {
+        const TREE_SIZE: usize = 10_000;
+        const NUM_QUERIES: usize = 1_000;
+        let radius: Fxd = n(0.1);
+
+        let content_to_add: Vec<[Fxd; 4]> = (0..TREE_SIZE)
+            .map(|_| rand_data_fixed_u16_point::<U14, 4>())
+            .collect();
+
+        let mut tree: KdTree<Fxd, u32, 4, 4, u32> = KdTree::with_capacity(TREE_SIZE);
+        for (item, point) in content_to_add.iter().enumerate() {
+            tree.add(point, item as u32);
+        }
+
+        for _ in 0..NUM_QUERIES {
+            let query_point = rand_data_fixed_u16_point::<U14, 4>();
+            let any_expected = content_to_add
+                .iter()
+                .any(|point| Manhattan::dist(&query_point, point) < radius);
+
+            match tree.any_within::<Manhattan>(&query_point, radius) {
+                Some(found) => {
+                    let point = content_to_add[found.item as usize];
+                    assert_eq!(found.distance, Manhattan::dist(&query_point, &point));
+                    assert!(found.distance < radius);
+                }
+                None => assert!(!any_expected),
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use az::Cast;
+use std::collections::BinaryHeap;
+
+use crate::fixed::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_filtered;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_filtered!(
+        (r#"Finds the nearest element to `query` for which `filter` returns `true`, using
+the specified distance metric function, or returns `None` if there are none.
+
+Items that are rejected by `filter` do not narrow the search, so this always finds
+the nearest accepted item, unlike filtering the results of `nearest_n`.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::fixed::kdtree::KdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let mut tree: KdTree<Fxd, u32, 3, 32, u32> = KdTree::new();
+
+    tree.add(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100);
+    tree.add(&[Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)], 101);
+    tree.add(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)], 102);
+
+    let nearest = tree.nearest_one_filtered::<SquaredEuclidean, _>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], |item| item != 100);
+
+    assert_eq!(nearest.unwrap().item, 101);
+```"#),
+        (r#"Finds the nearest `qty` elements to `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::fixed::kdtree::KdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let mut tree: KdTree<Fxd, u32, 3, 32, u32> = KdTree::new();
+
+    tree.add(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100);
+    tree.add(&[Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)], 101);
+    tree.add(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)], 102);
+
+    let nearest = tree.nearest_n_filtered::<SquaredEuclidean, _>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 2, |item| item != 101);
+
+    assert_eq!(nearest.len(), 2);
+    assert_eq!(nearest[1].item, 102);
+```"#),
+        (r#"Finds all elements within `dist` of `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::fixed::kdtree::KdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let mut tree: KdTree<Fxd, u32, 3, 32, u32> = KdTree::new();
+
+    tree.add(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100);
+    tree.add(&[Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)], 101);
+    tree.add(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)], 102);
+
+    let within = tree.within_filtered::<SquaredEuclidean, _>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], Fxd::from_num(10), |item| item != 100);
+
+    assert_eq!(within.len(), 1);
+    assert_eq!(within[0].item, 101);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::fixed::kdtree::KdTree;
+    use crate::test_utils::rand_data_fixed_u16_point;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    // keeps every distance well short of Fxd::MAX, which distances saturate at
+    fn rand_point() -> [Fxd; 4] {
+        rand_data_fixed_u16_point::<U14, 4>().map(|coord| coord / 8)
+    }
+
+    fn linear_search(
+        content: &[[Fxd; 4]],
+        query_point: &[Fxd; 4],
+        filter: impl Fn(u32) -> bool,
+    ) -> Vec<(Fxd, u32)> {
+        let mut matching_items: Vec<(Fxd, u32)> = content
+            .iter()
+            .enumerate()
+            .map(|(item, point)| (Manhattan::dist(query_point, point), item as u32))
+            .filter(|&(_, item)| filter(item))
+            .collect();
+        matching_items.sort_unstable();
+
+        matching_items
+    }
+
+    #[test]
+    fn can_query_filtered_items() {
+        const TREE_SIZE: usize = 10_000;
+        const NUM_QUERIES: usize = 100;
+
+        let content_to_add: Vec<[Fxd; 4]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+
+        let mut tree: KdTree<Fxd, u32, 4, 4, u32> = KdTree::with_capacity(TREE_SIZE);
+        for (item, point) in content_to_add.iter().enumerate() {
+            tree.add(point, item as u32);
+        }
+
+        let filter = |item: u32| item % 97 == 3;
+
+        for _ in 0..NUM_QUERIES {
+            let query_point = rand_point();
+            let expected = linear_search(&content_to_add, &query_point, filter);
+
+            // distances can tie at this precision, so only the distances are compared
+            let nearest = tree
+                .nearest_one_filtered::<Manhattan, _>(&query_point, filter)
+                .unwrap();
+            assert!(filter(nearest.item));
+            assert_eq!(nearest.distance, expected[0].0);
+
+            let nearest: Vec<_> = tree
+                .nearest_n_filtered::<Manhattan, _>(&query_point, 5, filter)
+                .into_iter()
+                .map(|n| n.distance)
+                .collect();
+            let expected_distances: Vec<_> = expected[..5]
+                .iter()
+                .map(|&(distance, _)| distance)
+                .collect();
+            assert_eq!(nearest, expected_distances);
+
+            let radius = n(0.3);
+            let mut within: Vec<_> = tree
+                .within_filtered::<Manhattan, _>(&query_point, radius, filter)
+                .into_iter()
+                .map(|n| (n.distance, n.item))
+                .collect();
+            within.sort_unstable();
+            assert_eq!(
+                within,
+                expected
+                    .into_iter()
+                    .filter(|&(distance, _)| distance < radius)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+}
@@ -1,7 +1,12 @@
+pub mod any_within;
 pub mod best_n_within;
+pub mod count_within;
+pub mod filtered;
+pub mod nearest_excluding;
 pub mod nearest_n;
 pub mod nearest_one;
 pub mod within;
+pub mod within_box;
 pub mod within_unsorted;
 
 #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
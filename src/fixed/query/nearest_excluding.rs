@@ -0,0 +1,144 @@
+use az::Cast;
+use std::collections::BinaryHeap;
+
+use crate::fixed::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_nearest_excluding;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_nearest_excluding!(
+        (r#"Queries the tree to find the nearest item to the `query` point, ignoring
+any elements whose item is `excluded`.
+
+Intended for querying with a point that is itself stored in the tree, such as when
+building a neighbour graph. Returns `None` if the tree holds no other items.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::fixed::kdtree::KdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let mut tree: KdTree<Fxd, u32, 3, 32, u32> = KdTree::new();
+
+    tree.add(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100);
+    tree.add(&[Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)], 101);
+    tree.add(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)], 102);
+
+    let nearest = tree.nearest_one_excluding::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100).unwrap();
+
+    assert_eq!(nearest.distance, Fxd::from_num(3));
+    assert_eq!(nearest.item, 101);
+```"#),
+        (r#"Finds the nearest `qty` elements to `query`, ignoring any elements whose
+item is `excluded`, using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::fixed::kdtree::KdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let mut tree: KdTree<Fxd, u32, 3, 32, u32> = KdTree::new();
+
+    tree.add(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100);
+    tree.add(&[Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)], 101);
+    tree.add(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)], 102);
+
+    let nearest = tree.nearest_n_excluding::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 2, 100);
+
+    assert_eq!(nearest.len(), 2);
+    assert_eq!(nearest[0].item, 101);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::fixed::kdtree::KdTree;
+    use crate::test_utils::rand_data_fixed_u16_point;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+
+    type Fxd = FixedU16<U14>;
+
+    #[test]
+    fn can_query_nearest_items_excluding_the_query_item() {
+        const TREE_SIZE: usize = 2_000;
+
+        let content: Vec<[Fxd; 4]> = (0..TREE_SIZE)
+            .map(|_| rand_data_fixed_u16_point::<U14, 4>())
+            .collect();
+
+        let mut tree: KdTree<Fxd, u32, 4, 4, u32> = KdTree::with_capacity(TREE_SIZE);
+        for (item, point) in content.iter().enumerate() {
+            tree.add(point, item as u32);
+        }
+
+        for (item, point) in content.iter().enumerate().step_by(10) {
+            let mut expected: Vec<Fxd> = content
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != item)
+                .map(|(_, other_point)| Manhattan::dist(point, other_point))
+                .collect();
+            expected.sort_unstable();
+
+            // distances can tie at this precision, so only the distances are compared
+            let nearest = tree
+                .nearest_one_excluding::<Manhattan>(point, item as u32)
+                .unwrap();
+            assert_ne!(nearest.item, item as u32);
+            assert_eq!(nearest.distance, expected[0]);
+
+            let nearest = tree.nearest_n_excluding::<Manhattan>(point, 5, item as u32);
+            assert!(nearest.iter().all(|n| n.item != item as u32));
+            assert_eq!(
+                nearest.iter().map(|n| n.distance).collect::<Vec<_>>(),
+                expected[..5]
+            );
+        }
+    }
+
+    #[test]
+    fn finds_nothing_when_only_the_excluded_item_is_present() {
+        let point = [Fxd::from_num(0.5); 4];
+        let mut tree: KdTree<Fxd, u32, 4, 4, u32> = KdTree::new();
+        tree.add(&point, 1);
+
+        assert_eq!(tree.nearest_one_excluding::<Manhattan>(&point, 1), None);
+        assert!(tree
+            .nearest_n_excluding::<Manhattan>(&point, 3, 1)
+            .is_empty());
+    }
+
+    #[test]
+    fn finds_nothing_in_an_empty_tree() {
+        let point = [Fxd::from_num(0.5); 4];
+        let tree: KdTree<Fxd, u32, 4, 4, u32> = KdTree::new();
+
+        assert_eq!(tree.nearest_one_excluding::<Manhattan>(&point, 1), None);
+        assert!(tree
+            .nearest_n_excluding::<Manhattan>(&point, 3, 1)
+            .is_empty());
+    }
+}
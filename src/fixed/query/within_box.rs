@@ -0,0 +1,157 @@
+use az::{Az, Cast};
+use std::ops::Rem;
+
+use crate::fixed::kdtree::{Axis, KdTree, LeafNode};
+use crate::traits::{is_stem_index, Content, Index};
+
+use crate::generate_within_box;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_within_box!(
+        LeafNode,
+        (r#"Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::fixed::kdtree::KdTree;
+
+    type Fxd = FixedU16<U0>;
+
+    let mut tree: KdTree<Fxd, u32, 3, 32, u32> = KdTree::new();
+
+    tree.add(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100);
+    tree.add(&[Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)], 101);
+    tree.add(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)], 102);
+
+    let within = tree.within_box(&[Fxd::from_num(0), Fxd::from_num(0), Fxd::from_num(0)], &[Fxd::from_num(2), Fxd::from_num(2), Fxd::from_num(5)]);
+
+    assert_eq!(within, vec![100]);
+```"#),
+        (r#"Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Returns an `Iterator`. Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::fixed::kdtree::KdTree;
+
+    type Fxd = FixedU16<U0>;
+
+    let mut tree: KdTree<Fxd, u32, 3, 32, u32> = KdTree::new();
+
+    tree.add(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100);
+    tree.add(&[Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)], 101);
+    tree.add(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)], 102);
+
+    let min = [Fxd::from_num(0), Fxd::from_num(0), Fxd::from_num(0)];
+    let max = [Fxd::from_num(10), Fxd::from_num(10), Fxd::from_num(10)];
+    let within = tree.within_box_iter(&min, &max).collect::<Vec<_>>();
+
+    assert_eq!(within.len(), 2);
+```"#),
+        (r#"Counts the elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Faster than `within_box(min, max).len()`, since leaves that lie entirely within the box
+are counted without looking at the points that they hold.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::fixed::kdtree::KdTree;
+
+    type Fxd = FixedU16<U0>;
+
+    let mut tree: KdTree<Fxd, u32, 3, 32, u32> = KdTree::new();
+
+    tree.add(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 100);
+    tree.add(&[Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)], 101);
+    tree.add(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)], 102);
+
+    let count = tree.count_in_box(&[Fxd::from_num(0), Fxd::from_num(0), Fxd::from_num(0)], &[Fxd::from_num(10), Fxd::from_num(10), Fxd::from_num(10)]);
+
+    assert_eq!(count, 2);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::kdtree::{Axis, KdTree};
+    use crate::split_axis::SplitAxis;
+    use crate::test_utils::{rand_data_fixed_u16_entry, rand_data_fixed_u16_point};
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+
+    type Fxd = FixedU16<U14>;
+
+    #[test]
+    fn can_query_items_within_box() {
+        const TREE_SIZE: usize = 10_000;
+        const NUM_QUERIES: usize = 100;
+
+        let content_to_add: Vec<([Fxd; 3], u32)> = (0..TREE_SIZE)
+            .map(|_| rand_data_fixed_u16_entry::<U14, u32, 3>())
+            .collect();
+
+        for split_axis in [SplitAxis::RoundRobin, SplitAxis::WidestSpread] {
+            let mut tree: KdTree<Fxd, u32, 3, 8, u32> =
+                KdTree::with_capacity_and_split_axis(TREE_SIZE, split_axis);
+            content_to_add
+                .iter()
+                .for_each(|(point, content)| tree.add(point, *content));
+
+            for _ in 0..NUM_QUERIES {
+                let a = rand_data_fixed_u16_point::<U14, 3>();
+                let b = rand_data_fixed_u16_point::<U14, 3>();
+                let min = [0, 1, 2].map(|dim| a[dim].min(b[dim]));
+                let max = [0, 1, 2].map(|dim| a[dim].max(b[dim]));
+                let expected = linear_search(&content_to_add, &min, &max);
+
+                let mut result = tree.within_box(&min, &max);
+                result.sort_unstable();
+                assert_eq!(result, expected);
+
+                let mut result: Vec<_> = tree.within_box_iter(&min, &max).collect();
+                result.sort_unstable();
+                assert_eq!(result, expected);
+
+                assert_eq!(tree.count_in_box(&min, &max), expected.len());
+            }
+
+            assert_eq!(tree.count_in_box(&[Fxd::MIN; 3], &[Fxd::MAX; 3]), TREE_SIZE);
+        }
+    }
+
+    fn linear_search<A: Axis, const K: usize>(
+        content: &[([A; K], u32)],
+        min: &[A; K],
+        max: &[A; K],
+    ) -> Vec<u32> {
+        let mut matching_items: Vec<u32> = content
+            .iter()
+            .filter(|(p, _)| (0..K).all(|dim| p[dim] >= min[dim] && p[dim] <= max[dim]))
+            .map(|&(_, item)| item)
+            .collect();
+
+        matching_items.sort_unstable();
+
+        matching_items
+    }
+}
@@ -1,14 +1,292 @@
-use crate::float::kdtree::{Axis, KdTree, LeafNode, StemNode};
+use crate::error::KiddoError;
+use crate::float::kdtree::{Axis, KdTree, LeafNode, RebalancePolicy, StemNode};
 use crate::mirror_select_nth_unstable_by::mirror_select_nth_unstable_by;
+use crate::packed_leaves::{partition_by, select_nth_by, MirroredContent, PackedContent};
+use crate::split_axis::SplitAxis;
 use crate::traits::{is_stem_index, Content, Index};
 use az::{Az, Cast};
-use std::ops::Rem;
+use smallvec::SmallVec;
+use std::cmp::Ordering;
+use std::ops::{Range, Rem};
+
+/// The stems passed through on the way down to a leaf, along with whether their left
+/// child was taken. Holds the path through a balanced tree of up to 2^32 leaves without
+/// allocating.
+pub(crate) type DescentPath<IDX> = SmallVec<[(IDX, bool); 32]>;
 
 impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
     KdTree<A, T, K, B, IDX>
 where
     usize: Cast<IDX>,
 {
+    /// Creates a balanced KdTree populated with the points in `points`,
+    /// storing `items[i]` as the item for `points[i]`.
+    ///
+    /// Rather than adding each point in turn, which results in a tree whose shape
+    /// depends upon the order that points were added in, the points are repeatedly
+    /// partitioned around their median. The resulting tree still supports
+    /// [`add`](`KdTree::add`) and [`remove`](`KdTree::remove`) afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `items` have different lengths, or if the tree can't be built.
+    /// See [`try_new_balanced_from`](`KdTree::try_new_balanced_from`) for the reasons that
+    /// this can happen.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::SquaredEuclidean;
+    ///
+    /// let points: Vec<[f64; 3]> = (0..1000).map(|i| [i as f64, 0.0, 0.0]).collect();
+    /// let items: Vec<u64> = (0..1000).map(|i| i * 10).collect();
+    /// let mut tree: KdTree<f64, 3> = KdTree::new_balanced_from(&points, &items);
+    ///
+    /// assert_eq!(tree.size(), 1000);
+    /// assert_eq!(tree.nearest_one::<SquaredEuclidean>(&[500.2, 0.0, 0.0]).item, 5000);
+    ///
+    /// tree.add(&[500.1, 0.0, 0.0], 1);
+    /// assert_eq!(tree.nearest_one::<SquaredEuclidean>(&[500.2, 0.0, 0.0]).item, 1);
+    /// ```
+    pub fn new_balanced_from(points: &[[A; K]], items: &[T]) -> Self
+    where
+        usize: Cast<T>,
+    {
+        match Self::try_new_balanced_from(points, items) {
+            Ok(tree) => tree,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Creates a balanced KdTree populated with the points in `points`, as
+    /// [`new_balanced_from`](`KdTree::new_balanced_from`) does, returning an error rather than
+    /// panicking if it can't be built.
+    ///
+    /// An error is returned if `IDX` can't address as many items as there are points with a
+    /// bucket size of `B` ([`KiddoError::CapacityExceeded`]), or if the balanced tree needs more
+    /// stems or leaves than `IDX` can index ([`KiddoError::IndexOverflow`]). Since the leaves of
+    /// a balanced tree are not all full, the latter can happen for slightly fewer points than
+    /// the capacity that `IDX` supports.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `items` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::kdtree::KdTree;
+    /// use kiddo::KiddoError;
+    ///
+    /// let points: Vec<[f64; 2]> = (0..200_000).map(|i| [i as f64, 0.0]).collect();
+    /// let items: Vec<u32> = (0..200_000).collect();
+    ///
+    /// let tree = KdTree::<f64, u32, 2, 4, u16>::try_new_balanced_from(&points[..1_000], &items[..1_000]).unwrap();
+    /// assert_eq!(tree.size(), 1_000);
+    ///
+    /// assert_eq!(
+    ///     KdTree::<f64, u32, 2, 4, u16>::try_new_balanced_from(&points, &items).unwrap_err(),
+    ///     KiddoError::CapacityExceeded {
+    ///         capacity: 200_000,
+    ///         max_capacity: 131_072,
+    ///     }
+    /// );
+    /// ```
+    pub fn try_new_balanced_from(points: &[[A; K]], items: &[T]) -> Result<Self, KiddoError>
+    where
+        usize: Cast<T>,
+    {
+        assert_eq!(
+            points.len(),
+            items.len(),
+            "points and items must have the same length"
+        );
+
+        let mut tree = Self::try_with_capacity(points.len())?;
+        tree.leaves.clear();
+
+        let len = points.len();
+        let mut points = points.to_vec();
+        let mut items = items.to_vec();
+        let mut content = MirroredContent {
+            points: &mut points,
+            items: &mut items,
+            leaf_sizes: Vec::new(),
+        };
+        tree.populate_balanced_root(&mut content, len)?;
+
+        let mut start = 0;
+        for size in content.leaf_sizes {
+            let mut leaf = LeafNode::new();
+            leaf.content_points[..size].copy_from_slice(&points[start..start + size]);
+            leaf.content_items[..size].copy_from_slice(&items[start..start + size]);
+            leaf.size = size.az::<IDX>();
+            tree.leaves.push(leaf);
+
+            start += size;
+        }
+        tree.size = len.az::<T>();
+
+        Ok(tree)
+    }
+
+    /// Builds a balanced tree out of the `len` items held packed `B` to a leaf at the
+    /// front of `self.leaves`, replacing any stems that were there before.
+    ///
+    /// Returns [`KiddoError::IndexOverflow`], leaving the tree without a usable root, if
+    /// it needs more stems or leaves than `IDX` can index.
+    fn populate_from_packed_leaves(&mut self, len: usize) -> Result<(), KiddoError> {
+        // the leaves are taken out of the tree whilst they are partitioned, so that
+        // the stems can be built alongside them
+        let mut leaves = std::mem::take(&mut self.leaves);
+        let leaf_count = self.populate_balanced_root(&mut leaves, len);
+        self.leaves = leaves;
+        let leaf_count = leaf_count?;
+
+        // each leaf only holds items from the packed positions before the end of its own
+        // slot, so moving them into place from the last leaf back never overwrites
+        // items that are still to be moved
+        let mut end = len;
+        for leaf_idx in (0..leaf_count).rev() {
+            let size = self.leaves[leaf_idx].size.az::<usize>();
+            let start = end - size;
+
+            let mut leaf = LeafNode::new();
+            for (slot, idx) in (start..end).enumerate() {
+                leaf.content_points[slot] = self.leaves[idx / B].content_points[idx % B];
+                leaf.content_items[slot] = self.leaves[idx / B].content_items[idx % B];
+            }
+            leaf.size = size.az::<IDX>();
+            self.leaves[leaf_idx] = leaf;
+
+            end = start;
+        }
+        self.leaves.truncate(leaf_count);
+
+        Ok(())
+    }
+
+    /// Builds a balanced set of stems over the `len` items held in `content`, replacing any
+    /// stems that were there before, and returns the number of leaves that they end up in.
+    ///
+    /// Returns [`KiddoError::IndexOverflow`], leaving the tree without a usable root, if
+    /// it needs more stems or leaves than `IDX` can index.
+    fn populate_balanced_root<S>(
+        &mut self,
+        content: &mut S,
+        len: usize,
+    ) -> Result<usize, KiddoError>
+    where
+        S: PackedContent<A, K> + ?Sized,
+    {
+        self.stems.clear();
+        self.split_dims.clear();
+        self.free_leaves.clear();
+        self.free_stems.clear();
+        self.empty_leaves = 0;
+
+        let mut leaf_count = 0;
+        self.root_index = self.populate_balanced(content, 0..len, 0, &mut leaf_count)?;
+
+        Ok(leaf_count)
+    }
+
+    /// Partitions the items at the positions in `range` of `content` around their median on
+    /// `split_dim`, recursing until each part fits into a leaf, and recording the size of
+    /// each of those leaves in turn as leaf `leaf_count`. Returns the index of the stem or
+    /// leaf that holds them.
+    fn populate_balanced<S>(
+        &mut self,
+        content: &mut S,
+        range: Range<usize>,
+        split_dim: usize,
+        leaf_count: &mut usize,
+    ) -> Result<IDX, KiddoError>
+    where
+        S: PackedContent<A, K> + ?Sized,
+    {
+        if range.len() <= B {
+            if *leaf_count >= <IDX as Index>::capacity_with_bucket_size(1) {
+                return Err(KiddoError::IndexOverflow);
+            }
+            if range.is_empty() {
+                self.empty_leaves += 1;
+            }
+
+            content.set_leaf_size(*leaf_count, range.len());
+            *leaf_count += 1;
+
+            return Ok((*leaf_count - 1).az::<IDX>() + IDX::leaf_offset());
+        }
+
+        let split_dim = match self.split_axis {
+            SplitAxis::RoundRobin => split_dim,
+            SplitAxis::WidestSpread => {
+                Self::widest_spread_dim(range.clone().map(|idx| content.point(idx)))
+            }
+        };
+
+        let median_idx = range.start + range.len() / 2;
+        content.select_nth_by(range.clone(), median_idx, |a, b| {
+            a[split_dim]
+                .partial_cmp(&b[split_dim])
+                .expect("Leaf node sort failed.")
+        });
+        let mut split_val = content.point(median_idx)[split_dim];
+
+        // items whose position on the split dimension is the same as the split
+        // value belong on the right, so move any of those out of the lower half
+        let mut pivot_idx = partition_by(content, range.start..median_idx, |point| {
+            point[split_dim] < split_val
+        });
+
+        // if nothing is below the median, split just above it instead
+        if pivot_idx == range.start {
+            pivot_idx = partition_by(content, range.clone(), |point| {
+                point[split_dim] <= split_val
+            });
+            if pivot_idx < range.end {
+                split_val = (pivot_idx..range.end)
+                    .map(|idx| content.point(idx)[split_dim])
+                    .fold(A::infinity(), A::min);
+            } else if range
+                .clone()
+                .all(|idx| content.point(idx) == content.point(range.start))
+            {
+                // every point is at the same position, so no split can separate them.
+                // Split them down the middle, as `split` does for a full leaf of them
+                pivot_idx = median_idx;
+            } else {
+                pivot_idx = range.start;
+            }
+        }
+
+        let stem_idx = self.stems.len();
+        if stem_idx >= IDX::leaf_offset().az::<usize>() {
+            return Err(KiddoError::IndexOverflow);
+        }
+        self.stems.push(StemNode {
+            left: IDX::zero(),
+            right: IDX::zero(),
+            split_val,
+        });
+        if self.split_axis == SplitAxis::WidestSpread {
+            self.split_dims.push(split_dim as u8);
+        }
+
+        let next_split_dim = (split_dim + 1).rem(K);
+        let left =
+            self.populate_balanced(content, range.start..pivot_idx, next_split_dim, leaf_count)?;
+        let right =
+            self.populate_balanced(content, pivot_idx..range.end, next_split_dim, leaf_count)?;
+
+        self.stems[stem_idx].left = left;
+        self.stems[stem_idx].right = right;
+
+        Ok(stem_idx.az::<IDX>())
+    }
+
     /// Adds an item to the tree.
     ///
     /// The first argument specifies co-ordinates of the point where the item is located.
@@ -25,8 +303,56 @@ where
     ///
     /// assert_eq!(tree.size(), 1);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the item cannot be stored. See [`try_add`](`KdTree::try_add`) for the reasons
+    /// that this can happen.
     #[inline]
     pub fn add(&mut self, query: &[A; K], item: T) {
+        if let Err(err) = self.try_add(query, item) {
+            panic!("{err}");
+        }
+    }
+
+    /// Adds an item to the tree, returning an error rather than panicking if it can't be stored.
+    ///
+    /// An error is returned, and the tree is left holding the same items as before, if the leaf
+    /// that the item belongs in is full and can't be split because the tree already has as many
+    /// stems or leaves as `IDX` can index ([`KiddoError::IndexOverflow`]).
+    ///
+    /// [`KiddoError::UnsplittableBucket`] is never returned: items that share the same position
+    /// on a leaf's split axis are spread over more than one leaf, however many of them there are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::kdtree::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, u32, 2, 4, u32> = KdTree::new();
+    ///
+    /// // more items than fit into one bucket can share the same position
+    /// for item in 0..40 {
+    ///     assert_eq!(tree.try_add(&[1.0, 2.0], item), Ok(()));
+    /// }
+    ///
+    /// assert_eq!(tree.size(), 40);
+    /// ```
+    #[inline]
+    pub fn try_add(&mut self, query: &[A; K], item: T) -> Result<(), KiddoError> {
+        self.try_add_tracking_moves(query, item, &mut |_, _| {})
+    }
+
+    /// Adds an item to the tree as [`try_add`](`KdTree::try_add`) does, calling `on_move`
+    /// with the index of the leaf that the item ends up in, and with each item that gets
+    /// moved to a different leaf along with the index of that leaf.
+    pub(crate) fn try_add_tracking_moves(
+        &mut self,
+        query: &[A; K],
+        item: T,
+        on_move: &mut impl FnMut(T, IDX),
+    ) -> Result<(), KiddoError> {
+        let mut depth = 0;
         unsafe {
             let mut stem_idx = self.root_index;
             let mut split_dim = 0;
@@ -35,10 +361,12 @@ where
             let mut is_left_child: bool = false;
 
             while is_stem_index(stem_idx) {
+                depth += 1;
                 parent_idx = stem_idx;
+                let dim = self.stem_split_dim(stem_idx, split_dim);
                 stem_node = self.stems.get_unchecked_mut(stem_idx.az::<usize>());
 
-                stem_idx = if *query.get_unchecked(split_dim) < stem_node.split_val {
+                stem_idx = if *query.get_unchecked(dim) < stem_node.split_val {
                     is_left_child = true;
                     stem_node.left
                 } else {
@@ -53,10 +381,12 @@ where
             let mut leaf_node = self.leaves.get_unchecked_mut(leaf_idx.az::<usize>());
 
             if leaf_node.size == B.az::<IDX>() {
-                stem_idx = self.split(leaf_idx, split_dim, parent_idx, is_left_child);
+                depth += 1;
+                stem_idx = self.split(leaf_idx, split_dim, parent_idx, is_left_child, on_move)?;
+                let dim = self.stem_split_dim(stem_idx, split_dim);
                 let node = self.stems.get_unchecked_mut(stem_idx.az::<usize>());
 
-                leaf_idx = (if *query.get_unchecked(split_dim) < node.split_val {
+                leaf_idx = (if *query.get_unchecked(dim) < node.split_val {
                     node.left
                 } else {
                     node.right
@@ -65,6 +395,10 @@ where
                 leaf_node = self.leaves.get_unchecked_mut(leaf_idx.az::<usize>());
             }
 
+            if self.rebalance_policy.is_some() && leaf_node.size == IDX::zero() {
+                self.empty_leaves -= 1;
+            }
+
             *leaf_node
                 .content_points
                 .get_unchecked_mut(leaf_node.size.az::<usize>()) = *query;
@@ -73,8 +407,18 @@ where
                 .get_unchecked_mut(leaf_node.size.az::<usize>()) = item;
 
             leaf_node.size = leaf_node.size + IDX::one();
+            on_move(item, leaf_idx);
         }
         self.size = self.size + T::one();
+
+        if let Some(policy) = self.rebalance_policy {
+            let balanced_depth = self.live_leaf_count().next_power_of_two().ilog2().max(1);
+            if depth as f32 > policy.max_depth_ratio * balanced_depth as f32 {
+                self.rebalance_tracking_moves(on_move);
+            }
+        }
+
+        Ok(())
     }
 
     /// Removes an item from the tree.
@@ -101,16 +445,310 @@ where
     /// ```
     #[inline]
     pub fn remove(&mut self, query: &[A; K], item: T) -> usize {
-        let mut stem_idx = self.root_index;
-        let mut split_dim = 0;
+        self.remove_tracking_moves(query, item, &mut |_, _| {})
+    }
+
+    /// Removes an item from the tree as [`remove`](`KdTree::remove`) does, calling `on_move`
+    /// with each other item that gets moved to a different leaf along with the index of that leaf.
+    pub(crate) fn remove_tracking_moves(
+        &mut self,
+        query: &[A; K],
+        item: T,
+        on_move: &mut impl FnMut(T, IDX),
+    ) -> usize {
         let mut removed: usize = 0;
+        let mut path = DescentPath::new();
 
-        while is_stem_index(stem_idx) {
-            let Some(stem_node) = self.stems.get_mut(stem_idx.az::<usize>()) else {
-                return removed;
-            };
+        // items at the same position can be spread over more than one leaf,
+        // if there were too many of them to fit into one
+        while let Some(leaf_idx) = self.find_item(query, item, self.root_index, 0, &mut path) {
+            let leaf_node = &mut self.leaves[leaf_idx.az::<usize>()];
+            let mut p_index = 0;
+            while p_index < leaf_node.size.az::<usize>() {
+                if &leaf_node.content_points[p_index] == query
+                    && leaf_node.content_items[p_index] == item
+                {
+                    leaf_node.content_points[p_index] =
+                        leaf_node.content_points[leaf_node.size.az::<usize>() - 1];
+                    leaf_node.content_items[p_index] =
+                        leaf_node.content_items[leaf_node.size.az::<usize>() - 1];
+
+                    self.size -= T::one();
+                    removed += 1;
+                    leaf_node.size = leaf_node.size - IDX::one();
+                } else {
+                    p_index += 1;
+                }
+            }
+
+            self.tidy_up_after_removal(&mut path, leaf_idx, on_move);
+            path.clear();
+        }
+
+        removed
+    }
+
+    /// Moves an item from one position to another.
+    ///
+    /// This is equivalent to [`remove`](`KdTree::remove`) followed by [`add`](`KdTree::add`),
+    /// except that only one item is moved, and that if the new position belongs in the same
+    /// leaf as the old one, the item's position is simply re-written in place. Returns `false`,
+    /// leaving the tree unchanged, if `item` is not stored at `old`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::SquaredEuclidean;
+    ///
+    /// let mut tree: KdTree<f64, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100);
+    ///
+    /// assert!(tree.update(&[1.0, 2.0, 5.0], &[1.5, 2.5, 5.5], 100));
+    /// assert!(!tree.update(&[1.0, 2.0, 5.0], &[1.5, 2.5, 5.5], 100));
+    ///
+    /// assert_eq!(tree.size(), 1);
+    /// assert_eq!(tree.nearest_one::<SquaredEuclidean>(&[1.5, 2.5, 5.5]).distance, 0.0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics, after putting the item back at `old`, if the item cannot be stored at `new`.
+    /// See [`try_add`](`KdTree::try_add`) for the reasons that this can happen.
+    pub fn update(&mut self, old: &[A; K], new: &[A; K], item: T) -> bool {
+        match self.try_update(old, new, item) {
+            Ok(updated) => updated,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Moves an item as [`update`](`KdTree::update`) does, returning an error rather than
+    /// panicking if it can't be stored at `new`.
+    ///
+    /// If an error is returned, the item is left at `old`, as if the update hadn't been attempted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100);
+    ///
+    /// assert_eq!(tree.try_update(&[1.0, 2.0, 5.0], &[1.5, 2.5, 5.5], 100), Ok(true));
+    /// assert_eq!(tree.try_update(&[1.0, 2.0, 5.0], &[1.5, 2.5, 5.5], 100), Ok(false));
+    /// ```
+    pub fn try_update(&mut self, old: &[A; K], new: &[A; K], item: T) -> Result<bool, KiddoError> {
+        self.update_tracking_moves(old, new, item, &mut |_, _| {})
+    }
+
+    /// Moves an item as [`try_update`](`KdTree::try_update`) does, calling `on_move` with the
+    /// index of the leaf that the item ends up in if it changes leaf, and with each other item
+    /// that gets moved to a different leaf along with the index of that leaf.
+    pub(crate) fn update_tracking_moves(
+        &mut self,
+        old: &[A; K],
+        new: &[A; K],
+        item: T,
+        on_move: &mut impl FnMut(T, IDX),
+    ) -> Result<bool, KiddoError> {
+        match self.move_in_place_or_remove(old, new, item, on_move) {
+            None => Ok(false),
+            Some(false) => Ok(true),
+            Some(true) => {
+                if let Err(err) = self.try_add_tracking_moves(new, item, on_move) {
+                    // the item's old leaf either has room for it again, or has been merged
+                    // and freed the stem and leaf that splitting it would need
+                    let restored = self.try_add_tracking_moves(old, item, on_move);
+                    debug_assert!(restored.is_ok());
+
+                    return Err(err);
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    /// Moves many items, each from its old position to its new one, as with
+    /// [`update`](`KdTree::update`). Returns the number of items that were moved.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, 2> = KdTree::new();
+    /// tree.add(&[1.0, 2.0], 100);
+    /// tree.add(&[3.0, 4.0], 200);
+    ///
+    /// let updated = tree.update_many([
+    ///     ([1.0, 2.0], [1.1, 2.1], 100),
+    ///     ([3.0, 4.0], [30.0, 40.0], 200),
+    ///     ([5.0, 6.0], [50.0, 60.0], 300),
+    /// ]);
+    ///
+    /// assert_eq!(updated, 2);
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics, after putting it back at its old position, if an item cannot be stored at its
+    /// new one. See [`try_update_many`](`KdTree::try_update_many`) for the details.
+    pub fn update_many<I: IntoIterator<Item = ([A; K], [A; K], T)>>(
+        &mut self,
+        updates: I,
+    ) -> usize {
+        match self.try_update_many(updates) {
+            Ok(updated) => updated,
+            Err(err) => panic!("{err}"),
+        }
+    }
 
-            stem_idx = if query[split_dim] < stem_node.split_val {
+    /// Moves many items at once as [`update_many`](`KdTree::update_many`) does, returning an
+    /// error rather than panicking if any of them can't be stored at their new position.
+    /// See [`try_add`](`KdTree::try_add`) for the reasons that this can happen.
+    ///
+    /// Items are moved in turn, stopping at the first that can't be. If an error is returned,
+    /// that item is left at its old position, as are the items after it, whilst the items
+    /// before it stay at their new one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, 2> = KdTree::new();
+    /// tree.add(&[1.0, 2.0], 100);
+    /// tree.add(&[3.0, 4.0], 200);
+    ///
+    /// let updated = tree.try_update_many([
+    ///     ([1.0, 2.0], [1.1, 2.1], 100),
+    ///     ([3.0, 4.0], [30.0, 40.0], 200),
+    ///     ([5.0, 6.0], [50.0, 60.0], 300),
+    /// ]);
+    ///
+    /// assert_eq!(updated, Ok(2));
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    pub fn try_update_many<I: IntoIterator<Item = ([A; K], [A; K], T)>>(
+        &mut self,
+        updates: I,
+    ) -> Result<usize, KiddoError> {
+        let mut updated = 0;
+
+        for (old, new, item) in updates {
+            if self.try_update(&old, &new, item)? {
+                updated += 1;
+            }
+        }
+
+        Ok(updated)
+    }
+
+    /// Re-writes the position of one occurrence of `item` at `old` to `new` if `new` belongs in
+    /// the same leaf, and otherwise removes it. Returns `None` if `item` is not stored at `old`,
+    /// or else whether the item still needs to be added at `new`.
+    fn move_in_place_or_remove(
+        &mut self,
+        old: &[A; K],
+        new: &[A; K],
+        item: T,
+        on_move: &mut impl FnMut(T, IDX),
+    ) -> Option<bool> {
+        let mut path = DescentPath::new();
+        let old_leaf_idx = self.find_item(old, item, self.root_index, 0, &mut path)?;
+        let new_leaf_idx = self.find_leaf_from(new, self.root_index, 0);
+
+        let leaf_node = &mut self.leaves[old_leaf_idx.az::<usize>()];
+        let size = leaf_node.size.az::<usize>();
+        let p_index = (0..size).find(|&p_index| {
+            &leaf_node.content_points[p_index] == old && leaf_node.content_items[p_index] == item
+        })?;
+
+        if old_leaf_idx == new_leaf_idx {
+            leaf_node.content_points[p_index] = *new;
+            return Some(false);
+        }
+
+        leaf_node.content_points[p_index] = leaf_node.content_points[size - 1];
+        leaf_node.content_items[p_index] = leaf_node.content_items[size - 1];
+        leaf_node.size = leaf_node.size - IDX::one();
+        self.size -= T::one();
+        self.tidy_up_after_removal(&mut path, old_leaf_idx, on_move);
+
+        Some(true)
+    }
+
+    /// Removes one occurrence of `item` at `query` from the leaf at `leaf_idx`, without
+    /// searching the tree for it, calling `on_move` with each item that gets moved to a
+    /// different leaf as a result, along with the index of that leaf. Returns `false`,
+    /// leaving the tree unchanged, if the leaf does not hold `item` at `query`.
+    pub(crate) fn remove_from_leaf_tracking_moves(
+        &mut self,
+        query: &[A; K],
+        item: T,
+        leaf_idx: IDX,
+        on_move: &mut impl FnMut(T, IDX),
+    ) -> bool {
+        let leaf_node = &mut self.leaves[leaf_idx.az::<usize>()];
+        let size = leaf_node.size.az::<usize>();
+        let Some(p_index) = (0..size).find(|&p_index| {
+            &leaf_node.content_points[p_index] == query && leaf_node.content_items[p_index] == item
+        }) else {
+            return false;
+        };
+
+        leaf_node.content_points[p_index] = leaf_node.content_points[size - 1];
+        leaf_node.content_items[p_index] = leaf_node.content_items[size - 1];
+        leaf_node.size = leaf_node.size - IDX::one();
+        self.size -= T::one();
+
+        // merging needs the stems above the leaf, which are found by following
+        // `query` down to it, rather than by comparing items at each leaf
+        let mut path = DescentPath::new();
+        if self.find_path_to_leaf(query, leaf_idx, self.root_index, 0, &mut path) {
+            self.tidy_up_after_removal(&mut path, leaf_idx, on_move);
+        }
+
+        true
+    }
+
+    /// Merges the leaf at `leaf_idx`, that an item was just removed from, with its siblings
+    /// where possible, and keeps track of empty leaves for the rebalance policy. `path` holds
+    /// the stems that lead to the leaf, as returned by [`find_item`](`KdTree::find_item`).
+    /// `on_move` is called with each item that gets moved to a different leaf, along with the
+    /// index of that leaf.
+    fn tidy_up_after_removal(
+        &mut self,
+        path: &mut DescentPath<IDX>,
+        leaf_idx: IDX,
+        on_move: &mut impl FnMut(T, IDX),
+    ) {
+        let leaf_idx = self.merge_with_siblings(path, leaf_idx, on_move);
+
+        if let Some(policy) = self.rebalance_policy {
+            if self.leaves[leaf_idx.az::<usize>()].size == IDX::zero() {
+                self.empty_leaves += 1;
+                if self.empty_leaves as f32
+                    > policy.max_empty_leaf_ratio * self.live_leaf_count() as f32
+                {
+                    self.rebalance_tracking_moves(on_move);
+                }
+            }
+        }
+    }
+
+    /// Returns the index of the leaf that `query` belongs in, starting the search
+    /// at `node_idx`, which splits along `split_dim` if the tree uses round-robin splits
+    fn find_leaf_from(&self, query: &[A; K], mut node_idx: IDX, mut split_dim: usize) -> IDX {
+        while is_stem_index(node_idx) {
+            let dim = self.stem_split_dim(node_idx, split_dim);
+            let stem_node = &self.stems[node_idx.az::<usize>()];
+
+            node_idx = if query[dim] < stem_node.split_val {
                 stem_node.left
             } else {
                 stem_node.right
@@ -119,39 +757,366 @@ where
             split_dim = (split_dim + 1).rem(K);
         }
 
-        let leaf_idx = stem_idx - IDX::leaf_offset();
+        node_idx - IDX::leaf_offset()
+    }
+
+    /// Returns the index of a leaf below `node_idx` that holds `item` at `query`, pushing the
+    /// stems on the way down to it onto `path`, along with whether their left child was taken.
+    ///
+    /// Items whose position on a stem's split dimension is the same as its split value belong
+    /// to its right, but may also have ended up to its left if there were more of them than
+    /// would fit into a single leaf.
+    fn find_item(
+        &self,
+        query: &[A; K],
+        item: T,
+        node_idx: IDX,
+        split_dim: usize,
+        path: &mut DescentPath<IDX>,
+    ) -> Option<IDX> {
+        if !is_stem_index(node_idx) {
+            let leaf_idx = node_idx - IDX::leaf_offset();
+            let leaf_node = &self.leaves[leaf_idx.az::<usize>()];
+            let holds_item = leaf_node
+                .content_points
+                .iter()
+                .zip(leaf_node.content_items.iter())
+                .take(leaf_node.size.az::<usize>())
+                .any(|(point, &stored_item)| point == query && stored_item == item);
+
+            return holds_item.then_some(leaf_idx);
+        }
+
+        let dim = self.stem_split_dim(node_idx, split_dim);
+        let stem_node = &self.stems[node_idx.az::<usize>()];
+        let goes_left = query[dim] < stem_node.split_val;
+        let on_split = query[dim] == stem_node.split_val;
+        let next_split_dim = (split_dim + 1).rem(K);
+
+        for (is_left_child, child_idx, could_hold_item) in [
+            (false, stem_node.right, !goes_left),
+            (true, stem_node.left, goes_left || on_split),
+        ] {
+            if could_hold_item {
+                path.push((node_idx, is_left_child));
+                if let Some(leaf_idx) = self.find_item(query, item, child_idx, next_split_dim, path)
+                {
+                    return Some(leaf_idx);
+                }
+                path.pop();
+            }
+        }
+
+        None
+    }
+
+    /// Pushes the stems on the way down from `node_idx` to the leaf at `leaf_idx` onto `path`,
+    /// along with whether their left child was taken, following `query`, which lies in that
+    /// leaf, down to it as [`find_item`](`KdTree::find_item`) does. Returns `false` if
+    /// the leaf is not below `node_idx`.
+    fn find_path_to_leaf(
+        &self,
+        query: &[A; K],
+        leaf_idx: IDX,
+        node_idx: IDX,
+        split_dim: usize,
+        path: &mut DescentPath<IDX>,
+    ) -> bool {
+        if !is_stem_index(node_idx) {
+            return node_idx - IDX::leaf_offset() == leaf_idx;
+        }
+
+        let dim = self.stem_split_dim(node_idx, split_dim);
+        let stem_node = &self.stems[node_idx.az::<usize>()];
+        let goes_left = query[dim] < stem_node.split_val;
+        let on_split = query[dim] == stem_node.split_val;
+        let next_split_dim = (split_dim + 1).rem(K);
+
+        for (is_left_child, child_idx, could_hold_leaf) in [
+            (false, stem_node.right, !goes_left),
+            (true, stem_node.left, goes_left || on_split),
+        ] {
+            if could_hold_leaf {
+                path.push((node_idx, is_left_child));
+                if self.find_path_to_leaf(query, leaf_idx, child_idx, next_split_dim, path) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+
+        false
+    }
+
+    /// Merges the leaf at `leaf_idx`, which is reached from the root by following `path`,
+    /// into its sibling, for as long as that sibling is also a leaf and the contents of both
+    /// fit into a single leaf, unlinking the stem that was their parent. The freed stem and
+    /// leaf are kept for re-use by later splits.
+    ///
+    /// Returns the index of the leaf that holds the merged contents. `on_move` is called with
+    /// each item moved out of a sibling, along with that index.
+    fn merge_with_siblings(
+        &mut self,
+        path: &mut DescentPath<IDX>,
+        leaf_idx: IDX,
+        on_move: &mut impl FnMut(T, IDX),
+    ) -> IDX {
+        while let Some(&(parent_idx, is_left_child)) = path.last() {
+            let parent_node = &self.stems[parent_idx.az::<usize>()];
+            let sibling_idx = if is_left_child {
+                parent_node.right
+            } else {
+                parent_node.left
+            };
+            if is_stem_index(sibling_idx) {
+                break;
+            }
+            let sibling_idx = sibling_idx - IDX::leaf_offset();
+
+            let leaf_size = self.leaves[leaf_idx.az::<usize>()].size.az::<usize>();
+            let sibling_size = self.leaves[sibling_idx.az::<usize>()].size.az::<usize>();
+            if leaf_size + sibling_size > B {
+                break;
+            }
+
+            for idx in 0..sibling_size {
+                let sibling = &self.leaves[sibling_idx.az::<usize>()];
+                let (point, item) = (sibling.content_points[idx], sibling.content_items[idx]);
+
+                let leaf = &mut self.leaves[leaf_idx.az::<usize>()];
+                leaf.content_points[leaf_size + idx] = point;
+                leaf.content_items[leaf_size + idx] = item;
+                on_move(item, leaf_idx);
+            }
+            self.leaves[leaf_idx.az::<usize>()].size = (leaf_size + sibling_size).az::<IDX>();
+
+            // the sibling no longer holds anything, and is no longer part of the tree
+            self.leaves[sibling_idx.az::<usize>()].size = IDX::zero();
+            if self.rebalance_policy.is_some() && sibling_size == 0 {
+                self.empty_leaves -= 1;
+            }
+
+            path.pop();
+            let node_idx = leaf_idx + IDX::leaf_offset();
+            match path.last() {
+                Some(&(grandparent_idx, true)) => {
+                    self.stems[grandparent_idx.az::<usize>()].left = node_idx;
+                }
+                Some(&(grandparent_idx, false)) => {
+                    self.stems[grandparent_idx.az::<usize>()].right = node_idx;
+                }
+                None => self.root_index = node_idx,
+            }
+
+            self.free_stems.push(parent_idx);
+            self.free_leaves.push(sibling_idx);
+        }
+
+        leaf_idx
+    }
+
+    /// Returns the number of leaves that are part of the tree
+    #[inline]
+    fn live_leaf_count(&self) -> usize {
+        self.leaves.len() - self.free_leaves.len()
+    }
+
+    /// Stores `leaf`, re-using the slot of a leaf that was freed by a merge if there is one,
+    /// and returns its index
+    fn push_leaf(&mut self, leaf: LeafNode<A, T, K, B, IDX>) -> IDX {
+        if let Some(leaf_idx) = self.free_leaves.pop() {
+            self.leaves[leaf_idx.az::<usize>()] = leaf;
+            leaf_idx
+        } else {
+            self.leaves.push(leaf);
+            (self.leaves.len() - 1).az::<IDX>()
+        }
+    }
+
+    /// Stores `stem`, re-using the slot of a stem that was freed by a merge if there is one,
+    /// and returns its index
+    fn push_stem(&mut self, stem: StemNode<A, K, IDX>, split_dim: usize) -> IDX {
+        if let Some(stem_idx) = self.free_stems.pop() {
+            self.stems[stem_idx.az::<usize>()] = stem;
+            if self.split_axis == SplitAxis::WidestSpread {
+                self.split_dims[stem_idx.az::<usize>()] = split_dim as u8;
+            }
+            stem_idx
+        } else {
+            self.stems.push(stem);
+            if self.split_axis == SplitAxis::WidestSpread {
+                self.split_dims.push(split_dim as u8);
+            }
+            (self.stems.len() - 1).az::<IDX>()
+        }
+    }
+
+    /// Re-partitions the contents of the tree around their medians, in the same way as
+    /// [`new_balanced_from`](`KdTree::new_balanced_from`), discarding any empty leaves and
+    /// evening out the depth of the tree. The existing stem and leaf allocations are re-used.
+    ///
+    /// Trees that have had many items added and removed can end up deep, lopsided and
+    /// with many empty leaves, since [`remove`](`KdTree::remove`) only merges leaves
+    /// whose sibling is also a leaf.
+    /// See also [`set_rebalance_policy`](`KdTree::set_rebalance_policy`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the balanced tree would need more stems or leaves than `IDX` can index,
+    /// which can only happen for trees that hold nearly as many items as `IDX` supports.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, 2> = KdTree::new();
+    /// for i in 0..1000 {
+    ///     tree.add(&[i as f64, (i * 7919 % 10_007) as f64], i);
+    /// }
+    /// for i in 0..900 {
+    ///     tree.remove(&[i as f64, (i * 7919 % 10_007) as f64], i);
+    /// }
+    ///
+    /// tree.rebalance();
+    ///
+    /// assert_eq!(tree.size(), 100);
+    /// assert_eq!(tree.stats().leaf_occupancy[0], 0);
+    /// ```
+    pub fn rebalance(&mut self) {
+        self.rebalance_tracking_moves(&mut |_, _| {});
+    }
+
+    /// Rebalances the tree as [`rebalance`](`KdTree::rebalance`) does, calling `on_move` with
+    /// every item in the tree along with the index of the leaf that it ends up in.
+    pub(crate) fn rebalance_tracking_moves(&mut self, on_move: &mut impl FnMut(T, IDX)) {
+        // pack every item to the front of the leaf storage. Items only ever move
+        // to an earlier position, so none get overwritten before they have moved
+        let mut len = 0;
+        for leaf_idx in 0..self.leaves.len() {
+            for slot in 0..self.leaves[leaf_idx].size.az::<usize>() {
+                let point = self.leaves[leaf_idx].content_points[slot];
+                let item = self.leaves[leaf_idx].content_items[slot];
+                self.leaves[len / B].content_points[len % B] = point;
+                self.leaves[len / B].content_items[len % B] = item;
+                len += 1;
+            }
+        }
+
+        if let Err(err) = self.populate_from_packed_leaves(len) {
+            panic!("{err}");
+        }
+
+        for (leaf_idx, leaf) in self.leaves.iter().enumerate() {
+            for &item in &leaf.content_items[..leaf.size.az::<usize>()] {
+                on_move(item, leaf_idx.az::<IDX>());
+            }
+        }
+    }
+
+    /// Sets the policy that determines when the tree automatically
+    /// [rebalances](`KdTree::rebalance`) itself as items get added and removed,
+    /// or stops it from doing so if `policy` is `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::float::kdtree::RebalancePolicy;
+    ///
+    /// let mut tree: KdTree<f64, 2> = KdTree::new();
+    /// tree.set_rebalance_policy(Some(RebalancePolicy::default()));
+    ///
+    /// // sorted input would otherwise leave the tree very lopsided
+    /// for i in 0..10_000 {
+    ///     tree.add(&[i as f64, (i * 7919 % 10_007) as f64], i);
+    /// }
+    ///
+    /// let stats = tree.stats();
+    /// assert!(stats.max_depth as f32 <= 2.0 * stats.leaf_count.next_power_of_two().ilog2() as f32);
+    /// ```
+    pub fn set_rebalance_policy(&mut self, policy: Option<RebalancePolicy>) {
+        self.rebalance_policy = policy;
+        self.empty_leaves = self
+            .leaves
+            .iter()
+            .filter(|leaf| leaf.size == IDX::zero())
+            .count()
+            - self.free_leaves.len();
+    }
 
-        if let Some(leaf_node) = self.leaves.get_mut(leaf_idx.az::<usize>()) {
-            let mut p_index = 0;
-            while p_index < leaf_node.size.az::<usize>() {
-                if &leaf_node.content_points[p_index] == query
-                    && leaf_node.content_items[p_index] == item
-                {
-                    leaf_node.content_points[p_index] =
-                        leaf_node.content_points[leaf_node.size.az::<usize>() - 1];
-                    leaf_node.content_items[p_index] =
-                        leaf_node.content_items[leaf_node.size.az::<usize>() - 1];
+    /// Returns the dimension that the stem at `stem_idx` splits on, given the dimension
+    /// that round-robin axis selection would have used at its depth
+    #[inline]
+    fn stem_split_dim(&self, stem_idx: IDX, round_robin_dim: usize) -> usize {
+        if self.split_dims.is_empty() {
+            round_robin_dim
+        } else {
+            self.split_dims[stem_idx.az::<usize>()] as usize
+        }
+    }
 
-                    self.size -= T::one();
-                    removed += 1;
-                    leaf_node.size = leaf_node.size - IDX::one();
-                } else {
-                    p_index += 1;
-                }
+    /// Returns the dimension along which `points` are most spread out
+    fn widest_spread_dim<'a>(points: impl IntoIterator<Item = &'a [A; K]> + Clone) -> usize
+    where
+        A: 'a,
+    {
+        let mut widest_dim = 0;
+        let mut widest_spread = A::zero();
+
+        for dim in 0..K {
+            let (min, max) = points
+                .clone()
+                .into_iter()
+                .fold((A::infinity(), A::neg_infinity()), |(min, max), point| {
+                    (min.min(point[dim]), max.max(point[dim]))
+                });
+
+            if max - min > widest_spread {
+                widest_dim = dim;
+                widest_spread = max - min;
             }
         }
 
-        removed
+        widest_dim
     }
 
+    /// Splits the full leaf at `leaf_idx` in two, under a new stem that takes its place
+    /// as the child of `parent_idx`.
+    ///
+    /// Items whose position on the split dimension is less than the split value go to the left
+    /// of the new stem, and items whose position is greater go to the right. Items whose position
+    /// is equal to the split value normally go to the right as well, but if every item in the leaf
+    /// shares the same position on the split dimension, there is no split value that would
+    /// separate them, so they are divided evenly between both children instead.
+    ///
+    /// The left child of a stem can therefore hold items that lie exactly on its split plane.
+    /// Queries still find these, since the distance from a point on the plane to the other side
+    /// is zero, but anything that looks for an item at a given position must search both
+    /// children when the position lies on the split plane, as [`find_item`](`KdTree::find_item`) does.
+    ///
+    /// `on_move` is called with each item that moves to the new right-hand leaf, along with
+    /// its index.
     unsafe fn split(
         &mut self,
         leaf_idx: IDX,
         split_dim: usize,
         parent_idx: IDX,
         was_parents_left: bool,
-    ) -> IDX {
+        on_move: &mut impl FnMut(T, IDX),
+    ) -> Result<IDX, KiddoError> {
+        if (self.free_leaves.is_empty()
+            && self.leaves.len() >= <IDX as Index>::capacity_with_bucket_size(1))
+            || (self.free_stems.is_empty() && self.stems.len() >= IDX::leaf_offset().az::<usize>())
+        {
+            return Err(KiddoError::IndexOverflow);
+        }
+
         let orig = self.leaves.get_unchecked_mut(leaf_idx.az::<usize>());
+        let split_dim = match self.split_axis {
+            SplitAxis::RoundRobin => split_dim,
+            SplitAxis::WidestSpread => Self::widest_spread_dim(&orig.content_points),
+        };
         let mut pivot_idx = (B / 2).az::<IDX>();
 
         mirror_select_nth_unstable_by(
@@ -211,17 +1176,22 @@ where
                 );
 
                 pivot_idx = orig_pivot_idx;
-                while *orig
-                    .content_points
-                    .get_unchecked(pivot_idx.az::<usize>())
-                    .get_unchecked(split_dim)
-                    == split_val
+                while pivot_idx.az::<usize>() < B
+                    && *orig
+                        .content_points
+                        .get_unchecked(pivot_idx.az::<usize>())
+                        .get_unchecked(split_dim)
+                        == split_val
                 {
                     pivot_idx = pivot_idx + IDX::one();
+                }
 
-                    if pivot_idx.az::<usize>() == B {
-                        panic!("Too many items with the same position on one axis. Bucket size must be increased to at least 1 more than the number of items with the same position on one axis.");
-                    }
+                // If every item in the bucket has the same position on the split dimension,
+                // split it down the middle anyway, with the split value being that position.
+                // Items on either side of this split lie on the split plane, so queries still
+                // find them, and further items at that position keep going to the right
+                if pivot_idx.az::<usize>() == B {
+                    pivot_idx = orig_pivot_idx;
                 }
             }
 
@@ -252,14 +1222,22 @@ where
 
         right.size = (B.az::<IDX>()) - pivot_idx;
 
-        self.leaves.push(right);
+        let right_leaf_idx = self.push_leaf(right);
+        for &item in self.leaves[right_leaf_idx.az::<usize>()]
+            .content_items
+            .get_unchecked(..dest_slice_end)
+        {
+            on_move(item, right_leaf_idx);
+        }
 
-        self.stems.push(StemNode {
-            left: leaf_idx + IDX::leaf_offset(),
-            right: (self.leaves.len().az::<IDX>()) - IDX::one() + IDX::leaf_offset(),
-            split_val,
-        });
-        let new_stem_index: IDX = (self.stems.len().az::<IDX>()) - IDX::one();
+        let new_stem_index = self.push_stem(
+            StemNode {
+                left: leaf_idx + IDX::leaf_offset(),
+                right: right_leaf_idx + IDX::leaf_offset(),
+                split_val,
+            },
+            split_dim,
+        );
 
         if parent_idx != <IDX as Index>::max() {
             let parent_node = self.stems.get_unchecked_mut(parent_idx.az::<usize>());
@@ -272,7 +1250,7 @@ where
             self.root_index = new_stem_index;
         }
 
-        new_stem_index
+        Ok(new_stem_index)
     }
 }
 
@@ -336,10 +1314,56 @@ where
     }
 }
 
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>> PackedContent<A, K>
+    for Vec<LeafNode<A, T, K, B, IDX>>
+where
+    usize: Cast<IDX>,
+{
+    fn point(&self, idx: usize) -> &[A; K] {
+        &self[idx / B].content_points[idx % B]
+    }
+
+    fn swap_content(&mut self, a: usize, b: usize) {
+        let (a, b) = (a.min(b), a.max(b));
+        let (a_leaf, b_leaf) = (a / B, b / B);
+        if a_leaf == b_leaf {
+            self[a_leaf].content_points.swap(a % B, b % B);
+            self[a_leaf].content_items.swap(a % B, b % B);
+        } else {
+            let (head, tail) = self.split_at_mut(b_leaf);
+            std::mem::swap(
+                &mut head[a_leaf].content_points[a % B],
+                &mut tail[0].content_points[b % B],
+            );
+            std::mem::swap(
+                &mut head[a_leaf].content_items[a % B],
+                &mut tail[0].content_items[b % B],
+            );
+        }
+    }
+
+    fn select_nth_by<F>(&mut self, range: Range<usize>, nth: usize, compare: F)
+    where
+        F: FnMut(&[A; K], &[A; K]) -> Ordering,
+    {
+        select_nth_by(self, range, nth, compare);
+    }
+
+    fn set_leaf_size(&mut self, leaf_idx: usize, size: usize) {
+        if leaf_idx == self.len() {
+            self.push(LeafNode::new());
+        }
+        self[leaf_idx].size = size.az::<IDX>();
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::float::kdtree::KdTree;
-    use rand::Rng;
+    use crate::error::KiddoError;
+    use crate::float::kdtree::{KdTree, RebalancePolicy};
+    use crate::split_axis::SplitAxis;
+    use crate::traits::Index;
+    use rand::{Rng, SeedableRng};
 
     type Flt = f32;
 
@@ -544,4 +1568,640 @@ mod tests {
             assert_eq!(tree.remove(pt, i), 1, "failed to remove point {i}");
         }
     }
+
+    #[test]
+    fn widest_spread_splits_along_the_widest_axis() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(17);
+        let points: Vec<[f64; 3]> = (0..5_000)
+            .map(|_| {
+                [
+                    rng.gen_range(0f64..4096f64),
+                    rng.gen_range(0f64..4f64),
+                    rng.gen_range(0f64..4096f64),
+                ]
+            })
+            .collect();
+
+        let mut tree: KdTree<f64, u32, 3, 32, u32> =
+            KdTree::with_capacity_and_split_axis(points.len(), SplitAxis::WidestSpread);
+        for (idx, point) in points.iter().enumerate() {
+            tree.add(point, idx as u32);
+        }
+
+        assert_eq!(tree.size(), 5_000);
+        assert_eq!(tree.split_dims.len(), tree.stems.len());
+        assert!(tree.split_dims.iter().all(|&dim| dim != 1));
+
+        for (idx, point) in points.iter().enumerate() {
+            assert_eq!(
+                tree.remove(point, idx as u32),
+                1,
+                "failed to remove point {idx}"
+            );
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn round_robin_does_not_store_split_dims() {
+        let mut tree: KdTree<f64, u32, 3, 4, u32> = KdTree::new();
+        for idx in 0..100 {
+            tree.add(
+                &[idx as f64, (idx * 7 % 100) as f64, (idx * 13 % 100) as f64],
+                idx,
+            );
+        }
+
+        assert_eq!(tree.split_axis(), SplitAxis::RoundRobin);
+        assert!(!tree.stems.is_empty());
+        assert!(tree.split_dims.is_empty());
+    }
+
+    #[test]
+    fn new_balanced_from_gives_same_results_as_adding_points() {
+        use crate::SquaredEuclidean;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(19);
+        let points: Vec<[f64; 3]> = (0..10_000)
+            .map(|_| [rng.gen(), rng.gen(), rng.gen()])
+            .collect();
+        let items: Vec<u32> = (0..10_000).collect();
+
+        let balanced: KdTree<f64, u32, 3, 32, u32> = KdTree::new_balanced_from(&points, &items);
+        let mut added: KdTree<f64, u32, 3, 32, u32> = KdTree::with_capacity(points.len());
+        for (point, &item) in points.iter().zip(&items) {
+            added.add(point, item);
+        }
+
+        assert_eq!(balanced.size(), 10_000);
+        for _ in 0..100 {
+            let query = [rng.gen(), rng.gen(), rng.gen()];
+            assert_eq!(
+                balanced.nearest_n::<SquaredEuclidean>(&query, 10),
+                added.nearest_n::<SquaredEuclidean>(&query, 10)
+            );
+        }
+    }
+
+    #[test]
+    fn new_balanced_from_is_balanced_for_sorted_input() {
+        let points: Vec<[f64; 3]> = (0..16)
+            .flat_map(|x| {
+                (0..16).flat_map(move |y| (0..16).map(move |z| [x as f64, y as f64, z as f64]))
+            })
+            .collect();
+        let items: Vec<u32> = (0..points.len() as u32).collect();
+
+        let tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new_balanced_from(&points, &items);
+        let stats = tree.stats();
+
+        assert_eq!(stats.size, 4096);
+        assert_eq!(stats.leaf_count, 128);
+        assert_eq!(stats.min_depth, 7);
+        assert_eq!(stats.max_depth, 7);
+    }
+
+    #[test]
+    fn new_balanced_from_keeps_equal_positions_on_the_same_side_of_a_split() {
+        let points: Vec<[f64; 2]> = (0..40).map(|i| [(i % 2) as f64, i as f64]).collect();
+        let items: Vec<u32> = (0..40).collect();
+
+        let mut tree: KdTree<f64, u32, 2, 4, u32> = KdTree::new_balanced_from(&points, &items);
+
+        for (point, &item) in points.iter().zip(&items) {
+            assert_eq!(tree.remove(point, item), 1, "failed to remove point {item}");
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn new_balanced_from_tree_accepts_additions_and_removals() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(23);
+        let points: Vec<[f64; 2]> = (0..2_000).map(|_| [rng.gen(), rng.gen()]).collect();
+        let (initial, added) = points.split_at(500);
+        let items: Vec<u32> = (0..500).collect();
+
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::new_balanced_from(initial, &items);
+        for (idx, point) in added.iter().enumerate() {
+            tree.add(point, 500 + idx as u32);
+        }
+        assert_eq!(tree.size(), 2_000);
+
+        for (idx, point) in points.iter().enumerate() {
+            assert_eq!(
+                tree.remove(point, idx as u32),
+                1,
+                "failed to remove point {idx}"
+            );
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn new_balanced_from_an_empty_slice_gives_an_empty_tree() {
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::new_balanced_from(&[], &[]);
+
+        assert_eq!(tree.size(), 0);
+        tree.add(&[1.0, 2.0], 1);
+        assert_eq!(tree.size(), 1);
+    }
+
+    #[test]
+    fn removal_merges_sibling_leaves_and_reuses_freed_slots() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(41);
+        let points: Vec<[f64; 2]> = (0..1_000).map(|_| [rng.gen(), rng.gen()]).collect();
+
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::with_capacity(points.len());
+        for (idx, point) in points.iter().enumerate() {
+            tree.add(point, idx as u32);
+        }
+        for (idx, point) in points.iter().enumerate().skip(100) {
+            assert_eq!(
+                tree.remove(point, idx as u32),
+                1,
+                "failed to remove point {idx}"
+            );
+        }
+
+        let stats = tree.stats();
+        assert_eq!(stats.size, 100);
+        assert!(!tree.free_leaves.is_empty());
+        assert_eq!(stats.leaf_count, tree.leaves.len() - tree.free_leaves.len());
+        assert_eq!(stats.stem_count, tree.stems.len() - tree.free_stems.len());
+        assert_eq!(stats.leaf_count, stats.stem_count + 1);
+        assert!(tree.iter().all(|(item, _)| item < 100));
+        assert_eq!(tree.iter().count(), 100);
+
+        // new leaves only get allocated once every freed slot has been re-used
+        let leaf_slots = tree.leaves.len();
+        for (idx, point) in points.iter().enumerate().skip(100) {
+            tree.add(point, idx as u32);
+            assert!(tree.free_leaves.is_empty() || tree.leaves.len() == leaf_slots);
+        }
+        for (idx, point) in points.iter().enumerate() {
+            assert_eq!(
+                tree.remove(point, idx as u32),
+                1,
+                "failed to remove point {idx}"
+            );
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn leaf_count_stays_proportional_to_live_items_under_churn() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(43);
+        let mut live: Vec<([f64; 2], u32)> =
+            (0..500).map(|idx| ([rng.gen(), rng.gen()], idx)).collect();
+
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::with_capacity(live.len());
+        for (point, item) in &live {
+            tree.add(point, *item);
+        }
+
+        for next_item in 500..20_500 {
+            let idx = rng.gen_range(0..live.len());
+            let (point, item) = live[idx];
+            assert_eq!(tree.remove(&point, item), 1);
+
+            live[idx] = ([rng.gen(), rng.gen()], next_item);
+            tree.add(&live[idx].0, next_item);
+        }
+
+        assert_eq!(tree.size(), 500);
+        assert!(tree.leaves.len() < 4 * 500 / 8);
+        assert_eq!(tree.stats().size, 500);
+    }
+
+    #[test]
+    fn rebalance_discards_empty_leaves_and_keeps_every_item() {
+        use crate::traits::DistanceMetric;
+        use crate::SquaredEuclidean;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(29);
+        let points: Vec<[f64; 2]> = (0..5_000).map(|_| [rng.gen(), rng.gen()]).collect();
+
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::with_capacity(points.len());
+        for (idx, point) in points.iter().enumerate() {
+            tree.add(point, idx as u32);
+        }
+        for (idx, point) in points.iter().enumerate().filter(|(_, p)| p[0] < 0.8) {
+            tree.remove(point, idx as u32);
+        }
+        assert!(tree.stats().leaf_occupancy[0] > 0);
+
+        let leaves_ptr = tree.leaves.as_ptr();
+        let leaves_capacity = tree.leaves.capacity();
+        tree.rebalance();
+
+        assert_eq!(tree.leaves.as_ptr(), leaves_ptr);
+        assert_eq!(tree.leaves.capacity(), leaves_capacity);
+
+        let stats = tree.stats();
+        let remaining: Vec<_> = points
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p[0] >= 0.8)
+            .collect();
+        assert_eq!(stats.size, remaining.len());
+        assert_eq!(stats.leaf_occupancy[0], 0);
+        assert!(stats.max_depth - stats.min_depth <= 1);
+
+        for _ in 0..100 {
+            let query = [rng.gen(), rng.gen()];
+            let expected = remaining
+                .iter()
+                .map(|(_, p)| <SquaredEuclidean as DistanceMetric<f64, 2>>::dist(&query, p))
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(
+                tree.nearest_one::<SquaredEuclidean>(&query).distance,
+                expected
+            );
+        }
+
+        for (idx, point) in remaining {
+            assert_eq!(
+                tree.remove(point, idx as u32),
+                1,
+                "failed to remove point {idx}"
+            );
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn rebalance_keeps_widest_spread_split_dims() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(31);
+        let points: Vec<[f64; 3]> = (0..2_000)
+            .map(|_| {
+                [
+                    rng.gen_range(0f64..4096f64),
+                    rng.gen_range(0f64..4f64),
+                    rng.gen_range(0f64..4096f64),
+                ]
+            })
+            .collect();
+
+        let mut tree: KdTree<f64, u32, 3, 32, u32> =
+            KdTree::with_capacity_and_split_axis(points.len(), SplitAxis::WidestSpread);
+        for (idx, point) in points.iter().enumerate() {
+            tree.add(point, idx as u32);
+        }
+
+        tree.rebalance();
+
+        assert_eq!(tree.split_dims.len(), tree.stems.len());
+        assert!(tree.split_dims.iter().all(|&dim| dim != 1));
+        for (idx, point) in points.iter().enumerate() {
+            assert_eq!(
+                tree.remove(point, idx as u32),
+                1,
+                "failed to remove point {idx}"
+            );
+        }
+    }
+
+    #[test]
+    fn rebalance_policy_limits_depth_for_sorted_input() {
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::new();
+        let mut rebalanced: KdTree<f64, u32, 2, 8, u32> = KdTree::new();
+        rebalanced.set_rebalance_policy(Some(RebalancePolicy::default()));
+
+        for i in 0..5_000u32 {
+            let point = [i as f64, (i * 7919 % 10_007) as f64];
+            tree.add(&point, i);
+            rebalanced.add(&point, i);
+        }
+
+        let stats = rebalanced.stats();
+        let balanced_depth = stats.leaf_count.next_power_of_two().ilog2() as usize;
+        assert!(stats.max_depth <= 2 * balanced_depth);
+        assert!(tree.stats().max_depth > 2 * balanced_depth);
+        assert_eq!(rebalanced.size(), 5_000);
+    }
+
+    #[test]
+    fn rebalance_policy_limits_empty_leaves() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(37);
+        let points: Vec<[f64; 2]> = (0..5_000).map(|_| [rng.gen(), rng.gen()]).collect();
+
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::with_capacity(points.len());
+        tree.set_rebalance_policy(Some(RebalancePolicy {
+            max_depth_ratio: f32::INFINITY,
+            max_empty_leaf_ratio: 0.1,
+        }));
+        for (idx, point) in points.iter().enumerate() {
+            tree.add(point, idx as u32);
+        }
+        for (idx, point) in points.iter().enumerate().take(4_500) {
+            assert_eq!(tree.remove(point, idx as u32), 1);
+        }
+
+        let stats = tree.stats();
+        assert_eq!(stats.size, 500);
+        assert_eq!(tree.empty_leaves, stats.leaf_occupancy[0]);
+        assert!(stats.leaf_occupancy[0] as f32 <= 0.1 * stats.leaf_count as f32);
+        assert!(stats.leaf_count < 5_000 / 8);
+    }
+
+    #[test]
+    fn new_balanced_from_stores_more_than_b_points_at_the_same_position() {
+        use crate::SquaredEuclidean;
+
+        let points = vec![[1.0f64, 2.0]; 80];
+        let items: Vec<u32> = (0..80).collect();
+
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::new_balanced_from(&points, &items);
+
+        assert_eq!(tree.size(), 80);
+        assert_eq!(tree.within::<SquaredEuclidean>(&[1.0, 2.0], 0.1).len(), 80);
+        for item in 0..80 {
+            assert_eq!(tree.remove(&[1.0, 2.0], item), 1);
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn update_rewrites_in_place_within_a_leaf() {
+        let mut tree: KdTree<Flt, u32, 2, 8, u32> = KdTree::new();
+        tree.add(&[n(0.1), n(0.2)], 1);
+        tree.add(&[n(0.1), n(0.2)], 2);
+        let stems_len = tree.stems.len();
+
+        assert!(tree.update(&[n(0.1), n(0.2)], &[n(0.3), n(0.4)], 2));
+
+        assert_eq!(tree.size(), 2);
+        assert_eq!(tree.stems.len(), stems_len);
+        assert_eq!(tree.remove(&[n(0.1), n(0.2)], 2), 0);
+        assert_eq!(tree.remove(&[n(0.3), n(0.4)], 2), 1);
+        assert_eq!(tree.remove(&[n(0.1), n(0.2)], 1), 1);
+    }
+
+    #[test]
+    fn update_returns_false_if_item_is_not_at_old_position() {
+        let mut tree: KdTree<Flt, u32, 2, 8, u32> = KdTree::new();
+        tree.add(&[n(0.1), n(0.2)], 1);
+
+        assert!(!tree.update(&[n(0.1), n(0.2)], &[n(0.3), n(0.4)], 2));
+        assert!(!tree.update(&[n(0.5), n(0.5)], &[n(0.3), n(0.4)], 1));
+
+        assert_eq!(tree.size(), 1);
+        assert_eq!(tree.remove(&[n(0.1), n(0.2)], 1), 1);
+    }
+
+    #[test]
+    fn update_and_update_many_relocate_items_across_leaves() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(47);
+        let mut live: Vec<[f64; 2]> = (0..1_000).map(|_| [rng.gen(), rng.gen()]).collect();
+
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::with_capacity(live.len());
+        for (idx, point) in live.iter().enumerate() {
+            tree.add(point, idx as u32);
+        }
+
+        for _ in 0..5_000 {
+            let idx = rng.gen_range(0..live.len());
+            let new = [rng.gen(), rng.gen()];
+            assert!(tree.update(&live[idx], &new, idx as u32));
+            live[idx] = new;
+        }
+
+        for _ in 0..5 {
+            let updates: Vec<_> = live
+                .iter()
+                .enumerate()
+                .map(|(idx, old)| {
+                    let new = [
+                        (old[0] + rng.gen_range(-0.01..0.01)).clamp(0.0, 1.0),
+                        (old[1] + rng.gen_range(-0.01..0.01)).clamp(0.0, 1.0),
+                    ];
+                    (*old, new, idx as u32)
+                })
+                .collect();
+            assert_eq!(tree.update_many(updates.iter().copied()), live.len());
+            for (idx, (_, new, _)) in updates.into_iter().enumerate() {
+                live[idx] = new;
+            }
+        }
+
+        assert_eq!(tree.size(), 1_000);
+        assert_eq!(tree.stats().size, 1_000);
+        for (idx, point) in live.iter().enumerate() {
+            assert_eq!(tree.remove(point, idx as u32), 1);
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn try_add_reports_an_index_overflow() {
+        let mut tree: KdTree<f64, u32, 2, 2, u16> = KdTree::new();
+
+        let result = (0..100_000u32).try_for_each(|item| {
+            tree.try_add(&[(item * 7919 % 100_003) as f64, item as f64], item)
+        });
+
+        assert_eq!(result, Err(KiddoError::IndexOverflow));
+        assert!(tree.size() as usize > u16::capacity_with_bucket_size(1));
+        assert!(tree.leaves.len() <= u16::capacity_with_bucket_size(1));
+        assert_eq!(tree.stats().size, tree.size() as usize);
+    }
+
+    #[test]
+    fn try_update_leaves_the_item_in_place_when_it_cannot_be_moved() {
+        let mut tree: KdTree<f64, u32, 2, 2, u16> = KdTree::new();
+        let point = |item: u32| [(item * 7919 % 100_003) as f64, item as f64];
+
+        let mut added = 0;
+        while tree.try_add(&point(added), added).is_ok() {
+            added += 1;
+        }
+        let size = tree.size();
+
+        // the leaf that the last point belongs in is full and can't be split
+        let full = point(added);
+        let failed = (0..added)
+            .filter(|&item| {
+                let result = tree.try_update(&point(item), &full, item);
+                if result.is_ok() {
+                    assert_eq!(tree.try_update(&full, &point(item), item), Ok(true));
+                }
+                result == Err(KiddoError::IndexOverflow)
+            })
+            .count();
+
+        assert!(failed > 0);
+        assert_eq!(tree.size(), size);
+        assert_eq!(tree.stats().size, size as usize);
+        for item in 0..added {
+            assert_eq!(tree.remove(&point(item), item), 1);
+        }
+    }
+
+    #[test]
+    fn try_update_many_leaves_the_unmoved_items_in_place_when_one_cannot_be_moved() {
+        let mut tree: KdTree<f64, u32, 2, 2, u16> = KdTree::new();
+        let point = |item: u32| [(item * 7919 % 100_003) as f64, item as f64];
+
+        let mut added = 0;
+        while tree.try_add(&point(added), added).is_ok() {
+            added += 1;
+        }
+
+        // make room for item 0 to move to where item 1 was, whilst the leaf that the
+        // last point belongs in is full and can't be split
+        assert_eq!(tree.remove(&point(1), 1), 1);
+        let full = point(added);
+        let updates = (2..added).map(|item| (point(item), full, item));
+        let result = tree.try_update_many([(point(0), point(1), 0)].into_iter().chain(updates));
+
+        assert_eq!(result, Err(KiddoError::IndexOverflow));
+        assert_eq!(tree.size(), added - 1);
+        assert_eq!(tree.stats().size, added as usize - 1);
+        assert_eq!(tree.remove(&point(1), 0), 1);
+
+        let mut unmoved = 0;
+        for item in 2..added {
+            if tree.remove(&full, item) == 0 {
+                assert_eq!(tree.remove(&point(item), item), 1);
+                unmoved += 1;
+            }
+        }
+        assert!(unmoved > 0);
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn try_add_succeeds_past_b_items_at_the_same_position() {
+        let mut tree: KdTree<f64, u32, 2, 4, u32> = KdTree::new();
+
+        for item in 0..40 {
+            assert_eq!(tree.try_add(&[1.0, 2.0], item), Ok(()));
+            assert_eq!(tree.try_add(&[1.0, item as f64], 100 + item), Ok(()));
+        }
+
+        assert_eq!(tree.size(), 80);
+        assert_eq!(tree.stats().size, 80);
+    }
+
+    #[test]
+    fn can_store_and_find_10x_b_items_at_the_same_position() {
+        use crate::SquaredEuclidean;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(59);
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::new();
+        for item in 0..500 {
+            tree.add(&[rng.gen(), rng.gen()], 1_000 + item);
+        }
+        for item in 0..80 {
+            tree.add(&[0.5, 0.5], item);
+        }
+        assert_eq!(tree.size(), 580);
+
+        for rebalanced in [false, true] {
+            if rebalanced {
+                tree.rebalance();
+            }
+
+            let mut found: Vec<u32> = tree
+                .within::<SquaredEuclidean>(&[0.5, 0.5], 1e-12)
+                .into_iter()
+                .map(|neighbour| neighbour.item)
+                .collect();
+            found.sort_unstable();
+            assert_eq!(found, (0..80).collect::<Vec<_>>());
+
+            let nearest = tree.nearest_n::<SquaredEuclidean>(&[0.5, 0.5], 80);
+            assert_eq!(nearest.len(), 80);
+            assert!(nearest.iter().all(|neighbour| neighbour.item < 80));
+        }
+
+        for item in 0..40 {
+            assert!(tree.update(&[0.5, 0.5], &[0.25, 0.75], item));
+        }
+        for item in 0..40 {
+            assert_eq!(tree.remove(&[0.25, 0.75], item), 1);
+        }
+        for item in 40..80 {
+            assert_eq!(tree.remove(&[0.5, 0.5], item), 1);
+        }
+        assert_eq!(tree.size(), 500);
+        assert_eq!(tree.stats().size, 500);
+    }
+
+    #[test]
+    fn can_store_10x_b_items_that_share_a_position_on_one_axis() {
+        use crate::SquaredEuclidean;
+
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::new();
+        for item in 0..80 {
+            tree.add(&[0.5, item as f64], item);
+        }
+
+        assert_eq!(
+            tree.within::<SquaredEuclidean>(&[0.5, 0.0], 10_000.0).len(),
+            80
+        );
+        for item in 0..80 {
+            assert_eq!(tree.remove(&[0.5, item as f64], item), 1);
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn queries_and_remove_find_more_than_b_items_split_either_side_of_a_stem() {
+        use crate::float::distance::SquaredEuclidean;
+        use crate::traits::{is_stem_index, DistanceMetric};
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(61);
+        let mut tree: KdTree<f64, u32, 2, 8, u32> = KdTree::new();
+
+        // all of these share a position on the root's split dimension, so the root leaf
+        // gets split down the middle, leaving some of them to the left of the split plane
+        let mut content: Vec<([f64; 2], u32)> =
+            (0..40).map(|item| ([0.5, rng.gen()], item)).collect();
+        content.extend((40..540).map(|item| ([rng.gen(), rng.gen()], item)));
+        for (point, item) in &content {
+            tree.add(point, *item);
+        }
+
+        let root = &tree.stems[tree.root_index as usize];
+        assert_eq!(root.split_val, 0.5);
+
+        let mut on_plane_to_left = 0;
+        let mut to_visit = vec![root.left];
+        while let Some(node_idx) = to_visit.pop() {
+            if is_stem_index(node_idx) {
+                let stem = &tree.stems[node_idx as usize];
+                to_visit.extend([stem.left, stem.right]);
+            } else {
+                let leaf = &tree.leaves[(node_idx - u32::leaf_offset()) as usize];
+                on_plane_to_left += leaf.content_points[..leaf.size as usize]
+                    .iter()
+                    .filter(|point| point[0] == 0.5)
+                    .count();
+            }
+        }
+        assert!(on_plane_to_left > 0);
+
+        while !content.is_empty() {
+            for _ in 0..10 {
+                let query = [0.5 + rng.gen_range(-0.01..0.01), rng.gen()];
+                let dists: Vec<f64> = content
+                    .iter()
+                    .map(|(point, _)| SquaredEuclidean::dist(&query, point))
+                    .collect();
+
+                let nearest = tree.nearest_one::<SquaredEuclidean>(&query);
+                let expected = dists.iter().copied().fold(f64::INFINITY, f64::min);
+                assert_eq!(nearest.distance, expected);
+
+                let within = tree.within::<SquaredEuclidean>(&query, 0.01);
+                assert_eq!(within.len(), dists.iter().filter(|&&d| d < 0.01).count());
+            }
+
+            let (point, item) = content.swap_remove(0);
+            assert_eq!(tree.remove(&point, item), 1);
+            assert_eq!(tree.size() as usize, content.len());
+        }
+    }
 }
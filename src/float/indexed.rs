@@ -0,0 +1,420 @@
+//! A wrapper around the float [`KdTree`] that keeps track of where each item is stored,
+//! so that items can be looked up or removed without knowing their position.
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Deref;
+
+use az::Cast;
+
+use crate::float::kdtree::{Axis, KdTree};
+use crate::traits::{Content, Index};
+
+/// Floating point k-d tree with a reverse index from item to position and leaf
+///
+/// Items must be unique: each item can only be stored at one position at a time.
+///
+/// The reverse index maps each item to its position and to the leaf that holds it, and is
+/// kept up to date as the wrapped tree moves items between leaves when it splits a leaf,
+/// merges leaves after a removal, or [rebalances](`IndexedKdTree::rebalance`) itself.
+/// [`contains_item`](`IndexedKdTree::contains_item`) and [`position_of`](`IndexedKdTree::position_of`)
+/// are O(1), and [`remove_item`](`IndexedKdTree::remove_item`) finds the item in its leaf in O(1)
+/// too. Merging that leaf with its sibling afterwards, as [`remove`](`KdTree::remove`) does, still
+/// follows the item's position down to the leaf to find the stems above it.
+///
+/// All of the query methods of the wrapped [`KdTree`] are available through [`Deref`].
+/// Adding and removing items must go through the methods on `IndexedKdTree`
+/// so that the reverse index stays up to date.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::float::indexed::IndexedKdTree;
+/// use kiddo::SquaredEuclidean;
+///
+/// let mut tree: IndexedKdTree<f64, u64, 3, 32, u32> = IndexedKdTree::new();
+///
+/// tree.add(&[1.0, 2.0, 5.0], 100);
+/// tree.add(&[2.0, 3.0, 6.0], 101);
+///
+/// assert_eq!(tree.position_of(100), Some([1.0, 2.0, 5.0]));
+/// assert_eq!(tree.nearest_one::<SquaredEuclidean>(&[2.0, 3.0, 6.0]).item, 101);
+///
+/// assert_eq!(tree.remove_item(101), Some([2.0, 3.0, 6.0]));
+/// assert!(!tree.contains_item(101));
+/// assert_eq!(tree.size(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct IndexedKdTree<A: Copy + Default, T: Copy + Default, const K: usize, const B: usize, IDX>
+{
+    tree: KdTree<A, T, K, B, IDX>,
+    entries: HashMap<T, Entry<A, K, IDX>>,
+}
+
+/// Where an item of an [`IndexedKdTree`] is stored
+#[derive(Clone, Copy, Debug)]
+struct Entry<A, const K: usize, IDX> {
+    position: [A; K],
+    leaf_idx: IDX,
+}
+
+impl<A, T, const K: usize, const B: usize, IDX> Default for IndexedKdTree<A, T, K, B, IDX>
+where
+    A: Axis,
+    T: Content + Hash,
+    IDX: Index<T = IDX>,
+    usize: Cast<IDX>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A, T, const K: usize, const B: usize, IDX> IndexedKdTree<A, T, K, B, IDX>
+where
+    A: Axis,
+    T: Content + Hash,
+    IDX: Index<T = IDX>,
+    usize: Cast<IDX>,
+{
+    /// Creates a new, empty IndexedKdTree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::indexed::IndexedKdTree;
+    ///
+    /// let tree: IndexedKdTree<f64, u64, 3, 32, u32> = IndexedKdTree::new();
+    ///
+    /// assert_eq!(tree.size(), 0);
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_capacity(B * 10)
+    }
+
+    /// Creates a new, empty IndexedKdTree with capacity reserved for a specific number of items.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::indexed::IndexedKdTree;
+    ///
+    /// let tree: IndexedKdTree<f64, u64, 3, 32, u32> = IndexedKdTree::with_capacity(1_000);
+    ///
+    /// assert_eq!(tree.size(), 0);
+    /// ```
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            tree: KdTree::with_capacity(capacity),
+            entries: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Adds an item to the tree at `query`.
+    ///
+    /// If `item` is already in the tree, it is moved to `query` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::indexed::IndexedKdTree;
+    ///
+    /// let mut tree: IndexedKdTree<f64, u64, 3, 32, u32> = IndexedKdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100);
+    /// tree.add(&[1.1, 2.1, 5.1], 100);
+    ///
+    /// assert_eq!(tree.size(), 1);
+    /// assert_eq!(tree.position_of(100), Some([1.1, 2.1, 5.1]));
+    /// ```
+    pub fn add(&mut self, query: &[A; K], item: T) {
+        let Self { tree, entries } = self;
+
+        // an item that is moved within its leaf isn't reported as moving, so it keeps its leaf
+        let leaf_idx = entries
+            .get(&item)
+            .map_or(IDX::zero(), |entry| entry.leaf_idx);
+        let old = entries.insert(
+            item,
+            Entry {
+                position: *query,
+                leaf_idx,
+            },
+        );
+        let mut on_move = record_moves(entries);
+
+        let result = match &old {
+            Some(old) => tree
+                .update_tracking_moves(&old.position, query, item, &mut on_move)
+                .map(|_| ()),
+            None => tree.try_add_tracking_moves(query, item, &mut on_move),
+        };
+        drop(on_move);
+
+        if let Err(err) = result {
+            // the tree still holds the item where it was before, if anywhere
+            match old {
+                Some(old) => {
+                    if let Some(entry) = entries.get_mut(&item) {
+                        entry.position = old.position;
+                    }
+                }
+                None => {
+                    entries.remove(&item);
+                }
+            }
+            panic!("{err}");
+        }
+    }
+
+    /// Removes an item from the tree, returning the position that it was stored at,
+    /// or `None` if it was not in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::indexed::IndexedKdTree;
+    ///
+    /// let mut tree: IndexedKdTree<f64, u64, 3, 32, u32> = IndexedKdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100);
+    ///
+    /// assert_eq!(tree.remove_item(100), Some([1.0, 2.0, 5.0]));
+    /// assert_eq!(tree.remove_item(100), None);
+    /// assert_eq!(tree.size(), 0);
+    /// ```
+    pub fn remove_item(&mut self, item: T) -> Option<[A; K]> {
+        let Self { tree, entries } = self;
+        let entry = entries.remove(&item)?;
+        let removed = tree.remove_from_leaf_tracking_moves(
+            &entry.position,
+            item,
+            entry.leaf_idx,
+            &mut record_moves(entries),
+        ) || {
+            // the recorded leaf should always be up to date, but if it isn't, fall back
+            // to searching for the item from the root rather than leaving it in the tree
+            tree.remove_tracking_moves(&entry.position, item, &mut record_moves(entries)) > 0
+        };
+
+        removed.then_some(entry.position)
+    }
+
+    /// Rebuilds the wrapped tree so that it is balanced, as [`KdTree::rebalance`] does,
+    /// keeping the reverse index up to date.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::indexed::IndexedKdTree;
+    ///
+    /// let mut tree: IndexedKdTree<f64, u64, 2, 32, u32> = IndexedKdTree::new();
+    /// for item in 0..1_000 {
+    ///     tree.add(&[item as f64, 0.0], item);
+    /// }
+    ///
+    /// tree.rebalance();
+    ///
+    /// assert_eq!(tree.remove_item(500), Some([500.0, 0.0]));
+    /// assert_eq!(tree.size(), 999);
+    /// ```
+    pub fn rebalance(&mut self) {
+        let Self { tree, entries } = self;
+        tree.rebalance_tracking_moves(&mut record_moves(entries));
+    }
+
+    /// Returns `true` if `item` is in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::indexed::IndexedKdTree;
+    ///
+    /// let mut tree: IndexedKdTree<f64, u64, 3, 32, u32> = IndexedKdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100);
+    ///
+    /// assert!(tree.contains_item(100));
+    /// assert!(!tree.contains_item(101));
+    /// ```
+    #[inline]
+    pub fn contains_item(&self, item: T) -> bool {
+        self.entries.contains_key(&item)
+    }
+
+    /// Returns the position that `item` is stored at, or `None` if it is not in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::indexed::IndexedKdTree;
+    ///
+    /// let mut tree: IndexedKdTree<f64, u64, 3, 32, u32> = IndexedKdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100);
+    ///
+    /// assert_eq!(tree.position_of(100), Some([1.0, 2.0, 5.0]));
+    /// assert_eq!(tree.position_of(101), None);
+    /// ```
+    #[inline]
+    pub fn position_of(&self, item: T) -> Option<[A; K]> {
+        self.entries.get(&item).map(|entry| entry.position)
+    }
+
+    /// Returns the wrapped [`KdTree`], discarding the reverse index.
+    pub fn into_inner(self) -> KdTree<A, T, K, B, IDX> {
+        self.tree
+    }
+}
+
+/// Returns a callback that records each item that the wrapped tree moves in `entries`
+fn record_moves<A, T: Eq + Hash, const K: usize, IDX>(
+    entries: &mut HashMap<T, Entry<A, K, IDX>>,
+) -> impl FnMut(T, IDX) + '_ {
+    |moved_item, leaf_idx| {
+        if let Some(entry) = entries.get_mut(&moved_item) {
+            entry.leaf_idx = leaf_idx;
+        }
+    }
+}
+
+impl<A: Copy + Default, T: Copy + Default, const K: usize, const B: usize, IDX> Deref
+    for IndexedKdTree<A, T, K, B, IDX>
+{
+    type Target = KdTree<A, T, K, B, IDX>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+
+    use crate::float::indexed::IndexedKdTree;
+    use crate::SquaredEuclidean;
+
+    /// Returns the index of the leaf of the wrapped tree that holds `item`
+    fn leaf_holding(tree: &IndexedKdTree<f64, u32, 2, 8, u32>, item: u32) -> Option<usize> {
+        tree.leaves
+            .iter()
+            .position(|leaf| leaf.content_items[..leaf.size as usize].contains(&item))
+    }
+
+    /// Checks that the leaf that the reverse index holds for each item really holds it
+    fn assert_leaves_are_indexed(tree: &IndexedKdTree<f64, u32, 2, 8, u32>) {
+        for (&item, entry) in &tree.entries {
+            let leaf = &tree.leaves[entry.leaf_idx as usize];
+            let size = leaf.size as usize;
+            assert!(
+                leaf.content_items[..size]
+                    .iter()
+                    .zip(&leaf.content_points[..size])
+                    .any(|(&stored, point)| stored == item && point == &entry.position),
+                "leaf {} does not hold item {item}",
+                entry.leaf_idx
+            );
+        }
+    }
+
+    #[test]
+    fn can_move_items_across_leaves() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(67);
+        let mut tree: IndexedKdTree<f64, u32, 2, 8, u32> = IndexedKdTree::new();
+        for item in 0..1_000 {
+            tree.add(&[rng.gen(), rng.gen()], item);
+        }
+
+        for item in 0..100 {
+            let old = tree.position_of(item).unwrap();
+            let new = [1.0 - old[0], 1.0 - old[1]];
+            let old_leaf_idx = leaf_holding(&tree, item);
+
+            tree.add(&new, item);
+
+            assert_ne!(leaf_holding(&tree, item), old_leaf_idx);
+            assert_eq!(
+                leaf_holding(&tree, item),
+                Some(tree.entries[&item].leaf_idx as usize)
+            );
+            assert_eq!(tree.position_of(item), Some(new));
+            assert_eq!(tree.nearest_one::<SquaredEuclidean>(&new).item, item);
+            assert!(tree
+                .within::<SquaredEuclidean>(&old, 1e-12)
+                .iter()
+                .all(|neighbour| neighbour.item != item));
+        }
+
+        assert_eq!(tree.size(), 1_000);
+        for item in 0..1_000 {
+            let position = tree.position_of(item);
+            assert_eq!(tree.remove_item(item), position);
+            assert_eq!(leaf_holding(&tree, item), None);
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn reverse_index_stays_consistent_under_churn() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(53);
+        let mut tree: IndexedKdTree<f64, u32, 2, 8, u32> = IndexedKdTree::new();
+        let mut live: Vec<Option<[f64; 2]>> = vec![None; 1_000];
+
+        for step in 0..20_000 {
+            if step % 5_000 == 4_999 {
+                tree.rebalance();
+            }
+            if step % 1_000 == 0 {
+                assert_leaves_are_indexed(&tree);
+            }
+            let item = rng.gen_range(0..live.len());
+            if rng.gen_bool(0.4) {
+                assert_eq!(tree.remove_item(item as u32), live[item]);
+                live[item] = None;
+            } else {
+                let point = [rng.gen(), rng.gen()];
+                tree.add(&point, item as u32);
+                live[item] = Some(point);
+            }
+        }
+
+        assert_leaves_are_indexed(&tree);
+        let expected = live.iter().filter(|point| point.is_some()).count();
+        assert_eq!(tree.size() as usize, expected);
+        assert_eq!(tree.stats().size, expected);
+        for (item, point) in live.iter().enumerate() {
+            assert_eq!(tree.contains_item(item as u32), point.is_some());
+            assert_eq!(tree.position_of(item as u32), *point);
+        }
+
+        let mut tree = tree.into_inner();
+        for (item, point) in live.iter().enumerate() {
+            if let Some(point) = point {
+                assert_eq!(tree.remove(point, item as u32), 1);
+            }
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn remove_item_falls_back_to_searching_when_the_recorded_leaf_is_stale() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(71);
+        let mut tree: IndexedKdTree<f64, u32, 2, 8, u32> = IndexedKdTree::new();
+        for item in 0..100 {
+            tree.add(&[rng.gen(), rng.gen()], item);
+        }
+
+        let position = tree.position_of(7);
+        let stale_leaf_idx = (0..tree.leaves.len() as u32)
+            .find(|&leaf_idx| leaf_idx != tree.entries[&7].leaf_idx)
+            .unwrap();
+        tree.entries.get_mut(&7).unwrap().leaf_idx = stale_leaf_idx;
+
+        assert_eq!(tree.remove_item(7), position);
+        assert_eq!(leaf_holding(&tree, 7), None);
+        assert_eq!(tree.size(), 99);
+        assert_leaves_are_indexed(&tree);
+    }
+}
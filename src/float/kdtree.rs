@@ -10,10 +10,15 @@ use divrem::DivCeil;
 use num_traits::float::FloatCore;
 use std::cmp::PartialEq;
 use std::fmt::Debug;
+use std::mem::size_of;
 
 use crate::{
+    error::KiddoError,
+    generate_visit_leaves,
     iter::{IterableTreeData, TreeIter},
-    traits::{Content, Index},
+    split_axis::SplitAxis,
+    stats::{MemoryUsage, TreeStats, TreeStatsBuilder},
+    traits::{is_stem_index, Content, Index, Unbounded},
 };
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -22,14 +27,18 @@ use serde::{Deserialize, Serialize};
 /// by the type that is used as the first generic parameter, `A`,
 /// on the float [`KdTree`]. This will be [`f64`] or [`f32`],
 /// or [`f16`](https://docs.rs/half/latest/half/struct.f16.html) if the `f16` feature is enabled
-pub trait Axis: FloatCore + Default + Debug + Copy + Sync + Send + std::ops::AddAssign {
+pub trait Axis:
+    FloatCore + Default + Debug + Copy + Sync + Send + std::ops::AddAssign + Unbounded
+{
     /// returns absolute diff between two values of a type implementing this trait
     fn saturating_dist(self, other: Self) -> Self;
 
     /// used in query methods to update the rd value. Basically a saturating add for Fixed and an add for Float
     fn rd_update(rd: Self, delta: Self) -> Self;
 }
-impl<T: FloatCore + Default + Debug + Copy + Sync + Send + std::ops::AddAssign> Axis for T {
+impl<T: FloatCore + Default + Debug + Copy + Sync + Send + std::ops::AddAssign + Unbounded> Axis
+    for T
+{
     fn saturating_dist(self, other: Self) -> Self {
         (self - other).abs()
     }
@@ -56,7 +65,6 @@ impl<T: FloatCore + Default + Debug + Copy + Sync + Send + std::ops::AddAssign>
 /// or [`f16`](https://docs.rs/half/latest/half/struct.f16.html) if the `f16` feature is enabled
 ///
 /// A convenient type alias exists for KdTree with some sensible defaults set: [`kiddo::KdTree`](`crate::KdTree`).
-
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(
     feature = "rkyv",
@@ -68,6 +76,55 @@ pub struct KdTree<A: Copy + Default, T: Copy + Default, const K: usize, const B:
     pub(crate) stems: Vec<StemNode<A, K, IDX>>,
     pub(crate) root_index: IDX,
     pub(crate) size: T,
+
+    /// split dimension of each stem, indexed in the same way as `stems`. Empty unless the
+    /// tree was created with [`SplitAxis::WidestSpread`], in which case the split dimensions
+    /// don't simply cycle through the axes level by level
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) split_dims: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) split_axis: SplitAxis,
+
+    /// number of leaves that hold no items. Only kept up to date while a
+    /// [`RebalancePolicy`] is set
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) empty_leaves: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) rebalance_policy: Option<RebalancePolicy>,
+
+    /// indices of leaves and stems that are no longer part of the tree, since their
+    /// contents were merged into a sibling, and which can be re-used by later splits
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) free_leaves: Vec<IDX>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) free_stems: Vec<IDX>,
+}
+
+/// Thresholds at which a mutable tree automatically [rebalances](`KdTree::rebalance`) itself.
+///
+/// Trees don't rebalance themselves unless a policy has been set with
+/// [`KdTree::set_rebalance_policy`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RebalancePolicy {
+    /// rebalance when an item gets added to a leaf that is more than this many times
+    /// deeper than the leaves of a balanced tree with the same number of leaves would be
+    pub max_depth_ratio: f32,
+    /// rebalance when removing an item leaves more than this proportion of leaves empty
+    pub max_empty_leaf_ratio: f32,
+}
+
+impl Default for RebalancePolicy {
+    fn default() -> Self {
+        Self {
+            max_depth_ratio: 2.0,
+            max_empty_leaf_ratio: 0.25,
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -185,12 +242,81 @@ where
     /// ```
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
+        KdTree::with_capacity_and_split_axis(capacity, SplitAxis::RoundRobin)
+    }
+
+    /// Creates a new float KdTree and reserves capacity for a specific number of items,
+    /// returning [`KiddoError::CapacityExceeded`] rather than panicking if `IDX` can't
+    /// address that many items with a bucket size of `B`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::float::kdtree::KdTree;
+    /// use kiddo::KiddoError;
+    ///
+    /// let tree = KdTree::<f64, u32, 3, 32, u16>::try_with_capacity(1_000).unwrap();
+    /// assert_eq!(tree.size(), 0);
+    ///
+    /// assert_eq!(
+    ///     KdTree::<f64, u32, 3, 32, u16>::try_with_capacity(10_000_000).unwrap_err(),
+    ///     KiddoError::CapacityExceeded {
+    ///         capacity: 10_000_000,
+    ///         max_capacity: 1_048_576,
+    ///     }
+    /// );
+    /// ```
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, KiddoError> {
+        let max_capacity = <IDX as Index>::capacity_with_bucket_size(B);
+        if capacity > max_capacity {
+            return Err(KiddoError::CapacityExceeded {
+                capacity,
+                max_capacity,
+            });
+        }
+
+        Ok(KdTree::with_capacity(capacity))
+    }
+
+    /// Creates a new float KdTree, reserving capacity for a specific number of items, and
+    /// choosing the axis that each leaf is split along when it fills up using `split_axis`.
+    ///
+    /// [`SplitAxis::WidestSpread`] suits data that is spread much further along some axes
+    /// than others, at the cost of an extra byte per stem.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::SplitAxis;
+    ///
+    /// let mut tree: KdTree<f64, 3> =
+    ///     KdTree::with_capacity_and_split_axis(1_000_000, SplitAxis::WidestSpread);
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100);
+    ///
+    /// assert_eq!(tree.size(), 1);
+    /// assert_eq!(tree.split_axis(), SplitAxis::WidestSpread);
+    /// ```
+    #[inline]
+    pub fn with_capacity_and_split_axis(capacity: usize, split_axis: SplitAxis) -> Self {
         assert!(capacity <= <IDX as Index>::capacity_with_bucket_size(B));
+        assert!(
+            split_axis == SplitAxis::RoundRobin || K <= u8::MAX as usize + 1,
+            "SplitAxis::WidestSpread supports at most 256 dimensions"
+        );
         let mut tree = Self {
             size: T::zero(),
             stems: Vec::with_capacity(capacity.max(1).ilog2() as usize),
             leaves: Vec::with_capacity(DivCeil::div_ceil(capacity, B.az::<usize>())),
             root_index: <IDX as Index>::leaf_offset(),
+            split_dims: Vec::new(),
+            split_axis,
+            empty_leaves: 0,
+            rebalance_policy: None,
+            free_leaves: Vec::new(),
+            free_stems: Vec::new(),
         };
 
         tree.leaves.push(LeafNode::new());
@@ -198,6 +324,18 @@ where
         tree
     }
 
+    /// Returns the strategy used to choose the axis that each leaf is split along
+    #[inline]
+    pub fn split_axis(&self) -> SplitAxis {
+        self.split_axis
+    }
+
+    /// Returns the policy that determines when the tree rebalances itself, if one is set
+    #[inline]
+    pub fn rebalance_policy(&self) -> Option<RebalancePolicy> {
+        self.rebalance_policy
+    }
+
     /// Iterate over all `(index, point)` tuples in arbitrary order.
     ///
     /// ```
@@ -216,6 +354,60 @@ where
     pub fn iter(&self) -> impl Iterator<Item = (T, [A; K])> + '_ {
         TreeIter::new(self, B)
     }
+
+    /// Returns statistics describing the shape, fill level and memory usage of the tree,
+    /// which can help with choosing suitable values for `B` and `IDX`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, 2> = KdTree::new();
+    /// for i in 0..100 {
+    ///     tree.add(&[i as f64, (i % 10) as f64], i);
+    /// }
+    ///
+    /// let stats = tree.stats();
+    ///
+    /// assert_eq!(stats.size, 100);
+    /// assert_eq!(stats.leaf_count, stats.stem_count + 1);
+    /// assert_eq!(stats.leaf_occupancy.iter().sum::<usize>(), stats.leaf_count);
+    /// ```
+    pub fn stats(&self) -> TreeStats {
+        let mut builder = TreeStatsBuilder::new();
+        let mut to_visit = vec![(self.root_index, 0)];
+        while let Some((node_idx, depth)) = to_visit.pop() {
+            if is_stem_index(node_idx) {
+                let stem = &self.stems[node_idx.az::<usize>()];
+                to_visit.push((stem.right, depth + 1));
+                to_visit.push((stem.left, depth + 1));
+            } else {
+                let leaf = &self.leaves[(node_idx - <IDX as Index>::leaf_offset()).az::<usize>()];
+                builder.add_leaf(
+                    depth,
+                    leaf.content_points[..leaf.size.az::<usize>()]
+                        .iter()
+                        .copied(),
+                );
+            }
+        }
+
+        let leaf_points = size_of::<[[A; K]; B]>();
+        let leaf_items = size_of::<[T; B]>();
+        let leaf_other = size_of::<LeafNode<A, T, K, B, IDX>>() - leaf_points - leaf_items;
+        builder.finish(
+            self.stems.len() - self.free_stems.len(),
+            MemoryUsage {
+                stems: self.stems.len() * size_of::<StemNode<A, K, IDX>>(),
+                leaf_points: self.leaves.len() * leaf_points,
+                leaf_items: self.leaves.len() * leaf_items,
+                other: self.leaves.len() * leaf_other
+                    + self.split_dims.len()
+                    + (self.free_leaves.len() + self.free_stems.len()) * size_of::<IDX>(),
+            },
+        )
+    }
 }
 
 impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
@@ -283,6 +475,7 @@ where
     usize: Cast<IDX>,
 {
     generate_common_methods!(KdTree);
+    generate_visit_leaves!();
 }
 
 #[cfg(feature = "rkyv")]
@@ -297,6 +490,7 @@ where
     usize: Cast<IDX>,
 {
     generate_common_methods!(ArchivedKdTree);
+    generate_visit_leaves!();
 }
 
 #[cfg(test)]
@@ -380,4 +574,46 @@ mod tests {
         let actual: HashMap<_, _> = t.iter().collect();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn stats_describe_every_leaf() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+        let mut tree: KdTree<f32, u32, 2, 8, u32> = KdTree::with_capacity(1_000);
+        for i in 0..1_000 {
+            tree.add(&[rng.gen(), rng.gen()], i);
+        }
+        for i in 0..3 {
+            tree.add(&[0.5, 0.25], 1_000 + i);
+        }
+
+        let stats = tree.stats();
+
+        let expected_duplicate_leaves = tree
+            .leaves
+            .iter()
+            .filter(|leaf| {
+                let points = &leaf.content_points[..leaf.size as usize];
+                (0..points.len()).any(|i| points[i + 1..].contains(&points[i]))
+            })
+            .count();
+
+        assert_eq!(stats.size, 1_003);
+        assert_eq!(stats.leaf_count, tree.leaves.len());
+        assert_eq!(stats.stem_count, tree.stems.len());
+        assert_eq!(stats.leaf_count, stats.stem_count + 1);
+        assert!(stats.min_depth >= 1);
+        assert!(stats.max_depth >= stats.leaf_count.ilog2() as usize);
+        assert!(stats.mean_depth >= stats.min_depth as f64);
+        assert!(stats.mean_depth <= stats.max_depth as f64);
+        assert_eq!(stats.leaf_occupancy.len(), 9);
+        assert_eq!(stats.leaf_occupancy.iter().sum::<usize>(), stats.leaf_count);
+        assert_eq!(expected_duplicate_leaves, 1);
+        assert_eq!(stats.duplicate_coordinate_leaves, expected_duplicate_leaves);
+        assert_eq!(
+            stats.memory.leaf_points,
+            tree.leaves.len() * 8 * 2 * std::mem::size_of::<f32>()
+        );
+    }
 }
@@ -9,6 +9,7 @@
 #[doc(hidden)]
 pub mod construction;
 pub mod distance;
+pub mod indexed;
 pub mod kdtree;
 #[doc(hidden)]
 pub mod query;
@@ -0,0 +1,109 @@
+use az::Cast;
+
+use crate::float::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_any_within;
+
+macro_rules! generate_float_any_within {
+    ($doctest_build_tree:tt) => {
+        generate_any_within!((
+            "Finds an element within `dist` of `query`, using the specified
+distance metric function, or returns `None` if there are none.
+
+Stops searching as soon as an element is found, so the element returned is not
+necessarily the nearest one. Faster than checking whether `within_unsorted` is empty.
+
+# Examples
+
+```rust
+use kiddo::KdTree;
+use kiddo::SquaredEuclidean;
+",
+            $doctest_build_tree,
+            "
+
+assert!(tree.any_within::<SquaredEuclidean>(&[1.0, 2.0, 5.5], 1f64).is_some());
+assert!(tree.any_within::<SquaredEuclidean>(&[10.0, 20.0, 50.0], 1f64).is_none());
+```"
+        ));
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_any_within!(
+        "
+let mut tree: KdTree<f64, 3> = KdTree::new();
+tree.add(&[1.0, 2.0, 5.0], 100);
+tree.add(&[2.0, 3.0, 6.0], 101);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::float::kdtree::ArchivedKdTree;
+#[cfg(feature = "rkyv")]
+impl<
+        A: Axis + rkyv::Archive<Archived = A>,
+        T: Content + rkyv::Archive<Archived = T>,
+        const K: usize,
+        const B: usize,
+        IDX: Index<T = IDX> + rkyv::Archive<Archived = IDX>,
+    > ArchivedKdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_any_within!(
+        "use std::fs::File;
+use memmap::MmapOptions;
+
+let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/float-doctest-tree.rkyv\").unwrap()).unwrap() };
+let tree = unsafe { rkyv::archived_root::<KdTree<f64, 3>>(&mmap) };"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::Manhattan;
+    use crate::float::kdtree::KdTree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f32;
+
+    #[test]
+    fn can_find_any_item_within_radius() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(79);
+        let content_to_add: Vec<([AX; 4], u32)> = (0..2_000)
+            .map(|item| (rng.gen::<[AX; 4]>(), item))
+            .collect();
+
+        let mut tree: KdTree<AX, u32, 4, 8, u32> = KdTree::with_capacity(content_to_add.len());
+        for (point, item) in &content_to_add {
+            tree.add(point, *item);
+        }
+
+        for _ in 0..1_000 {
+            let query_point = rng.gen::<[AX; 4]>();
+            let radius = rng.gen_range(0.0..0.2);
+
+            let any_expected = content_to_add
+                .iter()
+                .any(|(point, _)| Manhattan::dist(&query_point, point) < radius);
+
+            match tree.any_within::<Manhattan>(&query_point, radius) {
+                Some(found) => {
+                    let (point, _) = content_to_add[found.item as usize];
+                    assert_eq!(found.distance, Manhattan::dist(&query_point, &point));
+                    assert!(found.distance < radius);
+                }
+                None => assert!(!any_expected),
+            }
+        }
+    }
+}
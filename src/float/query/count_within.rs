@@ -0,0 +1,153 @@
+use az::Cast;
+
+use crate::float::kdtree::{Axis, KdTree};
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_count_within;
+
+macro_rules! generate_float_count_within {
+    ($doctest_build_tree:tt) => {
+        generate_count_within!(
+            (
+                "Counts the elements within `dist` of `query`, using the specified
+distance metric function.
+
+Equivalent to `within_unsorted(query, dist).len()`, but without collecting the results.
+
+# Examples
+
+```rust
+use kiddo::KdTree;
+use kiddo::SquaredEuclidean;
+",
+                $doctest_build_tree,
+                "
+
+assert_eq!(tree.count_within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64), 2);
+assert_eq!(tree.count_within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 1f64), 1);
+```"
+            ),
+            (
+                "Counts the elements within `dist` of `query`, using the specified
+distance metric function, stopping once `limit` elements have been found.
+
+Returns the smaller of `limit` and the number of elements within `dist` of `query`.
+Useful for threshold checks, such as whether at least `limit` elements are nearby.
+
+# Examples
+
+```rust
+use kiddo::KdTree;
+use kiddo::SquaredEuclidean;
+",
+                $doctest_build_tree,
+                "
+
+assert_eq!(tree.count_within_at_most::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64, 1), 1);
+assert_eq!(tree.count_within_at_most::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64, 5), 2);
+```"
+            )
+        );
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_count_within!(
+        "
+let mut tree: KdTree<f64, 3> = KdTree::new();
+tree.add(&[1.0, 2.0, 5.0], 100);
+tree.add(&[2.0, 3.0, 6.0], 101);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::float::kdtree::ArchivedKdTree;
+#[cfg(feature = "rkyv")]
+impl<
+        A: Axis + rkyv::Archive<Archived = A>,
+        T: Content + rkyv::Archive<Archived = T>,
+        const K: usize,
+        const B: usize,
+        IDX: Index<T = IDX> + rkyv::Archive<Archived = IDX>,
+    > ArchivedKdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_count_within!(
+        "use std::fs::File;
+use memmap::MmapOptions;
+
+let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/float-doctest-tree.rkyv\").unwrap()).unwrap() };
+let tree = unsafe { rkyv::archived_root::<KdTree<f64, 3>>(&mmap) };"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::{Manhattan, SquaredEuclidean};
+    use crate::float::kdtree::KdTree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f32;
+
+    #[test]
+    fn can_count_items_within_radius() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(71);
+        let content_to_add: Vec<([AX; 4], u32)> = (0..5_000)
+            .map(|item| (rng.gen::<[AX; 4]>(), item))
+            .collect();
+
+        let mut tree: KdTree<AX, u32, 4, 8, u32> = KdTree::with_capacity(content_to_add.len());
+        for (point, item) in &content_to_add {
+            tree.add(point, *item);
+        }
+
+        for _ in 0..100 {
+            let query_point = rng.gen::<[AX; 4]>();
+            let radius = rng.gen_range(0.0..0.5);
+
+            let expected = content_to_add
+                .iter()
+                .filter(|(point, _)| Manhattan::dist(&query_point, point) < radius)
+                .count();
+
+            assert_eq!(
+                tree.count_within::<Manhattan>(&query_point, radius),
+                expected
+            );
+            assert_eq!(
+                tree.count_within::<Manhattan>(&query_point, radius),
+                tree.within_unsorted::<Manhattan>(&query_point, radius)
+                    .len()
+            );
+
+            for limit in [0, 1, expected / 2, expected, expected + 1] {
+                assert_eq!(
+                    tree.count_within_at_most::<Manhattan>(&query_point, radius, limit),
+                    expected.min(limit)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn count_within_at_most_stops_at_limit_among_duplicates() {
+        let mut tree: KdTree<AX, u32, 2, 4, u32> = KdTree::new();
+        for item in 0..100 {
+            tree.add(&[0.5, 0.5], item);
+        }
+
+        assert_eq!(tree.count_within::<SquaredEuclidean>(&[0.5, 0.5], 0.1), 100);
+        assert_eq!(
+            tree.count_within_at_most::<SquaredEuclidean>(&[0.5, 0.5], 0.1, 7),
+            7
+        );
+        assert_eq!(tree.count_within::<SquaredEuclidean>(&[0.0, 0.0], 0.1), 0);
+    }
+}
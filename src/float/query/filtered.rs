@@ -0,0 +1,192 @@
+use az::Cast;
+use std::collections::BinaryHeap;
+
+use crate::float::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_filtered;
+
+macro_rules! generate_float_filtered {
+    ($doctest_build_tree:tt) => {
+        generate_filtered!(
+            (
+                "Finds the nearest element to `query` for which `filter` returns `true`, using
+the specified distance metric function, or returns `None` if there are none.
+
+Items that are rejected by `filter` do not narrow the search, so this always finds
+the nearest accepted item, unlike filtering the results of `nearest_n`.
+
+# Examples
+
+```rust
+    use kiddo::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let nearest = tree.nearest_one_filtered::<SquaredEuclidean, _>(&[1.0, 2.0, 5.1], |item| item != 100);
+
+    assert_eq!(nearest.unwrap().item, 101);
+```"
+            ),
+            (
+                "Finds the nearest `qty` elements to `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use kiddo::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let nearest = tree.nearest_n_filtered::<SquaredEuclidean, _>(&[1.0, 2.0, 5.1], 2, |item| item % 2 == 1);
+
+    assert_eq!(nearest.len(), 1);
+    assert_eq!(nearest[0].item, 101);
+```"
+            ),
+            (
+                "Finds all elements within `dist` of `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use kiddo::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let within = tree.within_filtered::<SquaredEuclidean, _>(&[1.0, 2.0, 5.0], 10f64, |item| item > 100);
+
+    assert_eq!(within.len(), 1);
+    assert_eq!(within[0].item, 101);
+```"
+            )
+        );
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_filtered!(
+        "let mut tree: KdTree<f64, 3> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::float::kdtree::ArchivedKdTree;
+#[cfg(feature = "rkyv")]
+impl<
+        A: Axis + rkyv::Archive<Archived = A>,
+        T: Content + rkyv::Archive<Archived = T>,
+        const K: usize,
+        const B: usize,
+        IDX: Index<T = IDX> + rkyv::Archive<Archived = IDX>,
+    > ArchivedKdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_filtered!(
+        "use std::fs::File;
+    use memmap::MmapOptions;
+
+    let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/float-doctest-tree.rkyv\").unwrap()).unwrap() };
+    let tree = unsafe { rkyv::archived_root::<KdTree<f64, 3>>(&mmap) };"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::SquaredEuclidean;
+    use crate::float::kdtree::KdTree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f64;
+
+    fn linear_search(
+        content: &[([AX; 3], u32)],
+        query_point: &[AX; 3],
+        radius: AX,
+        filter: impl Fn(u32) -> bool,
+    ) -> Vec<(AX, u32)> {
+        let mut matching_items: Vec<(AX, u32)> = content
+            .iter()
+            .map(|(point, item)| (SquaredEuclidean::dist(query_point, point), *item))
+            .filter(|&(distance, item)| distance < radius && filter(item))
+            .collect();
+        matching_items.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        matching_items
+    }
+
+    #[test]
+    fn can_query_filtered_items() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(89);
+        let content_to_add: Vec<([AX; 3], u32)> = (0..5_000)
+            .map(|item| (rng.gen::<[AX; 3]>(), item))
+            .collect();
+
+        let mut tree: KdTree<AX, u32, 3, 8, u32> = KdTree::with_capacity(content_to_add.len());
+        for (point, item) in &content_to_add {
+            tree.add(point, *item);
+        }
+
+        // rejects the vast majority of items, so that the nearest accepted ones are
+        // usually further away than many rejected ones
+        let filter = |item: u32| item % 97 == 3;
+
+        for _ in 0..100 {
+            let query_point = rng.gen::<[AX; 3]>();
+            let expected = linear_search(&content_to_add, &query_point, AX::MAX, filter);
+
+            let nearest = tree
+                .nearest_one_filtered::<SquaredEuclidean, _>(&query_point, filter)
+                .unwrap();
+            assert_eq!((nearest.distance, nearest.item), expected[0]);
+
+            let nearest: Vec<_> = tree
+                .nearest_n_filtered::<SquaredEuclidean, _>(&query_point, 5, filter)
+                .into_iter()
+                .map(|n| (n.distance, n.item))
+                .collect();
+            assert_eq!(nearest, expected[..5]);
+
+            let radius = 0.05;
+            let expected = linear_search(&content_to_add, &query_point, radius, filter);
+            let within: Vec<_> = tree
+                .within_filtered::<SquaredEuclidean, _>(&query_point, radius, filter)
+                .into_iter()
+                .map(|n| (n.distance, n.item))
+                .collect();
+            assert_eq!(within, expected);
+        }
+
+        assert!(tree
+            .nearest_one_filtered::<SquaredEuclidean, _>(&[0.5; 3], |_| false)
+            .is_none());
+        assert!(tree
+            .nearest_n_filtered::<SquaredEuclidean, _>(&[0.5; 3], 0, |_| true)
+            .is_empty());
+    }
+}
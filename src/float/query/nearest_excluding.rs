@@ -0,0 +1,190 @@
+use az::Cast;
+use std::collections::BinaryHeap;
+
+use crate::float::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_nearest_excluding;
+
+macro_rules! generate_float_nearest_excluding {
+    ($doctest_build_tree:tt) => {
+        generate_nearest_excluding!(
+            (
+                "Queries the tree to find the nearest item to the `query` point, ignoring
+any elements whose item is `excluded`.
+
+Intended for querying with a point that is itself stored in the tree, such as when
+building a neighbour graph. Returns `None` if the tree holds no other items.
+
+# Examples
+
+```rust
+    use kiddo::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let nearest = tree.nearest_one_excluding::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 100).unwrap();
+
+    assert_eq!(nearest.distance, 3.0);
+    assert_eq!(nearest.item, 101);
+```"
+            ),
+            (
+                "Finds the nearest `qty` elements to `query`, ignoring any elements whose
+item is `excluded`, using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use kiddo::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let nearest = tree.nearest_n_excluding::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 2, 100);
+
+    assert_eq!(nearest.len(), 1);
+    assert_eq!(nearest[0].item, 101);
+```"
+            )
+        );
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_nearest_excluding!(
+        "let mut tree: KdTree<f64, 3> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::float::kdtree::ArchivedKdTree;
+#[cfg(feature = "rkyv")]
+impl<
+        A: Axis + rkyv::Archive<Archived = A>,
+        T: Content + rkyv::Archive<Archived = T>,
+        const K: usize,
+        const B: usize,
+        IDX: Index<T = IDX> + rkyv::Archive<Archived = IDX>,
+    > ArchivedKdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_nearest_excluding!(
+        "use std::fs::File;
+    use memmap::MmapOptions;
+
+    let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/float-doctest-tree.rkyv\").unwrap()).unwrap() };
+    let tree = unsafe { rkyv::archived_root::<KdTree<f64, 3>>(&mmap) };"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::SquaredEuclidean;
+    use crate::float::kdtree::KdTree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f64;
+
+    fn linear_search(content: &[[AX; 3]], query_point: &[AX; 3], excluded: u32) -> Vec<(AX, u32)> {
+        let mut matching_items: Vec<(AX, u32)> = content
+            .iter()
+            .enumerate()
+            .filter(|&(item, _)| item as u32 != excluded)
+            .map(|(item, point)| (SquaredEuclidean::dist(query_point, point), item as u32))
+            .collect();
+        matching_items.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        matching_items
+    }
+
+    #[test]
+    fn can_query_nearest_items_excluding_the_query_item() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(101);
+        let content: Vec<[AX; 3]> = (0..2_000).map(|_| rng.gen::<[AX; 3]>()).collect();
+
+        let mut tree: KdTree<AX, u32, 3, 8, u32> = KdTree::with_capacity(content.len());
+        for (item, point) in content.iter().enumerate() {
+            tree.add(point, item as u32);
+        }
+
+        for (item, point) in content.iter().enumerate().step_by(10) {
+            let expected = linear_search(&content, point, item as u32);
+
+            let nearest = tree
+                .nearest_one_excluding::<SquaredEuclidean>(point, item as u32)
+                .unwrap();
+            assert_eq!((nearest.distance, nearest.item), expected[0]);
+
+            let nearest: Vec<_> = tree
+                .nearest_n_excluding::<SquaredEuclidean>(point, 5, item as u32)
+                .into_iter()
+                .map(|n| (n.distance, n.item))
+                .collect();
+            assert_eq!(nearest, expected[..5]);
+        }
+    }
+
+    #[test]
+    fn finds_other_items_at_the_same_position() {
+        let mut tree: KdTree<AX, u32, 2, 4, u32> = KdTree::new();
+        tree.add(&[0.5, 0.5], 1);
+        tree.add(&[0.5, 0.5], 2);
+        tree.add(&[0.9, 0.9], 3);
+
+        let nearest = tree
+            .nearest_one_excluding::<SquaredEuclidean>(&[0.5, 0.5], 1)
+            .unwrap();
+        assert_eq!((nearest.distance, nearest.item), (0.0, 2));
+
+        let nearest = tree.nearest_n_excluding::<SquaredEuclidean>(&[0.5, 0.5], 3, 2);
+        assert_eq!(
+            nearest.iter().map(|n| n.item).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+    }
+
+    #[test]
+    fn finds_nothing_when_only_the_excluded_item_is_present() {
+        let mut tree: KdTree<AX, u32, 2, 4, u32> = KdTree::new();
+        tree.add(&[0.5, 0.5], 1);
+
+        assert_eq!(
+            tree.nearest_one_excluding::<SquaredEuclidean>(&[0.5, 0.5], 1),
+            None
+        );
+        assert!(tree
+            .nearest_n_excluding::<SquaredEuclidean>(&[0.5, 0.5], 3, 1)
+            .is_empty());
+    }
+
+    #[test]
+    fn finds_nothing_in_an_empty_tree() {
+        let tree: KdTree<AX, u32, 2, 4, u32> = KdTree::new();
+
+        assert_eq!(
+            tree.nearest_one_excluding::<SquaredEuclidean>(&[0.5, 0.5], 1),
+            None
+        );
+        assert!(tree
+            .nearest_n_excluding::<SquaredEuclidean>(&[0.5, 0.5], 3, 1)
+            .is_empty());
+    }
+}
@@ -79,8 +79,9 @@ mod tests {
     use crate::float::distance::Manhattan;
     use crate::float::kdtree::{Axis, KdTree};
     use crate::nearest_neighbour::NearestNeighbour;
+    use crate::split_axis::SplitAxis;
     use crate::traits::DistanceMetric;
-    use rand::Rng;
+    use rand::{Rng, SeedableRng};
 
     type AX = f32;
 
@@ -168,6 +169,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_query_nearest_one_item_with_widest_spread_split_axis() {
+        const TREE_SIZE: usize = 20_000;
+        const NUM_QUERIES: usize = 200;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(29);
+        let mut rand_point = || -> [f32; 3] {
+            [
+                rng.gen_range(0f32..4096f32),
+                rng.gen_range(0f32..8f32),
+                rng.gen_range(0f32..4096f32),
+            ]
+        };
+
+        let content_to_add: Vec<([f32; 3], u32)> = (0..TREE_SIZE)
+            .map(|idx| (rand_point(), idx as u32))
+            .collect();
+        let query_points: Vec<[f32; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let mut tree: KdTree<AX, u32, 3, 32, u32> =
+            KdTree::with_capacity_and_split_axis(TREE_SIZE, SplitAxis::WidestSpread);
+        content_to_add
+            .iter()
+            .for_each(|(point, content)| tree.add(point, *content));
+
+        for query_point in query_points {
+            let expected = linear_search(&content_to_add, &query_point);
+
+            let result = tree.nearest_one::<Manhattan>(&query_point);
+
+            assert_eq!(result.distance, expected.distance);
+        }
+    }
+
     fn linear_search<A: Axis, const K: usize>(
         content: &[([A; K], u32)],
         query_point: &[A; K],
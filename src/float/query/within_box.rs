@@ -0,0 +1,184 @@
+use az::{Az, Cast};
+use std::ops::Rem;
+
+use crate::float::kdtree::{Axis, KdTree, LeafNode};
+use crate::generate_within_box;
+use crate::traits::{is_stem_index, Content, Index};
+
+macro_rules! generate_float_within_box {
+    ($leafnode:ident, $doctest_build_tree:tt) => {
+        generate_within_box!(
+            $leafnode,
+            (
+                "Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use kiddo::KdTree;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let within = tree.within_box(&[0.0, 0.0, 0.0], &[1.5, 2.5, 5.5]);
+
+    assert_eq!(within, vec![100]);
+```"
+            ),
+            (
+                "Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Returns an `Iterator`. Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use kiddo::KdTree;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let within = tree.within_box_iter(&[0.0, 0.0, 0.0], &[10.0, 10.0, 10.0]).collect::<Vec<_>>();
+
+    assert_eq!(within.len(), 2);
+```"
+            ),
+            (
+                "Counts the elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Faster than `within_box(min, max).len()`, since leaves that lie entirely within the box
+are counted without looking at the points that they hold.
+
+# Examples
+
+```rust
+    use kiddo::KdTree;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    assert_eq!(tree.count_in_box(&[0.0, 0.0, 0.0], &[10.0, 10.0, 10.0]), 2);
+    assert_eq!(tree.count_in_box(&[1.5, 2.5, 5.5], &[10.0, 10.0, 10.0]), 1);
+```"
+            )
+        );
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_within_box!(
+        LeafNode,
+        "let mut tree: KdTree<f64, 3> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::float::kdtree::{ArchivedKdTree, ArchivedLeafNode};
+#[cfg(feature = "rkyv")]
+impl<
+        A: Axis + rkyv::Archive<Archived = A>,
+        T: Content + rkyv::Archive<Archived = T>,
+        const K: usize,
+        const B: usize,
+        IDX: Index<T = IDX> + rkyv::Archive<Archived = IDX>,
+    > ArchivedKdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_float_within_box!(
+        ArchivedLeafNode,
+        "use std::fs::File;
+    use memmap::MmapOptions;
+
+    let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/float-doctest-tree.rkyv\").unwrap()).unwrap() };
+    let tree = unsafe { rkyv::archived_root::<KdTree<f64, 3>>(&mmap) };"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::kdtree::KdTree;
+    use crate::split_axis::SplitAxis;
+    use rand::{Rng, SeedableRng};
+
+    fn linear_search(content: &[([f64; 3], u32)], min: &[f64; 3], max: &[f64; 3]) -> Vec<u32> {
+        let mut matching: Vec<u32> = content
+            .iter()
+            .filter(|(point, _)| (0..3).all(|dim| point[dim] >= min[dim] && point[dim] <= max[dim]))
+            .map(|&(_, item)| item)
+            .collect();
+        matching.sort_unstable();
+        matching
+    }
+
+    #[test]
+    fn can_query_items_within_box() {
+        for split_axis in [SplitAxis::RoundRobin, SplitAxis::WidestSpread] {
+            let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(61);
+            let content: Vec<([f64; 3], u32)> = (0..2_000)
+                .map(|item| ([rng.gen(), rng.gen::<f64>() * 10.0, rng.gen()], item))
+                .collect();
+
+            let mut tree: KdTree<f64, u32, 3, 8, u32> =
+                KdTree::with_capacity_and_split_axis(content.len(), split_axis);
+            for (point, item) in &content {
+                tree.add(point, *item);
+            }
+
+            for _ in 0..100 {
+                let corners: [[f64; 3]; 2] = [
+                    [rng.gen(), rng.gen::<f64>() * 10.0, rng.gen()],
+                    [rng.gen(), rng.gen::<f64>() * 10.0, rng.gen()],
+                ];
+                let min = [0, 1, 2].map(|dim| corners[0][dim].min(corners[1][dim]));
+                let max = [0, 1, 2].map(|dim| corners[0][dim].max(corners[1][dim]));
+                let expected = linear_search(&content, &min, &max);
+
+                let mut result = tree.within_box(&min, &max);
+                result.sort_unstable();
+                assert_eq!(result, expected);
+
+                let mut result: Vec<_> = tree.within_box_iter(&min, &max).collect();
+                result.sort_unstable();
+                assert_eq!(result, expected);
+
+                assert_eq!(tree.count_in_box(&min, &max), expected.len());
+            }
+
+            let everything = [f64::NEG_INFINITY; 3];
+            assert_eq!(
+                tree.count_in_box(&everything, &[f64::INFINITY; 3]),
+                content.len()
+            );
+        }
+    }
+
+    #[test]
+    fn box_bounds_are_inclusive() {
+        let mut tree: KdTree<f64, u32, 2, 4, u32> = KdTree::new();
+        for item in 0..100 {
+            tree.add(&[(item % 10) as f64, (item / 10) as f64], item);
+        }
+
+        let mut result = tree.within_box(&[2.0, 3.0], &[4.0, 3.0]);
+        result.sort_unstable();
+
+        assert_eq!(result, vec![32, 33, 34]);
+        assert_eq!(tree.count_in_box(&[2.0, 3.0], &[4.0, 5.0]), 9);
+        assert_eq!(tree.count_in_box(&[4.0, 5.0], &[2.0, 3.0]), 0);
+    }
+}
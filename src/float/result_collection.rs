@@ -1,10 +1,9 @@
-use crate::float::kdtree::Axis;
 use crate::nearest_neighbour::NearestNeighbour;
-use crate::traits::Content;
+use crate::traits::{Content, Unbounded};
 use sorted_vec::SortedVec;
 use std::collections::BinaryHeap;
 
-pub trait ResultCollection<A: Axis, T: Content> {
+pub trait ResultCollection<A: Copy + PartialOrd + Unbounded, T: Content> {
     fn new_with_capacity(capacity: usize) -> Self;
     fn add(&mut self, entry: NearestNeighbour<A, T>);
     fn max_dist(&self) -> A;
@@ -12,7 +11,9 @@ pub trait ResultCollection<A: Axis, T: Content> {
     fn into_sorted_vec(self) -> Vec<NearestNeighbour<A, T>>;
 }
 
-impl<A: Axis, T: Content> ResultCollection<A, T> for BinaryHeap<NearestNeighbour<A, T>> {
+impl<A: Copy + PartialOrd + Unbounded, T: Content> ResultCollection<A, T>
+    for BinaryHeap<NearestNeighbour<A, T>>
+{
     fn new_with_capacity(capacity: usize) -> Self {
         BinaryHeap::with_capacity(capacity)
     }
@@ -29,9 +30,9 @@ impl<A: Axis, T: Content> ResultCollection<A, T> for BinaryHeap<NearestNeighbour
     }
     fn max_dist(&self) -> A {
         if self.len() < self.capacity() {
-            A::infinity()
+            A::unbounded()
         } else {
-            self.peek().map_or(A::infinity(), |n| n.distance)
+            self.peek().map_or(A::unbounded(), |n| n.distance)
         }
     }
     fn into_vec(self) -> Vec<NearestNeighbour<A, T>> {
@@ -42,7 +43,9 @@ impl<A: Axis, T: Content> ResultCollection<A, T> for BinaryHeap<NearestNeighbour
     }
 }
 
-impl<A: Axis, T: Content> ResultCollection<A, T> for Vec<NearestNeighbour<A, T>> {
+impl<A: Copy + PartialOrd + Unbounded, T: Content> ResultCollection<A, T>
+    for Vec<NearestNeighbour<A, T>>
+{
     fn new_with_capacity(capacity: usize) -> Self {
         Vec::with_capacity(capacity)
     }
@@ -52,7 +55,7 @@ impl<A: Axis, T: Content> ResultCollection<A, T> for Vec<NearestNeighbour<A, T>>
     }
 
     fn max_dist(&self) -> A {
-        A::infinity()
+        A::unbounded()
     }
 
     fn into_vec(self) -> Vec<NearestNeighbour<A, T>> {
@@ -65,7 +68,9 @@ impl<A: Axis, T: Content> ResultCollection<A, T> for Vec<NearestNeighbour<A, T>>
     }
 }
 
-impl<A: Axis, T: Content> ResultCollection<A, T> for SortedVec<NearestNeighbour<A, T>> {
+impl<A: Copy + PartialOrd + Unbounded, T: Content> ResultCollection<A, T>
+    for SortedVec<NearestNeighbour<A, T>>
+{
     fn new_with_capacity(capacity: usize) -> Self {
         SortedVec::with_capacity(capacity)
     }
@@ -82,9 +87,9 @@ impl<A: Axis, T: Content> ResultCollection<A, T> for SortedVec<NearestNeighbour<
 
     fn max_dist(&self) -> A {
         if self.len() < self.capacity() {
-            A::infinity()
+            A::unbounded()
         } else {
-            self.last().map_or(A::infinity(), |n| n.distance)
+            self.last().map_or(A::unbounded(), |n| n.distance)
         }
     }
 
@@ -97,32 +102,86 @@ impl<A: Axis, T: Content> ResultCollection<A, T> for SortedVec<NearestNeighbour<
     }
 }
 
-pub struct BinaryHeapRef<'v, A: Axis, T: Content> {
+/// Restores the max-heap property of `heap` after the element at `idx` may have increased.
+fn sift_up<A: Copy + PartialOrd + Unbounded, T: Content>(
+    heap: &mut [NearestNeighbour<A, T>],
+    mut idx: usize,
+) {
+    while idx > 0 {
+        let parent = (idx - 1) / 2;
+        if heap[idx] <= heap[parent] {
+            break;
+        }
+        heap.swap(idx, parent);
+        idx = parent;
+    }
+}
+
+/// Restores the max-heap property of `heap` after the element at `idx` may have decreased.
+fn sift_down<A: Copy + PartialOrd + Unbounded, T: Content>(
+    heap: &mut [NearestNeighbour<A, T>],
+    mut idx: usize,
+) {
+    loop {
+        let left = 2 * idx + 1;
+        if left >= heap.len() {
+            break;
+        }
+        let right = left + 1;
+        let largest = if right < heap.len() && heap[right] > heap[left] {
+            right
+        } else {
+            left
+        };
+        if heap[idx] >= heap[largest] {
+            break;
+        }
+        heap.swap(idx, largest);
+        idx = largest;
+    }
+}
+
+/// Inserts `entry` into the already-sorted first `len` elements of `sorted`,
+/// shifting later elements right. The last element is dropped if `len == sorted.len()`.
+fn insert_sorted<A: Copy + PartialOrd + Unbounded, T: Content>(
+    sorted: &mut [NearestNeighbour<A, T>],
+    len: usize,
+    entry: NearestNeighbour<A, T>,
+) {
+    let insert_at = sorted[..len].partition_point(|n| *n <= entry);
+    let end = len.min(sorted.len() - 1);
+    sorted.copy_within(insert_at..end, insert_at + 1);
+    sorted[insert_at] = entry;
+}
+
+pub struct BinaryHeapRef<'v, A: Copy + PartialOrd + Unbounded, T: Content> {
     pub buf: &'v mut Vec<NearestNeighbour<A, T>>,
     pub cap: usize,
 }
 
-impl<'v, A: Axis, T: Content> ResultCollection<A, T> for BinaryHeapRef<'v, A, T> {
+impl<'v, A: Copy + PartialOrd + Unbounded, T: Content> ResultCollection<A, T>
+    for BinaryHeapRef<'v, A, T>
+{
     fn new_with_capacity(_capacity: usize) -> Self {
         unimplemented!()
     }
 
     fn add(&mut self, entry: NearestNeighbour<A, T>) {
         if self.buf.len() < self.cap {
+            let idx = self.buf.len();
             self.buf.push(entry);
-        } else {
-            let max_heap_value = &mut self.buf[0];
-            if entry < *max_heap_value {
-                *max_heap_value = entry;
-            }
+            sift_up(self.buf, idx);
+        } else if entry < self.buf[0] {
+            self.buf[0] = entry;
+            sift_down(self.buf, 0);
         }
     }
 
     fn max_dist(&self) -> A {
         if self.buf.len() < self.cap {
-            A::infinity()
+            A::unbounded()
         } else {
-            self.buf.get(0).map_or(A::infinity(), |n| n.distance)
+            self.buf.first().map_or(A::unbounded(), |n| n.distance)
         }
     }
 
@@ -135,12 +194,14 @@ impl<'v, A: Axis, T: Content> ResultCollection<A, T> for BinaryHeapRef<'v, A, T>
     }
 }
 
-pub struct SortedVecRef<'v, A: Axis, T: Content> {
+pub struct SortedVecRef<'v, A: Copy + PartialOrd + Unbounded, T: Content> {
     pub buf: &'v mut Vec<NearestNeighbour<A, T>>,
     pub cap: usize,
 }
 
-impl<'v, A: Axis, T: Content> ResultCollection<A, T> for SortedVecRef<'v, A, T> {
+impl<'v, A: Copy + PartialOrd + Unbounded, T: Content> ResultCollection<A, T>
+    for SortedVecRef<'v, A, T>
+{
     fn new_with_capacity(_capacity: usize) -> Self {
         unimplemented!()
     }
@@ -152,16 +213,15 @@ impl<'v, A: Axis, T: Content> ResultCollection<A, T> for SortedVecRef<'v, A, T>
                 Ok (insert_at) | Err (insert_at) => self.buf.insert(insert_at, entry),
             };
         } else if entry < *self.buf.last().unwrap() {
-            self.buf.pop();
-            self.buf.push(entry);
+            insert_sorted(self.buf, len, entry);
         }
     }
 
     fn max_dist(&self) -> A {
         if self.buf.len() < self.cap {
-            A::infinity()
+            A::unbounded()
         } else {
-            self.buf.last().map_or(A::infinity(), |n| n.distance)
+            self.buf.last().map_or(A::unbounded(), |n| n.distance)
         }
     }
 
@@ -174,12 +234,15 @@ impl<'v, A: Axis, T: Content> ResultCollection<A, T> for SortedVecRef<'v, A, T>
     }
 }
 
-pub struct ArrayRef<'v, A: Axis, T: Content, const N: usize> {
+#[allow(dead_code)]
+pub struct ArrayRef<'v, A: Copy + PartialOrd + Unbounded, T: Content, const N: usize> {
     pub array: &'v mut [NearestNeighbour<A, T>; N],
     pub len: usize,
 }
 
-impl<'v, A: Axis, T: Content, const N: usize> ResultCollection<A, T> for ArrayRef<'v, A, T, N> {
+impl<'v, A: Copy + PartialOrd + Unbounded, T: Content, const N: usize> ResultCollection<A, T>
+    for ArrayRef<'v, A, T, N>
+{
     fn new_with_capacity(_capacity: usize) -> Self {
         unimplemented!()
     }
@@ -192,7 +255,7 @@ impl<'v, A: Axis, T: Content, const N: usize> ResultCollection<A, T> for ArrayRe
     }
 
     fn max_dist(&self) -> A {
-        A::infinity()
+        A::unbounded()
     }
 
     fn into_vec(self) -> Vec<NearestNeighbour<A, T>> {
@@ -204,40 +267,32 @@ impl<'v, A: Axis, T: Content, const N: usize> ResultCollection<A, T> for ArrayRe
     }
 }
 
-pub struct SortedArrayRef<'v, A: Axis, T: Content, const N: usize> {
+pub struct SortedArrayRef<'v, A: Copy + PartialOrd + Unbounded, T: Content, const N: usize> {
     pub array: &'v mut [NearestNeighbour<A, T>; N],
     pub len: usize,
 }
 
-impl<'v, A: Axis, T: Content, const N: usize> ResultCollection<A, T> for SortedArrayRef<'v, A, T, N> {
+impl<'v, A: Copy + PartialOrd + Unbounded, T: Content, const N: usize> ResultCollection<A, T>
+    for SortedArrayRef<'v, A, T, N>
+{
     fn new_with_capacity(_capacity: usize) -> Self {
         unimplemented!()
     }
 
     fn add(&mut self, entry: NearestNeighbour<A, T>) {
-        let len = self.len;
-        if len < N {
-            for i in 0..self.len {
-                if entry <= self.array[i] {
-                    for k in (i+1)..N {
-                        self.array[k] = self.array[k-1];
-                    }
-
-                    self.array[i] = entry;
-                    if self.len < N { self.len += 1 }
-                    break;
-                }
-            }
-        } else if entry < self.array[N-1] {
-            self.array[N-1] = entry;
+        if self.len < N {
+            insert_sorted(self.array, self.len, entry);
+            self.len += 1;
+        } else if N > 0 && entry < self.array[N - 1] {
+            insert_sorted(self.array, N, entry);
         }
     }
 
     fn max_dist(&self) -> A {
         if self.len < N {
-            A::infinity()
+            A::unbounded()
         } else {
-            self.array[N-1].distance
+            self.array[N - 1].distance
         }
     }
 
@@ -250,12 +305,14 @@ impl<'v, A: Axis, T: Content, const N: usize> ResultCollection<A, T> for SortedA
     }
 }
 
-pub struct BinaryHeapArray<'v, A: Axis, T: Content, const N: usize> {
+pub struct BinaryHeapArray<'v, A: Copy + PartialOrd + Unbounded, T: Content, const N: usize> {
     pub array: &'v mut [NearestNeighbour<A, T>; N],
     pub len: usize,
 }
 
-impl<'v, A: Axis, T: Content, const N: usize> ResultCollection<A, T> for BinaryHeapArray<'v, A, T, N> {
+impl<'v, A: Copy + PartialOrd + Unbounded, T: Content, const N: usize> ResultCollection<A, T>
+    for BinaryHeapArray<'v, A, T, N>
+{
     fn new_with_capacity(_capacity: usize) -> Self {
         unimplemented!()
     }
@@ -264,17 +321,16 @@ impl<'v, A: Axis, T: Content, const N: usize> ResultCollection<A, T> for BinaryH
         if self.len < N {
             self.array[self.len] = entry;
             self.len += 1;
-        } else {
-            let max_heap_value = &mut self.array[0];
-            if entry < *max_heap_value {
-                *max_heap_value = entry;
-            }
+            sift_up(&mut self.array[..self.len], self.len - 1);
+        } else if N > 0 && entry < self.array[0] {
+            self.array[0] = entry;
+            sift_down(&mut self.array[..], 0);
         }
     }
 
     fn max_dist(&self) -> A {
         if self.len < N {
-            A::infinity()
+            A::unbounded()
         } else {
             self.array[0].distance
         }
@@ -287,4 +343,104 @@ impl<'v, A: Axis, T: Content, const N: usize> ResultCollection<A, T> for BinaryH
     fn into_sorted_vec(self) -> Vec<NearestNeighbour<A, T>> {
         unimplemented!()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    const CAP: usize = 8;
+
+    fn random_entries() -> Vec<NearestNeighbour<f64, u32>> {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+        (0..200)
+            .map(|item| NearestNeighbour {
+                distance: rng.gen::<f64>(),
+                item,
+            })
+            .collect()
+    }
+
+    fn expected_nearest(entries: &[NearestNeighbour<f64, u32>]) -> Vec<NearestNeighbour<f64, u32>> {
+        let mut expected = entries.to_vec();
+        expected.sort_unstable();
+        expected.truncate(CAP);
+        expected
+    }
+
+    #[test]
+    fn binary_heap_ref_keeps_the_nearest_entries() {
+        let entries = random_entries();
+        let mut buf = Vec::new();
+        let mut results = BinaryHeapRef {
+            buf: &mut buf,
+            cap: CAP,
+        };
+        for &entry in &entries {
+            results.add(entry);
+        }
+
+        let expected = expected_nearest(&entries);
+        assert_eq!(results.max_dist(), expected[CAP - 1].distance);
+
+        buf.sort_unstable();
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn sorted_vec_ref_keeps_the_nearest_entries_in_order() {
+        let entries = random_entries();
+        let mut buf = Vec::new();
+        let mut results = SortedVecRef {
+            buf: &mut buf,
+            cap: CAP,
+        };
+        for &entry in &entries {
+            results.add(entry);
+        }
+
+        assert_eq!(buf, expected_nearest(&entries));
+    }
+
+    #[test]
+    fn sorted_array_ref_keeps_the_nearest_entries_in_order() {
+        let entries = random_entries();
+        let mut array = [NearestNeighbour {
+            distance: 0.0,
+            item: 0,
+        }; CAP];
+        let mut results = SortedArrayRef {
+            array: &mut array,
+            len: 0,
+        };
+        for &entry in &entries {
+            results.add(entry);
+        }
+
+        assert_eq!(results.len, CAP);
+        assert_eq!(array.to_vec(), expected_nearest(&entries));
+    }
+
+    #[test]
+    fn binary_heap_array_keeps_the_nearest_entries() {
+        let entries = random_entries();
+        let mut array = [NearestNeighbour {
+            distance: 0.0,
+            item: 0,
+        }; CAP];
+        let mut results = BinaryHeapArray {
+            array: &mut array,
+            len: 0,
+        };
+        for &entry in &entries {
+            results.add(entry);
+        }
+
+        let expected = expected_nearest(&entries);
+        assert_eq!(results.max_dist(), expected[CAP - 1].distance);
+
+        array.sort_unstable();
+        assert_eq!(array.to_vec(), expected);
+    }
+}
@@ -48,6 +48,15 @@ pub(crate) fn update_nearest_dists_within_autovec<A: Axis, T: Content, R>(
         });
 }
 
+#[inline]
+pub(crate) fn count_dists_within_autovec<A: Axis>(dists: &[A], radius: A) -> usize {
+    // Counting with a branchless sum rather than filtering lets this autovectorize
+    dists
+        .iter()
+        .map(|&distance| usize::from(distance < radius))
+        .sum()
+}
+
 #[inline]
 pub(crate) fn update_best_dists_within_autovec<A: Axis, T: Content>(
     dists: &[A],
@@ -79,7 +88,7 @@ pub(crate) fn update_best_dists_within_autovec<A: Axis, T: Content>(
 #[cfg(test)]
 mod tests {
     use crate::float_leaf_slice::fallback::{
-        update_best_dists_within_autovec, update_nearest_dist_autovec,
+        count_dists_within_autovec, update_best_dists_within_autovec, update_nearest_dist_autovec,
         update_nearest_dists_within_autovec,
     };
     use crate::{BestNeighbour, NearestNeighbour};
@@ -113,6 +122,15 @@ mod tests {
         assert_eq!(best_item, 5u32);
     }
 
+    #[test]
+    fn test_count_dists_within_autovec_counts_dists_below_radius() {
+        let dists = [10000f64, 200f64, 20f64, 20000f64, 2f64];
+
+        assert_eq!(count_dists_within_autovec(&dists[..], 200f64), 2);
+        assert_eq!(count_dists_within_autovec(&dists[..], 201f64), 3);
+        assert_eq!(count_dists_within_autovec(&dists[..], 1f64), 0);
+    }
+
     #[test]
     fn test_update_nearest_dists_within_autovec_leaves_nearest() {
         let dists = [10000f64, 20000f64, 20f64];
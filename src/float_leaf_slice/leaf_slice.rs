@@ -21,7 +21,7 @@ use super::f64_avx2::get_best_from_dists_f64_avx2;*/
 // use super::f64_avx512::get_best_from_dists_f64_avx512;
 
 use super::fallback::{
-    update_best_dists_within_autovec, update_nearest_dist_autovec,
+    count_dists_within_autovec, update_best_dists_within_autovec, update_nearest_dist_autovec,
     update_nearest_dists_within_autovec,
 };
 
@@ -145,6 +145,10 @@ where
         usize: Cast<T>,
         Self: Axis + Sized;
 
+    fn count_dists_within<const C: usize>(acc: [Self; C], radius: Self) -> usize
+    where
+        Self: Axis + Sized;
+
     fn update_best_dists_within<const C: usize>(
         acc: [Self; C],
         items: &[T; C],
@@ -178,7 +182,7 @@ where
     }
 
     #[inline]
-    fn as_full_chunks<const C: usize>(&self) -> LeafFixedSliceIterator<A, T, K, C> {
+    fn as_full_chunks<const C: usize>(&self) -> LeafFixedSliceIterator<'_, A, T, K, C> {
         let points_iterators = self.content_points.map(|i| i.chunks_exact(C));
         let items_iterator = self.content_items.chunks_exact(C);
 
@@ -219,6 +223,90 @@ where
         }
     }
 
+    /// Updates `best_dist` and `best_item` with the nearest point to `query` whose item
+    /// isn't `excluded`. Items are only compared when the nearest point in a chunk is closer
+    /// than the best so far.
+    #[inline]
+    pub(crate) fn nearest_one_excluding<D>(
+        &self,
+        query: &[A; K],
+        excluded: T,
+        best_dist: &mut A,
+        best_item: &mut T,
+    ) where
+        D: DistanceMetric<A, K>,
+    {
+        let chunk_iter = self.as_full_chunks::<CHUNK_SIZE>();
+        let (remainder_points, remainder_items) = chunk_iter.remainder();
+        for chunk in chunk_iter {
+            let dists = A::dists_for_chunk::<D, CHUNK_SIZE>(chunk.0, query);
+            let (prev_dist, prev_item) = (*best_dist, *best_item);
+            A::update_nearest_dist(dists, chunk.1, best_dist, best_item);
+
+            // the excluded item was the nearest in this chunk, so look for the nearest of the rest
+            if *best_item == excluded {
+                *best_dist = prev_dist;
+                *best_item = prev_item;
+
+                for (&distance, &item) in dists.iter().zip(chunk.1.iter()) {
+                    if distance < *best_dist && item != excluded {
+                        *best_dist = distance;
+                        *best_item = item;
+                    }
+                }
+            }
+        }
+
+        #[allow(clippy::needless_range_loop)]
+        for idx in 0..remainder_items.len() {
+            let mut distance = A::zero();
+            (0..K).step_by(1).for_each(|dim| {
+                distance += D::dist1(remainder_points[dim][idx], query[dim]);
+            });
+
+            if distance < *best_dist && remainder_items[idx] != excluded {
+                *best_dist = distance;
+                *best_item = remainder_items[idx];
+            }
+        }
+    }
+
+    /// Adds the points nearest to `query` whose items aren't `excluded` to `results`.
+    /// Items are only compared for points that `results` would accept.
+    #[inline]
+    pub(crate) fn nearest_n_excluding<D, R>(&self, query: &[A; K], excluded: T, results: &mut R)
+    where
+        D: DistanceMetric<A, K>,
+        R: ResultCollection<A, T>,
+    {
+        let chunk_iter = self.as_full_chunks::<CHUNK_SIZE>();
+        let (remainder_points, remainder_items) = chunk_iter.remainder();
+        for chunk in chunk_iter {
+            let dists = A::dists_for_chunk::<D, CHUNK_SIZE>(chunk.0, query);
+
+            for (&distance, &item) in dists.iter().zip(chunk.1.iter()) {
+                if distance < results.max_dist() && item != excluded {
+                    results.add(NearestNeighbour { distance, item });
+                }
+            }
+        }
+
+        #[allow(clippy::needless_range_loop)]
+        for idx in 0..remainder_items.len() {
+            let mut distance = A::zero();
+            (0..K).step_by(1).for_each(|dim| {
+                distance += D::dist1(remainder_points[dim][idx], query[dim]);
+            });
+
+            if distance < results.max_dist() && remainder_items[idx] != excluded {
+                results.add(NearestNeighbour {
+                    distance,
+                    item: remainder_items[idx],
+                });
+            }
+        }
+    }
+
     #[inline]
     pub(crate) fn nearest_n_within<D, R>(&self, query: &[A; K], radius: A, results: &mut R)
     where
@@ -243,12 +331,122 @@ where
             if distance < radius {
                 results.add(NearestNeighbour {
                     distance,
-                    item: *unsafe { self.content_items.get_unchecked(idx) },
+                    item: *unsafe { remainder_items.get_unchecked(idx) },
                 });
             }
         }
     }
 
+    /// Adds the points within `radius` of `query` whose items are accepted by `filter`
+    /// to `results`. `filter` is only called for points that `results` would accept.
+    #[inline]
+    pub(crate) fn nearest_n_within_filtered<D, R, F>(
+        &self,
+        query: &[A; K],
+        radius: A,
+        results: &mut R,
+        filter: &F,
+    ) where
+        D: DistanceMetric<A, K>,
+        R: ResultCollection<A, T>,
+        F: Fn(T) -> bool,
+    {
+        let chunk_iter = self.as_full_chunks::<CHUNK_SIZE>();
+        let (remainder_points, remainder_items) = chunk_iter.remainder();
+        for chunk in chunk_iter {
+            let dists = A::dists_for_chunk::<D, CHUNK_SIZE>(chunk.0, query);
+
+            for (&distance, &item) in dists.iter().zip(chunk.1.iter()) {
+                if distance < radius && distance < results.max_dist() && filter(item) {
+                    results.add(NearestNeighbour { distance, item });
+                }
+            }
+        }
+
+        #[allow(clippy::needless_range_loop)]
+        for idx in 0..remainder_items.len() {
+            let mut distance = A::zero();
+            (0..K).step_by(1).for_each(|dim| {
+                distance += D::dist1(remainder_points[dim][idx], query[dim]);
+            });
+
+            let item = remainder_items[idx];
+            if distance < radius && distance < results.max_dist() && filter(item) {
+                results.add(NearestNeighbour { distance, item });
+            }
+        }
+    }
+
+    /// Returns the first point found within `radius` of `query`, if any
+    #[inline]
+    pub(crate) fn any_within<D>(&self, query: &[A; K], radius: A) -> Option<NearestNeighbour<A, T>>
+    where
+        D: DistanceMetric<A, K>,
+    {
+        let chunk_iter = self.as_full_chunks::<CHUNK_SIZE>();
+        let (remainder_points, remainder_items) = chunk_iter.remainder();
+        for chunk in chunk_iter {
+            let dists = A::dists_for_chunk::<D, CHUNK_SIZE>(chunk.0, query);
+
+            if let Some(idx) = dists.iter().position(|&distance| distance < radius) {
+                return Some(NearestNeighbour {
+                    distance: dists[idx],
+                    item: chunk.1[idx],
+                });
+            }
+        }
+
+        #[allow(clippy::needless_range_loop)]
+        for idx in 0..remainder_items.len() {
+            let mut distance = A::zero();
+            (0..K).step_by(1).for_each(|dim| {
+                distance += D::dist1(remainder_points[dim][idx], query[dim]);
+            });
+
+            if distance < radius {
+                return Some(NearestNeighbour {
+                    distance,
+                    item: remainder_items[idx],
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Adds the number of points within `radius` of `query` to `count`, stopping
+    /// between chunks once it reaches `limit`
+    #[inline]
+    pub(crate) fn count_within<D>(&self, query: &[A; K], radius: A, limit: usize, count: &mut usize)
+    where
+        D: DistanceMetric<A, K>,
+    {
+        let chunk_iter = self.as_full_chunks::<CHUNK_SIZE>();
+        let (remainder_points, remainder_items) = chunk_iter.remainder();
+        for chunk in chunk_iter {
+            if *count >= limit {
+                return;
+            }
+
+            let dists = A::dists_for_chunk::<D, CHUNK_SIZE>(chunk.0, query);
+            *count += A::count_dists_within(dists, radius);
+        }
+
+        #[allow(clippy::needless_range_loop)]
+        for idx in 0..remainder_items.len() {
+            if *count >= limit {
+                return;
+            }
+
+            let mut distance = A::zero();
+            (0..K).step_by(1).for_each(|dim| {
+                distance += D::dist1(remainder_points[dim][idx], query[dim]);
+            });
+
+            *count += usize::from(distance < radius);
+        }
+    }
+
     #[inline]
     pub(crate) fn best_n_within<D>(
         &self,
@@ -341,6 +539,11 @@ where
         update_nearest_dists_within_autovec(&acc, items, radius, results)
     }
 
+    #[inline]
+    fn count_dists_within<const C: usize>(acc: [f64; C], radius: f64) -> usize {
+        count_dists_within_autovec(&acc, radius)
+    }
+
     #[inline]
     fn update_best_dists_within<const C: usize>(
         acc: [f64; C],
@@ -427,6 +630,11 @@ where
         update_nearest_dists_within_autovec(&acc, items, radius, results)
     }
 
+    #[inline]
+    fn count_dists_within<const C: usize>(acc: [f32; C], radius: f32) -> usize {
+        count_dists_within_autovec(&acc, radius)
+    }
+
     #[inline]
     fn update_best_dists_within<const C: usize>(
         acc: [f32; C],
@@ -464,6 +672,76 @@ where
     }
 }
 
+#[cfg(feature = "f16")]
+impl<T: Content> LeafSliceFloat<T> for half::f16
+where
+    T: Content,
+    usize: Cast<T>,
+{
+    #[inline]
+    fn update_nearest_dist<const C: usize>(
+        acc: [half::f16; C],
+        items: &[T; C],
+        best_dist: &mut half::f16,
+        best_item: &mut T,
+    ) {
+        update_nearest_dist_autovec(&acc, items, best_dist, best_item)
+    }
+
+    #[inline]
+    fn update_nearest_dists_within<R, const C: usize>(
+        acc: [half::f16; C],
+        items: &[T; C],
+        radius: half::f16,
+        results: &mut R,
+    ) where
+        R: ResultCollection<half::f16, T>,
+    {
+        update_nearest_dists_within_autovec(&acc, items, radius, results)
+    }
+
+    #[inline]
+    fn count_dists_within<const C: usize>(acc: [half::f16; C], radius: half::f16) -> usize {
+        count_dists_within_autovec(&acc, radius)
+    }
+
+    #[inline]
+    fn update_best_dists_within<const C: usize>(
+        acc: [half::f16; C],
+        items: &[T; C],
+        radius: half::f16,
+        max_qty: usize,
+        results: &mut BinaryHeap<BestNeighbour<half::f16, T>>,
+    ) {
+        update_best_dists_within_autovec(&acc, items, radius, max_qty, results)
+    }
+}
+
+#[cfg(feature = "f16")]
+impl<T: Content, const K: usize> LeafSliceFloatChunk<T, K> for half::f16
+where
+    T: Content,
+    usize: Cast<T>,
+{
+    #[inline]
+    fn dists_for_chunk<D, const C: usize>(chunk: [&[Self; C]; K], query: &[Self; K]) -> [Self; C]
+    where
+        D: DistanceMetric<Self, K>,
+        Self: Sized,
+    {
+        let mut acc = [half::f16::ZERO; C];
+        (0..K).step_by(1).for_each(|dim| {
+            let qd = [query[dim]; C];
+
+            (0..C).step_by(1).for_each(|idx| {
+                acc[idx] += D::dist1(chunk[dim][idx], qd[idx]);
+            });
+        });
+
+        acc
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::float_leaf_slice::leaf_slice::{LeafFixedSlice, LeafSliceFloat};
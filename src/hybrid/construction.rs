@@ -1,13 +1,19 @@
-use crate::float_sss::kdtree::{Axis, KdTree, StemNode};
+use crate::error::KiddoError;
+use crate::hybrid::kdtree::{Axis, KdTree, LeafNode, Node, NodeIdx, StemNode};
 use crate::mirror_select_nth_unstable_by::mirror_select_nth_unstable_by;
-use crate::types::{Content, Index};
+use crate::traits::{Content, Index};
 use az::{Az, Cast};
 use std::ops::Rem;
 
-#[derive(Clone)]
-enum LeafParent<T> {
-    Stem(usize, bool),
-    DStem(T, bool),
+/// The node that a leaf hangs off, as found when traversing the tree
+#[derive(Clone, Copy, Debug)]
+enum LeafParent {
+    /// an unsplit static stem, all of whose items are in the leaf
+    Stem(usize),
+    /// a static stem on the bottom layer, and whether the leaf is its right child
+    BottomStem(usize, bool),
+    /// a dynamic stem, and whether the leaf is its right child
+    DStem(usize, bool),
 }
 
 impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
@@ -23,7 +29,7 @@ where
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
+    /// use kiddo::hybrid::kdtree::KdTree;
     ///
     /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
     ///
@@ -31,150 +37,66 @@ where
     ///
     /// assert_eq!(tree.size(), 1);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the item cannot be stored. See [`try_add`](`KdTree::try_add`) for the reasons
+    /// that this can happen.
     #[inline]
     pub fn add(&mut self, query: &[A; K], item: T) {
-        let mut split_dim = 0;
-        let mut is_right_child: bool = false;
-        let mut stem_idx: usize = 1;
-        let mut parent_idx = 1;
-        let mut val: A = A::zero();
-
-        while stem_idx < self.stems.capacity() {
-            val = *unsafe { self.stems.get_unchecked(stem_idx) };
-
-            if val.is_nan() {
-                // if bottom-level stem
-                // corresponding leaf node will be leftmost child
-                while stem_idx < self.stems.capacity().div_ceil(2) {
-                    stem_idx = stem_idx << 1;
-                }
-
-                let leaf_idx: IDX = (stem_idx * 2 - self.stems.capacity()).az::<IDX>();
-                return self.add_to_leaf(
-                    query,
-                    item,
-                    split_dim,
-                    leaf_idx,
-                    LeafParent::Stem(parent_idx, is_right_child),
-                );
-            }
-
-            parent_idx = stem_idx;
-            is_right_child = *unsafe { query.get_unchecked(split_dim) } > val;
-            stem_idx = (stem_idx << 1) + usize::from(is_right_child);
-            split_dim = (split_dim + 1).rem(K);
-        }
-
-        // non-NaN stem val on bottom layer
-        if (!is_right_child && val.is_lsb_set()) || (is_right_child && val.is_2lsb_set()) {
-            // we're moving to the dstems
-            stem_idx = stem_idx - self.stems.capacity();
-
-            while KdTree::<A, T, K, B, IDX>::is_stem_index(stem_idx.az::<IDX>()) {
-                let node = unsafe { self.dstems.get_unchecked(stem_idx) };
-
-                parent_idx = stem_idx;
-                is_right_child = *unsafe { query.get_unchecked(split_dim) } > val;
-
-                stem_idx = (*unsafe { node.children.get_unchecked(usize::from(is_right_child)) })
-                    .az::<usize>();
-                split_dim = (split_dim + 1).rem(K);
-            }
-            let leaf_idx: IDX = stem_idx.az::<IDX>() - IDX::leaf_offset();
-            return self.add_to_leaf(
-                query,
-                item,
-                split_dim,
-                leaf_idx,
-                LeafParent::DStem(parent_idx.az::<IDX>(), is_right_child),
-            );
-        } else {
-            let leaf_idx: IDX = (stem_idx - self.stems.capacity()).az::<IDX>();
-            return self.add_to_leaf(
-                query,
-                item,
-                split_dim,
-                leaf_idx,
-                LeafParent::Stem(parent_idx, is_right_child),
-            );
+        if let Err(err) = self.try_add(query, item) {
+            panic!("{err}");
         }
     }
 
+    /// Adds an item to the tree, returning an error rather than panicking if it can't be stored.
+    ///
+    /// An error is returned, and the tree is left holding the same items as before, if the leaf
+    /// that the item belongs in is full and can't be split, either because all of its items share
+    /// the same position on the axis that it would be split along
+    /// ([`KiddoError::UnsplittableBucket`]), or because the tree already has as many dynamic
+    /// stems or leaves as `IDX` can index ([`KiddoError::IndexOverflow`]).
+    ///
+    /// Unlike the [float](crate::float::kdtree::KdTree) and [fixed point](crate::fixed::kdtree::KdTree)
+    /// trees, which spread such items over more than one leaf, the hybrid tree can store at most
+    /// `B` items that share the same position on any one axis. Choose `B` accordingly.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::hybrid::kdtree::KdTree;
+    /// use kiddo::KiddoError;
+    ///
+    /// let mut tree: KdTree<f64, u32, 2, 4, u32> = KdTree::new();
+    ///
+    /// for item in 0..4 {
+    ///     assert_eq!(tree.try_add(&[1.0, 2.0], item), Ok(()));
+    /// }
+    ///
+    /// assert_eq!(tree.try_add(&[1.0, 2.0], 4), Err(KiddoError::UnsplittableBucket));
+    /// assert_eq!(tree.size(), 4);
+    /// ```
     #[inline]
-    pub(crate) fn add_to_optimized(&mut self, query: &[A; K], item: T) {
-        assert!(self.optimized_read_only);
-
-        let mut dim = 0;
-        let mut idx: usize = 1;
-        let mut val: A;
-
-        while idx < self.stems.capacity() {
-            val = *unsafe { self.stems.get_unchecked(idx) };
-
-            let is_right_child = *unsafe { query.get_unchecked(dim) } >= val;
-            idx = (idx << 1) + usize::from(is_right_child);
-            dim = (dim + 1).rem(K);
-        }
-        idx -= self.stems.len();
-
-        let node_size = (unsafe { self.leaves.get_unchecked_mut(idx) })
-            .size
-            .az::<usize>();
-
-        if node_size == B {
-            println!("Tree Stats: {:?}", self.generate_stats())
-        }
-
-        let node = unsafe { self.leaves.get_unchecked_mut(idx) };
-        debug_assert!(node.size.az::<usize>() < B);
-
-        *unsafe {
-            node.content_points
-                .get_unchecked_mut(node.size.az::<usize>())
-        } = *query;
-        *unsafe {
-            node.content_items
-                .get_unchecked_mut(node.size.az::<usize>())
-        } = item;
-
-        node.size = node.size + IDX::one();
-        self.size += 1;
-    }
-
-    fn add_to_leaf(
-        &mut self,
-        query: &[A; K],
-        item: T,
-        split_dim: usize,
-        leaf_idx: IDX,
-        parent: LeafParent<IDX>,
-    ) {
-        let node: &mut super::kdtree::LeafNode<A, T, K, B, IDX>;
-
-        let node_size = unsafe { self.leaves.get_unchecked_mut(leaf_idx.az::<usize>()) }.size;
-
-        if node_size == B.az::<IDX>() {
-            let (right_leaf_idx, split_val) = self.split(leaf_idx, split_dim, parent);
-            if query[split_dim] > split_val {
-                node = unsafe { self.leaves.get_unchecked_mut(right_leaf_idx.az::<usize>()) };
-            } else {
-                node = unsafe { self.leaves.get_unchecked_mut(leaf_idx.az::<usize>()) };
+    pub fn try_add(&mut self, query: &[A; K], item: T) -> Result<(), KiddoError> {
+        loop {
+            let (leaf_idx, split_dim, parent) = self.find_leaf(query);
+            let leaf_node = &mut self.leaves[leaf_idx];
+            let leaf_size = leaf_node.size.az::<usize>();
+
+            if leaf_size < B {
+                leaf_node.content_points[leaf_size] = *query;
+                leaf_node.content_items[leaf_size] = item;
+                leaf_node.size = leaf_node.size + IDX::one();
+                self.size += 1;
+
+                return Ok(());
             }
-        } else {
-            node = unsafe { self.leaves.get_unchecked_mut(leaf_idx.az::<usize>()) };
-        }
 
-        *unsafe {
-            node.content_points
-                .get_unchecked_mut(node.size.az::<usize>())
-        } = *query;
-        *unsafe {
-            node.content_items
-                .get_unchecked_mut(node.size.az::<usize>())
-        } = item;
-
-        node.size = node.size + IDX::one();
-        self.size += 1;
+            // the split may not leave any space on the side that the new item falls on
+            // if the masked split value of a bottom-layer static stem ends up in a different
+            // place to the median, so search again from the top rather than assuming
+            self.split(leaf_idx, split_dim, parent)?;
+        }
     }
 
     /// Removes an item from the tree.
@@ -185,7 +107,7 @@ where
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
+    /// use kiddo::hybrid::kdtree::KdTree;
     ///
     /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
     ///
@@ -199,248 +121,201 @@ where
     /// tree.remove(&[1.0, 2.0, 5.0], 200);
     /// assert_eq!(tree.size(), 0);
     /// ```
-    /*     #[inline]
+    #[inline]
     pub fn remove(&mut self, query: &[A; K], item: T) -> usize {
-        let mut stem_idx = self.root_index;
-        let mut split_dim = 0;
+        let (leaf_idx, _, _) = self.find_leaf(query);
+        let leaf_node = &mut self.leaves[leaf_idx];
         let mut removed: usize = 0;
 
-        while KdTree::<A, T, K, B, IDX>::is_stem_index(stem_idx) {
-            let Some(stem_node) = self.stems.get_mut(stem_idx.az::<usize>()) else {
-                return removed;
-            };
-
-            stem_idx = if query[split_dim] <= stem_node.split_val {
-                stem_node.left
+        let mut p_index = 0;
+        while p_index < leaf_node.size.az::<usize>() {
+            if &leaf_node.content_points[p_index] == query
+                && leaf_node.content_items[p_index] == item
+            {
+                leaf_node.content_points[p_index] =
+                    leaf_node.content_points[leaf_node.size.az::<usize>() - 1];
+                leaf_node.content_items[p_index] =
+                    leaf_node.content_items[leaf_node.size.az::<usize>() - 1];
+
+                self.size -= 1;
+                removed += 1;
+                leaf_node.size = leaf_node.size - IDX::one();
             } else {
-                stem_node.right
-            };
-
-            split_dim = (split_dim + 1).rem(K);
+                p_index += 1;
+            }
         }
 
-        let leaf_idx = stem_idx - IDX::leaf_offset();
-
-        if let Some(mut leaf_node) = self.leaves.get_mut(leaf_idx.az::<usize>()) {
-            let mut p_index = 0;
-            while p_index < leaf_node.size.az::<usize>() {
-                if &leaf_node.content_points[p_index] == query
-                    && leaf_node.content_items[p_index] == item
-                {
-                    leaf_node.content_points[p_index] =
-                        leaf_node.content_points[leaf_node.size.az::<usize>() - 1];
-                    leaf_node.content_items[p_index] =
-                        leaf_node.content_items[leaf_node.size.az::<usize>() - 1];
-
-                    self.size -= T::one();
-                    removed += 1;
-                    leaf_node.size = leaf_node.size - IDX::one();
-                } else {
-                    p_index += 1;
+        removed
+    }
+
+    /// Returns the index of the leaf that `query` belongs in, the dimension that
+    /// the leaf would be split along, and the node that the leaf hangs off.
+    fn find_leaf(&self, query: &[A; K]) -> (usize, usize, LeafParent) {
+        let mut node_idx = Self::ROOT;
+        let mut split_dim = 0;
+        let mut parent = LeafParent::Stem(1);
+
+        loop {
+            match self.node(node_idx) {
+                Node::Stem {
+                    split_val,
+                    children,
+                } => {
+                    let is_right_child = query[split_dim] >= split_val;
+
+                    parent = match node_idx {
+                        NodeIdx::Stem(stem_idx) => LeafParent::BottomStem(stem_idx, is_right_child),
+                        NodeIdx::DStem(dstem_idx) => LeafParent::DStem(dstem_idx, is_right_child),
+                        NodeIdx::Leaf(_) => unreachable!(),
+                    };
+
+                    node_idx = children[usize::from(is_right_child)];
+                    split_dim = (split_dim + 1).rem(K);
+                }
+                Node::Leaf(leaf_idx) => {
+                    if let NodeIdx::Stem(stem_idx) = node_idx {
+                        parent = LeafParent::Stem(stem_idx);
+                    }
+
+                    return (leaf_idx, split_dim, parent);
                 }
             }
         }
+    }
 
-        removed
-    } */
-
-    fn split(&mut self, leaf_idx: IDX, split_dim: usize, parent: LeafParent<IDX>) -> (IDX, A) {
-        let mut split_val: A;
-        let pivot_idx: IDX;
+    fn split(
+        &mut self,
+        leaf_idx: usize,
+        split_dim: usize,
+        parent: LeafParent,
+    ) -> Result<(), KiddoError> {
+        let needs_new_leaf = !matches!(parent, LeafParent::Stem(_));
+        let needs_new_dstem = matches!(parent, LeafParent::DStem(..));
+        if (needs_new_leaf && self.leaves.len() >= <IDX as Index>::capacity_with_bucket_size(1))
+            || (needs_new_dstem && self.dstems.len() >= IDX::leaf_offset().az::<usize>())
         {
-            let orig = unsafe { self.leaves.get_unchecked_mut(leaf_idx.az::<usize>()) };
-            pivot_idx = (B / 2).az::<IDX>();
+            return Err(KiddoError::IndexOverflow);
+        }
+
+        let stem_capacity = self.stems.len();
+        let mut split_val = {
+            let orig = &mut self.leaves[leaf_idx];
 
             // partially sort original leaf so that first half of content
             // is sorted ascending
             mirror_select_nth_unstable_by(
                 &mut orig.content_points,
                 &mut orig.content_items,
-                pivot_idx.az::<usize>(),
-                |a, b| unsafe {
-                    a.get_unchecked(split_dim)
-                        .partial_cmp(b.get_unchecked(split_dim))
+                B / 2,
+                |a, b| {
+                    a[split_dim]
+                        .partial_cmp(&b[split_dim])
                         .expect("Leaf node sort failed.")
                 },
             );
 
-            split_val = unsafe {
-                *orig
-                    .content_points
-                    .get_unchecked(pivot_idx.az::<usize>())
-                    .get_unchecked(split_dim)
-            };
-        }
+            let mut split_vals = orig.content_points.map(|point| point[split_dim]);
+            let split_val = Self::choose_split_val(&mut split_vals);
 
-        // determine where to copy the upper half of points to
-        let mut right_idx;
-
-        match parent {
-            LeafParent::Stem(parent_idx, _) if parent_idx == 1 && self.stems[1].is_nan() => {
-                // parent is a static stem, root level, first split:
-
-                // 1) update stems to use the root static stem
-                //    as the first split plane
-                self.stems[1] = split_val;
-                self.stems[2] = A::nan();
-                self.stems[3] = A::nan();
-
-                // 2) update the destination to copy the split
-                //    points to be the left leaf of the left-most
-                //    final layer idx of the right child of the
-                //    root static stem
-                right_idx = 3.az::<IDX>();
-                while right_idx <= self.stems.capacity().az::<IDX>() {
-                    right_idx = right_idx * 2.az::<IDX>();
-                }
-                right_idx = right_idx - self.stems.capacity().az::<IDX>();
+            if split_vals.iter().all(|&val| val == split_val) {
+                return Err(KiddoError::UnsplittableBucket);
             }
-            LeafParent::Stem(parent_idx, is_right_child)
-                if parent_idx < self.stems.capacity().div_ceil(2) =>
-            {
-                // parent is a static stem, not first split, not bottom layer:
 
-                // 1) if the new stem is at base level, adjust the split
-                //    plane value to have it's LSB clear
-                if parent_idx >= self.stems.capacity() / 4 {
-                    split_val = split_val.with_lsb_clear().with_2lsb_clear();
-                }
-
-                let new_stem_idx = (parent_idx << 1) + usize::from(is_right_child);
-                self.stems[new_stem_idx] = split_val;
+            split_val
+        };
 
-                // if new stem is not bottom layer, add more NaNs
-                if new_stem_idx < self.stems.capacity().div_ceil(2) {
-                    self.stems[new_stem_idx * 2] = A::nan();
-                    self.stems[new_stem_idx * 2 + 1] = A::nan();
+        // determine where to move the points above the split value to
+        let right_idx = match parent {
+            LeafParent::Stem(stem_idx) => {
+                // parent is a static stem that hasn't been split yet. Use it
+                // as the split plane. Its children are already NaN if they are stems.
+                if Self::is_bottom_stem(stem_idx, stem_capacity) {
+                    split_val = Self::without_flags(split_val);
                 }
+                self.stems[stem_idx] = split_val;
 
-                // 4) determine the index of the leaf node we need to
-                //    populate with the split-off points
-                right_idx = (new_stem_idx * 2 + 1).az::<IDX>();
-                while right_idx <= self.stems.capacity().az::<IDX>() {
-                    right_idx = right_idx * 2.az::<IDX>();
-                }
-                right_idx = right_idx - (self.stems.capacity()).az::<IDX>();
+                Self::leftmost_leaf_index((stem_idx << 1) + 1, stem_capacity)
             }
-            LeafParent::Stem(parent_idx, is_right_child) => {
-                // parent is a static stem, bottom level:
-
-                // check to see if the stem is already used:
-                if self.stems[parent_idx].is_nan() {
-                    // 1) if it isn't, we will use it. Clear the split value
-                    //    LSB.
-
-                    split_val = split_val.with_lsb_clear().with_2lsb_clear();
-                    self.stems[parent_idx] = split_val;
+            LeafParent::BottomStem(stem_idx, is_right_child) => {
+                // parent is a static stem on the bottom layer. Replace the leaf
+                // with the dstem at the same index, flagging it in the parent's split value
+                if self.dstems.is_empty() {
+                    self.initialise_dstems()
+                }
 
-                    // 4) determine the index of the leaf node we need to
-                    //    populate with the split-off points
-                    right_idx = (parent_idx * 2 + 1).az::<IDX>();
-                    right_idx = right_idx - (self.stems.capacity() + 1).az::<IDX>();
+                let existing_split_val = self.stems[stem_idx];
+                self.stems[stem_idx] = if is_right_child {
+                    existing_split_val.with_2lsb_set()
                 } else {
-                    // 1) allocate space for twice as many dstem nodes
-                    //    as there are bottom-level stem values
-                    if self.dstems.capacity() == 0 {
-                        self.initialise_dstems()
-                    }
+                    existing_split_val.with_lsb_set()
+                };
 
-                    // 2) update the parent's split value to set one of the lowest 2 bits as reqd
-                    let existing_split_val = self.stems[parent_idx];
-                    if is_right_child {
-                        self.stems[parent_idx] = existing_split_val.with_2lsb_set();
-                    } else {
-                        self.stems[parent_idx] = existing_split_val.with_lsb_set();
-                    }
-                    //    TODO: check to see if this alters the pivot
-                    //    position (perhaps re-run mirror-select-unstable)
-
-                    // 3) insert a dstem at the same index as the leaf
-                    //    that we came from. It's left child should be
-                    //    the leaf index & MSB, and the right child should
-                    //    be the index of a new leaf node & MSB.
-                    let new_dstem =
-                        unsafe { self.dstems.get_unchecked_mut(leaf_idx.az::<usize>()) };
-                    new_dstem.split_val = split_val;
-                    new_dstem.children[0] = leaf_idx + IDX::leaf_offset();
-
-                    if self.leaves.capacity() < self.unreserved_leaf_idx + 1 {
-                        self.leaves.reserve(1);
-                    }
-                    right_idx = self.unreserved_leaf_idx.az::<IDX>();
-                    self.unreserved_leaf_idx += 1;
+                let right_idx = self.leaves.len();
+                self.leaves.push(LeafNode::new());
 
-                    new_dstem.children[1] = right_idx + IDX::leaf_offset();
-                }
+                self.dstems[leaf_idx] = StemNode {
+                    children: [
+                        leaf_idx.az::<IDX>() + IDX::leaf_offset(),
+                        right_idx.az::<IDX>() + IDX::leaf_offset(),
+                    ],
+                    split_val,
+                };
+
+                right_idx
             }
             LeafParent::DStem(parent_idx, is_right_child) => {
-                // parent is a dynamic stem:
+                // parent is a dynamic stem. Insert a new dstem between it and the leaf
+                let right_idx = self.leaves.len();
+                self.leaves.push(LeafNode::new());
 
-                let leaf_len = self.unreserved_leaf_idx;
-                let leaf_cap = self.leaves.capacity();
-
-                // 1) insert a new dstem
                 self.dstems.push(StemNode {
                     children: [
-                        leaf_idx + IDX::leaf_offset(),
-                        (leaf_len.az::<IDX>()) /*- IDX::one()*/ + IDX::leaf_offset(),
+                        leaf_idx.az::<IDX>() + IDX::leaf_offset(),
+                        right_idx.az::<IDX>() + IDX::leaf_offset(),
                     ],
                     split_val,
                 });
-                let new_stem_index: IDX = (self.dstems.len() - 1).az::<IDX>();
+                let new_stem_index = (self.dstems.len() - 1).az::<IDX>();
 
-                let parent_node =
-                    unsafe { self.dstems.get_unchecked_mut(parent_idx.az::<usize>()) };
-                if is_right_child {
-                    parent_node.children[1] = new_stem_index;
-                } else {
-                    parent_node.children[0] = new_stem_index;
-                }
+                self.dstems[parent_idx].children[usize::from(is_right_child)] = new_stem_index;
 
-                // 2) insert a new leaf, after any reserved or existing positions.
-                if leaf_cap < self.unreserved_leaf_idx + 1 {
-                    self.leaves.reserve(1);
-                }
-                right_idx = self.unreserved_leaf_idx.az::<IDX>();
-                self.unreserved_leaf_idx += 1;
+                right_idx
             }
-        }
-
-        let [right, orig] = unsafe {
-            self.leaves
-                .get_many_unchecked_mut([right_idx.az::<usize>(), leaf_idx.az::<usize>()])
         };
 
-        unsafe {
-            right
-                .content_points
-                .get_unchecked_mut(..(pivot_idx.az::<usize>()))
-                .copy_from_slice(
-                    orig.content_points
-                        .get_unchecked((pivot_idx.az::<usize>())..),
-                );
-            right
-                .content_items
-                .get_unchecked_mut(..(pivot_idx.az::<usize>()))
-                .copy_from_slice(
-                    orig.content_items
-                        .get_unchecked((pivot_idx.az::<usize>())..),
-                );
+        let mut right = LeafNode::new();
+        let orig = &mut self.leaves[leaf_idx];
+        let mut orig_size = 0;
+        let mut right_size = 0;
+
+        for idx in 0..B {
+            let point = orig.content_points[idx];
+            let item = orig.content_items[idx];
+
+            if point[split_dim] < split_val {
+                orig.content_points[orig_size] = point;
+                orig.content_items[orig_size] = item;
+                orig_size += 1;
+            } else {
+                right.content_points[right_size] = point;
+                right.content_items[right_size] = item;
+                right_size += 1;
+            }
         }
 
-        right.size = (B.az::<IDX>()) - pivot_idx;
-        orig.size = pivot_idx;
+        orig.size = orig_size.az::<IDX>();
+        right.size = right_size.az::<IDX>();
+        self.leaves[right_idx] = right;
 
-        (right_idx, split_val)
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{
-        float_sss::kdtree::{FloatLSB, KdTree},
-        types::Index,
-    };
+    use crate::error::KiddoError;
+    use crate::hybrid::kdtree::{FloatLSB, KdTree, LeafNode};
+    use crate::traits::Index;
     use rand::Rng;
 
     type Flt = f32;
@@ -592,7 +467,7 @@ mod tests {
         tree.initialise_dstems();
         tree.dstems[0].split_val = 0.5f32;
         tree.dstems[0].children = [
-            0 + <u32 as Index>::leaf_offset(),
+            <u32 as Index>::leaf_offset(),
             5 + <u32 as Index>::leaf_offset(),
         ];
 
@@ -605,26 +480,21 @@ mod tests {
             7 + <u32 as Index>::leaf_offset(),
         ];
 
+        for _ in 4..8 {
+            tree.leaves.push(LeafNode::new());
+        }
+
         tree.add(&point_1, item_1);
         assert_eq!(tree.size(), 1);
 
         assert_eq!(tree.leaves[0].content_items[0], 111);
         assert_eq!(&tree.leaves[0].content_points[0], &point_1);
 
-        tree.leaves.reserve(1);
-        tree.unreserved_leaf_idx += 1;
-
         tree.add(&point_2, item_2);
         assert_eq!(tree.size(), 2);
 
-        assert_eq!(
-            unsafe { tree.leaves.get_unchecked(5) }.content_items[0],
-            222
-        );
-        assert_eq!(
-            &unsafe { tree.leaves.get_unchecked(5) }.content_points[0],
-            &point_2
-        );
+        assert_eq!(tree.leaves[5].content_items[0], 222);
+        assert_eq!(&tree.leaves[5].content_points[0], &point_2);
 
         tree.add(&point_3, item_3);
         assert_eq!(tree.size(), 3);
@@ -641,17 +511,8 @@ mod tests {
         tree.add(&point_5, item_5);
         assert_eq!(tree.size(), 5);
 
-        tree.leaves.reserve(1);
-        tree.unreserved_leaf_idx += 1;
-
-        assert_eq!(
-            unsafe { tree.leaves.get_unchecked(6) }.content_items[0],
-            555
-        );
-        assert_eq!(
-            &unsafe { tree.leaves.get_unchecked(6) }.content_points[0],
-            &point_5
-        );
+        assert_eq!(tree.leaves[6].content_items[0], 555);
+        assert_eq!(&tree.leaves[6].content_points[0], &point_5);
     }
 
     #[test]
@@ -682,10 +543,10 @@ mod tests {
         tree.leaves[0].content_items[1] = item_2;
         tree.leaves[0].content_items[2] = item_3;
         tree.leaves[0].content_items[3] = item_4;
-        tree.leaves[0].content_points[0] = point_1.clone();
-        tree.leaves[0].content_points[1] = point_2.clone();
-        tree.leaves[0].content_points[2] = point_3.clone();
-        tree.leaves[0].content_points[3] = point_4.clone();
+        tree.leaves[0].content_points[0] = point_1;
+        tree.leaves[0].content_points[1] = point_2;
+        tree.leaves[0].content_points[2] = point_3;
+        tree.leaves[0].content_points[3] = point_4;
         tree.leaves[0].size = 4;
         tree.size = 4;
 
@@ -740,10 +601,10 @@ mod tests {
         tree.leaves[0].content_items[1] = item_2;
         tree.leaves[0].content_items[2] = item_3;
         tree.leaves[0].content_items[3] = item_4;
-        tree.leaves[0].content_points[0] = point_1.clone();
-        tree.leaves[0].content_points[1] = point_2.clone();
-        tree.leaves[0].content_points[2] = point_3.clone();
-        tree.leaves[0].content_points[3] = point_4.clone();
+        tree.leaves[0].content_points[0] = point_1;
+        tree.leaves[0].content_points[1] = point_2;
+        tree.leaves[0].content_points[2] = point_3;
+        tree.leaves[0].content_points[3] = point_4;
         tree.leaves[0].size = 4;
         tree.size = 4;
 
@@ -802,10 +663,10 @@ mod tests {
         tree.leaves[0].content_items[1] = item_2;
         tree.leaves[0].content_items[2] = item_3;
         tree.leaves[0].content_items[3] = item_4;
-        tree.leaves[0].content_points[0] = point_1.clone();
-        tree.leaves[0].content_points[1] = point_2.clone();
-        tree.leaves[0].content_points[2] = point_3.clone();
-        tree.leaves[0].content_points[3] = point_4.clone();
+        tree.leaves[0].content_points[0] = point_1;
+        tree.leaves[0].content_points[1] = point_2;
+        tree.leaves[0].content_points[2] = point_3;
+        tree.leaves[0].content_points[3] = point_4;
         tree.leaves[0].size = 4;
         tree.size = 4;
 
@@ -864,10 +725,10 @@ mod tests {
         tree.leaves[0].content_items[1] = item_2;
         tree.leaves[0].content_items[2] = item_3;
         tree.leaves[0].content_items[3] = item_4;
-        tree.leaves[0].content_points[0] = point_1.clone();
-        tree.leaves[0].content_points[1] = point_2.clone();
-        tree.leaves[0].content_points[2] = point_3.clone();
-        tree.leaves[0].content_points[3] = point_4.clone();
+        tree.leaves[0].content_points[0] = point_1;
+        tree.leaves[0].content_points[1] = point_2;
+        tree.leaves[0].content_points[2] = point_3;
+        tree.leaves[0].content_points[3] = point_4;
         tree.leaves[0].size = 4;
         tree.size = 4;
 
@@ -883,7 +744,7 @@ mod tests {
         assert_eq!(
             tree.dstems[0].children,
             [
-                0 + <u32 as Index>::leaf_offset(),
+                <u32 as Index>::leaf_offset(),
                 4 + <u32 as Index>::leaf_offset()
             ]
         );
@@ -894,31 +755,13 @@ mod tests {
         assert_eq!(&tree.leaves[0].content_points[1], &point_2);
         assert_eq!(tree.leaves[0].size, 2);
 
-        assert_eq!(
-            unsafe { tree.leaves.get_unchecked(4) }.content_items[0],
-            item_4
-        );
-        assert_eq!(
-            unsafe { tree.leaves.get_unchecked(4) }.content_items[1],
-            item_3
-        );
-        assert_eq!(
-            unsafe { tree.leaves.get_unchecked(4) }.content_items[2],
-            item_5
-        );
-        assert_eq!(
-            &unsafe { tree.leaves.get_unchecked(4) }.content_points[0],
-            &point_4
-        );
-        assert_eq!(
-            &unsafe { tree.leaves.get_unchecked(4) }.content_points[1],
-            &point_3
-        );
-        assert_eq!(
-            &unsafe { tree.leaves.get_unchecked(4) }.content_points[2],
-            &point_5
-        );
-        assert_eq!(unsafe { tree.leaves.get_unchecked(4) }.size, 3);
+        assert_eq!(tree.leaves[4].content_items[0], item_4);
+        assert_eq!(tree.leaves[4].content_items[1], item_3);
+        assert_eq!(tree.leaves[4].content_items[2], item_5);
+        assert_eq!(&tree.leaves[4].content_points[0], &point_4);
+        assert_eq!(&tree.leaves[4].content_points[1], &point_3);
+        assert_eq!(&tree.leaves[4].content_points[2], &point_5);
+        assert_eq!(tree.leaves[4].size, 3);
     }
 
     #[test]
@@ -943,14 +786,14 @@ mod tests {
         let point_2: [Flt; 4] = [n(0.11f32), n(0.1f32), n(0.1f32), n(0.101f32)];
         let item_2 = 222;
 
-        let point_3: [Flt; 4] = [n(0.16f32), n(0.4f32), n(0.4f32), n(0.106f32)];
+        let point_3: [Flt; 4] = [n(0.16f32), n(0.4f32), n(0.24f32), n(0.106f32)];
         let item_3 = 333;
 
-        let point_4: [Flt; 4] = [n(0.17f32), n(0.3f32), n(0.3f32), n(0.107f32)];
+        let point_4: [Flt; 4] = [n(0.17f32), n(0.3f32), n(0.27f32), n(0.107f32)];
         let item_4 = 444;
 
         // to be added
-        let point_5: [Flt; 4] = [n(0.18f32), n(0.45f32), n(0.45f32), n(0.108f32)];
+        let point_5: [Flt; 4] = [n(0.18f32), n(0.45f32), n(0.29f32), n(0.108f32)];
         let item_5 = 555;
 
         tree.stems[1] = 0.21f32;
@@ -960,21 +803,20 @@ mod tests {
         tree.initialise_dstems();
         tree.dstems[0].split_val = 0.3f32;
         tree.dstems[0].children = [
-            0 + <u32 as Index>::leaf_offset(),
+            <u32 as Index>::leaf_offset(),
             4 + <u32 as Index>::leaf_offset(),
         ];
 
-        tree.leaves.reserve(1);
-        tree.unreserved_leaf_idx += 1;
+        tree.leaves.push(LeafNode::new());
 
         tree.leaves[0].content_items[0] = item_1;
         tree.leaves[0].content_items[1] = item_2;
         tree.leaves[0].content_items[2] = item_3;
         tree.leaves[0].content_items[3] = item_4;
-        tree.leaves[0].content_points[0] = point_1.clone();
-        tree.leaves[0].content_points[1] = point_2.clone();
-        tree.leaves[0].content_points[2] = point_3.clone();
-        tree.leaves[0].content_points[3] = point_4.clone();
+        tree.leaves[0].content_points[0] = point_1;
+        tree.leaves[0].content_points[1] = point_2;
+        tree.leaves[0].content_points[2] = point_3;
+        tree.leaves[0].content_points[3] = point_4;
         tree.leaves[0].size = 4;
         tree.size = 4;
 
@@ -996,7 +838,7 @@ mod tests {
         assert_eq!(
             tree.dstems[4].children,
             [
-                0 + <u32 as Index>::leaf_offset(),
+                <u32 as Index>::leaf_offset(),
                 5 + <u32 as Index>::leaf_offset()
             ]
         );
@@ -1007,31 +849,13 @@ mod tests {
         assert_eq!(&tree.leaves[0].content_points[1], &point_2);
         assert_eq!(tree.leaves[0].size, 2);
 
-        assert_eq!(
-            unsafe { tree.leaves.get_unchecked(5) }.content_items[0],
-            item_3
-        );
-        assert_eq!(
-            unsafe { tree.leaves.get_unchecked(5) }.content_items[1],
-            item_4
-        );
-        assert_eq!(
-            unsafe { tree.leaves.get_unchecked(5) }.content_items[2],
-            item_5
-        );
-        assert_eq!(
-            &unsafe { tree.leaves.get_unchecked(5) }.content_points[0],
-            &point_3
-        );
-        assert_eq!(
-            &unsafe { tree.leaves.get_unchecked(5) }.content_points[1],
-            &point_4
-        );
-        assert_eq!(
-            &unsafe { tree.leaves.get_unchecked(5) }.content_points[2],
-            &point_5
-        );
-        assert_eq!(unsafe { tree.leaves.get_unchecked(5) }.size, 3);
+        assert_eq!(tree.leaves[5].content_items[0], item_3);
+        assert_eq!(tree.leaves[5].content_items[1], item_4);
+        assert_eq!(tree.leaves[5].content_items[2], item_5);
+        assert_eq!(&tree.leaves[5].content_points[0], &point_3);
+        assert_eq!(&tree.leaves[5].content_points[1], &point_4);
+        assert_eq!(&tree.leaves[5].content_points[2], &point_5);
+        assert_eq!(tree.leaves[5].size, 3);
     }
 
     #[test]
@@ -1069,10 +893,10 @@ mod tests {
         tree.leaves[0].content_items[1] = item_2;
         tree.leaves[0].content_items[2] = item_3;
         tree.leaves[0].content_items[3] = item_4;
-        tree.leaves[0].content_points[0] = point_1.clone();
-        tree.leaves[0].content_points[1] = point_2.clone();
-        tree.leaves[0].content_points[2] = point_3.clone();
-        tree.leaves[0].content_points[3] = point_4.clone();
+        tree.leaves[0].content_points[0] = point_1;
+        tree.leaves[0].content_points[1] = point_2;
+        tree.leaves[0].content_points[2] = point_3;
+        tree.leaves[0].content_points[3] = point_4;
         tree.leaves[0].size = 4;
         tree.size = 4;
 
@@ -1131,7 +955,7 @@ mod tests {
         assert_eq!(tree.size(), 16);
     }
 
-    /* #[test]
+    #[test]
     fn can_remove_an_item() {
         let mut tree: KdTree<Flt, u32, 4, 4, u32> = KdTree::new();
 
@@ -1164,7 +988,7 @@ mod tests {
 
         assert_eq!(removed, 1);
         assert_eq!(tree.size(), 15);
-    } */
+    }
 
     #[test]
     fn can_add_shitloads_of_points() {
@@ -1191,16 +1015,15 @@ mod tests {
             rand::random()
         }
 
-        let points_to_add: Vec<([f64; 2], u32)> =
-            (0..100_000).into_iter().map(|_| rand_data_2d()).collect();
+        let points_to_add: Vec<([f64; 2], u32)> = (0..100_000).map(|_| rand_data_2d()).collect();
 
         let mut points = vec![];
         let mut kdtree = KdTree::<f64, u32, 2, 32, u32>::with_capacity(200_000);
         for _ in 0..100_000 {
             points.push(rand_data_2d());
         }
-        for i in 0..points.len() {
-            kdtree.add(&points[i].0, points[i].1);
+        for point in points.iter() {
+            kdtree.add(&point.0, point.1);
         }
 
         points_to_add
@@ -1210,7 +1033,7 @@ mod tests {
         assert_eq!(kdtree.size(), 200_000);
     }
 
-    /* #[test]
+    #[test]
     fn test_can_handle_remove_edge_case_from_issue_12() {
         // See: https://github.com/sdd/kiddo/issues/12
         let pts = vec![
@@ -1257,5 +1080,36 @@ mod tests {
         }
 
         assert_eq!(tree.remove(&pts[0], 0), 1);
-    } */
+    }
+
+    #[test]
+    fn try_add_reports_an_unsplittable_bucket_and_leaves_the_tree_usable() {
+        let mut tree: KdTree<f64, u32, 2, 4, u32> = KdTree::new();
+        for item in 0..4 {
+            tree.add(&[1.0, item as f64], item);
+        }
+
+        assert_eq!(
+            tree.try_add(&[1.0, 5.0], 4),
+            Err(KiddoError::UnsplittableBucket)
+        );
+
+        assert_eq!(tree.size(), 4);
+        for item in 0..4 {
+            assert_eq!(tree.remove(&[1.0, item as f64], item), 1);
+        }
+    }
+
+    #[test]
+    fn try_add_reports_an_index_overflow() {
+        let mut tree: KdTree<f64, u32, 2, 2, u16> = KdTree::new();
+
+        let result = (0..100_000u32).try_for_each(|item| {
+            tree.try_add(&[(item * 7919 % 100_003) as f64, item as f64], item)
+        });
+
+        assert_eq!(result, Err(KiddoError::IndexOverflow));
+        assert!(tree.size() > u16::capacity_with_bucket_size(1));
+        assert!(tree.leaves.len() <= u16::capacity_with_bucket_size(1));
+    }
 }
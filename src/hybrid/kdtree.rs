@@ -1,27 +1,40 @@
-//! Floating point k-d tree, for use when the co-ordinates of the points being stored in the tree
-//! are floats. f64 or f32 are supported currently.
+//! Hybrid floating point k-d tree, for use when the co-ordinates of the points being stored in
+//! the tree are floats. f64 or f32 are supported currently.
+//!
+//! The top of the tree is a fixed-size layer of "static" stems, stored in Eytzinger order
+//! so that no child indices need to be stored. A `NaN` split value in a static stem marks
+//! a subtree that has not been split yet, all of whose items live in the leaf below its
+//! leftmost descendant. Once the bottom layer of static stems is full, further splits
+//! hang "dynamic" stems (`dstems`) off it, that do store the indices of their children.
+//! The two least significant bits of a bottom-layer static stem's split value indicate
+//! whether its left and right children respectively are dynamic stems rather than leaves.
+//! These bits are always masked off before the split value is compared against.
 
 use az::{Az, Cast};
 use divrem::DivCeil;
-use num_traits::Float;
-use ordered_float::OrderedFloat;
-use std::alloc::{AllocError, Allocator, Global, Layout};
 use std::cmp::PartialEq;
 use std::fmt::Debug;
-use std::ops::Rem;
+use std::ops::{ControlFlow, Rem};
 
-#[cfg(feature = "serde")]
-use crate::custom_serde::*;
-use crate::types::{Content, Index};
+use crate::error::KiddoError;
+use crate::stats::{MemoryUsage, TreeStats, TreeStatsBuilder};
+use crate::traits::{is_stem_index, Content, DistanceMetric, Index};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-pub trait FloatLSB {
+/// Allows the lowest two bits of a float's mantissa to be used as flags
+pub trait FloatLSB: Copy {
+    /// returns true if the least significant bit is set
     fn is_lsb_set(self) -> bool;
+    /// returns a copy with the least significant bit set
     fn with_lsb_set(self) -> Self;
+    /// returns a copy with the least significant bit cleared
     fn with_lsb_clear(self) -> Self;
+    /// returns true if the second least significant bit is set
     fn is_2lsb_set(self) -> bool;
+    /// returns a copy with the second least significant bit set
     fn with_2lsb_set(self) -> Self;
+    /// returns a copy with the second least significant bit cleared
     fn with_2lsb_clear(self) -> Self;
 }
 
@@ -79,14 +92,17 @@ impl FloatLSB for f64 {
 
 /// Axis trait represents the traits that must be implemented
 /// by the type that is used as the first generic parameter, `A`,
-/// on the float `KdTree`. This will be `f64` or `f32`.
-pub trait Axis: Float + Default + Debug + Copy + Sync + FloatLSB {}
-impl<T: Float + Default + Debug + Copy + Sync + FloatLSB> Axis for T {}
+/// on the hybrid `KdTree`. This will be `f64` or `f32`.
+pub trait Axis: crate::float::kdtree::Axis + FloatLSB {}
+impl<T: crate::float::kdtree::Axis + FloatLSB> Axis for T {}
 
-/// Floating point k-d tree
+/// Hybrid floating point k-d tree
 ///
 /// For use when the co-ordinates of the points being stored in the tree
-/// are floats. f64 or f32 are supported currently
+/// are floats. f64 or f32 are supported currently.
+///
+/// Can be created already balanced with [`optimize_from`](`KdTree::optimize_from`), and
+/// still accepts further [`add`](`KdTree::add`)s and [`remove`](`KdTree::remove`)s afterwards.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(
     feature = "rkyv",
@@ -98,9 +114,6 @@ pub struct KdTree<A: Copy + Default, T: Copy + Default, const K: usize, const B:
     pub(crate) stems: Vec<A>,
     pub(crate) dstems: Vec<StemNode<A, K, IDX>>,
     pub(crate) size: usize,
-
-    pub(crate) unreserved_leaf_idx: usize,
-    pub(crate) optimized_read_only: bool,
 }
 
 #[doc(hidden)]
@@ -111,8 +124,6 @@ pub struct KdTree<A: Copy + Default, T: Copy + Default, const K: usize, const B:
 )]
 #[derive(Clone, Debug, PartialEq)]
 pub struct StemNode<A: Copy + Default, const K: usize, IDX> {
-    // pub(crate) left: IDX,
-    // pub(crate) right: IDX,
     pub(crate) children: [IDX; 2],
     pub(crate) split_val: A,
 }
@@ -125,7 +136,10 @@ pub struct StemNode<A: Copy + Default, const K: usize, IDX> {
 )]
 #[derive(Clone, Debug, PartialEq)]
 pub struct LeafNode<A: Copy + Default, T: Copy + Default, const K: usize, const B: usize, IDX> {
-    #[cfg_attr(feature = "serde", serde(with = "array_of_arrays"))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::custom_serde::array_of_arrays")
+    )]
     #[cfg_attr(
         feature = "serde",
         serde(bound(serialize = "A: Serialize", deserialize = "A: Deserialize<'de>"))
@@ -133,7 +147,7 @@ pub struct LeafNode<A: Copy + Default, T: Copy + Default, const K: usize, const
     // TODO: Refactor content_points to be [[A; B]; K] to see if this helps vectorisation
     pub(crate) content_points: [[A; K]; B],
 
-    #[cfg_attr(feature = "serde", serde(with = "array"))]
+    #[cfg_attr(feature = "serde", serde(with = "crate::custom_serde::array"))]
     #[cfg_attr(
         feature = "serde",
         serde(bound(
@@ -146,7 +160,7 @@ pub struct LeafNode<A: Copy + Default, T: Copy + Default, const K: usize, const
     pub(crate) size: IDX,
 }
 
-/* impl<A: Copy + Default, T: Copy + Default, const K: usize, const B: usize, IDX>
+impl<A: Copy + Default, T: Copy + Default, const K: usize, const B: usize, IDX>
     LeafNode<A, T, K, B, IDX>
 where
     A: Axis,
@@ -160,16 +174,38 @@ where
             size: IDX::zero(),
         }
     }
-} */
+}
+
+/// Reference to a node of the tree, used when traversing it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum NodeIdx {
+    /// index into `stems`
+    Stem(usize),
+    /// index into `dstems`
+    DStem(usize),
+    /// index into `leaves`
+    Leaf(usize),
+}
 
-#[allow(dead_code)]
-#[derive(Debug)]
-pub struct TreeStats {
-    dstem_node_count: usize,
-    leaf_fill_counts: Vec<usize>,
-    leaf_fill_ratio: f32,
-    stem_fill_ratio: f32,
-    unused_stem_count: usize,
+/// A node of the tree, as seen by a traversal
+pub(crate) enum Node<A> {
+    Stem {
+        split_val: A,
+        children: [NodeIdx; 2],
+    },
+    Leaf(usize),
+}
+
+impl<A, T, const K: usize, const B: usize, IDX> Default for KdTree<A, T, K, B, IDX>
+where
+    A: Axis,
+    T: Content,
+    IDX: Index<T = IDX>,
+    usize: Cast<IDX>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<A, T, const K: usize, const B: usize, IDX> KdTree<A, T, K, B, IDX>
@@ -179,14 +215,16 @@ where
     IDX: Index<T = IDX>,
     usize: Cast<IDX>,
 {
-    /// Creates a new float KdTree.
+    pub(crate) const ROOT: NodeIdx = NodeIdx::Stem(1);
+
+    /// Creates a new hybrid KdTree.
     ///
-    /// Capacity is set by default to 10x the bucket size (32 in this case).
+    /// Capacity is set by default to 16x the bucket size.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
+    /// use kiddo::hybrid::kdtree::KdTree;
     ///
     /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
     ///
@@ -199,12 +237,15 @@ where
         KdTree::with_capacity(B * 16)
     }
 
-    /// Creates a new float KdTree and reserve capacity for a specific number of items.
+    /// Creates a new hybrid KdTree and reserve capacity for a specific number of items.
+    ///
+    /// The capacity determines the number of static stems. Items can still be added
+    /// beyond it, at which point dynamic stems get created.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
+    /// use kiddo::hybrid::kdtree::KdTree;
     ///
     /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::with_capacity(1_000_000);
     ///
@@ -216,296 +257,170 @@ where
     pub fn with_capacity(capacity: usize) -> Self {
         assert!(capacity <= <IDX as Index>::capacity_with_bucket_size(B));
 
-        let leaf_capacity = DivCeil::div_ceil(capacity, B.az::<usize>()).next_power_of_two();
-        let stem_capacity = leaf_capacity.max(1);
+        let leaf_capacity = DivCeil::div_ceil(capacity, B).next_power_of_two().max(2);
 
-        let layout = Layout::array::<A>(stem_capacity).unwrap();
-        let stems = unsafe {
-            let mem = match Global.allocate(layout) {
-                Ok(mem) => mem.cast::<A>().as_ptr(),
-                Err(AllocError) => panic!(),
-            };
-
-            Vec::from_raw_parts_in(mem, stem_capacity, stem_capacity, Global)
-        };
+        // one leaf per child of the bottom layer of static stems. Stem 0 is never
+        // used so that the children of stem N are always at 2N and 2N + 1.
+        let mut stems = vec![A::nan(); leaf_capacity];
 
-        let layout = Layout::array::<LeafNode<A, T, K, B, IDX>>(leaf_capacity).unwrap();
-        let leaves = unsafe {
-            let mem = match Global.allocate(layout) {
-                Ok(mem) => mem.cast::<LeafNode<A, T, K, B, IDX>>().as_ptr(),
-                Err(AllocError) => panic!(),
-            };
-
-            Vec::from_raw_parts_in(mem, leaf_capacity, leaf_capacity, Global)
-        };
+        // Set this to infinity so that if it is accessed, things will break
+        stems[0] = A::infinity();
 
-        let mut tree = Self {
-            size: 0,
+        Self {
+            leaves: vec![LeafNode::new(); leaf_capacity],
             stems,
-            dstems: Vec::with_capacity(0),
-            leaves,
-            unreserved_leaf_idx: leaf_capacity,
-            optimized_read_only: false,
-        };
-
-        tree.leaves[0].size = IDX::zero();
-
-        // Set this to infinity so that if it is accessed, things will break
-        tree.stems[0] = A::infinity();
+            dstems: Vec::new(),
+            size: 0,
+        }
+    }
 
-        // 1 is the true root, so that we can use *2 and *2+1 to traverse down
-        tree.stems[1] = A::nan();
+    /// Creates a new hybrid KdTree and reserves capacity for a specific number of items,
+    /// returning [`KiddoError::CapacityExceeded`] rather than panicking if `IDX` can't
+    /// address that many items with a bucket size of `B`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::hybrid::kdtree::KdTree;
+    /// use kiddo::KiddoError;
+    ///
+    /// let tree = KdTree::<f64, u32, 3, 32, u16>::try_with_capacity(1_000).unwrap();
+    /// assert_eq!(tree.size(), 0);
+    ///
+    /// assert_eq!(
+    ///     KdTree::<f64, u32, 3, 32, u16>::try_with_capacity(10_000_000).unwrap_err(),
+    ///     KiddoError::CapacityExceeded {
+    ///         capacity: 10_000_000,
+    ///         max_capacity: 1_048_576,
+    ///     }
+    /// );
+    /// ```
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, KiddoError> {
+        let max_capacity = <IDX as Index>::capacity_with_bucket_size(B);
+        if capacity > max_capacity {
+            return Err(KiddoError::CapacityExceeded {
+                capacity,
+                max_capacity,
+            });
+        }
 
-        tree
+        Ok(KdTree::with_capacity(capacity))
     }
 
-    /// Creates a new float KdTree, balanced and optimized.
+    /// Creates a new hybrid KdTree, balanced and optimized.
     ///
-    /// Trees constructed using this method will not be modifiable
-    /// after construction, ant will be optimally balanced and tuned.
+    /// The static stems are populated by repeatedly partitioning `source` about its median.
+    /// The item stored for each point is its index within `source`. Unlike the
+    /// [`ImmutableKdTree`](`crate::immutable::float::kdtree::ImmutableKdTree`), trees
+    /// constructed using this method can still be modified afterwards.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
+    /// use kiddo::hybrid::kdtree::KdTree;
     ///
-    /// let points: Vec<[f64; 3]> = vec!();
-    /// let tree: KdTree<f64, u32, 3, 32, u32> = KdTree::optimize_from(points);
+    /// let points: Vec<[f64; 3]> = vec!([1.0, 2.0, 5.0], [2.0, 3.0, 6.0]);
+    /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::optimize_from(&points);
     ///
-    /// assert_eq!(tree.size(), 1);
+    /// assert_eq!(tree.size(), 2);
+    ///
+    /// tree.add(&[3.0, 4.0, 7.0], 2);
+    ///
+    /// assert_eq!(tree.size(), 3);
     /// ```
     #[inline]
     pub fn optimize_from(source: &[[A; K]]) -> Self
     where
         usize: Cast<T>,
     {
-        let item_count = source.len();
-        let mut leaf_node_count = item_count.div_ceil(B);
-        let mut stem_node_count = leaf_node_count.next_power_of_two();
-
-        let mut stems;
-        let mut leaves;
+        let mut tree = Self::with_capacity(source.len());
 
-        let mut total_shift = 0;
-        loop {
-            leaves = Self::allocate_leaves(leaf_node_count);
-
-            stems = vec![A::infinity(); stem_node_count];
-            let mut sort_index = Vec::from_iter(0..item_count);
-            stems[0] = A::infinity();
-
-            let requested_shift =
-                Self::optimize_stems(&mut stems, source, &mut sort_index, 1, 0, total_shift);
-
-            if requested_shift == 0 {
-                break;
-            }
-            total_shift += requested_shift;
-
-            stem_node_count = (stem_node_count + 1).next_power_of_two();
-            leaf_node_count += requested_shift.div_ceil(B);
-        }
-
-        let mut tree = Self {
-            size: 0,
-            stems,
-            dstems: Vec::with_capacity(0),
-            leaves,
-            unreserved_leaf_idx: leaf_node_count,
-            optimized_read_only: true,
-        };
-
-        for (idx, point) in source.iter().enumerate() {
-            tree.add_to_optimized(point, idx.az::<T>());
-        }
+        let mut sort_index = Vec::from_iter(0..source.len());
+        tree.optimize_stems(source, &mut sort_index, 1, 0);
 
         tree
     }
 
-    fn allocate_leaves(count: usize) -> Vec<LeafNode<A, T, K, B, IDX>> {
-        let layout = Layout::array::<LeafNode<A, T, K, B, IDX>>(count).unwrap();
-        let mut leaves = unsafe {
-            let mem = match Global.allocate(layout) {
-                Ok(mem) => mem.cast::<LeafNode<A, T, K, B, IDX>>().as_ptr(),
-                Err(AllocError) => panic!(),
-            };
-
-            Vec::from_raw_parts_in(mem, count, count, Global)
-        };
-        for leaf in &mut leaves {
-            leaf.size = IDX::zero();
-        }
-
-        leaves
-    }
-
-    /**
-     *  1234456789ABCDEF: Initial alloc
-     *
-     *  12344567 89ABCDEF: First split
-     *
-     *  1234 4567        : Second split
-     *
-     *  123 44567        :  Second split adjust: overflow
-     *
-     *  1234456 789ABCDE F : desired readjustment
-     *
-     *  123 4456           : Revised second split
-     */
-
-    /// the value returned is zero if balancing was successful. If however a child splitpoint has
-    /// landed in between two (or more) items with the same value, the value returned is a hint to
-    /// the caller of how many items overflowed out of the second bucket due to the optimization
-    /// attempt overflowing after the pivot was moved.
+    /// Recursively populates the static stem at `stem_index` and its descendants with split
+    /// values that partition the points referred to by `sort_index` in half. Any points that
+    /// still don't fit in a single leaf once the bottom of the static stems is reached get
+    /// added in the usual way, creating dynamic stems as needed.
     fn optimize_stems(
-        stems: &mut Vec<A>,
+        &mut self,
         source: &[[A; K]],
         sort_index: &mut [usize],
         stem_index: usize,
         dim: usize,
-        shifted: usize,
-    ) -> usize {
-        let next_dim = (dim + 1).rem(K);
+    ) where
+        usize: Cast<T>,
+    {
+        let stem_capacity = self.stems.len();
 
-        // TODO: should this have one subtracted from it?
-        let stem_levels_below = stems.len().ilog2() - stem_index.ilog2() - 1;
-
-        // TODO: this is wrong, it assumes that there is a leaf for every stem
-        let items_below = 2usize.pow(stem_levels_below + 1) * B;
-
-        // If there are few enough items that we could fit all of them in the left subtree,
-        // leave the current stem val as +inf to push everything down into the left and
-        // recurse down without splitting.
-        if sort_index.len() + shifted <= items_below / 2 {
-            if sort_index.len() > B {
-                Self::optimize_stems(
-                    stems,
-                    source,
-                    sort_index,
-                    stem_index << 1,
-                    next_dim,
-                    shifted,
-                );
+        if sort_index.len() <= B {
+            let leaf = &mut self.leaves[Self::leftmost_leaf_index(stem_index, stem_capacity)];
+            for (leaf_pos, &idx) in sort_index.iter().enumerate() {
+                leaf.content_points[leaf_pos] = source[idx];
+                leaf.content_items[leaf_pos] = idx.az::<T>();
             }
-            return 0;
-        }
+            leaf.size = sort_index.len().az::<IDX>();
+            self.size += sort_index.len();
 
-        let mut pivot = (sort_index.len() + shifted) >> 1;
-        if stem_index == 1 {
-            // If at the top level, check if there's been a shift
-            pivot = if shifted > 0 {
-                // if so,
-                sort_index.len()
-            } else {
-                // otherwise, do a special case pivot shift to ensure the left subtree is full.
-                //(pivot - (B / 2) + 1).next_power_of_two()
-                if sort_index.len() & 1 == 1 {
-                    (pivot + 1).next_power_of_two()
-                } else {
-                    pivot.next_power_of_two()
-                }
-            };
-        } else if sort_index.len() & 0x01 == 1 && shifted == 0 {
-            pivot = (pivot + 1).next_power_of_two()
-        } else {
-            pivot = pivot.next_power_of_two();
+            return;
         }
-        pivot = pivot - shifted;
-        let orig_pivot = pivot;
-
-        // let max_sort_index = (pivot + 1).min(sort_index.len() - 1);
-        sort_index.select_nth_unstable_by_key(pivot, |&i| OrderedFloat(source[i][dim]));
 
-        // this is overkill. We can select_nth_unstable_by_key of (&mut sort_index[..max_sort_index]) for max_sort_index - 1
-        // and then, if we get inside the while loop below, keep call it again on a slice of 1 smaller
-        // (&mut sort_index[..max_sort_index]).sort_by_cached_key(|&i| OrderedFloat(source[i][dim]));
-
-        (&mut sort_index[..pivot])
-            .select_nth_unstable_by_key(pivot - 1, |&i| OrderedFloat(source[i][dim]));
+        if stem_index >= stem_capacity {
+            for &idx in sort_index.iter() {
+                self.add(&source[idx], idx.az::<T>());
+            }
 
-        // if the pivot straddles two values that are equal,
-        // keep nudging it left until they aren't
-        while sort_index.len() > 1
-            && source[sort_index[pivot]][dim] == source[sort_index[pivot - 1]][dim]
-            && pivot > 0
-        {
-            pivot -= 1;
-            (&mut sort_index[..pivot])
-                .select_nth_unstable_by_key(pivot - 1, |&i| OrderedFloat(source[i][dim]));
+            return;
         }
 
-        // if we end up with a pivot of 0, something has gone wrong,
-        // unless we only had a slice of len 1 anyway
-        debug_assert!(pivot > 0 || sort_index.len() == 1);
-
-        // if we have had to nudge left, abort early with non-zero to instruct parent to rebalance
-        if pivot < orig_pivot {
-            return orig_pivot - pivot;
+        let mut split_vals: Vec<A> = sort_index.iter().map(|&idx| source[idx][dim]).collect();
+        let mut split_val = Self::choose_split_val(&mut split_vals);
+        if Self::is_bottom_stem(stem_index, stem_capacity) {
+            split_val = Self::without_flags(split_val);
         }
+        self.stems[stem_index] = split_val;
 
-        stems[stem_index] = source[sort_index[pivot]][dim];
-
-        // if the total number of items that we have to the left of the pivot can fit
-        // in a single bucket, we're done
-        if pivot <= B {
-            return 0;
+        let mut pivot = 0;
+        for i in 0..sort_index.len() {
+            if source[sort_index[i]][dim] < split_val {
+                sort_index.swap(pivot, i);
+                pivot += 1;
+            }
         }
 
-        // are we on the bottom row? Recursion termination case
-        if stem_levels_below == 0 {
-            // if the right bucket will overflow, return the overflow amount
-            if (source.len() - pivot) > B {
-                return (source.len() - pivot) - B;
-            }
+        let (lower, upper) = sort_index.split_at_mut(pivot);
+        let next_dim = (dim + 1).rem(K);
+        self.optimize_stems(source, lower, stem_index << 1, next_dim);
+        self.optimize_stems(source, upper, (stem_index << 1) + 1, next_dim);
+    }
 
-            // if the right bucket won't overflow, we're good.
-            return 0;
-        }
+    /// Picks a split value for a set of co-ordinates on the split axis such that roughly
+    /// half of them lie below it. Only if all of the co-ordinates are equal can none of
+    /// them be below the returned value.
+    pub(crate) fn choose_split_val(vals: &mut [A]) -> A {
+        let pivot = vals.len() / 2;
+        vals.select_nth_unstable_by(pivot, |a, b| {
+            a.partial_cmp(b).expect("Leaf node sort failed.")
+        });
+        let split_val = vals[pivot];
 
-        let next_stem_index = stem_index << 1;
-        let mut requested_shift_amount;
-        let mut shift = 0;
-        let mut lower_sort_index;
-        let mut upper_sort_index;
-        loop {
-            (lower_sort_index, upper_sort_index) = sort_index.split_at_mut(pivot);
-
-            requested_shift_amount = Self::optimize_stems(
-                stems,
-                source,
-                lower_sort_index,
-                next_stem_index,
-                next_dim,
-                shift,
-            );
-
-            if requested_shift_amount == 0 {
-                break;
-            } else {
-                pivot -= requested_shift_amount;
-                stems[stem_index] = source[sort_index[pivot]][dim];
-                shift += requested_shift_amount;
-
-                // Test for RHS now having more items than can fit
-                // in the buckets present in its subtree. If it does,
-                // return with a value so that the parent reduces our
-                // total allocation
-                let new_upper_size = sort_index.len() - pivot;
-                if new_upper_size > items_below >> 1 {
-                    return new_upper_size - (items_below >> 1);
-                }
-            }
+        if vals[..pivot].iter().any(|&val| val < split_val) {
+            return split_val;
         }
 
-        let next_stem_index = next_stem_index + 1;
-        Self::optimize_stems(
-            stems,
-            source,
-            upper_sort_index,
-            next_stem_index,
-            next_dim,
-            0,
-        )
+        // everything below the pivot is equal to it. Use the smallest value
+        // above the pivot instead, if there is one.
+        vals[pivot..]
+            .iter()
+            .filter(|&&val| val > split_val)
+            .fold(None, |min: Option<A>, &val| {
+                Some(min.map_or(val, |min| min.min(val)))
+            })
+            .unwrap_or(split_val)
     }
 
     /// Returns the current number of elements stored in the tree
@@ -513,7 +428,7 @@ where
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
+    /// use kiddo::hybrid::kdtree::KdTree;
     ///
     /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
     ///
@@ -527,51 +442,244 @@ where
         self.size
     }
 
+    /// Returns the number of items that the currently allocated leaves can hold
     #[inline]
     pub fn capacity(&self) -> usize {
         self.leaves.len() * B
     }
 
+    /// Returns the node at `node_idx`, resolving unsplit static stems into the leaf
+    /// that holds their items, and the children of the bottom layer of static stems
+    /// into either leaves or dynamic stems.
     #[inline]
-    pub(crate) fn is_stem_index(x: IDX) -> bool {
-        x < <IDX as Index>::leaf_offset()
+    pub(crate) fn node(&self, node_idx: NodeIdx) -> Node<A> {
+        match node_idx {
+            NodeIdx::Stem(stem_idx) => {
+                let stem_capacity = self.stems.len();
+                let val = self.stems[stem_idx];
+
+                if val.is_nan() {
+                    Node::Leaf(Self::leftmost_leaf_index(stem_idx, stem_capacity))
+                } else if !Self::is_bottom_stem(stem_idx, stem_capacity) {
+                    Node::Stem {
+                        split_val: val,
+                        children: [
+                            NodeIdx::Stem(stem_idx << 1),
+                            NodeIdx::Stem((stem_idx << 1) + 1),
+                        ],
+                    }
+                } else {
+                    let left_idx = (stem_idx << 1) - stem_capacity;
+                    let right_idx = left_idx + 1;
+
+                    Node::Stem {
+                        split_val: Self::without_flags(val),
+                        children: [
+                            if val.is_lsb_set() {
+                                NodeIdx::DStem(left_idx)
+                            } else {
+                                NodeIdx::Leaf(left_idx)
+                            },
+                            if val.is_2lsb_set() {
+                                NodeIdx::DStem(right_idx)
+                            } else {
+                                NodeIdx::Leaf(right_idx)
+                            },
+                        ],
+                    }
+                }
+            }
+            NodeIdx::DStem(dstem_idx) => {
+                let node = &self.dstems[dstem_idx];
+
+                Node::Stem {
+                    split_val: node.split_val,
+                    children: node.children.map(|child| {
+                        if is_stem_index(child) {
+                            NodeIdx::DStem(child.az::<usize>())
+                        } else {
+                            NodeIdx::Leaf((child - IDX::leaf_offset()).az::<usize>())
+                        }
+                    }),
+                }
+            }
+            NodeIdx::Leaf(leaf_idx) => Node::Leaf(leaf_idx),
+        }
     }
 
-    pub(crate) fn initialise_dstems(&mut self) {
-        let leaf_capacity = self.leaves.capacity();
+    /// Returns the index of the leaf below the leftmost descendant of
+    /// the bottom layer of static stems under `stem_idx`
+    #[inline]
+    pub(crate) fn leftmost_leaf_index(stem_idx: usize, stem_capacity: usize) -> usize {
+        (stem_idx << (stem_capacity.ilog2() - stem_idx.ilog2())) - stem_capacity
+    }
 
-        let layout = Layout::array::<StemNode<A, K, IDX>>(leaf_capacity).unwrap();
-        self.dstems = unsafe {
-            let mem = match Global.allocate(layout) {
-                Ok(mem) => mem.cast::<StemNode<A, K, IDX>>().as_ptr(),
-                Err(AllocError) => panic!(),
-            };
+    /// Calls `visitor` with the points and items of each leaf whose side of every
+    /// stem above it is within the current bound of `query`, visiting the side of
+    /// each stem that `query` falls on first.
+    ///
+    /// The visitor returns `ControlFlow::Continue` with the distance that the remaining
+    /// subtrees must lie within to be visited, or `ControlFlow::Break` to end the traversal.
+    #[inline]
+    pub(crate) fn visit_leaves<D, V>(&self, query: &[A; K], visitor: &mut V)
+    where
+        D: DistanceMetric<A, K>,
+        V: FnMut(&[[A; K]], &[T]) -> ControlFlow<(), A>,
+    {
+        let mut off = [A::zero(); K];
 
-            Vec::from_raw_parts_in(mem, leaf_capacity, leaf_capacity, Global)
-        };
+        let _ =
+            self.visit_leaves_recurse::<D, V>(query, Self::ROOT, 0, &mut off, A::zero(), visitor);
     }
 
-    pub fn generate_stats(&self) -> TreeStats {
-        let mut leaf_fill_counts = vec![0usize; B + 1];
-        for leaf in &self.leaves {
-            leaf_fill_counts[leaf.size.az::<usize>()] += 1;
-        }
+    fn visit_leaves_recurse<D, V>(
+        &self,
+        query: &[A; K],
+        curr_node_idx: NodeIdx,
+        split_dim: usize,
+        off: &mut [A; K],
+        rd: A,
+        visitor: &mut V,
+    ) -> ControlFlow<(), A>
+    where
+        D: DistanceMetric<A, K>,
+        V: FnMut(&[[A; K]], &[T]) -> ControlFlow<(), A>,
+    {
+        match self.node(curr_node_idx) {
+            Node::Stem {
+                split_val,
+                children: [left, right],
+            } => {
+                let mut rd = rd;
+                let old_off = off[split_dim];
+                let new_off = query[split_dim].saturating_dist(split_val);
+
+                let [closer_node_idx, further_node_idx] = if query[split_dim] < split_val {
+                    [left, right]
+                } else {
+                    [right, left]
+                };
+                let next_split_dim = (split_dim + 1).rem(K);
+
+                let bound = self.visit_leaves_recurse::<D, V>(
+                    query,
+                    closer_node_idx,
+                    next_split_dim,
+                    off,
+                    rd,
+                    visitor,
+                )?;
+
+                rd = A::rd_update(rd, D::dist1(new_off, old_off));
+
+                if rd > bound {
+                    return ControlFlow::Continue(bound);
+                }
 
-        let leaf_fill_ratio = (self.size as f32) / (self.capacity() as f32);
+                off[split_dim] = new_off;
+                let result = self.visit_leaves_recurse::<D, V>(
+                    query,
+                    further_node_idx,
+                    next_split_dim,
+                    off,
+                    rd,
+                    visitor,
+                );
+                off[split_dim] = old_off;
 
-        let unused_stem_count = self.stems.iter().filter(|x| x.is_infinite()).count() - 1;
+                result
+            }
+            Node::Leaf(leaf_idx) => {
+                let leaf_node = &self.leaves[leaf_idx];
+                let size = leaf_node.size.az::<usize>();
+
+                visitor(
+                    &leaf_node.content_points[..size],
+                    &leaf_node.content_items[..size],
+                )
+            }
+        }
+    }
 
-        let stem_fill_ratio = 1.0 - (unused_stem_count as f32 / ((self.stems.len() - 1) as f32));
+    #[inline]
+    pub(crate) fn is_bottom_stem(stem_idx: usize, stem_capacity: usize) -> bool {
+        stem_idx >= stem_capacity / 2
+    }
+
+    #[inline]
+    pub(crate) fn without_flags(split_val: A) -> A {
+        split_val.with_lsb_clear().with_2lsb_clear()
+    }
 
-        let dstem_node_count = self.dstems.len();
+    pub(crate) fn initialise_dstems(&mut self) {
+        self.dstems = vec![
+            StemNode {
+                children: [IDX::zero(); 2],
+                split_val: A::nan(),
+            };
+            self.leaves.len()
+        ];
+    }
 
-        TreeStats {
-            dstem_node_count,
-            leaf_fill_counts,
-            leaf_fill_ratio,
-            stem_fill_ratio,
-            unused_stem_count,
+    /// Returns statistics describing the shape, fill level and memory usage of the tree.
+    ///
+    /// Only the leaves and stems that can be reached from the root are described. Leaves
+    /// below a static stem that has not been split yet are counted in `memory`, but not
+    /// in `leaf_count` or `leaf_occupancy`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::hybrid::kdtree::KdTree;
+    ///
+    /// let points: Vec<[f64; 2]> = (0..100).map(|i| [i as f64, (i % 10) as f64]).collect();
+    /// let tree: KdTree<f64, u32, 2, 32, u32> = KdTree::optimize_from(&points);
+    ///
+    /// let stats = tree.stats();
+    ///
+    /// assert_eq!(stats.size, 100);
+    /// assert_eq!(stats.leaf_count, stats.stem_count + 1);
+    /// assert_eq!(stats.leaf_occupancy.iter().sum::<usize>(), stats.leaf_count);
+    /// ```
+    pub fn stats(&self) -> TreeStats {
+        let mut builder = TreeStatsBuilder::new();
+        let mut stem_count = 0;
+        let mut to_visit = vec![(Self::ROOT, 0)];
+        while let Some((node_idx, depth)) = to_visit.pop() {
+            match self.node(node_idx) {
+                Node::Stem {
+                    children: [left, right],
+                    ..
+                } => {
+                    stem_count += 1;
+                    to_visit.push((right, depth + 1));
+                    to_visit.push((left, depth + 1));
+                }
+                Node::Leaf(leaf_idx) => {
+                    let leaf = &self.leaves[leaf_idx];
+                    builder.add_leaf(
+                        depth,
+                        leaf.content_points[..leaf.size.az::<usize>()]
+                            .iter()
+                            .copied(),
+                    );
+                }
+            }
         }
+
+        let leaf_points = size_of::<[[A; K]; B]>();
+        let leaf_items = size_of::<[T; B]>();
+        let leaf_other = size_of::<LeafNode<A, T, K, B, IDX>>() - leaf_points - leaf_items;
+        builder.finish(
+            stem_count,
+            MemoryUsage {
+                stems: self.stems.len() * size_of::<A>()
+                    + self.dstems.len() * size_of::<StemNode<A, K, IDX>>(),
+                leaf_points: self.leaves.len() * leaf_points,
+                leaf_items: self.leaves.len() * leaf_items,
+                other: self.leaves.len() * leaf_other,
+            },
+        )
     }
 }
 
@@ -594,8 +702,9 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::float_sss::kdtree::KdTree;
-    use num_traits::Pow;
+    use crate::hybrid::kdtree::{KdTree, Node, NodeIdx};
+    use crate::traits::Index;
+    use az::{Az, Cast};
     use ordered_float::OrderedFloat;
     use rand::{Rng, SeedableRng};
     use std::panic;
@@ -621,41 +730,84 @@ mod tests {
 
         assert_eq!(tree.size(), 0);
     }
-    /*    #[cfg(feature = "serde")]
+
+    #[cfg(feature = "serde")]
     #[test]
     fn can_serde() {
-        let mut tree: KdTree<u16, u32, 4, 32, u32> = KdTree::new();
-
-        let content_to_add: [(PT, T); 16] = [
-            ([9f32, 0f32, 9f32, 0f32], 9),
-            ([4f32, 500f32, 4f32, 500f32], 4),
-            ([12f32, -300f32, 12f32, -300f32], 12),
-            ([7f32, 200f32, 7f32, 200f32], 7),
-            ([13f32, -400f32, 13f32, -400f32], 13),
-            ([6f32, 300f32, 6f32, 300f32], 6),
-            ([2f32, 700f32, 2f32, 700f32], 2),
-            ([14f32, -500f32, 14f32, -500f32], 14),
-            ([3f32, 600f32, 3f32, 600f32], 3),
-            ([10f32, -100f32, 10f32, -100f32], 10),
-            ([16f32, -700f32, 16f32, -700f32], 16),
-            ([1f32, 800f32, 1f32, 800f32], 1),
-            ([15f32, -600f32, 15f32, -600f32], 15),
-            ([5f32, 400f32, 5f32, 400f32], 5),
-            ([8f32, 100f32, 8f32, 100f32], 8),
-            ([11f32, -200f32, 11f32, -200f32], 11),
-        ];
+        let content_to_add: Vec<[f32; 2]> =
+            (0..100).map(|i| [i as f32, (i * 7 % 13) as f32]).collect();
+        let tree: KdTree<f32, u32, 2, 4, u32> = KdTree::optimize_from(&content_to_add);
 
-        for (point, item) in content_to_add {
-            tree.add(&point, item);
+        // unsplit stems are NaN, which JSON can't represent, so use bincode
+        let serialized = bincode::serialize(&tree).unwrap();
+        let deserialized: KdTree<f32, u32, 2, 4, u32> = bincode::deserialize(&serialized).unwrap();
+
+        let stem_bits = |t: &KdTree<f32, u32, 2, 4, u32>| -> Vec<u32> {
+            t.stems.iter().map(|s| s.to_bits()).collect()
+        };
+        assert_eq!(stem_bits(&tree), stem_bits(&deserialized));
+        assert_eq!(tree.leaves, deserialized.leaves);
+        assert_eq!(tree.size(), deserialized.size());
+    }
+
+    /// Walks the whole tree, checking that every leaf is no larger than B and that every
+    /// point in it lies on the correct side of each of its ancestors' split planes, and
+    /// returns every item found
+    fn assert_tree_is_consistent<const K: usize, const B: usize, IDX>(
+        tree: &KdTree<f32, usize, K, B, IDX>,
+    ) -> Vec<usize>
+    where
+        IDX: Index<T = IDX>,
+        usize: Cast<IDX>,
+    {
+        fn walk<const K: usize, const B: usize, IDX>(
+            tree: &KdTree<f32, usize, K, B, IDX>,
+            node_idx: NodeIdx,
+            split_dim: usize,
+            bounds: &mut Vec<(usize, f32, bool)>,
+            items: &mut Vec<usize>,
+        ) where
+            IDX: Index<T = IDX>,
+            usize: Cast<IDX>,
+        {
+            match tree.node(node_idx) {
+                Node::Stem {
+                    split_val,
+                    children,
+                } => {
+                    for (is_right, child) in children.into_iter().enumerate() {
+                        bounds.push((split_dim, split_val, is_right == 1));
+                        walk(tree, child, (split_dim + 1) % K, bounds, items);
+                        bounds.pop();
+                    }
+                }
+                Node::Leaf(leaf_idx) => {
+                    let leaf = &tree.leaves[leaf_idx];
+                    assert!(leaf.size.az::<usize>() <= B);
+
+                    for idx in 0..leaf.size.az::<usize>() {
+                        let point = leaf.content_points[idx];
+                        for &(dim, split_val, is_right) in bounds.iter() {
+                            assert_eq!(point[dim] >= split_val, is_right);
+                        }
+                        items.push(leaf.content_items[idx]);
+                    }
+                }
+            }
         }
-        assert_eq!(tree.size(), 16);
 
-        let serialized = serde_json::to_string(&tree).unwrap();
-        println!("JSON: {:?}", &serialized);
+        let mut items = vec![];
+        walk(
+            tree,
+            KdTree::<f32, usize, K, B, IDX>::ROOT,
+            0,
+            &mut vec![],
+            &mut items,
+        );
+        items.sort();
 
-        let deserialized: KdTree = serde_json::from_str(&serialized).unwrap();
-        assert_eq!(tree, deserialized);
-    }*/
+        items
+    }
 
     #[test]
     fn can_construct_optimized_tree_with_straddled_split() {
@@ -680,13 +832,11 @@ mod tests {
 
         let tree: KdTree<f32, usize, 2, 4, u32> = KdTree::optimize_from(&content_to_add);
 
-        println!("Tree Stats: {:?}", tree.generate_stats());
-
-        assert_eq!(tree.leaves[0].size, 3);
-        assert_eq!(tree.leaves[1].size, 4);
-        assert_eq!(tree.leaves[2].size, 4);
-        assert_eq!(tree.leaves[3].size, 4);
-        assert_eq!(tree.leaves[4].size, 1);
+        assert_eq!(tree.size(), 16);
+        assert_eq!(
+            assert_tree_is_consistent(&tree),
+            (0..16).collect::<Vec<_>>()
+        );
     }
 
     #[test]
@@ -715,13 +865,11 @@ mod tests {
 
         let tree: KdTree<f32, usize, 2, 4, u32> = KdTree::optimize_from(&content_to_add);
 
-        println!("Tree Stats: {:?}", tree.generate_stats());
-
-        assert_eq!(tree.leaves[0].size, 3);
-        assert_eq!(tree.leaves[1].size, 4);
-        assert_eq!(tree.leaves[2].size, 4);
-        assert_eq!(tree.leaves[3].size, 4);
-        assert_eq!(tree.leaves[4].size, 4);
+        assert_eq!(tree.size(), 19);
+        assert_eq!(
+            assert_tree_is_consistent(&tree),
+            (0..19).collect::<Vec<_>>()
+        );
     }
 
     #[test]
@@ -755,13 +903,11 @@ mod tests {
 
         let tree: KdTree<f32, usize, 2, 4, u32> = KdTree::optimize_from(&content_to_add);
 
-        println!("Tree Stats: {:?}", tree.generate_stats());
-
-        assert_eq!(tree.leaves[0].size, 3);
-        assert_eq!(tree.leaves[1].size, 4);
-        assert_eq!(tree.leaves[2].size, 4);
-        assert_eq!(tree.leaves[3].size, 4);
-        assert_eq!(tree.leaves[4].size, 4);
+        assert_eq!(tree.size(), 19);
+        assert_eq!(
+            assert_tree_is_consistent(&tree),
+            (0..19).collect::<Vec<_>>()
+        );
     }
 
     #[test]
@@ -772,23 +918,32 @@ mod tests {
         let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
         let content_to_add: Vec<[f32; 4]> = (0..tree_size).map(|_| rng.gen::<[f32; 4]>()).collect();
 
-        let _tree: KdTree<f32, usize, 4, 4, u32> = KdTree::optimize_from(&content_to_add);
+        let tree: KdTree<f32, usize, 4, 4, u32> = KdTree::optimize_from(&content_to_add);
+
+        assert_eq!(
+            assert_tree_is_consistent(&tree),
+            (0..tree_size).collect::<Vec<_>>()
+        );
     }
 
-    #[ignore]
     #[test]
     fn can_construct_optimized_tree_multi_rand_increasing_size() {
         let mut failed = false;
 
-        for tree_size in 16..=1024 {
-            for seed in 0..100 {
+        for tree_size in (16..=1024).step_by(37) {
+            for seed in 0..10 {
                 let result = panic::catch_unwind(|| {
                     let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
                     let content_to_add: Vec<[f32; 4]> =
                         (0..tree_size).map(|_| rng.gen::<[f32; 4]>()).collect();
 
-                    let _tree: KdTree<f32, usize, 4, 4, u32> =
+                    let tree: KdTree<f32, usize, 4, 4, u32> =
                         KdTree::optimize_from(&content_to_add);
+
+                    assert_eq!(
+                        assert_tree_is_consistent(&tree),
+                        (0..tree_size).collect::<Vec<_>>()
+                    );
                 });
 
                 if result.is_err() {
@@ -805,7 +960,7 @@ mod tests {
     fn can_construct_optimized_tree_medium_rand() {
         use itertools::Itertools;
 
-        const TREE_SIZE: usize = 2usize.pow(19); // ~ 500k
+        const TREE_SIZE: usize = 2usize.pow(16);
 
         let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(493);
         let content_to_add: Vec<[f32; 4]> = (0..TREE_SIZE).map(|_| rng.gen::<[f32; 4]>()).collect();
@@ -821,9 +976,13 @@ mod tests {
 
         let tree: KdTree<f32, usize, 4, 4, u32> = KdTree::optimize_from(&content_to_add);
 
-        println!("Tree Stats: {:?}", tree.generate_stats())
+        assert_eq!(
+            assert_tree_is_consistent(&tree),
+            (0..TREE_SIZE).collect::<Vec<_>>()
+        );
     }
 
+    #[ignore]
     #[test]
     fn can_construct_optimized_tree_large_rand() {
         const TREE_SIZE: usize = 2usize.pow(23); // ~8M
@@ -831,15 +990,57 @@ mod tests {
         let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(493);
         let content_to_add: Vec<[f32; 4]> = (0..TREE_SIZE).map(|_| rng.gen::<[f32; 4]>()).collect();
 
-        // let num_uniq = content_to_add
-        //     .iter()
-        //     .flatten()
-        //     .map(|&x| OrderedFloat(x))
-        //     .unique()
-        //     .count();
-
         let tree: KdTree<f32, usize, 4, 32, u32> = KdTree::optimize_from(&content_to_add);
 
-        println!("Tree Stats: {:?}", tree.generate_stats())
+        assert_eq!(tree.size(), TREE_SIZE);
+    }
+
+    #[test]
+    fn stats_describe_every_reachable_leaf() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+        let content_to_add: Vec<[f32; 2]> = (0..1_000).map(|_| rng.gen::<[f32; 2]>()).collect();
+
+        let mut tree: KdTree<f32, usize, 2, 8, u32> = KdTree::optimize_from(&content_to_add[..500]);
+        for (idx, point) in content_to_add.iter().enumerate().skip(500) {
+            tree.add(point, idx);
+        }
+        for idx in 0..3 {
+            tree.add(&[0.5, 0.25], 1_000 + idx);
+        }
+
+        let stats = tree.stats();
+
+        assert_eq!(stats.size, 1_003);
+        assert_eq!(stats.leaf_count, stats.stem_count + 1);
+        assert!(stats.leaf_count <= tree.leaves.len());
+        assert!(stats.min_depth >= 1);
+        assert!(stats.max_depth >= stats.leaf_count.ilog2() as usize);
+        assert!(stats.mean_depth >= stats.min_depth as f64);
+        assert!(stats.mean_depth <= stats.max_depth as f64);
+        assert_eq!(stats.leaf_occupancy.len(), 9);
+        assert_eq!(stats.leaf_occupancy.iter().sum::<usize>(), stats.leaf_count);
+        assert!(stats.duplicate_coordinate_leaves >= 1);
+        assert_eq!(
+            stats.memory.leaf_points,
+            tree.leaves.len() * 8 * 2 * std::mem::size_of::<f32>()
+        );
+    }
+
+    #[test]
+    fn optimized_tree_still_accepts_additions() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+        let content_to_add: Vec<[f32; 3]> = (0..1000).map(|_| rng.gen::<[f32; 3]>()).collect();
+
+        let mut tree: KdTree<f32, usize, 3, 8, u32> = KdTree::optimize_from(&content_to_add[..500]);
+        for (idx, point) in content_to_add.iter().enumerate().skip(500) {
+            tree.add(point, idx);
+        }
+
+        assert_eq!(tree.size(), 1000);
+        assert!(!tree.dstems.is_empty());
+        assert_eq!(
+            assert_tree_is_consistent(&tree),
+            (0..1000).collect::<Vec<_>>()
+        );
     }
 }
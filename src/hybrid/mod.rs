@@ -1,10 +1,15 @@
-//! Floating point k-d tree, for use when the co-ordinates of the points being stored in the tree
+//! Hybrid floating point k-d tree, for use when the co-ordinates of the points being stored in the tree
 //! are floats. [`f64`] or [`f32`] are supported currently.
+//!
+//! Combines index-less static stems, like those of the
+//! [`ImmutableKdTree`](`crate::immutable::float::kdtree::ImmutableKdTree`), with dynamic stems
+//! like those of the [`float::kdtree::KdTree`](`crate::float::kdtree::KdTree`). A tree can be created
+//! already balanced with [`optimize_from`](`kdtree::KdTree::optimize_from`) and still accept inserts.
 
 #[doc(hidden)]
 pub mod construction;
-pub mod distance;
 pub mod kdtree;
-pub mod neighbour;
 #[doc(hidden)]
 pub mod query;
+#[cfg(test)]
+pub(crate) mod test_utils;
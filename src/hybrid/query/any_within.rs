@@ -0,0 +1,73 @@
+use az::Cast;
+
+use crate::hybrid::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_any_within;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_any_within!(
+        (r#"Finds an element within `dist` of `query`, using the specified
+distance metric function, or returns `None` if there are none.
+
+Stops searching as soon as an element is found, so the element returned is not
+necessarily the nearest one. Faster than checking whether `within_unsorted` is empty.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    assert!(tree.any_within::<SquaredEuclidean>(&[1.0, 2.0, 5.5], 1f64).is_some());
+    assert!(tree.any_within::<SquaredEuclidean>(&[10.0, 20.0, 50.0], 1f64).is_none());
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::Manhattan;
+    use crate::hybrid::test_utils::build_tree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f64;
+
+    #[test]
+    fn can_find_any_item_within_radius() {
+        const TREE_SIZE: usize = 2_000;
+        const NUM_QUERIES: usize = 1_000;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(83);
+        let content: Vec<[AX; 3]> = (0..TREE_SIZE).map(|_| rng.gen::<[AX; 3]>()).collect();
+        let tree = build_tree(&content);
+
+        for _ in 0..NUM_QUERIES {
+            let query_point = rng.gen::<[AX; 3]>();
+            let radius = rng.gen_range(0.0..0.1);
+            let any_expected = content
+                .iter()
+                .any(|point| Manhattan::dist(&query_point, point) < radius);
+
+            match tree.any_within::<Manhattan>(&query_point, radius) {
+                Some(found) => {
+                    let point = content[found.item as usize];
+                    assert_eq!(found.distance, Manhattan::dist(&query_point, &point));
+                    assert!(found.distance < radius);
+                }
+                None => assert!(!any_expected),
+            }
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use crate::float_sss::kdtree::{Axis, KdTree, LeafNode};
-
-use crate::types::{Content, Index};
 use az::{Az, Cast};
 use std::collections::BinaryHeap;
 use std::ops::Rem;
 
+use crate::best_neighbour::BestNeighbour;
+use crate::hybrid::kdtree::{Axis, KdTree, Node, NodeIdx};
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
 impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
     KdTree<A, T, K, B, IDX>
 where
@@ -13,162 +15,132 @@ where
     /// Finds the "best" `n` elements within `dist` of `query`.
     ///
     /// Results are returned in arbitrary order. 'Best' is determined by
-    /// performing a comparison of the elements using < (ie, std::ord::lt).
+    /// performing a comparison of the elements using < (ie, [`std::cmp::Ordering::is_lt`]).
     /// Returns an iterator.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
-    /// use kiddo::distance::squared_euclidean;
+    /// use kiddo::hybrid::kdtree::KdTree;
+    /// use kiddo::best_neighbour::BestNeighbour;
+    /// use kiddo::SquaredEuclidean;
     ///
     /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
-    ///
     /// tree.add(&[1.0, 2.0, 5.0], 100);
-    /// tree.add(&[2.0, 3.0, 6.0], 1);
-    /// tree.add(&[200.0, 300.0, 600.0], 102);
+    /// tree.add(&[2.0, 3.0, 6.0], 101);
     ///
-    /// let mut best_n_within = tree.best_n_within(&[1.0, 2.0, 5.0], 10f64, 1, &squared_euclidean);
+    /// let mut best_n_within = tree.best_n_within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64, 1);
     /// let first = best_n_within.next().unwrap();
     ///
-    /// assert_eq!(first, 1);
+    /// assert_eq!(first, BestNeighbour { distance: 0.0, item: 100 });
     /// ```
     #[inline]
-    pub fn best_n_within<F>(
+    pub fn best_n_within<D>(
         &self,
         query: &[A; K],
         dist: A,
-        max_qty: NonZero<usize>,
-        distance_fn: &F,
-    ) -> impl Iterator<Item = T>
+        max_qty: usize,
+    ) -> impl Iterator<Item = BestNeighbour<A, T>>
     where
-        F: Fn(&[A; K], &[A; K]) -> A,
+        D: DistanceMetric<A, K>,
     {
         let mut off = [A::zero(); K];
-        let mut best_items: BinaryHeap<T> = BinaryHeap::new();
-
-        unsafe {
-            self.best_n_within_recurse(
-                query,
-                dist,
-                max_qty,
-                distance_fn,
-                self.root_index,
-                0,
-                &mut best_items,
-                &mut off,
-                A::zero(),
-            );
-        }
+        let mut best_items: BinaryHeap<BestNeighbour<A, T>> = BinaryHeap::new();
+
+        self.best_n_within_recurse::<D>(
+            query,
+            dist,
+            max_qty,
+            Self::ROOT,
+            0,
+            &mut best_items,
+            &mut off,
+            A::zero(),
+        );
 
         best_items.into_iter()
     }
 
     #[allow(clippy::too_many_arguments)]
-    unsafe fn best_n_within_recurse<F>(
+    fn best_n_within_recurse<D>(
         &self,
         query: &[A; K],
         radius: A,
-        max_qty: NonZero<usize>,
-        distance_fn: &F,
-        curr_node_idx: IDX,
+        max_qty: usize,
+        curr_node_idx: NodeIdx,
         split_dim: usize,
-        best_items: &mut BinaryHeap<T>,
+        best_items: &mut BinaryHeap<BestNeighbour<A, T>>,
         off: &mut [A; K],
         rd: A,
     ) where
-        F: Fn(&[A; K], &[A; K]) -> A,
+        D: DistanceMetric<A, K>,
     {
-        if KdTree::<A, T, K, B, IDX>::is_stem_index(curr_node_idx) {
-            let node = self.stems.get_unchecked(curr_node_idx.az::<usize>());
-
-            let mut rd = rd;
-            let old_off = off[split_dim];
-            let new_off = query[split_dim] - node.split_val;
-
-            let [closer_node_idx, further_node_idx] =
-                if *query.get_unchecked(split_dim) < node.split_val {
-                    [node.left, node.right]
+        match self.node(curr_node_idx) {
+            Node::Stem {
+                split_val,
+                children: [left, right],
+            } => {
+                let mut rd = rd;
+                let old_off = off[split_dim];
+                let new_off = query[split_dim].saturating_dist(split_val);
+
+                let [closer_node_idx, further_node_idx] = if query[split_dim] < split_val {
+                    [left, right]
                 } else {
-                    [node.right, node.left]
+                    [right, left]
                 };
-            let next_split_dim = (split_dim + 1).rem(K);
-
-            self.best_n_within_recurse(
-                query,
-                radius,
-                max_qty,
-                distance_fn,
-                closer_node_idx,
-                next_split_dim,
-                best_items,
-                off,
-                rd,
-            );
-
-            // TODO: switch from dist_fn to a dist trait that can apply to 1D as well as KD
-            //       so that updating rd is not hardcoded to sq euclidean
-            rd = rd + new_off * new_off - old_off * old_off;
+                let next_split_dim = (split_dim + 1).rem(K);
 
-            if rd <= radius {
-                off[split_dim] = new_off;
-                self.best_n_within_recurse(
+                self.best_n_within_recurse::<D>(
                     query,
                     radius,
                     max_qty,
-                    distance_fn,
-                    further_node_idx,
+                    closer_node_idx,
                     next_split_dim,
                     best_items,
                     off,
                     rd,
                 );
-                off[split_dim] = old_off;
-            }
-        } else {
-            let leaf_node = self
-                .leaves
-                .get_unchecked((curr_node_idx - IDX::leaf_offset()).az::<usize>());
-
-            Self::process_leaf_node(query, radius, max_qty, distance_fn, best_items, leaf_node);
-        }
-    }
-
-    unsafe fn process_leaf_node<F>(
-        query: &[A; K],
-        radius: A,
-        max_qty: NonZero<usize>,
-        distance_fn: &F,
-        best_items: &mut BinaryHeap<T>,
-        leaf_node: &LeafNode<A, T, K, B, IDX>,
-    ) where
-        F: Fn(&[A; K], &[A; K]) -> A,
-    {
-        leaf_node
-            .content_points
-            .iter()
-            .take(leaf_node.size.az::<usize>())
-            .map(|entry| distance_fn(query, entry))
-            .enumerate()
-            .filter(|(_, distance)| *distance <= radius)
-            .for_each(|(idx, _)| {
-                Self::get_item_and_add_if_good(max_qty, best_items, leaf_node, idx)
-            });
-    }
 
-    unsafe fn get_item_and_add_if_good(
-        max_qty: NonZero<usize>,
-        best_items: &mut BinaryHeap<T>,
-        leaf_node: &LeafNode<A, T, K, B, IDX>,
-        idx: usize,
-    ) {
-        let item = *leaf_node.content_items.get_unchecked(idx.az::<usize>());
-        if best_items.len() < max_qty {
-            best_items.push(item);
-        } else {
-            let mut top = best_items.peek_mut().unwrap();
-            if item < *top {
-                *top = item;
+                rd = A::rd_update(rd, D::dist1(new_off, old_off));
+
+                if rd <= radius {
+                    off[split_dim] = new_off;
+                    self.best_n_within_recurse::<D>(
+                        query,
+                        radius,
+                        max_qty,
+                        further_node_idx,
+                        next_split_dim,
+                        best_items,
+                        off,
+                        rd,
+                    );
+                    off[split_dim] = old_off;
+                }
+            }
+            Node::Leaf(leaf_idx) => {
+                let leaf_node = &self.leaves[leaf_idx];
+
+                leaf_node
+                    .content_points
+                    .iter()
+                    .take(leaf_node.size.az::<usize>())
+                    .map(|entry| D::dist(query, entry))
+                    .enumerate()
+                    .filter(|(_, distance)| *distance <= radius)
+                    .for_each(|(idx, distance)| {
+                        let item = leaf_node.content_items[idx];
+                        if best_items.len() < max_qty {
+                            best_items.push(BestNeighbour { distance, item });
+                        } else {
+                            let mut top = best_items.peek_mut().unwrap();
+                            if item < top.item {
+                                top.item = item;
+                                top.distance = distance;
+                            }
+                        }
+                    });
             }
         }
     }
@@ -176,8 +148,10 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::float::distance::squared_euclidean;
-    use crate::float::kdtree::KdTree;
+    use crate::best_neighbour::BestNeighbour;
+    use crate::float::distance::SquaredEuclidean;
+    use crate::hybrid::kdtree::KdTree;
+    use crate::traits::DistanceMetric;
     use rand::Rng;
 
     type AX = f64;
@@ -213,10 +187,23 @@ mod tests {
         let query = [9f64, 0f64];
         let radius = 20000f64;
         let max_qty = 3;
-        let expected = vec![10, 9, 8];
+        let expected = vec![
+            BestNeighbour {
+                distance: 10001.0,
+                item: 10,
+            },
+            BestNeighbour {
+                distance: 0.0,
+                item: 9,
+            },
+            BestNeighbour {
+                distance: 10001.0,
+                item: 8,
+            },
+        ];
 
         let result: Vec<_> = tree
-            .best_n_within(&query, radius, max_qty, &squared_euclidean)
+            .best_n_within::<SquaredEuclidean>(&query, radius, max_qty)
             .collect();
         assert_eq!(result, expected);
 
@@ -230,10 +217,9 @@ mod tests {
             ];
             let radius = 100000f64;
             let expected = linear_search(&content_to_add, &query, radius, max_qty);
-            println!("{}, {}", query[0].to_string(), query[1].to_string());
 
             let result: Vec<_> = tree
-                .best_n_within(&query, radius, max_qty, &squared_euclidean)
+                .best_n_within::<SquaredEuclidean>(&query, radius, max_qty)
                 .collect();
             assert_eq!(result, expected);
         }
@@ -253,7 +239,7 @@ mod tests {
         content_to_add
             .iter()
             .for_each(|(point, content)| tree.add(point, *content));
-        assert_eq!(tree.size(), TREE_SIZE as i32);
+        assert_eq!(tree.size(), TREE_SIZE);
 
         let query_points: Vec<[AX; 2]> = (0..NUM_QUERIES)
             .map(|_| rand::random::<[AX; 2]>())
@@ -264,7 +250,7 @@ mod tests {
             let expected = linear_search(&content_to_add, &query_point, radius, max_qty);
 
             let result: Vec<_> = tree
-                .best_n_within(&query_point, radius, max_qty, &squared_euclidean)
+                .best_n_within::<SquaredEuclidean>(&query_point, radius, max_qty)
                 .collect();
             assert_eq!(result, expected);
         }
@@ -274,20 +260,18 @@ mod tests {
         content: &[([f64; 2], i32)],
         query: &[f64; 2],
         radius: f64,
-        max_qty: NonZero<usize>,
-    ) -> Vec<i32> {
+        max_qty: usize,
+    ) -> Vec<BestNeighbour<f64, i32>> {
         let mut best_items = Vec::with_capacity(max_qty);
 
         for &(p, item) in content {
-            let dist = squared_euclidean(query, &p);
-            if dist <= radius {
+            let distance = SquaredEuclidean::dist(query, &p);
+            if distance <= radius {
                 if best_items.len() < max_qty {
-                    best_items.push(item);
-                } else {
-                    if item < *best_items.last().unwrap() {
-                        best_items.pop().unwrap();
-                        best_items.push(item);
-                    }
+                    best_items.push(BestNeighbour { distance, item });
+                } else if item < best_items.last().unwrap().item {
+                    best_items.pop().unwrap();
+                    best_items.push(BestNeighbour { distance, item });
                 }
             }
             best_items.sort_unstable();
@@ -0,0 +1,93 @@
+use az::Cast;
+
+use crate::hybrid::kdtree::{Axis, KdTree};
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_count_within;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_count_within!(
+        (r#"Counts the elements within `dist` of `query`, using the specified
+distance metric function.
+
+Equivalent to `within_unsorted(query, dist).len()`, but without collecting the results.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    assert_eq!(tree.count_within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64), 2);
+    assert_eq!(tree.count_within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 1f64), 1);
+```"#),
+        (r#"Counts the elements within `dist` of `query`, using the specified
+distance metric function, stopping once `limit` elements have been found.
+
+Returns the smaller of `limit` and the number of elements within `dist` of `query`.
+Useful for threshold checks, such as whether at least `limit` elements are nearby.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    assert_eq!(tree.count_within_at_most::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64, 1), 1);
+    assert_eq!(tree.count_within_at_most::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64, 5), 2);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::Manhattan;
+    use crate::hybrid::test_utils::build_tree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f64;
+
+    #[test]
+    fn can_count_items_within_radius() {
+        const TREE_SIZE: usize = 2_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(89);
+        let content: Vec<[AX; 3]> = (0..TREE_SIZE).map(|_| rng.gen::<[AX; 3]>()).collect();
+        let tree = build_tree(&content);
+
+        for _ in 0..NUM_QUERIES {
+            let query_point = rng.gen::<[AX; 3]>();
+            let radius = rng.gen_range(0.0..0.3);
+            let expected = content
+                .iter()
+                .filter(|point| Manhattan::dist(&query_point, point) < radius)
+                .count();
+
+            assert_eq!(
+                tree.count_within::<Manhattan>(&query_point, radius),
+                expected
+            );
+            for limit in [0, 1, expected / 2, expected, expected + 1] {
+                assert_eq!(
+                    tree.count_within_at_most::<Manhattan>(&query_point, radius, limit),
+                    expected.min(limit)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use az::Cast;
+use std::collections::BinaryHeap;
+
+use crate::hybrid::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_filtered;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_filtered!(
+        (r#"Finds the nearest element to `query` for which `filter` returns `true`, using
+the specified distance metric function, or returns `None` if there are none.
+
+Items that are rejected by `filter` do not narrow the search, so this always finds
+the nearest accepted item, unlike filtering the results of `nearest_n`.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    let nearest = tree.nearest_one_filtered::<SquaredEuclidean, _>(&[1.0, 2.0, 5.1], |item| item != 100);
+
+    assert_eq!(nearest.unwrap().item, 101);
+```"#),
+        (r#"Finds the nearest `qty` elements to `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    let nearest = tree.nearest_n_filtered::<SquaredEuclidean, _>(&[1.0, 2.0, 5.1], 2, |item| item % 2 == 1);
+
+    assert_eq!(nearest.len(), 1);
+    assert_eq!(nearest[0].item, 101);
+```"#),
+        (r#"Finds all elements within `dist` of `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    let within = tree.within_filtered::<SquaredEuclidean, _>(&[1.0, 2.0, 5.0], 10f64, |item| item > 100);
+
+    assert_eq!(within.len(), 1);
+    assert_eq!(within[0].item, 101);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::Manhattan;
+    use crate::hybrid::test_utils::build_tree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f64;
+
+    #[test]
+    fn can_query_filtered() {
+        const TREE_SIZE: usize = 2_000;
+        const NUM_QUERIES: usize = 50;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(97);
+        let content: Vec<[AX; 3]> = (0..TREE_SIZE).map(|_| rng.gen::<[AX; 3]>()).collect();
+        let tree = build_tree(&content);
+        let filter = |item: u32| item.is_multiple_of(3);
+
+        for _ in 0..NUM_QUERIES {
+            let query_point = rng.gen::<[AX; 3]>();
+            let radius = rng.gen_range(0.0..0.3);
+            let mut expected: Vec<(AX, u32)> = content
+                .iter()
+                .enumerate()
+                .map(|(idx, p)| (Manhattan::dist(&query_point, p), idx as u32))
+                .filter(|&(_, item)| filter(item))
+                .collect();
+            expected.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            let nearest = tree
+                .nearest_one_filtered::<Manhattan, _>(&query_point, filter)
+                .unwrap();
+            assert_eq!(nearest.distance, expected[0].0);
+            assert!(filter(nearest.item));
+
+            for qty in [1, 5, 50] {
+                let result = tree.nearest_n_filtered::<Manhattan, _>(&query_point, qty, filter);
+                let dists: Vec<AX> = result.iter().map(|n| n.distance).collect();
+                let expected_dists: Vec<AX> = expected.iter().take(qty).map(|e| e.0).collect();
+                assert_eq!(dists, expected_dists);
+                assert!(result.iter().all(|n| filter(n.item)));
+            }
+
+            let within = tree.within_filtered::<Manhattan, _>(&query_point, radius, filter);
+            let dists: Vec<AX> = within.iter().map(|n| n.distance).collect();
+            let expected_dists: Vec<AX> = expected
+                .iter()
+                .map(|e| e.0)
+                .filter(|&dist| dist < radius)
+                .collect();
+            assert_eq!(dists, expected_dists);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use az::Cast;
+use std::collections::BinaryHeap;
+
+use crate::hybrid::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
+use crate::generate_nearest_excluding;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_nearest_excluding!(
+        (r#"Queries the tree to find the nearest item to the `query` point, ignoring
+any elements whose item is `excluded`.
+
+Intended for querying with a point that is itself stored in the tree, such as when
+building a neighbour graph. Returns `None` if the tree holds no other items.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    let nearest = tree.nearest_one_excluding::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 100).unwrap();
+
+    assert_eq!(nearest.distance, 3.0);
+    assert_eq!(nearest.item, 101);
+```"#),
+        (r#"Finds the nearest `qty` elements to `query`, ignoring any elements whose
+item is `excluded`, using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+    use kiddo::SquaredEuclidean;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    let nearest = tree.nearest_n_excluding::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 2, 100);
+
+    assert_eq!(nearest.len(), 1);
+    assert_eq!(nearest[0].item, 101);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::Manhattan;
+    use crate::hybrid::kdtree::KdTree;
+    use crate::hybrid::test_utils::build_tree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f64;
+
+    #[test]
+    fn can_query_nearest_excluding_a_stored_point() {
+        const TREE_SIZE: usize = 2_000;
+        const NUM_QUERIES: usize = 50;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(101);
+        let content: Vec<[AX; 3]> = (0..TREE_SIZE).map(|_| rng.gen::<[AX; 3]>()).collect();
+        let tree = build_tree(&content);
+
+        for item in (0..TREE_SIZE as u32).step_by(TREE_SIZE / NUM_QUERIES) {
+            let query_point = content[item as usize];
+            let mut expected: Vec<AX> = content
+                .iter()
+                .enumerate()
+                .filter(|&(idx, _)| idx as u32 != item)
+                .map(|(_, p)| Manhattan::dist(&query_point, p))
+                .collect();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            let nearest = tree
+                .nearest_one_excluding::<Manhattan>(&query_point, item)
+                .unwrap();
+            assert_ne!(nearest.item, item);
+            assert_eq!(nearest.distance, expected[0]);
+
+            let result = tree.nearest_n_excluding::<Manhattan>(&query_point, 5, item);
+            assert!(result.iter().all(|n| n.item != item));
+            let dists: Vec<AX> = result.iter().map(|n| n.distance).collect();
+            assert_eq!(dists, expected[..5]);
+        }
+    }
+
+    #[test]
+    fn nearest_excluding_the_only_item_is_none() {
+        let mut tree: KdTree<AX, u32, 3, 8, u32> = KdTree::new();
+        assert!(tree
+            .nearest_one_excluding::<Manhattan>(&[0.5; 3], 0)
+            .is_none());
+
+        tree.add(&[0.5; 3], 0);
+
+        assert!(tree
+            .nearest_one_excluding::<Manhattan>(&[0.5; 3], 0)
+            .is_none());
+        assert!(tree
+            .nearest_n_excluding::<Manhattan>(&[0.5; 3], 3, 0)
+            .is_empty());
+    }
+}
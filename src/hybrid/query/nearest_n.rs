@@ -1,10 +1,12 @@
-use crate::float_sss::kdtree::{Axis, KdTree};
-use crate::float_sss::neighbour::Neighbour;
-use crate::types::{Content, Index};
 use az::{Az, Cast};
 use std::collections::BinaryHeap;
 use std::ops::Rem;
 
+use crate::hybrid::kdtree::{Axis, KdTree, Node, NodeIdx};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
+
 impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
     KdTree<A, T, K, B, IDX>
 where
@@ -16,133 +18,121 @@ where
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
-    /// use kiddo::distance::squared_euclidean;
+    /// use kiddo::hybrid::kdtree::KdTree;
+    /// use kiddo::SquaredEuclidean;
     ///
     /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
-    ///
     /// tree.add(&[1.0, 2.0, 5.0], 100);
     /// tree.add(&[2.0, 3.0, 6.0], 101);
     ///
-    /// let nearest: Vec<_> = tree.nearest_n(&[1.0, 2.0, 5.1], 1, &squared_euclidean);
+    /// let nearest: Vec<_> = tree.nearest_n::<SquaredEuclidean>(&[1.0, 2.0, 5.1], 1);
     ///
     /// assert_eq!(nearest.len(), 1);
     /// assert!((nearest[0].distance - 0.01f64).abs() < f64::EPSILON);
     /// assert_eq!(nearest[0].item, 100);
     /// ```
     #[inline]
-    pub fn nearest_n<F>(&self, query: &[A; K], qty: usize, distance_fn: &F) -> Vec<Neighbour<A, T>>
+    pub fn nearest_n<D>(&self, query: &[A; K], qty: usize) -> Vec<NearestNeighbour<A, T>>
     where
-        F: Fn(&[A; K], &[A; K]) -> A,
+        D: DistanceMetric<A, K>,
     {
         let mut off = [A::zero(); K];
-        let mut result: BinaryHeap<Neighbour<A, T>> = BinaryHeap::with_capacity(qty);
-
-        unsafe {
-            self.nearest_n_recurse(
-                query,
-                distance_fn,
-                self.root_index,
-                0,
-                &mut result,
-                &mut off,
-                A::zero(),
-            )
-        }
+        let mut result: BinaryHeap<NearestNeighbour<A, T>> = BinaryHeap::with_capacity(qty);
+
+        self.nearest_n_recurse::<D>(query, Self::ROOT, 0, &mut result, &mut off, A::zero());
 
         result.into_sorted_vec()
     }
 
-    unsafe fn nearest_n_recurse<F>(
+    fn nearest_n_recurse<D>(
         &self,
         query: &[A; K],
-        distance_fn: &F,
-        curr_node_idx: IDX,
+        curr_node_idx: NodeIdx,
         split_dim: usize,
-        results: &mut BinaryHeap<Neighbour<A, T>>,
+        results: &mut BinaryHeap<NearestNeighbour<A, T>>,
         off: &mut [A; K],
         rd: A,
     ) where
-        F: Fn(&[A; K], &[A; K]) -> A,
+        D: DistanceMetric<A, K>,
     {
-        if KdTree::<A, T, K, B, IDX>::is_stem_index(curr_node_idx) {
-            let node = &self.stems.get_unchecked(curr_node_idx.az::<usize>());
-
-            let mut rd = rd;
-            let old_off = off[split_dim];
-            let new_off = query[split_dim] - node.split_val;
-
-            let [closer_node_idx, further_node_idx] =
-                if *query.get_unchecked(split_dim) < node.split_val {
-                    [node.left, node.right]
+        match self.node(curr_node_idx) {
+            Node::Stem {
+                split_val,
+                children: [left, right],
+            } => {
+                let mut rd = rd;
+                let old_off = off[split_dim];
+                let new_off = query[split_dim].saturating_dist(split_val);
+
+                let [closer_node_idx, further_node_idx] = if query[split_dim] < split_val {
+                    [left, right]
                 } else {
-                    [node.right, node.left]
+                    [right, left]
                 };
-            let next_split_dim = (split_dim + 1).rem(K);
-
-            self.nearest_n_recurse(
-                query,
-                distance_fn,
-                closer_node_idx,
-                next_split_dim,
-                results,
-                off,
-                rd,
-            );
-
-            // TODO: switch from dist_fn to a dist trait that can apply to 1D as well as KD
-            //       so that updating rd is not hardcoded to sq euclidean
-            rd = rd + new_off * new_off - old_off * old_off;
-            if Self::dist_belongs_in_heap(rd, results) {
-                off[split_dim] = new_off;
-                self.nearest_n_recurse(
+                let next_split_dim = (split_dim + 1).rem(K);
+
+                self.nearest_n_recurse::<D>(
                     query,
-                    distance_fn,
-                    further_node_idx,
+                    closer_node_idx,
                     next_split_dim,
                     results,
                     off,
                     rd,
                 );
-                off[split_dim] = old_off;
+
+                rd = A::rd_update(rd, D::dist1(new_off, old_off));
+
+                if Self::dist_belongs_in_heap(rd, results) {
+                    off[split_dim] = new_off;
+                    self.nearest_n_recurse::<D>(
+                        query,
+                        further_node_idx,
+                        next_split_dim,
+                        results,
+                        off,
+                        rd,
+                    );
+                    off[split_dim] = old_off;
+                }
             }
-        } else {
-            let leaf_node = self
-                .leaves
-                .get_unchecked((curr_node_idx - IDX::leaf_offset()).az::<usize>());
-
-            leaf_node
-                .content_points
-                .iter()
-                .take(leaf_node.size.az::<usize>())
-                .enumerate()
-                .for_each(|(idx, entry)| {
-                    let distance: A = distance_fn(query, entry);
-                    if Self::dist_belongs_in_heap(distance, results) {
-                        let item = unsafe { *leaf_node.content_items.get_unchecked(idx) };
-                        let element = Neighbour { distance, item };
-                        if results.len() < results.capacity() {
-                            results.push(element)
-                        } else {
-                            let mut top = results.peek_mut().unwrap();
-                            if element.distance < top.distance {
-                                *top = element;
+            Node::Leaf(leaf_idx) => {
+                let leaf_node = &self.leaves[leaf_idx];
+
+                leaf_node
+                    .content_points
+                    .iter()
+                    .take(leaf_node.size.az::<usize>())
+                    .enumerate()
+                    .for_each(|(idx, entry)| {
+                        let distance: A = D::dist(query, entry);
+                        if Self::dist_belongs_in_heap(distance, results) {
+                            let item = leaf_node.content_items[idx];
+                            let element = NearestNeighbour { distance, item };
+                            if results.len() < results.capacity() {
+                                results.push(element)
+                            } else {
+                                let mut top = results.peek_mut().unwrap();
+                                if element.distance < top.distance {
+                                    *top = element;
+                                }
                             }
                         }
-                    }
-                });
+                    });
+            }
         }
     }
 
-    fn dist_belongs_in_heap(dist: A, heap: &BinaryHeap<Neighbour<A, T>>) -> bool {
+    #[inline]
+    fn dist_belongs_in_heap(dist: A, heap: &BinaryHeap<NearestNeighbour<A, T>>) -> bool {
         heap.is_empty() || dist < heap.peek().unwrap().distance || heap.len() < heap.capacity()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::float::distance::squared_euclidean;
-    use crate::float::kdtree::{Axis, KdTree};
+    use crate::float::distance::SquaredEuclidean;
+    use crate::hybrid::kdtree::{Axis, KdTree};
+    use crate::traits::DistanceMetric;
     use rand::Rng;
 
     type AX = f32;
@@ -181,7 +171,7 @@ mod tests {
         let expected = vec![(0.17569996, 6), (0.19139998, 5), (0.24420004, 7)];
 
         let result: Vec<_> = tree
-            .nearest_n(&query_point, 3, &squared_euclidean)
+            .nearest_n::<SquaredEuclidean>(&query_point, 3)
             .into_iter()
             .map(|n| (n.distance, n.item))
             .collect();
@@ -199,7 +189,7 @@ mod tests {
             let expected = linear_search(&content_to_add, qty, &query_point);
 
             let result: Vec<_> = tree
-                .nearest_n(&query_point, qty, &squared_euclidean)
+                .nearest_n::<SquaredEuclidean>(&query_point, qty)
                 .into_iter()
                 .map(|n| (n.distance, n.item))
                 .collect();
@@ -225,7 +215,7 @@ mod tests {
         content_to_add
             .iter()
             .for_each(|(point, content)| tree.add(point, *content));
-        assert_eq!(tree.size(), TREE_SIZE as u32);
+        assert_eq!(tree.size(), TREE_SIZE);
 
         let query_points: Vec<[f32; 4]> = (0..NUM_QUERIES)
             .map(|_| rand::random::<[f32; 4]>())
@@ -235,7 +225,7 @@ mod tests {
             let expected = linear_search(&content_to_add, N, &query_point);
 
             let result: Vec<_> = tree
-                .nearest_n(&query_point, N, &squared_euclidean)
+                .nearest_n::<SquaredEuclidean>(&query_point, N)
                 .into_iter()
                 .map(|n| (n.distance, n.item))
                 .collect();
@@ -255,13 +245,13 @@ mod tests {
         let mut results = vec![];
 
         for &(p, item) in content {
-            let dist = squared_euclidean(query_point, &p);
+            let dist = SquaredEuclidean::dist(query_point, &p);
             if results.len() < qty {
                 results.push((dist, item));
-                results.sort_by(|(a_dist, _), (b_dist, _)| a_dist.partial_cmp(&b_dist).unwrap());
+                results.sort_by(|(a_dist, _), (b_dist, _)| a_dist.partial_cmp(b_dist).unwrap());
             } else if dist < results[qty - 1].0 {
                 results[qty - 1] = (dist, item);
-                results.sort_by(|(a_dist, _), (b_dist, _)| a_dist.partial_cmp(&b_dist).unwrap());
+                results.sort_by(|(a_dist, _), (b_dist, _)| a_dist.partial_cmp(b_dist).unwrap());
             }
         }
 
@@ -1,12 +1,9 @@
-use az::{Az, Cast};
-use std::collections::BinaryHeap;
-use std::ops::Rem;
+use az::Cast;
 
-use crate::float_sss::{
-    kdtree::{Axis, KdTree},
-    neighbour::Neighbour,
-};
-use crate::types::{Content, Index};
+use crate::hybrid::kdtree::{Axis, KdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
 
 impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
     KdTree<A, T, K, B, IDX>
@@ -21,129 +18,35 @@ where
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
-    /// use kiddo::distance::squared_euclidean;
+    /// use kiddo::hybrid::kdtree::KdTree;
+    /// use kiddo::SquaredEuclidean;
     ///
     /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
-    ///
     /// tree.add(&[1.0, 2.0, 5.0], 100);
     /// tree.add(&[2.0, 3.0, 6.0], 101);
-    /// tree.add(&[200.0, 300.0, 600.0], 102);
     ///
-    /// let within = tree.within(&[1.0, 2.0, 5.0], 10f64, &squared_euclidean);
+    /// let within = tree.within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64);
     ///
     /// assert_eq!(within.len(), 2);
     /// ```
     #[inline]
-    pub fn within<F>(&self, query: &[A; K], dist: A, distance_fn: &F) -> Vec<Neighbour<A, T>>
+    pub fn within<D>(&self, query: &[A; K], dist: A) -> Vec<NearestNeighbour<A, T>>
     where
-        F: Fn(&[A; K], &[A; K]) -> A,
+        D: DistanceMetric<A, K>,
     {
-        let mut off = [A::zero(); K];
-        let mut matching_items: BinaryHeap<Neighbour<A, T>> = BinaryHeap::new();
-
-        unsafe {
-            self.within_recurse(
-                query,
-                dist,
-                distance_fn,
-                self.root_index,
-                0,
-                &mut matching_items,
-                &mut off,
-                A::zero(),
-            );
-        }
-
-        matching_items.into_sorted_vec()
-    }
-
-    unsafe fn within_recurse<F>(
-        &self,
-        query: &[A; K],
-        radius: A,
-        distance_fn: &F,
-        curr_node_idx: IDX,
-        split_dim: usize,
-        matching_items: &mut BinaryHeap<Neighbour<A, T>>,
-        off: &mut [A; K],
-        rd: A,
-    ) where
-        F: Fn(&[A; K], &[A; K]) -> A,
-    {
-        if KdTree::<A, T, K, B, IDX>::is_stem_index(curr_node_idx) {
-            let node = self.stems.get_unchecked(curr_node_idx.az::<usize>());
-
-            let mut rd = rd;
-            let old_off = off[split_dim];
-            let new_off = query[split_dim] - node.split_val;
-
-            let [closer_node_idx, further_node_idx] =
-                if *query.get_unchecked(split_dim) < node.split_val {
-                    [node.left, node.right]
-                } else {
-                    [node.right, node.left]
-                };
-            let next_split_dim = (split_dim + 1).rem(K);
-
-            self.within_recurse(
-                query,
-                radius,
-                distance_fn,
-                closer_node_idx,
-                next_split_dim,
-                matching_items,
-                off,
-                rd,
-            );
-
-            // TODO: switch from dist_fn to a dist trait that can apply to 1D as well as KD
-            //       so that updating rd is not hardcoded to sq euclidean
-            rd = rd + new_off * new_off - old_off * old_off;
-
-            if rd <= radius {
-                off[split_dim] = new_off;
-                self.within_recurse(
-                    query,
-                    radius,
-                    distance_fn,
-                    further_node_idx,
-                    next_split_dim,
-                    matching_items,
-                    off,
-                    rd,
-                );
-                off[split_dim] = old_off;
-            }
-        } else {
-            let leaf_node = self
-                .leaves
-                .get_unchecked((curr_node_idx - IDX::leaf_offset()).az::<usize>());
-
-            leaf_node
-                .content_points
-                .iter()
-                .enumerate()
-                .take(leaf_node.size.az::<usize>())
-                .for_each(|(idx, entry)| {
-                    let distance = distance_fn(query, entry);
-
-                    if distance < radius {
-                        matching_items.push(Neighbour {
-                            distance,
-                            item: *leaf_node.content_items.get_unchecked(idx.az::<usize>()),
-                        })
-                    }
-                });
-        }
+        let mut matching_items = self.within_unsorted::<D>(query, dist);
+        matching_items.sort();
+        matching_items
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::float::distance::manhattan;
-    use crate::float::kdtree::{Axis, KdTree};
-    use rand::Rng;
+    use crate::float::distance::Manhattan;
+    use crate::hybrid::kdtree::{Axis, KdTree};
+    use crate::nearest_neighbour::NearestNeighbour;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
     use std::cmp::Ordering;
 
     type AX = f32;
@@ -182,11 +85,7 @@ mod tests {
         let radius = 0.2;
         let expected = linear_search(&content_to_add, &query_point, radius);
 
-        let mut result: Vec<_> = tree
-            .within(&query_point, radius, &manhattan)
-            .into_iter()
-            .map(|n| (n.distance, n.item))
-            .collect();
+        let mut result: Vec<_> = tree.within::<Manhattan>(&query_point, radius);
         stabilize_sort(&mut result);
         assert_eq!(result, expected);
 
@@ -201,11 +100,7 @@ mod tests {
             let radius: f32 = 2.0;
             let expected = linear_search(&content_to_add, &query_point, radius);
 
-            let mut result: Vec<_> = tree
-                .within(&query_point, radius, &manhattan)
-                .into_iter()
-                .map(|n| (n.distance, n.item))
-                .collect();
+            let mut result: Vec<_> = tree.within::<Manhattan>(&query_point, radius);
             stabilize_sort(&mut result);
 
             assert_eq!(result, expected);
@@ -226,7 +121,7 @@ mod tests {
         content_to_add
             .iter()
             .for_each(|(point, content)| tree.add(point, *content));
-        assert_eq!(tree.size(), TREE_SIZE as u32);
+        assert_eq!(tree.size(), TREE_SIZE);
 
         let query_points: Vec<[f32; 4]> = (0..NUM_QUERIES)
             .map(|_| rand::random::<[f32; 4]>())
@@ -235,11 +130,46 @@ mod tests {
         for query_point in query_points {
             let expected = linear_search(&content_to_add, &query_point, RADIUS);
 
-            let result: Vec<_> = tree
-                .within(&query_point, RADIUS, &manhattan)
-                .into_iter()
-                .map(|n| (n.distance, n.item))
-                .collect();
+            let mut result: Vec<_> = tree.within::<Manhattan>(&query_point, RADIUS);
+
+            // TODO: ensure that adjacent results with the same dist are sorted in order of item val
+            //       to prevent occasional test failures due to the linear search returning items
+            //       with the same dist in a different order to the query
+            stabilize_sort(&mut result);
+
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn can_query_items_within_radius_in_optimized_tree_after_additions() {
+        const TREE_SIZE: usize = 20_000;
+        const NUM_QUERIES: usize = 100;
+        const RADIUS: f32 = 0.2;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(31);
+        let content_to_add: Vec<([f32; 4], u32)> = (0..TREE_SIZE)
+            .map(|idx| (rng.gen::<[f32; 4]>(), idx as u32))
+            .collect();
+        let query_points: Vec<[f32; 4]> = (0..NUM_QUERIES).map(|_| rng.gen::<[f32; 4]>()).collect();
+
+        // most of the items get added after the static stems have been populated,
+        // so that the tree ends up with plenty of dynamic stems
+        let initial_points: Vec<[f32; 4]> = content_to_add[..TREE_SIZE / 4]
+            .iter()
+            .map(|(point, _)| *point)
+            .collect();
+        let mut tree: KdTree<AX, u32, 4, 32, u32> = KdTree::optimize_from(&initial_points);
+        content_to_add[TREE_SIZE / 4..]
+            .iter()
+            .for_each(|(point, content)| tree.add(point, *content));
+        assert_eq!(tree.size(), TREE_SIZE);
+
+        for query_point in query_points {
+            let expected = linear_search(&content_to_add, &query_point, RADIUS);
+
+            let mut result: Vec<_> = tree.within::<Manhattan>(&query_point, RADIUS);
+            stabilize_sort(&mut result);
 
             assert_eq!(result, expected);
         }
@@ -249,13 +179,13 @@ mod tests {
         content: &[([A; K], u32)],
         query_point: &[A; K],
         radius: A,
-    ) -> Vec<(A, u32)> {
+    ) -> Vec<NearestNeighbour<A, u32>> {
         let mut matching_items = vec![];
 
         for &(p, item) in content {
-            let dist = manhattan(query_point, &p);
-            if dist < radius {
-                matching_items.push((dist, item));
+            let distance = Manhattan::dist(query_point, &p);
+            if distance < radius {
+                matching_items.push(NearestNeighbour { distance, item });
             }
         }
 
@@ -264,11 +194,11 @@ mod tests {
         matching_items
     }
 
-    fn stabilize_sort<A: Axis>(matching_items: &mut Vec<(A, u32)>) {
+    fn stabilize_sort<A: Axis>(matching_items: &mut [NearestNeighbour<A, u32>]) {
         matching_items.sort_unstable_by(|a, b| {
-            let dist_cmp = a.0.partial_cmp(&b.0).unwrap();
+            let dist_cmp = a.distance.partial_cmp(&b.distance).unwrap();
             if dist_cmp == Ordering::Equal {
-                a.1.cmp(&b.1)
+                a.item.cmp(&b.item)
             } else {
                 dist_cmp
             }
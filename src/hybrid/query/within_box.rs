@@ -0,0 +1,170 @@
+use az::{Az, Cast};
+use std::ops::Rem;
+
+use crate::hybrid::kdtree::{Axis, KdTree, LeafNode, Node};
+use crate::traits::{Content, Index};
+
+use crate::generate_within_box;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
+    KdTree<A, T, K, B, IDX>
+where
+    usize: Cast<IDX>,
+{
+    generate_within_box!(
+        (r#"Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    let within = tree.within_box(&[0.0, 0.0, 0.0], &[1.5, 2.5, 5.5]);
+
+    assert_eq!(within, vec![100]);
+```"#),
+        (r#"Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Returns an `Iterator`. Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    let within = tree.within_box_iter(&[0.0, 0.0, 0.0], &[10.0, 10.0, 10.0]).collect::<Vec<_>>();
+
+    assert_eq!(within.len(), 2);
+```"#),
+        (r#"Counts the elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Faster than `within_box(min, max).len()`, since leaves that lie entirely within the box
+are counted without looking at the points that they hold.
+
+# Examples
+
+```rust
+    use kiddo::hybrid::kdtree::KdTree;
+
+    let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
+    tree.add(&[1.0, 2.0, 5.0], 100);
+    tree.add(&[2.0, 3.0, 6.0], 101);
+
+    assert_eq!(tree.count_in_box(&[0.0, 0.0, 0.0], &[10.0, 10.0, 10.0]), 2);
+```"#)
+    );
+
+    /// Returns each leaf that could hold points in the box from `min` to `max`,
+    /// along with whether every point that it could hold is inside the box.
+    ///
+    /// `lo_inside[dim]` and `hi_inside[dim]` record whether the bounds on `dim`
+    /// implied by the stems passed through so far lie within the box.
+    fn within_box_leaves<'a>(
+        &'a self,
+        min: &'a [A; K],
+        max: &'a [A; K],
+    ) -> impl Iterator<Item = (&'a LeafNode<A, T, K, B, IDX>, bool)> + 'a {
+        let mut stack = vec![(Self::ROOT, 0, [false; K], [false; K])];
+
+        std::iter::from_fn(move || {
+            while let Some((node_idx, split_dim, lo_inside, hi_inside)) = stack.pop() {
+                match self.node(node_idx) {
+                    Node::Stem {
+                        split_val,
+                        children: [left, right],
+                    } => {
+                        let next_split_dim = (split_dim + 1).rem(K);
+
+                        if max[split_dim] >= split_val {
+                            let mut lo_inside = lo_inside;
+                            lo_inside[split_dim] = split_val >= min[split_dim];
+                            stack.push((right, next_split_dim, lo_inside, hi_inside));
+                        }
+                        if min[split_dim] <= split_val {
+                            let mut hi_inside = hi_inside;
+                            hi_inside[split_dim] = split_val <= max[split_dim];
+                            stack.push((left, next_split_dim, lo_inside, hi_inside));
+                        }
+                    }
+                    Node::Leaf(leaf_idx) => {
+                        let is_contained = lo_inside
+                            .iter()
+                            .chain(hi_inside.iter())
+                            .all(|&inside| inside);
+
+                        return Some((&self.leaves[leaf_idx], is_contained));
+                    }
+                }
+            }
+
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hybrid::kdtree::KdTree;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn can_query_items_within_box() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(103);
+        let content: Vec<[f64; 3]> = (0..2_000)
+            .map(|_| [rng.gen(), rng.gen::<f64>() * 10.0, rng.gen()])
+            .collect();
+
+        let (optimized, added) = content.split_at(content.len() / 2);
+        let mut tree: KdTree<f64, u32, 3, 8, u32> = KdTree::optimize_from(optimized);
+        for (idx, point) in added.iter().enumerate() {
+            tree.add(point, (optimized.len() + idx) as u32);
+        }
+
+        for _ in 0..100 {
+            let corners: [[f64; 3]; 2] = [
+                [rng.gen(), rng.gen::<f64>() * 10.0, rng.gen()],
+                [rng.gen(), rng.gen::<f64>() * 10.0, rng.gen()],
+            ];
+            let min = [0, 1, 2].map(|dim| corners[0][dim].min(corners[1][dim]));
+            let max = [0, 1, 2].map(|dim| corners[0][dim].max(corners[1][dim]));
+
+            let mut expected: Vec<u32> = content
+                .iter()
+                .enumerate()
+                .filter(|(_, point)| {
+                    (0..3).all(|dim| point[dim] >= min[dim] && point[dim] <= max[dim])
+                })
+                .map(|(idx, _)| idx as u32)
+                .collect();
+            expected.sort_unstable();
+
+            let mut result = tree.within_box(&min, &max);
+            result.sort_unstable();
+            assert_eq!(result, expected);
+
+            let mut result: Vec<_> = tree.within_box_iter(&min, &max).collect();
+            result.sort_unstable();
+            assert_eq!(result, expected);
+
+            assert_eq!(tree.count_in_box(&min, &max), expected.len());
+        }
+
+        assert_eq!(
+            tree.count_in_box(&[f64::NEG_INFINITY; 3], &[f64::INFINITY; 3]),
+            content.len()
+        );
+    }
+}
@@ -1,9 +1,10 @@
-use crate::float_sss::neighbour::Neighbour;
 use az::{Az, Cast};
 use std::ops::Rem;
 
-use crate::float_sss::kdtree::{Axis, KdTree};
-use crate::types::{Content, Index};
+use crate::hybrid::kdtree::{Axis, KdTree, Node, NodeIdx};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::DistanceMetric;
+use crate::traits::{Content, Index};
 
 impl<A: Axis, T: Content, const K: usize, const B: usize, IDX: Index<T = IDX>>
     KdTree<A, T, K, B, IDX>
@@ -18,133 +19,121 @@ where
     /// # Examples
     ///
     /// ```rust
-    /// use kiddo::float::kdtree::KdTree;
-    /// use kiddo::distance::squared_euclidean;
+    /// use kiddo::hybrid::kdtree::KdTree;
+    /// use kiddo::SquaredEuclidean;
     ///
     /// let mut tree: KdTree<f64, u32, 3, 32, u32> = KdTree::new();
-    ///
     /// tree.add(&[1.0, 2.0, 5.0], 100);
     /// tree.add(&[2.0, 3.0, 6.0], 101);
-    /// tree.add(&[200.0, 300.0, 600.0], 102);
     ///
-    /// let within = tree.within(&[1.0, 2.0, 5.0], 10f64, &squared_euclidean);
+    /// let within = tree.within_unsorted::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64);
     ///
     /// assert_eq!(within.len(), 2);
     /// ```
     #[inline]
-    pub fn within_unsorted<F>(
-        &self,
-        query: &[A; K],
-        dist: A,
-        distance_fn: &F,
-    ) -> Vec<Neighbour<A, T>>
+    pub fn within_unsorted<D>(&self, query: &[A; K], dist: A) -> Vec<NearestNeighbour<A, T>>
     where
-        F: Fn(&[A; K], &[A; K]) -> A,
+        D: DistanceMetric<A, K>,
     {
         let mut off = [A::zero(); K];
         let mut matching_items = Vec::new();
 
-        unsafe {
-            self.within_unsorted_recurse(
-                query,
-                dist,
-                distance_fn,
-                self.root_index,
-                0,
-                &mut matching_items,
-                &mut off,
-                A::zero(),
-            );
-        }
+        self.within_unsorted_recurse::<D>(
+            query,
+            dist,
+            Self::ROOT,
+            0,
+            &mut matching_items,
+            &mut off,
+            A::zero(),
+        );
 
         matching_items
     }
 
-    unsafe fn within_unsorted_recurse<F>(
+    #[allow(clippy::too_many_arguments)]
+    fn within_unsorted_recurse<D>(
         &self,
         query: &[A; K],
         radius: A,
-        distance_fn: &F,
-        curr_node_idx: IDX,
+        curr_node_idx: NodeIdx,
         split_dim: usize,
-        matching_items: &mut Vec<Neighbour<A, T>>,
+        matching_items: &mut Vec<NearestNeighbour<A, T>>,
         off: &mut [A; K],
         rd: A,
     ) where
-        F: Fn(&[A; K], &[A; K]) -> A,
+        D: DistanceMetric<A, K>,
     {
-        if KdTree::<A, T, K, B, IDX>::is_stem_index(curr_node_idx) {
-            let node = self.stems.get_unchecked(curr_node_idx.az::<usize>());
-
-            let mut rd = rd;
-            let old_off = off[split_dim];
-            let new_off = query[split_dim] - node.split_val;
-
-            let [closer_node_idx, further_node_idx] =
-                if *query.get_unchecked(split_dim) < node.split_val {
-                    [node.left, node.right]
+        match self.node(curr_node_idx) {
+            Node::Stem {
+                split_val,
+                children: [left, right],
+            } => {
+                let mut rd = rd;
+                let old_off = off[split_dim];
+                let new_off = query[split_dim].saturating_dist(split_val);
+
+                let [closer_node_idx, further_node_idx] = if query[split_dim] < split_val {
+                    [left, right]
                 } else {
-                    [node.right, node.left]
+                    [right, left]
                 };
-            let next_split_dim = (split_dim + 1).rem(K);
-
-            self.within_unsorted_recurse(
-                query,
-                radius,
-                distance_fn,
-                closer_node_idx,
-                next_split_dim,
-                matching_items,
-                off,
-                rd,
-            );
-
-            // TODO: switch from dist_fn to a dist trait that can apply to 1D as well as KD
-            //       so that updating rd is not hardcoded to sq euclidean
-            rd = rd + new_off * new_off - old_off * old_off;
-
-            if rd <= radius {
-                off[split_dim] = new_off;
-                self.within_unsorted_recurse(
+                let next_split_dim = (split_dim + 1).rem(K);
+
+                self.within_unsorted_recurse::<D>(
                     query,
                     radius,
-                    distance_fn,
-                    further_node_idx,
+                    closer_node_idx,
                     next_split_dim,
                     matching_items,
                     off,
                     rd,
                 );
-                off[split_dim] = old_off;
+
+                rd = A::rd_update(rd, D::dist1(new_off, old_off));
+
+                if rd <= radius {
+                    off[split_dim] = new_off;
+                    self.within_unsorted_recurse::<D>(
+                        query,
+                        radius,
+                        further_node_idx,
+                        next_split_dim,
+                        matching_items,
+                        off,
+                        rd,
+                    );
+                    off[split_dim] = old_off;
+                }
+            }
+            Node::Leaf(leaf_idx) => {
+                let leaf_node = &self.leaves[leaf_idx];
+
+                leaf_node
+                    .content_points
+                    .iter()
+                    .enumerate()
+                    .take(leaf_node.size.az::<usize>())
+                    .for_each(|(idx, entry)| {
+                        let distance = D::dist(query, entry);
+
+                        if distance < radius {
+                            matching_items.push(NearestNeighbour {
+                                distance,
+                                item: leaf_node.content_items[idx],
+                            })
+                        }
+                    });
             }
-        } else {
-            let leaf_node = self
-                .leaves
-                .get_unchecked((curr_node_idx - IDX::leaf_offset()).az::<usize>());
-
-            leaf_node
-                .content_points
-                .iter()
-                .enumerate()
-                .take(leaf_node.size.az::<usize>())
-                .for_each(|(idx, entry)| {
-                    let distance = distance_fn(query, entry);
-
-                    if distance < radius {
-                        matching_items.push(Neighbour {
-                            distance,
-                            item: *leaf_node.content_items.get_unchecked(idx.az::<usize>()),
-                        });
-                    }
-                });
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::float::distance::squared_euclidean;
-    use crate::float::kdtree::{Axis, KdTree};
+    use crate::float::distance::SquaredEuclidean;
+    use crate::hybrid::kdtree::{Axis, KdTree};
+    use crate::traits::DistanceMetric;
     use rand::Rng;
     use std::cmp::Ordering;
 
@@ -185,7 +174,7 @@ mod tests {
         let expected = linear_search(&content_to_add, &query_point, radius);
 
         let result: Vec<_> = tree
-            .within_unsorted(&query_point, radius, &squared_euclidean)
+            .within_unsorted::<SquaredEuclidean>(&query_point, radius)
             .into_iter()
             .map(|n| (n.distance, n.item))
             .collect();
@@ -203,7 +192,7 @@ mod tests {
             let expected = linear_search(&content_to_add, &query_point, radius);
 
             let mut result: Vec<_> = tree
-                .within_unsorted(&query_point, radius, &squared_euclidean)
+                .within_unsorted::<SquaredEuclidean>(&query_point, radius)
                 .into_iter()
                 .map(|n| (n.distance, n.item))
                 .collect();
@@ -227,7 +216,7 @@ mod tests {
         content_to_add
             .iter()
             .for_each(|(point, content)| tree.add(point, *content));
-        assert_eq!(tree.size(), TREE_SIZE as u32);
+        assert_eq!(tree.size(), TREE_SIZE);
 
         let query_points: Vec<[f32; 4]> = (0..NUM_QUERIES)
             .map(|_| rand::random::<[f32; 4]>())
@@ -237,7 +226,7 @@ mod tests {
             let expected = linear_search(&content_to_add, &query_point, RADIUS);
 
             let mut result: Vec<_> = tree
-                .within_unsorted(&query_point, RADIUS, &squared_euclidean)
+                .within_unsorted::<SquaredEuclidean>(&query_point, RADIUS)
                 .into_iter()
                 .map(|n| (n.distance, n.item))
                 .collect();
@@ -255,7 +244,7 @@ mod tests {
         let mut matching_items = vec![];
 
         for &(p, item) in content {
-            let dist = squared_euclidean(query_point, &p);
+            let dist = SquaredEuclidean::dist(query_point, &p);
             if dist < radius {
                 matching_items.push((dist, item));
             }
@@ -266,7 +255,7 @@ mod tests {
         matching_items
     }
 
-    fn stabilize_sort<A: Axis>(matching_items: &mut Vec<(A, u32)>) {
+    fn stabilize_sort<A: Axis>(matching_items: &mut [(A, u32)]) {
         matching_items.sort_unstable_by(|a, b| {
             let dist_cmp = a.0.partial_cmp(&b.0).unwrap();
             if dist_cmp == Ordering::Equal {
@@ -0,0 +1,14 @@
+//! Fixtures shared by the tests of the hybrid tree's queries
+
+use crate::hybrid::kdtree::KdTree;
+
+/// Builds a tree whose first half is added by `optimize_from`, and whose second
+/// half is added afterwards, so that it has both static and dynamic stems
+pub(crate) fn build_tree(content: &[[f64; 3]]) -> KdTree<f64, u32, 3, 8, u32> {
+    let (optimized, added) = content.split_at(content.len() / 2);
+    let mut tree: KdTree<f64, u32, 3, 8, u32> = KdTree::optimize_from(optimized);
+    for (idx, point) in added.iter().enumerate() {
+        tree.add(point, (optimized.len() + idx) as u32);
+    }
+    tree
+}
@@ -0,0 +1,29 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_immutable_any_within {
+    ($comments:tt) => {
+        doc_comment! {
+            concat!$comments,
+            #[inline]
+            pub fn any_within<D>(&self, query: &[A; K], dist: A) -> Option<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+            {
+                let mut found = None;
+
+                self.visit_leaves::<D, _>(query, &mut |leaf_idx| {
+                    if !self.leaf_is_beyond::<D>(query, leaf_idx, dist) {
+                        found = self.get_leaf_slice(leaf_idx).any_within::<D>(query, dist);
+                    }
+
+                    match found {
+                        Some(_) => std::ops::ControlFlow::Break(()),
+                        None => std::ops::ControlFlow::Continue(dist),
+                    }
+                });
+
+                found
+            }
+        }
+    };
+}
@@ -7,9 +7,7 @@ macro_rules! generate_immutable_approx_nearest_one {
             #[inline]
             pub fn approx_nearest_one<D>(&self, query: &[A; K]) -> NearestNeighbour<A, T>
             where
-                A: $crate::float_leaf_slice::leaf_slice::LeafSliceFloat<T> + $crate::float_leaf_slice::leaf_slice::LeafSliceFloatChunk<T, K>,
                 D: DistanceMetric<A, K>,
-                usize: Cast<T>,
             {
                 #[cfg(feature = "modified_van_emde_boas")]
                 use $crate::modified_van_emde_boas::modified_van_emde_boas_get_child_idx_v2;
@@ -25,12 +23,17 @@ macro_rules! generate_immutable_approx_nearest_one {
                 let mut level: usize = 0;
                 let mut leaf_idx: usize = 0;
 
+                if self.leaf_extents.is_empty() {
+                    return NearestNeighbour { distance: best_dist, item: best_item };
+                }
+
                 while level as isize <= self.max_stem_level as isize {
                     let val = *unsafe { self.stems.get_unchecked(curr_idx) };
+                    dim = self.stem_split_dim(curr_idx, dim);
                     let is_right_child = *unsafe { query.get_unchecked(dim) } >= val;
 
                     #[cfg(feature = "modified_van_emde_boas")]
-                    let next_idx = modified_van_emde_boas_get_child_idx_v2(curr_idx as u32, is_right_child, level as u32) as usize;
+                    let next_idx = modified_van_emde_boas_get_child_idx_v2::<A>(curr_idx as u32, is_right_child, level as u32) as usize;
                     #[cfg(not(feature = "modified_van_emde_boas"))]
                     let next_idx = (curr_idx << 1) + usize::from(is_right_child);
 
@@ -43,14 +46,7 @@ macro_rules! generate_immutable_approx_nearest_one {
                     dim = (dim + 1) % K;
                 }
 
-                let (start, end) = unsafe { *self.leaf_extents.get_unchecked(leaf_idx) };
-
-                let leaf_slice = $crate::float_leaf_slice::leaf_slice::LeafSlice::new(
-                    array_init::array_init(|i|
-                        &self.leaf_points[i][start as usize..end as usize]
-                    ),
-                    &self.leaf_items[start as usize..end as usize],
-                );
+                let leaf_slice = self.get_leaf_slice(leaf_idx);
 
                 leaf_slice.nearest_one::<D>(
                     query,
@@ -12,8 +12,6 @@ macro_rules! generate_immutable_best_n_within {
                 max_qty: NonZero<usize>,
             ) -> impl Iterator<Item = BestNeighbour<A, T>>
             where
-                A: LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
-                usize: Cast<T>,
                 D: DistanceMetric<A, K>,
             {
                 let mut off = [A::zero(); K];
@@ -71,8 +69,6 @@ macro_rules! generate_immutable_best_n_within {
                 mut level: usize,
                 mut leaf_idx: usize,
             ) where
-                A: LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
-                usize: Cast<T>,
                 D: DistanceMetric<A, K>,
             {
                 if level as isize > self.max_stem_level as isize {
@@ -81,6 +77,7 @@ macro_rules! generate_immutable_best_n_within {
                 }
 
                 let val = *unsafe { self.stems.get_unchecked(stem_idx as usize) };
+                let split_dim = self.stem_split_dim(stem_idx as usize, split_dim);
                 let is_right_child = usize::from(*unsafe { query.get_unchecked(split_dim as usize) } >= val);
 
                 leaf_idx <<= 1;
@@ -146,12 +143,10 @@ macro_rules! generate_immutable_best_n_within {
                 mut minor_level: u32,
                 mut leaf_idx: usize,
             ) where
-                A: LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
-                usize: Cast<T>,
                 D: DistanceMetric<A, K>,
             {
                 use cmov::Cmov;
-                use $crate::modified_van_emde_boas::modified_van_emde_boas_get_child_idx_v2_branchless;
+                use $crate::modified_van_emde_boas::{log2_items_per_cache_line, modified_van_emde_boas_get_child_idx_v2_branchless};
 
                 if level > self.max_stem_level {
                     self.search_leaf_for_best_n_within::<D>(query, radius, max_qty, best_items, leaf_idx as usize);
@@ -159,14 +154,15 @@ macro_rules! generate_immutable_best_n_within {
                 }
 
                 let val = *unsafe { self.stems.get_unchecked(stem_idx as usize) };
+                let split_dim = self.stem_split_dim(stem_idx as usize, split_dim);
                 let is_right_child = usize::from(*unsafe { query.get_unchecked(split_dim as usize) } >= val);
 
                 leaf_idx <<= 1;
                 let closer_leaf_idx = leaf_idx + is_right_child;
                 let further_leaf_idx = leaf_idx + (1 - is_right_child);
 
-                let closer_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless(stem_idx, is_right_child == 1, minor_level);
-                let further_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless(stem_idx, is_right_child == 0, minor_level);
+                let closer_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(stem_idx, is_right_child == 1, minor_level);
+                let further_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(stem_idx, is_right_child == 0, minor_level);
 
                 let mut rd = rd;
                 let old_off = off[split_dim];
@@ -175,7 +171,7 @@ macro_rules! generate_immutable_best_n_within {
                 level += 1;
                 let next_split_dim = (split_dim + 1).rem(K);
                 minor_level += 1;
-                minor_level.cmovnz(&0, u8::from(minor_level == 3));
+                minor_level.cmovnz(&0, u8::from(minor_level == log2_items_per_cache_line::<A>()));
 
                 self.best_n_within_recurse::<D>(
                     query,
@@ -223,6 +219,10 @@ macro_rules! generate_immutable_best_n_within {
             ) where
                 D: DistanceMetric<A, K>,
             {
+                if self.leaf_is_beyond::<D>(query, leaf_idx, radius) {
+                    return;
+                }
+
                 let leaf_slice = self.get_leaf_slice(leaf_idx);
 
                 leaf_slice.best_n_within::<D>(
@@ -0,0 +1,74 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_immutable_filtered {
+    ($nearest_one_comments:tt, $nearest_n_comments:tt, $within_comments:tt) => {
+        doc_comment! {
+            concat!$nearest_one_comments,
+            #[inline]
+            pub fn nearest_one_filtered<D, F>(&self, query: &[A; K], filter: F) -> Option<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+                F: Fn(T) -> bool,
+            {
+                let mut result: SortedVec<NearestNeighbour<A, T>> = SortedVec::with_capacity(1);
+                self.filtered_stub::<D, _, F>(query, A::unbounded(), &mut result, &filter);
+
+                result.first().copied()
+            }
+        }
+
+        doc_comment! {
+            concat!$nearest_n_comments,
+            #[inline]
+            pub fn nearest_n_filtered<D, F>(&self, query: &[A; K], max_qty: NonZero<usize>, filter: F) -> Vec<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+                F: Fn(T) -> bool,
+            {
+                let max_qty = max_qty.get();
+
+                if max_qty <= MAX_VEC_RESULT_SIZE {
+                    let mut results: SortedVec<NearestNeighbour<A, T>> = SortedVec::with_capacity(max_qty);
+                    self.filtered_stub::<D, _, F>(query, A::unbounded(), &mut results, &filter);
+                    results.into_vec()
+                } else {
+                    let mut results: BinaryHeap<NearestNeighbour<A, T>> = BinaryHeap::with_capacity(max_qty);
+                    self.filtered_stub::<D, _, F>(query, A::unbounded(), &mut results, &filter);
+                    results.into_sorted_vec()
+                }
+            }
+        }
+
+        doc_comment! {
+            concat!$within_comments,
+            #[inline]
+            pub fn within_filtered<D, F>(&self, query: &[A; K], dist: A, filter: F) -> Vec<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+                F: Fn(T) -> bool,
+            {
+                let mut results: Vec<NearestNeighbour<A, T>> = Vec::new();
+                self.filtered_stub::<D, _, F>(query, dist, &mut results, &filter);
+                results.sort_unstable();
+
+                results
+            }
+        }
+
+        fn filtered_stub<D, R, F>(&self, query: &[A; K], dist: A, results: &mut R, filter: &F)
+        where
+            D: DistanceMetric<A, K>,
+            R: ResultCollection<A, T>,
+            F: Fn(T) -> bool,
+        {
+            self.visit_leaves::<D, _>(query, &mut |leaf_idx| {
+                if !self.leaf_is_beyond::<D>(query, leaf_idx, dist.min(results.max_dist())) {
+                    self.get_leaf_slice(leaf_idx)
+                        .nearest_n_within_filtered::<D, R, F>(query, dist, results, filter);
+                }
+
+                std::ops::ControlFlow::Continue(dist.min(results.max_dist()))
+            });
+        }
+    };
+}
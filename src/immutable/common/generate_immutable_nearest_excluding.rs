@@ -0,0 +1,67 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_immutable_nearest_excluding {
+    ($nearest_one_comments:tt, $nearest_n_comments:tt) => {
+        doc_comment! {
+            concat!$nearest_one_comments,
+            #[inline]
+            pub fn nearest_one_excluding<D>(&self, query: &[A; K], excluded: T) -> Option<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+            {
+                let mut nearest = NearestNeighbour {
+                    distance: A::unbounded(),
+                    item: T::zero(),
+                };
+
+                self.visit_leaves::<D, _>(query, &mut |leaf_idx| {
+                    if !self.leaf_is_beyond::<D>(query, leaf_idx, nearest.distance) {
+                        self.get_leaf_slice(leaf_idx).nearest_one_excluding::<D>(
+                            query,
+                            excluded,
+                            &mut nearest.distance,
+                            &mut nearest.item,
+                        );
+                    }
+
+                    std::ops::ControlFlow::Continue(nearest.distance)
+                });
+
+                (nearest.distance < A::unbounded()).then_some(nearest)
+            }
+        }
+
+        doc_comment! {
+            concat!$nearest_n_comments,
+            #[inline]
+            pub fn nearest_n_excluding<D>(&self, query: &[A; K], max_qty: NonZero<usize>, excluded: T) -> Vec<NearestNeighbour<A, T>>
+            where
+                D: DistanceMetric<A, K>,
+            {
+                let max_qty = max_qty.get();
+
+                if max_qty <= MAX_VEC_RESULT_SIZE {
+                    let mut results: SortedVec<NearestNeighbour<A, T>> = SortedVec::with_capacity(max_qty);
+                    self.nearest_n_excluding_stub::<D, _>(query, excluded, &mut results);
+                    results.into_vec()
+                } else {
+                    let mut results: BinaryHeap<NearestNeighbour<A, T>> = BinaryHeap::with_capacity(max_qty);
+                    self.nearest_n_excluding_stub::<D, _>(query, excluded, &mut results);
+                    results.into_sorted_vec()
+                }
+            }
+        }
+
+        fn nearest_n_excluding_stub<D: DistanceMetric<A, K>, R: ResultCollection<A, T>>(
+            &self, query: &[A; K], excluded: T, results: &mut R
+        ) {
+            self.visit_leaves::<D, _>(query, &mut |leaf_idx| {
+                if !self.leaf_is_beyond::<D>(query, leaf_idx, results.max_dist()) {
+                    self.get_leaf_slice(leaf_idx).nearest_n_excluding::<D, _>(query, excluded, results);
+                }
+
+                std::ops::ControlFlow::Continue(results.max_dist())
+            });
+        }
+    };
+}
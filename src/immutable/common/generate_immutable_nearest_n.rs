@@ -7,11 +7,9 @@ macro_rules! generate_immutable_nearest_n {
             #[inline]
             pub fn nearest_n<D>(&self, query: &[A; K], max_qty: NonZero<usize>) -> Vec<NearestNeighbour<A, T>>
             where
-                A: LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
                 D: DistanceMetric<A, K>,
-                usize: Cast<T>,
             {
-                self.nearest_n_within::<D>(query, A::infinity(), max_qty, true)
+                self.nearest_n_within::<D>(query, A::unbounded(), max_qty, true)
             }
         }
     };
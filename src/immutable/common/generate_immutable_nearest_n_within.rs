@@ -33,6 +33,7 @@ macro_rules! generate_immutable_nearest_n_within {
                 } else {
                     let mut items = Vec::new_with_capacity(0);
                     self.nearest_n_within_stub::<D, _>(query, dist, &mut items);
+                    if sorted { items.sort_unstable() }
                     items
                 }
             }
@@ -85,7 +86,7 @@ macro_rules! generate_immutable_nearest_n_within {
                 }
             }
 
-            fn nearest_n_within_stub<D: DistanceMetric<A, K>, H: ResultCollection<A, T>>(
+            pub(crate) fn nearest_n_within_stub<D: DistanceMetric<A, K>, H: ResultCollection<A, T>>(
                 &self, query: &[A; K], dist: A, matching_items: &mut H
             ) {
                 let mut off = [A::zero(); K];
@@ -141,6 +142,7 @@ macro_rules! generate_immutable_nearest_n_within {
                 }
 
                 let val = *unsafe { self.stems.get_unchecked(stem_idx as usize) };
+                let split_dim = self.stem_split_dim(stem_idx as usize, split_dim);
                 let is_right_child = usize::from(*unsafe { query.get_unchecked(split_dim as usize) } >= val);
 
                 leaf_idx <<= 1;
@@ -207,7 +209,7 @@ macro_rules! generate_immutable_nearest_n_within {
                 R: ResultCollection<A, T>,
             {
                 use cmov::Cmov;
-                use $crate::modified_van_emde_boas::modified_van_emde_boas_get_child_idx_v2_branchless;
+                use $crate::modified_van_emde_boas::{log2_items_per_cache_line, modified_van_emde_boas_get_child_idx_v2_branchless};
 
                 if level > self.max_stem_level || self.stems.is_empty() {
                     self.search_leaf_for_nearest_n_within::<D, R>(query, radius, matching_items, leaf_idx as usize);
@@ -215,14 +217,15 @@ macro_rules! generate_immutable_nearest_n_within {
                 }
 
                 let val = *unsafe { self.stems.get_unchecked(stem_idx as usize) };
+                let split_dim = self.stem_split_dim(stem_idx as usize, split_dim);
                 let is_right_child = usize::from(*unsafe { query.get_unchecked(split_dim as usize) } >= val);
 
                 leaf_idx <<= 1;
                 let closer_leaf_idx = leaf_idx + is_right_child;
                 let further_leaf_idx = leaf_idx + (1 - is_right_child);
 
-                let closer_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless(stem_idx, is_right_child == 1, minor_level);
-                let further_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless(stem_idx, is_right_child == 0, minor_level);
+                let closer_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(stem_idx, is_right_child == 1, minor_level);
+                let further_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(stem_idx, is_right_child == 0, minor_level);
 
                 let mut rd = rd;
                 let old_off = off[split_dim];
@@ -231,7 +234,7 @@ macro_rules! generate_immutable_nearest_n_within {
                 level += 1;
                 let next_split_dim = (split_dim + 1).rem(K);
                 minor_level += 1;
-                minor_level.cmovnz(&0, u8::from(minor_level == 3));
+                minor_level.cmovnz(&0, u8::from(minor_level == log2_items_per_cache_line::<A>()));
 
                 self.nearest_n_within_recurse::<D, R>(
                     query,
@@ -277,6 +280,10 @@ macro_rules! generate_immutable_nearest_n_within {
                 D: DistanceMetric<A, K>,
                 R: ResultCollection<A, T>,
             {
+                if self.leaf_is_beyond::<D>(query, leaf_idx, radius.min(results.max_dist())) {
+                    return;
+                }
+
                 let leaf_slice = self.get_leaf_slice(leaf_idx);
 
                 leaf_slice.nearest_n_within::<D, R>(
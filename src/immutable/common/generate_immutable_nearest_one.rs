@@ -70,7 +70,7 @@ macro_rules! generate_immutable_nearest_one {
                     D: DistanceMetric<A, K>,
             {
                 use cmov::Cmov;
-                use $crate::modified_van_emde_boas::modified_van_emde_boas_get_child_idx_v2_branchless;
+                use $crate::modified_van_emde_boas::{log2_items_per_cache_line, modified_van_emde_boas_get_child_idx_v2_branchless};
 
                 if level > self.max_stem_level {
                     self.search_leaf_for_nearest_one::<D>(query, nearest, leaf_idx as usize);
@@ -78,14 +78,15 @@ macro_rules! generate_immutable_nearest_one {
                 }
 
                 let val = *unsafe { self.stems.get_unchecked(stem_idx as usize) };
+                let split_dim = self.stem_split_dim(stem_idx as usize, split_dim as usize) as u64;
                 let is_right_child = u32::from(*unsafe { query.get_unchecked(split_dim as usize) } >= val);
 
                 leaf_idx <<= 1;
                 let closer_leaf_idx = leaf_idx + is_right_child;
                 let farther_leaf_idx = leaf_idx + (1 - is_right_child);
 
-                let closer_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless(stem_idx, is_right_child == 1, minor_level);
-                let further_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless(stem_idx, is_right_child == 0, minor_level);
+                let closer_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(stem_idx, is_right_child == 1, minor_level);
+                let further_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(stem_idx, is_right_child == 0, minor_level);
 
                 let mut rd = rd;
                 let old_off = off[split_dim as usize];
@@ -93,7 +94,7 @@ macro_rules! generate_immutable_nearest_one {
 
                 level += 1;
                 minor_level += 1;
-                minor_level.cmovnz(&0, u8::from(minor_level == 3));
+                minor_level.cmovnz(&0, u8::from(minor_level == log2_items_per_cache_line::<A>()));
 
                 let mut next_split_dim = split_dim + 1;
                 next_split_dim.cmovnz(&0, u8::from(next_split_dim == K as u64));
@@ -157,6 +158,7 @@ macro_rules! generate_immutable_nearest_one {
                 // self.prefetch_stems(left_child_idx);
 
                 let val = *unsafe { self.stems.get_unchecked(stem_idx as usize) };
+                let split_dim = self.stem_split_dim(stem_idx as usize, split_dim as usize) as u64;
                 let is_right_child = usize::from(*unsafe { query.get_unchecked(split_dim as usize) } >= val);
 
                 let closer_node_idx = left_child_idx + is_right_child;
@@ -203,6 +205,10 @@ macro_rules! generate_immutable_nearest_one {
             ) where
                 D: DistanceMetric<A, K>,
             {
+                if self.leaf_is_beyond::<D>(query, leaf_idx, nearest.distance) {
+                    return;
+                }
+
                 let leaf_slice = self.get_leaf_slice(leaf_idx);
 
                 leaf_slice.nearest_one::<D>(
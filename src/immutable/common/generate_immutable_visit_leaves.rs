@@ -0,0 +1,91 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_immutable_visit_leaves {
+    () => {
+        /// Calls `visitor` with the index of each leaf whose side of every stem above it
+        /// is within the current bound of `query`, visiting the side of each stem that
+        /// `query` falls on first.
+        ///
+        /// The visitor returns `ControlFlow::Continue` with the distance that the remaining
+        /// subtrees must lie within to be visited, or `ControlFlow::Break` to end the traversal.
+        /// Leaves are not checked against their bounding boxes; that is left to the visitor.
+        #[inline]
+        pub(crate) fn visit_leaves<D, V>(&self, query: &[A; K], visitor: &mut V)
+        where
+            D: $crate::traits::DistanceMetric<A, K>,
+            V: FnMut(usize) -> std::ops::ControlFlow<(), A>,
+        {
+            if self.leaf_extents.is_empty() {
+                return;
+            }
+
+            let mut off = [A::zero(); K];
+
+            let _ = self.visit_leaves_recurse::<D, V>(
+                query,
+                $crate::immutable::common::stem_cursor::StemCursor::root(),
+                0,
+                &mut off,
+                A::zero(),
+                visitor,
+            );
+        }
+
+        #[inline]
+        fn visit_leaves_recurse<D, V>(
+            &self,
+            query: &[A; K],
+            cursor: $crate::immutable::common::stem_cursor::StemCursor,
+            split_dim: usize,
+            off: &mut [A; K],
+            rd: A,
+            visitor: &mut V,
+        ) -> std::ops::ControlFlow<(), A>
+        where
+            D: $crate::traits::DistanceMetric<A, K>,
+            V: FnMut(usize) -> std::ops::ControlFlow<(), A>,
+        {
+            if cursor.is_leaf(self.max_stem_level) || self.stems.is_empty() {
+                return visitor(cursor.leaf_idx);
+            }
+
+            let val = *unsafe { self.stems.get_unchecked(cursor.stem_idx) };
+            let split_dim = self.stem_split_dim(cursor.stem_idx, split_dim);
+            let is_right_child = *unsafe { query.get_unchecked(split_dim) } >= val;
+
+            let mut rd = rd;
+            let old_off = off[split_dim];
+            let new_off = query[split_dim].saturating_dist(val);
+
+            let next_split_dim = (split_dim + 1) % K;
+
+            let bound = self.visit_leaves_recurse::<D, V>(
+                query,
+                cursor.child::<A>(is_right_child),
+                next_split_dim,
+                off,
+                rd,
+                visitor,
+            )?;
+
+            rd = Axis::rd_update(rd, D::dist1(new_off, old_off));
+
+            if rd > bound {
+                return std::ops::ControlFlow::Continue(bound);
+            }
+
+            off[split_dim] = new_off;
+            let result = self.visit_leaves_recurse::<D, V>(
+                query,
+                cursor.child::<A>(!is_right_child),
+                next_split_dim,
+                off,
+                rd,
+                visitor,
+            );
+            off[split_dim] = old_off;
+
+            result
+        }
+    };
+}
@@ -7,9 +7,8 @@ macro_rules! generate_immutable_within {
             #[inline]
             pub fn within<D>(&self, query: &[A; K], dist: A) -> Vec<NearestNeighbour<A, T>>
             where
-                A: LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
                 D: DistanceMetric<A, K>,
-                usize: Cast<T>,            {
+            {
                 self.nearest_n_within::<D>(query, dist, std::num::NonZero::new(usize::MAX).unwrap(), true)
             }
 
@@ -17,9 +16,7 @@ macro_rules! generate_immutable_within {
             #[inline]
             pub fn collect_within<D>(&self, query: &[A; K], dist: A, buf: &mut Vec<NearestNeighbour<A, T>>) 
             where
-                A: LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
                 D: DistanceMetric<A, K>,
-                usize: Cast<T>,            
             {
                 self.collect_nearest_n_within::<D>(query, dist, std::num::NonZero::new(usize::MAX).unwrap(), true, buf)
             }
@@ -0,0 +1,133 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! generate_immutable_within_box {
+    ($within_box_comments:tt, $within_box_iter_comments:tt, $count_in_box_comments:tt) => {
+        doc_comment! {
+            concat!$within_box_comments,
+            #[inline]
+            pub fn within_box(&self, min: &[A; K], max: &[A; K]) -> Vec<T> {
+                let mut items = Vec::new();
+
+                for (leaf_slice, is_contained) in self.within_box_leaves(min, max) {
+                    if is_contained {
+                        items.extend_from_slice(leaf_slice.content_items);
+                    } else {
+                        items.extend(
+                            leaf_slice
+                                .content_items
+                                .iter()
+                                .enumerate()
+                                .filter(|&(idx, _)| Self::leaf_point_is_in_box(&leaf_slice.content_points, idx, min, max))
+                                .map(|(_, &item)| item),
+                        );
+                    }
+                }
+
+                items
+            }
+        }
+
+        doc_comment! {
+            concat!$within_box_iter_comments,
+            #[inline]
+            pub fn within_box_iter<'a>(
+                &'a self,
+                min: &'a [A; K],
+                max: &'a [A; K],
+            ) -> impl Iterator<Item = T> + 'a {
+                self.within_box_leaves(min, max)
+                    .flat_map(move |(leaf_slice, is_contained)| {
+                        let content_points = leaf_slice.content_points;
+                        leaf_slice
+                            .content_items
+                            .iter()
+                            .enumerate()
+                            .filter(move |&(idx, _)| {
+                                is_contained || Self::leaf_point_is_in_box(&content_points, idx, min, max)
+                            })
+                            .map(|(_, &item)| item)
+                    })
+            }
+        }
+
+        doc_comment! {
+            concat!$count_in_box_comments,
+            #[inline]
+            pub fn count_in_box(&self, min: &[A; K], max: &[A; K]) -> usize {
+                self.within_box_leaves(min, max)
+                    .map(|(leaf_slice, is_contained)| {
+                        if is_contained {
+                            leaf_slice.content_items.len()
+                        } else {
+                            (0..leaf_slice.content_items.len())
+                                .filter(|&idx| Self::leaf_point_is_in_box(&leaf_slice.content_points, idx, min, max))
+                                .count()
+                        }
+                    })
+                    .sum()
+            }
+        }
+
+        /// Returns a slice of each leaf that could hold points in the box from `min` to `max`,
+        /// along with whether every point in it is inside the box.
+        ///
+        /// Whether a leaf lies entirely within the box is determined from its bounding box
+        /// if the tree has leaf bounding boxes, and otherwise from the split values of the
+        /// stems above it, which bound it on one side of each split dimension.
+        fn within_box_leaves<'a>(
+            &'a self,
+            min: &'a [A; K],
+            max: &'a [A; K],
+        ) -> impl Iterator<Item = (LeafSlice<'a, A, T, K>, bool)> + 'a {
+            use $crate::immutable::common::stem_cursor::StemCursor;
+
+            // the position reached, the split dim, and whether the lower and upper bounds
+            // implied by the stems passed through lie within the box
+            let mut stack = if self.leaf_extents.is_empty() {
+                vec![]
+            } else {
+                vec![(StemCursor::root(), 0usize, [false; K], [false; K])]
+            };
+
+            std::iter::from_fn(move || {
+                while let Some((cursor, split_dim, lo_inside, hi_inside)) = stack.pop() {
+                    if cursor.is_leaf(self.max_stem_level) || self.stems.is_empty() {
+                        let leaf_idx = cursor.leaf_idx;
+                        let mut is_contained = lo_inside.iter().chain(hi_inside.iter()).all(|&inside| inside);
+
+                        if let Some((mins, maxes)) = self.leaf_bounding_box(leaf_idx) {
+                            if (0..K).any(|dim| maxes[dim] < min[dim] || mins[dim] > max[dim]) {
+                                continue;
+                            }
+                            is_contained = is_contained || (0..K).all(|dim| mins[dim] >= min[dim] && maxes[dim] <= max[dim]);
+                        }
+
+                        return Some((self.get_leaf_slice(leaf_idx), is_contained));
+                    }
+
+                    let val = self.stems[cursor.stem_idx];
+                    let split_dim = self.stem_split_dim(cursor.stem_idx, split_dim);
+                    let next_split_dim = (split_dim + 1).rem(K);
+
+                    if max[split_dim] >= val {
+                        let mut lo_inside = lo_inside;
+                        lo_inside[split_dim] = val >= min[split_dim];
+                        stack.push((cursor.child::<A>(true), next_split_dim, lo_inside, hi_inside));
+                    }
+                    if min[split_dim] <= val {
+                        let mut hi_inside = hi_inside;
+                        hi_inside[split_dim] = val <= max[split_dim];
+                        stack.push((cursor.child::<A>(false), next_split_dim, lo_inside, hi_inside));
+                    }
+                }
+
+                None
+            })
+        }
+
+        #[inline]
+        fn leaf_point_is_in_box(content_points: &[&[A]; K], idx: usize, min: &[A; K], max: &[A; K]) -> bool {
+            (0..K).all(|dim| content_points[dim][idx] >= min[dim] && content_points[dim][idx] <= max[dim])
+        }
+    };
+}
@@ -50,18 +50,19 @@ macro_rules! generate_immutable_within_unsorted_iter {
             where
                 D: DistanceMetric<A, K>,
             {
-                use $crate::modified_van_emde_boas::modified_van_emde_boas_get_child_idx_v2_branchless;
+                use $crate::modified_van_emde_boas::modified_van_emde_boas_get_child_idx_v2;
 
                 if level <= self.max_stem_level as usize {
                     let val = *unsafe { self.stems.get_unchecked(stem_idx as usize) };
+                    let split_dim = if self.split_dims.is_empty() { split_dim } else { *unsafe { self.split_dims.get_unchecked(stem_idx as usize) } as usize };
                     let is_right_child = usize::from(*unsafe { query.get_unchecked(split_dim as usize) } >= val);
 
                     leaf_idx <<= 1;
                     let closer_leaf_idx = leaf_idx + is_right_child;
                     let further_leaf_idx = leaf_idx + (1 - is_right_child);
 
-                    let closer_node_idx = modified_van_emde_boas_get_child_idx_v2_branchless(stem_idx as u32, is_right_child == 1, /*minor_*/level as u32) as usize;
-                    let further_node_idx =  modified_van_emde_boas_get_child_idx_v2_branchless(stem_idx as u32, is_right_child == 0, /*minor_*/level as u32) as usize;
+                    let closer_node_idx = modified_van_emde_boas_get_child_idx_v2::<A>(stem_idx as u32, is_right_child == 1, level as u32) as usize;
+                    let further_node_idx = modified_van_emde_boas_get_child_idx_v2::<A>(stem_idx as u32, is_right_child == 0, level as u32) as usize;
 
                     let mut rd = rd;
                     let old_off = off[split_dim];
@@ -69,8 +70,6 @@ macro_rules! generate_immutable_within_unsorted_iter {
 
                     level += 1;
                     let next_split_dim = (split_dim + 1).rem(K);
-                    // minor_level += 1;
-                    // minor_level.cmovnz(&0, u8::from(minor_level == 3));
 
                     gen_scope = self.within_unsorted_iter_recurse::<D>(
                         query,
@@ -1,8 +1,15 @@
+pub(crate) mod generate_immutable_any_within;
 pub(crate) mod generate_immutable_approx_nearest_one;
 pub(crate) mod generate_immutable_best_n_within;
+pub(crate) mod generate_immutable_count_within;
+pub(crate) mod generate_immutable_filtered;
+pub(crate) mod generate_immutable_nearest_excluding;
 pub(crate) mod generate_immutable_nearest_n;
 pub(crate) mod generate_immutable_nearest_n_within;
 pub(crate) mod generate_immutable_nearest_one;
+pub(crate) mod generate_immutable_visit_leaves;
 pub(crate) mod generate_immutable_within;
+pub(crate) mod generate_immutable_within_box;
 pub(crate) mod generate_immutable_within_unsorted;
 // pub(crate) mod generate_immutable_within_unsorted_iter;
+pub(crate) mod stem_cursor;
@@ -0,0 +1,74 @@
+#[cfg(feature = "modified_van_emde_boas")]
+use crate::modified_van_emde_boas::{
+    log2_items_per_cache_line, modified_van_emde_boas_get_child_idx_v2_branchless,
+};
+
+/// The position reached while descending through the stems of an immutable tree.
+///
+/// Stems are stored in Eytzinger order, or in modified van Emde Boas order when the
+/// `modified_van_emde_boas` feature is enabled. [`StemCursor::child`] steps to the
+/// correct index for whichever layout is in use, whilst tracking the index of the
+/// leaf that the descent will end at.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct StemCursor {
+    pub(crate) stem_idx: usize,
+    pub(crate) leaf_idx: usize,
+    level: i32,
+    #[cfg(feature = "modified_van_emde_boas")]
+    minor_level: u32,
+}
+
+impl StemCursor {
+    /// Returns a cursor positioned at the root stem
+    #[inline]
+    pub(crate) fn root() -> Self {
+        StemCursor {
+            #[cfg(not(feature = "modified_van_emde_boas"))]
+            stem_idx: 1,
+            #[cfg(feature = "modified_van_emde_boas")]
+            stem_idx: 0,
+            leaf_idx: 0,
+            level: 0,
+            #[cfg(feature = "modified_van_emde_boas")]
+            minor_level: 0,
+        }
+    }
+
+    /// Returns true once the cursor has descended past the lowest level of stems,
+    /// at which point `leaf_idx` identifies the leaf that has been reached
+    #[inline]
+    pub(crate) fn is_leaf(&self, max_stem_level: i32) -> bool {
+        self.level > max_stem_level
+    }
+
+    /// Returns a cursor positioned at the left or right child of the current stem.
+    /// `A` is the stem type, which determines the modified van Emde Boas layout.
+    #[inline]
+    #[cfg_attr(
+        not(feature = "modified_van_emde_boas"),
+        allow(clippy::extra_unused_type_parameters)
+    )]
+    pub(crate) fn child<A>(self, is_right_child: bool) -> Self {
+        #[cfg(not(feature = "modified_van_emde_boas"))]
+        let stem_idx = (self.stem_idx << 1) + usize::from(is_right_child);
+
+        #[cfg(feature = "modified_van_emde_boas")]
+        let stem_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(
+            self.stem_idx as u32,
+            is_right_child,
+            self.minor_level,
+        ) as usize;
+
+        StemCursor {
+            stem_idx,
+            leaf_idx: (self.leaf_idx << 1) + usize::from(is_right_child),
+            level: self.level + 1,
+            #[cfg(feature = "modified_van_emde_boas")]
+            minor_level: if self.minor_level + 1 == log2_items_per_cache_line::<A>() {
+                0
+            } else {
+                self.minor_level + 1
+            },
+        }
+    }
+}
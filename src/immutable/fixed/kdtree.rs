@@ -0,0 +1,782 @@
+//! Immutable Fixed point k-d tree.
+//!
+//! [`ImmutableKdTree`] offers improved memory utilisation, smaller size
+//! when serialized, and faster more consistent query performance, when compared to [`crate::fixed::kdtree::KdTree`].
+//! This comes at the expense of not being able to modify the contents of the tree after its initial
+//! construction, and longer construction times.
+//! [`u8`], [`u16`], [`u32`], and [`u64`] based fixed-point / integers are supported
+//! via the [`Fixed`](https://docs.rs/fixed/1.21.0/fixed) crate.
+
+pub use crate::fixed::kdtree::Axis;
+use crate::generate_immutable_visit_leaves;
+use crate::immutable::common::stem_cursor::StemCursor;
+use crate::immutable::fixed::leaf_slice::LeafSlice;
+use crate::immutable::float::kdtree::{immutable_tree_stats, RebuildScratch};
+#[cfg(feature = "modified_van_emde_boas")]
+use crate::modified_van_emde_boas::modified_van_emde_boas_stem_count;
+use crate::stats::{MemoryUsage, TreeStats};
+use crate::traits::{Content, DistanceMetric};
+use aligned_vec::{avec, AVec};
+use array_init::array_init;
+use az::{Az, Cast};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::PartialEq;
+use std::fmt::Debug;
+
+/// Immutable fixed point k-d tree
+///
+/// For use when the co-ordinates of the points being stored in the tree
+/// are fixed point or integers. [`u8`], [`u16`], [`u32`], and [`u64`] based fixed-point / integers are supported
+/// via the [`Fixed`](https://docs.rs/fixed/1.21.0/fixed) crate, eg [`FixedU16<U14>`](https://docs.rs/fixed/1.21.0/fixed/struct.FixedU16.html) for a 16-bit fixed point number with 14 bits after the
+/// decimal point.
+///
+/// Offers less memory utilisation, smaller size vs the mutable [`crate::fixed::kdtree::KdTree`]
+/// when serialized, and faster more consistent query performance. This comes at the
+/// expense of not being able to modify the contents of the tree after its initial
+/// construction, and longer construction times.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImmutableKdTree<A: Copy + Default, T: Copy + Default, const K: usize, const B: usize> {
+    pub(crate) stems: AVec<A>,
+
+    #[cfg_attr(feature = "serde", serde(with = "crate::custom_serde::array_of_vecs"))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(bound(
+            serialize = "A: Serialize, T: Serialize",
+            deserialize = "A: Deserialize<'de>, T: Deserialize<'de> + Copy + Default"
+        ))
+    )]
+    pub(crate) leaf_points: [Vec<A>; K],
+    pub(crate) leaf_items: Vec<T>,
+    pub(crate) leaf_extents: Vec<(u32, u32)>,
+    pub(crate) max_stem_level: i32,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) scratch: RebuildScratch,
+}
+
+/// Rkyv-serializable immutable fixed point k-d tree
+///
+/// This is only required when using Rkyv to serialize to / deserialize from
+/// an [`ImmutableKdTree`](crate::immutable::fixed::kdtree::ImmutableKdTree). The types in the
+/// [`Fixed`](https://docs.rs/fixed/1.21.0/fixed) crate do not support [`Rkyv`](https://crates.io/crates/rkyv/0.7.39) yet.
+/// As a workaround, convert an [`ImmutableKdTree`] into an equivalent `ImmutableKdTreeRK`
+/// over the underlying integer representation of its co-ordinates before serializing via Rkyv,
+/// and convert back again after deserializing.
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[cfg(feature = "rkyv")]
+pub struct ImmutableKdTreeRK<A: num_traits::PrimInt, T: Content, const K: usize, const B: usize> {
+    pub(crate) stems: Vec<A>,
+    pub(crate) leaf_points: [Vec<A>; K],
+    pub(crate) leaf_items: Vec<T>,
+    pub(crate) leaf_extents: Vec<(u32, u32)>,
+    pub(crate) max_stem_level: i32,
+}
+
+#[cfg(feature = "rkyv")]
+impl<A: Axis, T: Content, const K: usize, const B: usize> From<ImmutableKdTree<A, T, K, B>>
+    for ImmutableKdTreeRK<A::Bits, T, K, B>
+where
+    A::Bits: num_traits::PrimInt,
+{
+    /// Creates an [`ImmutableKdTreeRK`] from an [`ImmutableKdTree`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U0;
+    /// use kiddo::immutable::fixed::kdtree::{ImmutableKdTree, ImmutableKdTreeRK};
+    ///
+    /// type Fxd = FixedU16<U0>;
+    ///
+    /// let points: Vec<[Fxd; 3]> = vec!([Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(3)]);
+    /// let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// let tree_rk: ImmutableKdTreeRK<u16, u32, 3, 32> = tree.clone().into();
+    /// let roundtripped: ImmutableKdTree<Fxd, u32, 3, 32> = tree_rk.into();
+    ///
+    /// assert_eq!(roundtripped, tree);
+    /// ```
+    fn from(orig: ImmutableKdTree<A, T, K, B>) -> Self {
+        let ImmutableKdTree {
+            stems,
+            leaf_points,
+            leaf_items,
+            leaf_extents,
+            max_stem_level,
+            ..
+        } = orig;
+
+        ImmutableKdTreeRK {
+            stems: stems.iter().map(|&val| val.to_bits()).collect(),
+            leaf_points: leaf_points.map(|vec| vec.into_iter().map(A::to_bits).collect()),
+            leaf_items,
+            leaf_extents,
+            max_stem_level,
+        }
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<A: Axis, T: Content, const K: usize, const B: usize> From<ImmutableKdTreeRK<A::Bits, T, K, B>>
+    for ImmutableKdTree<A, T, K, B>
+where
+    A::Bits: num_traits::PrimInt,
+{
+    /// Creates an [`ImmutableKdTree`] from an [`ImmutableKdTreeRK`]
+    fn from(orig: ImmutableKdTreeRK<A::Bits, T, K, B>) -> Self {
+        let ImmutableKdTreeRK {
+            stems,
+            leaf_points,
+            leaf_items,
+            leaf_extents,
+            max_stem_level,
+        } = orig;
+
+        ImmutableKdTree {
+            stems: AVec::from_iter(
+                aligned_vec::CACHELINE_ALIGN,
+                stems.into_iter().map(A::from_bits),
+            ),
+            leaf_points: leaf_points.map(|vec| vec.into_iter().map(A::from_bits).collect()),
+            leaf_items,
+            leaf_extents,
+            max_stem_level,
+            scratch: RebuildScratch::default(),
+        }
+    }
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> From<&[[A; K]]>
+    for ImmutableKdTree<A, T, K, B>
+where
+    usize: Cast<T>,
+{
+    /// Creates an `ImmutableKdTree`, balanced and optimized, populated
+    /// with items from `source`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U0;
+    /// use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    ///
+    /// type Fxd = FixedU16<U0>;
+    ///
+    /// let points: Vec<[Fxd; 3]> = vec!([Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(3)]);
+    /// let tree: ImmutableKdTree<Fxd, u32, 3, 32> = (&*points).into();
+    ///
+    /// assert_eq!(tree.size(), 1);
+    /// ```
+    fn from(slice: &[[A; K]]) -> Self {
+        ImmutableKdTree::new_from_slice(slice)
+    }
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> FromIterator<([A; K], T)>
+    for ImmutableKdTree<A, T, K, B>
+{
+    /// Creates an `ImmutableKdTree`, balanced and optimized, populated
+    /// with the `(point, item)` pairs from `iter`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U0;
+    /// use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    ///
+    /// type Fxd = FixedU16<U0>;
+    ///
+    /// let entries = vec!(
+    ///     ([Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(3)], 1001u64),
+    ///     ([Fxd::from_num(4), Fxd::from_num(5), Fxd::from_num(6)], 2002u64),
+    /// );
+    /// let tree: ImmutableKdTree<Fxd, u64, 3, 32> = entries.into_iter().collect();
+    ///
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = ([A; K], T)>>(iter: I) -> Self {
+        let (points, items): (Vec<[A; K]>, Vec<T>) = iter.into_iter().unzip();
+        ImmutableKdTree::new_from_points_and_items(&points, &items)
+    }
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    /// Creates an `ImmutableKdTree`, balanced and optimized, populated
+    /// with items from `source`.
+    ///
+    /// The item stored for each point is its index within `source`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U0;
+    /// use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    ///
+    /// type Fxd = FixedU16<U0>;
+    ///
+    /// let points: Vec<[Fxd; 3]> = vec!([Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(3)]);
+    /// let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// assert_eq!(tree.size(), 1);
+    /// ```
+    #[inline]
+    pub fn new_from_slice(source: &[[A; K]]) -> Self
+    where
+        usize: Cast<T>,
+    {
+        let mut result = Self::new_empty();
+        result.rebuild_from_slice(source);
+        result
+    }
+
+    /// Creates an `ImmutableKdTree`, balanced and optimized, populated with the
+    /// points in `points`, storing `items[i]` as the item for `points[i]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `items` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedI32;
+    /// use fixed::types::extra::U0;
+    /// use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    /// use kiddo::fixed::distance::Manhattan;
+    ///
+    /// type Fxd = FixedI32<U0>;
+    ///
+    /// let points: Vec<[Fxd; 3]> = vec!(
+    ///     [Fxd::from_num(-10), Fxd::from_num(64), Fxd::from_num(3)],
+    ///     [Fxd::from_num(200), Fxd::from_num(70), Fxd::from_num(-45)],
+    /// );
+    /// let items: Vec<u64> = vec!(1001, 2002);
+    /// let tree: ImmutableKdTree<Fxd, u64, 3, 32> =
+    ///     ImmutableKdTree::new_from_points_and_items(&points, &items);
+    ///
+    /// let nearest = tree.nearest_one::<Manhattan>(&[Fxd::from_num(199), Fxd::from_num(70), Fxd::from_num(-45)]);
+    /// assert_eq!(nearest.item, 2002);
+    /// ```
+    #[inline]
+    pub fn new_from_points_and_items(points: &[[A; K]], items: &[T]) -> Self {
+        let mut result = Self::new_empty();
+        result.rebuild_from_points_and_items(points, items);
+        result
+    }
+
+    fn new_empty() -> Self {
+        Self {
+            stems: avec![],
+            leaf_points: array_init(|_| Vec::new()),
+            leaf_items: Vec::new(),
+            leaf_extents: Vec::new(),
+            max_stem_level: 0,
+            scratch: RebuildScratch::default(),
+        }
+    }
+
+    /// Rebuilds the tree from `source`, re-using the existing buffers.
+    ///
+    /// Once their capacity has grown to fit the largest `source` seen so far,
+    /// subsequent rebuilds perform no heap allocations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U0;
+    /// use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    ///
+    /// type Fxd = FixedU16<U0>;
+    ///
+    /// let mut points: Vec<[Fxd; 3]> = vec!([Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(3)]);
+    /// let mut tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// points.push([Fxd::from_num(4), Fxd::from_num(5), Fxd::from_num(6)]);
+    /// tree.rebuild_from_slice(&points);
+    ///
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    #[inline]
+    pub fn rebuild_from_slice(&mut self, source: &[[A; K]])
+    where
+        usize: Cast<T>,
+    {
+        self.rebuild_with_items(source, |idx| idx.az::<T>());
+    }
+
+    /// Rebuilds the tree from `points` and `items`, re-using the existing buffers
+    /// in the same way as [`ImmutableKdTree::rebuild_from_slice`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `items` have different lengths.
+    #[inline]
+    pub fn rebuild_from_points_and_items(&mut self, points: &[[A; K]], items: &[T]) {
+        assert_eq!(
+            points.len(),
+            items.len(),
+            "points and items must have the same length"
+        );
+        self.rebuild_with_items(points, |idx| items[idx]);
+    }
+
+    /// Rebuilds the tree from `source`, storing `item_at(i)` as the item for `source[i]`.
+    fn rebuild_with_items<F: Fn(usize) -> T>(&mut self, source: &[[A; K]], item_at: F) {
+        let stem_node_count = self.reset_for_rebuild(source.len());
+
+        if stem_node_count == 0 {
+            self.leaf_extents.push((0u32, source.len() as u32));
+            self.scratch.sort_index.iter().for_each(|&idx| {
+                (0..K).for_each(|dim| self.leaf_points[dim].push(source[idx][dim]));
+                self.leaf_items.push(item_at(idx))
+            });
+            return;
+        }
+
+        let first_point = source[self.scratch.sort_index[0]];
+
+        Self::populate_recursive(
+            &mut self.stems,
+            0,
+            source,
+            &mut self.scratch.sort_index,
+            StemCursor::root(),
+            self.max_stem_level,
+            first_point,
+            &mut self.leaf_points,
+            &mut self.leaf_items,
+            &mut self.leaf_extents,
+            &item_at,
+        );
+    }
+
+    /// Clears the tree's contents and sizes its buffers, ready for `item_count` items
+    /// to be added. Returns the number of stems to be populated, which will be zero
+    /// if all the items fit in a single leaf.
+    fn reset_for_rebuild(&mut self, item_count: usize) -> usize {
+        let leaf_node_count = item_count.div_ceil(B);
+
+        let max_stem_level: i32 = leaf_node_count.next_power_of_two().ilog2() as i32 - 1;
+
+        #[cfg(not(feature = "modified_van_emde_boas"))]
+        let stem_node_count = if leaf_node_count < 2 {
+            0
+        } else {
+            leaf_node_count.next_power_of_two()
+        };
+
+        #[cfg(feature = "modified_van_emde_boas")]
+        let stem_node_count = if leaf_node_count < 2 {
+            0
+        } else {
+            modified_van_emde_boas_stem_count::<A>(max_stem_level as u32 + 1)
+        };
+
+        self.stems.clear();
+        self.leaf_points.iter_mut().for_each(|vec| vec.clear());
+        self.leaf_items.clear();
+        self.leaf_extents.clear();
+        self.scratch.sort_index.clear();
+
+        self.stems.resize(stem_node_count, A::ZERO);
+        self.leaf_points
+            .iter_mut()
+            .for_each(|vec| vec.reserve(item_count));
+        self.leaf_items.reserve(item_count);
+        self.leaf_extents
+            .reserve(leaf_node_count.next_power_of_two());
+        self.scratch.sort_index.extend(0..item_count);
+        self.max_stem_level = max_stem_level;
+
+        stem_node_count
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn populate_recursive<F: Fn(usize) -> T>(
+        stems: &mut AVec<A>,
+        dim: usize,
+        source: &[[A; K]],
+        sort_index: &mut [usize],
+        cursor: StemCursor,
+        max_stem_level: i32,
+        mut last_split: [A; K],
+        leaf_points: &mut [Vec<A>; K],
+        leaf_items: &mut Vec<T>,
+        leaf_extents: &mut Vec<(u32, u32)>,
+        item_at: &F,
+    ) {
+        let chunk_length = sort_index.len();
+
+        if cursor.is_leaf(max_stem_level) {
+            // Write leaf and terminate recursion
+            leaf_extents.push((
+                leaf_items.len() as u32,
+                (leaf_items.len() + chunk_length) as u32,
+            ));
+
+            (0..chunk_length).for_each(|i| {
+                (0..K).for_each(|dim| leaf_points[dim].push(source[sort_index[i]][dim]));
+                leaf_items.push(item_at(sort_index[i]))
+            });
+
+            return;
+        }
+
+        let mut pivot = chunk_length >> 1;
+
+        // only bother with this if we are putting at least one item in the right hand child
+        if pivot < chunk_length {
+            pivot = Self::update_pivot(source, sort_index, dim, pivot);
+
+            // if we end up with a pivot of 0, something has gone wrong,
+            // unless we only had a slice of len 1 anyway
+            debug_assert!(pivot > 0 || chunk_length == 1);
+
+            stems[cursor.stem_idx] = source[sort_index[pivot]][dim];
+            last_split[dim] = stems[cursor.stem_idx];
+        } else {
+            // This subtree is empty, so its stems never direct a query towards any items.
+            // Unlike the float tree's infinite stems, a placeholder such as A::MAX would
+            // overflow distance metrics, so re-use the most recent split value on this axis.
+            stems[cursor.stem_idx] = last_split[dim];
+        }
+
+        let (lower_sort_index, upper_sort_index) = sort_index.split_at_mut(pivot);
+
+        let next_dim = (dim + 1) % K;
+
+        Self::populate_recursive(
+            stems,
+            next_dim,
+            source,
+            lower_sort_index,
+            cursor.child::<A>(false),
+            max_stem_level,
+            last_split,
+            leaf_points,
+            leaf_items,
+            leaf_extents,
+            item_at,
+        );
+
+        Self::populate_recursive(
+            stems,
+            next_dim,
+            source,
+            upper_sort_index,
+            cursor.child::<A>(true),
+            max_stem_level,
+            last_split,
+            leaf_points,
+            leaf_items,
+            leaf_extents,
+            item_at,
+        );
+    }
+
+    #[inline]
+    fn update_pivot(
+        source: &[[A; K]],
+        sort_index: &mut [usize],
+        dim: usize,
+        mut pivot: usize,
+    ) -> usize {
+        // ensure the item whose index = pivot is in its correctly sorted position, and any
+        // items that are equal to it are adjacent
+        sort_index.select_nth_unstable_by_key(pivot, |&i| source[i][dim]);
+
+        if pivot == 0 {
+            return pivot;
+        }
+
+        // if the pivot straddles two values that are equal, keep nudging it left until they aren't
+        while source[sort_index[pivot]][dim] == source[sort_index[pivot - 1]][dim] && pivot > 1 {
+            pivot -= 1;
+        }
+
+        pivot
+    }
+
+    /// Returns the current number of elements stored in the tree
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U0;
+    /// use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    ///
+    /// type Fxd = FixedU16<U0>;
+    ///
+    /// let points: Vec<[Fxd; 3]> = vec!([Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(3)]);
+    /// let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// assert_eq!(tree.size(), 1);
+    /// ```
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.leaf_items.len()
+    }
+
+    /// Returns the theoretical max capacity of this tree
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.size()
+    }
+
+    /// Returns statistics describing the shape, fill level and memory usage of the tree,
+    /// which can help with choosing a suitable value for `B`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fixed::FixedU16;
+    /// use fixed::types::extra::U0;
+    /// use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    /// use std::mem::size_of;
+    ///
+    /// type Fxd = FixedU16<U0>;
+    ///
+    /// let points: Vec<[Fxd; 2]> = (0..1000u16)
+    ///     .map(|i| [Fxd::from_num(i), Fxd::from_num(i % 10)])
+    ///     .collect();
+    /// let tree: ImmutableKdTree<Fxd, u32, 2, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// let stats = tree.stats();
+    ///
+    /// assert_eq!(stats.size, 1000);
+    /// assert_eq!(stats.min_depth, stats.max_depth);
+    /// assert_eq!(stats.leaf_count, stats.stem_count + 1);
+    /// assert_eq!(stats.memory.leaf_items, 1000 * size_of::<u32>());
+    /// ```
+    pub fn stats(&self) -> TreeStats {
+        immutable_tree_stats(
+            (0..self.leaf_extents.len()).map(|leaf_idx| {
+                let leaf = self.get_leaf_slice(leaf_idx);
+                (leaf.content_points, leaf.content_items)
+            }),
+            self.max_stem_level,
+            MemoryUsage {
+                stems: self.stems.len() * size_of::<A>(),
+                leaf_points: self.leaf_points.iter().map(Vec::len).sum::<usize>() * size_of::<A>(),
+                leaf_items: self.leaf_items.len() * size_of::<T>(),
+                other: self.leaf_extents.len() * size_of::<(u32, u32)>(),
+            },
+        )
+    }
+
+    /// Returns a LeafSlice for a given leaf index
+    #[inline]
+    pub(crate) fn get_leaf_slice(&self, leaf_idx: usize) -> LeafSlice<'_, A, T, K> {
+        let (start, end) = unsafe { *self.leaf_extents.get_unchecked(leaf_idx) };
+
+        LeafSlice::new(
+            array_init(|i| &self.leaf_points[i][start as usize..end as usize]),
+            &self.leaf_items[start as usize..end as usize],
+        )
+    }
+
+    /// Always false, as the fixed point tree does not store leaf bounding boxes
+    #[inline]
+    #[allow(clippy::extra_unused_type_parameters)]
+    pub(crate) fn leaf_is_beyond<D>(&self, _query: &[A; K], _leaf_idx: usize, _dist: A) -> bool
+    where
+        D: DistanceMetric<A, K>,
+    {
+        false
+    }
+
+    /// Returns the dimension that the stem at `stem_idx` splits on, which is always
+    /// `split_dim`, as the fixed point tree cycles through the dimensions in turn
+    #[inline]
+    pub(crate) fn stem_split_dim(&self, _stem_idx: usize, split_dim: usize) -> usize {
+        split_dim
+    }
+
+    /// Always `None`, as the fixed point tree does not store leaf bounding boxes
+    #[inline]
+    pub(crate) fn leaf_bounding_box(&self, _leaf_idx: usize) -> Option<(&[A], &[A])> {
+        None
+    }
+
+    generate_immutable_visit_leaves!();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::SquaredEuclidean;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use fixed::types::extra::{U0, U14};
+    use fixed::{FixedI32, FixedU16};
+    use rand::{Rng, SeedableRng};
+
+    type Fxd = FixedU16<U14>;
+
+    fn rand_points<const K: usize>(rng: &mut impl Rng, qty: usize) -> Vec<[Fxd; K]> {
+        (0..qty)
+            .map(|_| array_init::array_init(|_| Fxd::from_bits(rng.gen())))
+            .collect()
+    }
+
+    #[test]
+    fn can_construct_an_empty_tree() {
+        let tree = ImmutableKdTree::<Fxd, u32, 3, 32>::new_from_slice(&[]);
+        let result = tree.nearest_one::<SquaredEuclidean>(&[Fxd::ZERO; 3]);
+
+        assert_eq!(tree.size(), 0);
+        assert_eq!(result.distance, Fxd::MAX);
+    }
+
+    #[test]
+    fn leaves_partition_all_points_in_order() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(5);
+
+        for tree_size in [1, 31, 32, 33, 1_000, 10_000] {
+            let points: Vec<[Fxd; 3]> = rand_points(&mut rng, tree_size);
+            let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+
+            assert_eq!(tree.size(), tree_size);
+
+            let mut seen: Vec<u32> = tree.leaf_items.clone();
+            seen.sort_unstable();
+            assert_eq!(seen, (0..tree_size as u32).collect::<Vec<_>>());
+
+            for (leaf_idx, &(start, end)) in tree.leaf_extents.iter().enumerate() {
+                for idx in start as usize..end as usize {
+                    let item = tree.leaf_items[idx] as usize;
+                    let point: [Fxd; 3] = array_init::array_init(|dim| tree.leaf_points[dim][idx]);
+                    assert_eq!(point, points[item], "leaf {leaf_idx} stores wrong point");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stats_describe_every_leaf() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+        let mut points: Vec<[Fxd; 2]> = rand_points(&mut rng, 5_000);
+        points.extend([[Fxd::from_bits(8_192), Fxd::from_bits(4_096)]; 100]);
+        let tree: ImmutableKdTree<Fxd, u32, 2, 32> = ImmutableKdTree::new_from_slice(&points);
+
+        let stats = tree.stats();
+
+        let expected_duplicate_leaves = tree
+            .leaf_extents
+            .iter()
+            .filter(|&&(start, end)| {
+                let mut seen = std::collections::HashSet::new();
+                !(start as usize..end as usize)
+                    .all(|idx| seen.insert((tree.leaf_points[0][idx], tree.leaf_points[1][idx])))
+            })
+            .count();
+
+        assert_eq!(stats.size, 5_100);
+        assert_eq!(stats.leaf_count, tree.leaf_extents.len());
+        assert_eq!(stats.stem_count, stats.leaf_count - 1);
+        assert_eq!(stats.min_depth, (tree.max_stem_level + 1) as usize);
+        assert_eq!(stats.max_depth, stats.min_depth);
+        assert_eq!(stats.leaf_occupancy.iter().sum::<usize>(), stats.leaf_count);
+        assert!(expected_duplicate_leaves > 0);
+        assert_eq!(stats.duplicate_coordinate_leaves, expected_duplicate_leaves);
+        assert_eq!(stats.memory.leaf_points, 5_100 * 2 * size_of::<Fxd>());
+        assert_eq!(stats.memory.leaf_items, 5_100 * size_of::<u32>());
+    }
+
+    #[test]
+    fn can_construct_with_many_dupes() {
+        let point = [FixedI32::<U0>::from_num(7); 2];
+        let points = vec![point; 1_000];
+
+        let tree: ImmutableKdTree<FixedI32<U0>, u32, 2, 8> =
+            ImmutableKdTree::new_from_slice(&points);
+
+        assert_eq!(tree.size(), 1_000);
+        assert_eq!(
+            tree.nearest_one::<SquaredEuclidean>(&point).distance,
+            FixedI32::<U0>::ZERO
+        );
+    }
+
+    #[test]
+    fn can_construct_with_caller_supplied_items() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+        let points: Vec<[Fxd; 2]> = rand_points(&mut rng, 1_000);
+        let items: Vec<u64> = (0..1_000u64).map(|i| i * 7 + 3).collect();
+
+        let by_index: ImmutableKdTree<Fxd, u64, 2, 32> = ImmutableKdTree::new_from_slice(&points);
+        let tree: ImmutableKdTree<Fxd, u64, 2, 32> =
+            ImmutableKdTree::new_from_points_and_items(&points, &items);
+
+        assert_eq!(tree.stems, by_index.stems);
+        assert_eq!(tree.leaf_points, by_index.leaf_points);
+        let expected_items: Vec<u64> = by_index
+            .leaf_items
+            .iter()
+            .map(|&idx| items[idx as usize])
+            .collect();
+        assert_eq!(tree.leaf_items, expected_items);
+
+        let collected: ImmutableKdTree<Fxd, u64, 2, 32> =
+            points.iter().copied().zip(items.iter().copied()).collect();
+        assert_eq!(collected, tree);
+    }
+
+    #[test]
+    fn rebuild_from_slice_matches_new_from_slice() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(9);
+        let mut tree: ImmutableKdTree<Fxd, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&rand_points(&mut rng, 5_000));
+
+        for tree_size in [10_000, 20, 0, 3_000] {
+            let points: Vec<[Fxd; 3]> = rand_points(&mut rng, tree_size);
+            tree.rebuild_from_slice(&points);
+            assert_eq!(tree, ImmutableKdTree::new_from_slice(&points));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn can_serde() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(13);
+        let tree: ImmutableKdTree<Fxd, u32, 4, 32> =
+            ImmutableKdTree::new_from_slice(&rand_points(&mut rng, 500));
+
+        let serialized = serde_json::to_string(&tree).unwrap();
+        let deserialized: ImmutableKdTree<Fxd, u32, 4, 32> =
+            serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(tree, deserialized);
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn can_rkyv_roundtrip() {
+        use crate::immutable::fixed::kdtree::ImmutableKdTreeRK;
+        use rkyv::Deserialize;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(17);
+        let tree: ImmutableKdTree<Fxd, u32, 4, 32> =
+            ImmutableKdTree::new_from_slice(&rand_points(&mut rng, 500));
+
+        let tree_rk: ImmutableKdTreeRK<u16, u32, 4, 32> = tree.clone().into();
+        let bytes = rkyv::to_bytes::<_, 256>(&tree_rk).unwrap();
+
+        let archived = unsafe { rkyv::archived_root::<ImmutableKdTreeRK<u16, u32, 4, 32>>(&bytes) };
+        let deserialized: ImmutableKdTreeRK<u16, u32, 4, 32> =
+            archived.deserialize(&mut rkyv::Infallible).unwrap();
+        let roundtripped: ImmutableKdTree<Fxd, u32, 4, 32> = deserialized.into();
+
+        assert_eq!(roundtripped, tree);
+    }
+}
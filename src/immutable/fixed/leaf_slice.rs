@@ -0,0 +1,187 @@
+use std::collections::BinaryHeap;
+
+use crate::best_neighbour::BestNeighbour;
+use crate::float::result_collection::ResultCollection;
+use crate::immutable::fixed::kdtree::Axis;
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::{Content, DistanceMetric};
+
+/// The points and items stored in one leaf of an
+/// [`ImmutableKdTree`](crate::immutable::fixed::kdtree::ImmutableKdTree).
+///
+/// Provides the same per-leaf operations as the float tree's `LeafSlice`, so that
+/// the shared immutable query macros can be instantiated on the fixed point tree.
+/// Distances are calculated one point at a time using [`DistanceMetric::dist`],
+/// which saturates rather than overflowing for fixed point types.
+#[derive(Debug)]
+pub(crate) struct LeafSlice<'a, A: Axis, T: Content, const K: usize> {
+    pub content_points: [&'a [A]; K],
+    pub content_items: &'a [T],
+}
+
+impl<'a, A: Axis, T: Content, const K: usize> LeafSlice<'a, A, T, K> {
+    #[inline]
+    pub(crate) fn new(content_points: [&'a [A]; K], content_items: &'a [T]) -> Self {
+        let size = content_items.len();
+        for arr in content_points {
+            debug_assert_eq!(arr.len(), size);
+        }
+
+        LeafSlice {
+            content_points,
+            content_items,
+        }
+    }
+
+    /// Returns an iterator over the distance from `query` to each point, along with its item
+    #[inline]
+    fn dists<'q, D>(&self, query: &'q [A; K]) -> impl Iterator<Item = (A, T)> + 'q
+    where
+        'a: 'q,
+        D: DistanceMetric<A, K>,
+    {
+        let content_points = self.content_points;
+
+        self.content_items
+            .iter()
+            .enumerate()
+            .map(move |(idx, &item)| {
+                let point = array_init::array_init(|dim| content_points[dim][idx]);
+                (D::dist(query, &point), item)
+            })
+    }
+
+    #[inline]
+    pub(crate) fn nearest_one<D>(&self, query: &[A; K], best_dist: &mut A, best_item: &mut T)
+    where
+        D: DistanceMetric<A, K>,
+    {
+        for (distance, item) in self.dists::<D>(query) {
+            if distance < *best_dist {
+                *best_dist = distance;
+                *best_item = item;
+            }
+        }
+    }
+
+    /// Updates `best_dist` and `best_item` with the nearest point to `query` whose item
+    /// isn't `excluded`. Items are only compared for points closer than the best so far.
+    #[inline]
+    pub(crate) fn nearest_one_excluding<D>(
+        &self,
+        query: &[A; K],
+        excluded: T,
+        best_dist: &mut A,
+        best_item: &mut T,
+    ) where
+        D: DistanceMetric<A, K>,
+    {
+        for (distance, item) in self.dists::<D>(query) {
+            if distance < *best_dist && item != excluded {
+                *best_dist = distance;
+                *best_item = item;
+            }
+        }
+    }
+
+    /// Adds the points nearest to `query` whose items aren't `excluded` to `results`.
+    /// Items are only compared for points that `results` would accept.
+    #[inline]
+    pub(crate) fn nearest_n_excluding<D, R>(&self, query: &[A; K], excluded: T, results: &mut R)
+    where
+        D: DistanceMetric<A, K>,
+        R: ResultCollection<A, T>,
+    {
+        for (distance, item) in self.dists::<D>(query) {
+            if distance < results.max_dist() && item != excluded {
+                results.add(NearestNeighbour { distance, item });
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn nearest_n_within<D, R>(&self, query: &[A; K], radius: A, results: &mut R)
+    where
+        D: DistanceMetric<A, K>,
+        R: ResultCollection<A, T>,
+    {
+        for (distance, item) in self.dists::<D>(query) {
+            if distance < radius {
+                results.add(NearestNeighbour { distance, item });
+            }
+        }
+    }
+
+    /// Adds the points within `radius` of `query` whose items are accepted by `filter`
+    /// to `results`. `filter` is only called for points that `results` would accept.
+    #[inline]
+    pub(crate) fn nearest_n_within_filtered<D, R, F>(
+        &self,
+        query: &[A; K],
+        radius: A,
+        results: &mut R,
+        filter: &F,
+    ) where
+        D: DistanceMetric<A, K>,
+        R: ResultCollection<A, T>,
+        F: Fn(T) -> bool,
+    {
+        for (distance, item) in self.dists::<D>(query) {
+            if distance < radius && distance < results.max_dist() && filter(item) {
+                results.add(NearestNeighbour { distance, item });
+            }
+        }
+    }
+
+    /// Returns the first point found within `radius` of `query`, if any
+    #[inline]
+    pub(crate) fn any_within<D>(&self, query: &[A; K], radius: A) -> Option<NearestNeighbour<A, T>>
+    where
+        D: DistanceMetric<A, K>,
+    {
+        self.dists::<D>(query)
+            .find(|&(distance, _)| distance < radius)
+            .map(|(distance, item)| NearestNeighbour { distance, item })
+    }
+
+    /// Adds the number of points within `radius` of `query` to `count`, stopping
+    /// once it reaches `limit`
+    #[inline]
+    pub(crate) fn count_within<D>(&self, query: &[A; K], radius: A, limit: usize, count: &mut usize)
+    where
+        D: DistanceMetric<A, K>,
+    {
+        for (distance, _) in self.dists::<D>(query) {
+            if *count >= limit {
+                return;
+            }
+
+            *count += usize::from(distance < radius);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn best_n_within<D>(
+        &self,
+        query: &[A; K],
+        radius: A,
+        max_qty: usize,
+        results: &mut BinaryHeap<BestNeighbour<A, T>>,
+    ) where
+        D: DistanceMetric<A, K>,
+    {
+        for (distance, item) in self.dists::<D>(query) {
+            if distance <= radius {
+                if results.len() < max_qty {
+                    results.push(BestNeighbour { distance, item });
+                } else {
+                    let mut top = results.peek_mut().unwrap();
+                    if item < top.item {
+                        top.item = item;
+                        top.distance = distance;
+                    }
+                }
+            }
+        }
+    }
+}
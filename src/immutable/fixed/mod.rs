@@ -0,0 +1,12 @@
+//! Immutable fixed point k-d tree, for use when the co-ordinates of the points being stored in the tree
+//! are fixed point or integers. [`u8`], [`u16`], [`u32`], and [`u64`] based fixed-point / integers are supported
+//! via the [`Fixed`](https://docs.rs/fixed/1.21.0/fixed) crate, eg [`FixedU16<U14>`](https://docs.rs/fixed/1.21.0/fixed/struct.FixedU16.html) for a 16-bit fixed point number with 14 bits after the
+//! decimal point.
+//!
+//! Shares the flat stem / leaf layout of [`immutable::float::kdtree::ImmutableKdTree`](`crate::immutable::float::kdtree::ImmutableKdTree`),
+//! including its stem ordering, and its queries are generated from the same macros.
+
+pub mod kdtree;
+pub(crate) mod leaf_slice;
+#[doc(hidden)]
+pub mod query;
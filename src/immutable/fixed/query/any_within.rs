@@ -0,0 +1,87 @@
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_any_within;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_any_within!(
+        (r#"Finds an element within `dist` of `query`, using the specified
+distance metric function, or returns `None` if there are none.
+
+Stops searching as soon as an element is found, so the element returned is not
+necessarily the nearest one. Faster than checking whether `within_unsorted` is empty.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let found = tree.any_within::<SquaredEuclidean>(&[Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(61)], Fxd::from_num(10));
+
+    assert_eq!(found.unwrap().item, 2);
+    assert!(tree.any_within::<SquaredEuclidean>(&[Fxd::from_num(10), Fxd::from_num(10), Fxd::from_num(30)], Fxd::from_num(10)).is_none());
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+    use rand::{Rng, SeedableRng};
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn can_find_any_item_within_radius() {
+        const TREE_SIZE: usize = 10_000;
+        const NUM_QUERIES: usize = 1_000;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(53);
+        let mut rand_point =
+            || -> [Fxd; 3] { array_init::array_init(|_| n(rng.gen_range(0f32..1f32))) };
+
+        let content_to_add: Vec<[Fxd; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Fxd; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Fxd, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+        let radius = n(0.1);
+
+        for query_point in query_points {
+            let any_expected = content_to_add
+                .iter()
+                .any(|point| Manhattan::dist(&query_point, point) < radius);
+
+            match tree.any_within::<Manhattan>(&query_point, radius) {
+                Some(found) => {
+                    let point = content_to_add[found.item as usize];
+                    assert_eq!(found.distance, Manhattan::dist(&query_point, &point));
+                    assert!(found.distance < radius);
+                }
+                None => assert!(!any_expected),
+            }
+        }
+    }
+}
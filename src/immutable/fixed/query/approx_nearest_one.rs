@@ -0,0 +1,105 @@
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_approx_nearest_one;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_approx_nearest_one!(
+        (r#"Queries the tree to find the approximate nearest element to `query`, using
+the specified distance metric function.
+
+Faster than querying for nearest_one(point) due
+to not recursing up the tree to find potentially closer points in other branches.
+
+# Examples
+
+```rust
+use fixed::FixedU16;
+use fixed::types::extra::U0;
+use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+use kiddo::fixed::distance::SquaredEuclidean;
+
+type Fxd = FixedU16<U0>;
+
+let content: Vec<[Fxd; 3]> = vec!(
+    [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+);
+let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+let nearest = tree.approx_nearest_one::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(6)]);
+
+assert_eq!(nearest.distance, Fxd::from_num(1));
+assert_eq!(nearest.item, 0);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+    use rand::{Rng, SeedableRng};
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn approx_nearest_one_is_never_closer_than_nearest_one() {
+        const TREE_SIZE: usize = 10_000;
+        const NUM_QUERIES: usize = 1_000;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(61);
+        let mut rand_point =
+            || -> [Fxd; 2] { array_init::array_init(|_| n(rng.gen_range(0f32..1f32))) };
+
+        let content_to_add: Vec<[Fxd; 2]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Fxd; 2]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Fxd, u32, 2, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        let mut exact_matches = 0;
+        for query_point in query_points {
+            let approx = tree.approx_nearest_one::<Manhattan>(&query_point);
+            let exact = tree.nearest_one::<Manhattan>(&query_point);
+
+            assert_eq!(
+                Manhattan::dist(&query_point, &content_to_add[approx.item as usize]),
+                approx.distance
+            );
+            assert!(approx.distance >= exact.distance);
+            if approx.distance == exact.distance {
+                exact_matches += 1;
+            }
+        }
+
+        // the query point's own leaf usually contains its nearest neighbour
+        assert!(exact_matches > NUM_QUERIES / 2);
+    }
+
+    #[test]
+    fn approx_nearest_one_finds_stored_points_exactly() {
+        // co-ordinates are distinct along each axis, so no point can be
+        // stored on the opposite side of a split plane from where it is looked up
+        let content_to_add: Vec<[Fxd; 2]> = (0..1_000)
+            .map(|i| [n(i as f32 / 1000.0), n(((i * 7919) % 1000) as f32 / 1000.0)])
+            .collect();
+        let tree: ImmutableKdTree<Fxd, u32, 2, 8> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        for (idx, point) in content_to_add.iter().enumerate() {
+            let result = tree.approx_nearest_one::<Manhattan>(point);
+            assert_eq!(result.distance, Fxd::ZERO);
+            assert_eq!(result.item, idx as u32);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::BinaryHeap;
+use std::num::NonZero;
+use std::ops::Rem;
+
+use crate::best_neighbour::BestNeighbour;
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_best_n_within;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_best_n_within!(
+        (r#"Finds the "best" `n` elements within `dist` of `query`.
+
+Results are returned in arbitrary order. 'Best' is determined by
+performing a comparison of the elements using < (ie, [`std::cmp::Ordering::is_lt`]). Returns an iterator.
+
+# Examples
+
+```rust
+use std::num::NonZero;
+use std::ops::Rem;
+use fixed::FixedU16;
+use fixed::types::extra::U0;
+use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+use kiddo::fixed::distance::SquaredEuclidean;
+use kiddo::best_neighbour::BestNeighbour;
+
+type Fxd = FixedU16<U0>;
+
+let content: Vec<[Fxd; 3]> = vec!(
+    [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+    [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+);
+let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+let mut best_n_within = tree.best_n_within::<SquaredEuclidean>(
+    &[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    Fxd::from_num(10),
+    NonZero::new(1).unwrap(),
+);
+let first = best_n_within.next().unwrap();
+
+assert_eq!(first, BestNeighbour { distance: Fxd::from_num(0), item: 0 });
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::best_neighbour::BestNeighbour;
+    use crate::fixed::distance::Manhattan;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+    use rand::{Rng, SeedableRng};
+    use std::num::NonZero;
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn can_query_best_n_items_within_radius() {
+        const TREE_SIZE: usize = 20_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(59);
+        let mut rand_point =
+            || -> [Fxd; 3] { array_init::array_init(|_| n(rng.gen_range(0f32..1f32))) };
+
+        let content_to_add: Vec<[Fxd; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Fxd; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Fxd, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+        let radius = n(0.2);
+        let max_qty = 5;
+
+        for query_point in query_points {
+            let mut expected: Vec<BestNeighbour<Fxd, u32>> = content_to_add
+                .iter()
+                .enumerate()
+                .map(|(idx, p)| BestNeighbour {
+                    distance: Manhattan::dist(&query_point, p),
+                    item: idx as u32,
+                })
+                .filter(|bn| bn.distance <= radius)
+                .take(max_qty)
+                .collect();
+            expected.sort_unstable();
+
+            let mut result: Vec<_> = tree
+                .best_n_within::<Manhattan>(&query_point, radius, NonZero::new(max_qty).unwrap())
+                .collect();
+            result.sort_unstable();
+
+            assert_eq!(result, expected);
+        }
+    }
+}
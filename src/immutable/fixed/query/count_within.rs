@@ -0,0 +1,114 @@
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_count_within;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_count_within!(
+        (r#"Counts the elements within `dist` of `query`, using the specified
+distance metric function.
+
+Equivalent to `within_unsorted(query, dist).len()`, but without collecting the results.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let count = tree.count_within::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], Fxd::from_num(10));
+
+    assert_eq!(count, 2);
+```"#),
+        (r#"Counts the elements within `dist` of `query`, using the specified
+distance metric function, stopping once `limit` elements have been found.
+
+Returns the smaller of `limit` and the number of elements within `dist` of `query`.
+Useful for threshold checks, such as whether at least `limit` elements are nearby.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let count = tree.count_within_at_most::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], Fxd::from_num(10), 1);
+
+    assert_eq!(count, 1);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+    use rand::{Rng, SeedableRng};
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn can_count_items_within_radius() {
+        const TREE_SIZE: usize = 20_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(59);
+        let mut rand_point =
+            || -> [Fxd; 3] { array_init::array_init(|_| n(rng.gen_range(0f32..1f32))) };
+
+        let content_to_add: Vec<[Fxd; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Fxd; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Fxd, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+        let radius = n(0.2);
+
+        for query_point in query_points {
+            let expected = content_to_add
+                .iter()
+                .filter(|point| Manhattan::dist(&query_point, point) < radius)
+                .count();
+
+            assert_eq!(
+                tree.count_within::<Manhattan>(&query_point, radius),
+                expected
+            );
+            for limit in [0, 1, expected / 2, expected, expected + 1] {
+                assert_eq!(
+                    tree.count_within_at_most::<Manhattan>(&query_point, radius, limit),
+                    expected.min(limit)
+                );
+            }
+        }
+    }
+}
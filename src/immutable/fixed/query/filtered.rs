@@ -0,0 +1,177 @@
+use sorted_vec::SortedVec;
+use std::collections::BinaryHeap;
+use std::num::NonZero;
+
+use crate::float::result_collection::ResultCollection;
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_filtered;
+
+const MAX_VEC_RESULT_SIZE: usize = 20;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_filtered!(
+        (r#"Finds the nearest element to `query` for which `filter` returns `true`, using
+the specified distance metric function, or returns `None` if there are none.
+
+Items that are rejected by `filter` do not narrow the search, so this always finds
+the nearest accepted item, unlike filtering the results of `nearest_n`.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let nearest = tree.nearest_one_filtered::<SquaredEuclidean, _>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], |item| item != 0);
+
+    assert_eq!(nearest.unwrap().item, 1);
+```"#),
+        (r#"Finds the nearest `max_qty` elements to `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use std::num::NonZero;
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let nearest = tree.nearest_n_filtered::<SquaredEuclidean, _>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], NonZero::new(2).unwrap(), |item| item != 1);
+
+    assert_eq!(nearest.len(), 2);
+    assert_eq!(nearest[1].item, 2);
+```"#),
+        (r#"Finds all elements within `dist` of `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let within = tree.within_filtered::<SquaredEuclidean, _>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], Fxd::from_num(10), |item| item != 0);
+
+    assert_eq!(within.len(), 1);
+    assert_eq!(within[0].item, 1);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+    use rand::{Rng, SeedableRng};
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    use std::num::NonZero;
+
+    #[test]
+    fn can_query_filtered() {
+        const TREE_SIZE: usize = 20_000;
+        const NUM_QUERIES: usize = 50;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(61);
+        let mut rand_point =
+            || -> [Fxd; 3] { array_init::array_init(|_| n(rng.gen_range(0f32..1f32))) };
+
+        let content_to_add: Vec<[Fxd; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Fxd; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Fxd, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+        let radius = n(0.1);
+        let filter = |item: u32| item.is_multiple_of(3);
+
+        for query_point in query_points {
+            let mut expected: Vec<(Fxd, u32)> = content_to_add
+                .iter()
+                .enumerate()
+                .map(|(idx, p)| (Manhattan::dist(&query_point, p), idx as u32))
+                .filter(|&(_, item)| filter(item))
+                .collect();
+            expected.sort_unstable();
+
+            let nearest = tree
+                .nearest_one_filtered::<Manhattan, _>(&query_point, filter)
+                .unwrap();
+            assert_eq!(nearest.distance, expected[0].0);
+            assert!(filter(nearest.item));
+
+            for qty in [1, 5, 50] {
+                let result = tree.nearest_n_filtered::<Manhattan, _>(
+                    &query_point,
+                    NonZero::new(qty).unwrap(),
+                    filter,
+                );
+                let dists: Vec<Fxd> = result.iter().map(|n| n.distance).collect();
+                let expected_dists: Vec<Fxd> = expected.iter().take(qty).map(|e| e.0).collect();
+                assert_eq!(dists, expected_dists);
+                assert!(result.iter().all(|n| filter(n.item)));
+            }
+
+            let mut within: Vec<(Fxd, u32)> = tree
+                .within_filtered::<Manhattan, _>(&query_point, radius, filter)
+                .into_iter()
+                .map(Into::into)
+                .collect();
+            assert!(within.windows(2).all(|w| w[0].0 <= w[1].0));
+            within.sort_unstable();
+            let expected_within: Vec<(Fxd, u32)> = expected
+                .into_iter()
+                .filter(|&(dist, _)| dist < radius)
+                .collect();
+            assert_eq!(within, expected_within);
+        }
+    }
+}
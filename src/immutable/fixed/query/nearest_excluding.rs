@@ -0,0 +1,143 @@
+use sorted_vec::SortedVec;
+use std::collections::BinaryHeap;
+use std::num::NonZero;
+
+use crate::float::result_collection::ResultCollection;
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_nearest_excluding;
+
+const MAX_VEC_RESULT_SIZE: usize = 20;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_nearest_excluding!(
+        (r#"Queries the tree to find the nearest item to the `query` point, ignoring
+any elements whose item is `excluded`.
+
+Intended for querying with a point that is itself stored in the tree, such as when
+building a neighbour graph. Returns `None` if the tree holds no other items.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let nearest = tree.nearest_one_excluding::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], 0).unwrap();
+
+    assert_eq!(nearest.distance, Fxd::from_num(3));
+    assert_eq!(nearest.item, 1);
+```"#),
+        (r#"Finds the nearest `max_qty` elements to `query`, ignoring any elements whose
+item is `excluded`, using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use std::num::NonZero;
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+    use kiddo::fixed::distance::SquaredEuclidean;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let nearest = tree.nearest_n_excluding::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], NonZero::new(2).unwrap(), 0);
+
+    assert_eq!(nearest.len(), 2);
+    assert_eq!(nearest[0].item, 1);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+    use rand::{Rng, SeedableRng};
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    use std::num::NonZero;
+
+    #[test]
+    fn can_query_nearest_excluding_a_stored_point() {
+        const TREE_SIZE: usize = 10_000;
+        const NUM_QUERIES: usize = 50;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(67);
+        let mut rand_point =
+            || -> [Fxd; 3] { array_init::array_init(|_| n(rng.gen_range(0f32..1f32))) };
+
+        let content_to_add: Vec<[Fxd; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Fxd, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        for item in (0..TREE_SIZE as u32).step_by(TREE_SIZE / NUM_QUERIES) {
+            let query_point = content_to_add[item as usize];
+            let mut expected: Vec<Fxd> = content_to_add
+                .iter()
+                .enumerate()
+                .filter(|&(idx, _)| idx as u32 != item)
+                .map(|(_, p)| Manhattan::dist(&query_point, p))
+                .collect();
+            expected.sort_unstable();
+
+            let nearest = tree
+                .nearest_one_excluding::<Manhattan>(&query_point, item)
+                .unwrap();
+            assert_ne!(nearest.item, item);
+            assert_eq!(nearest.distance, expected[0]);
+
+            let result =
+                tree.nearest_n_excluding::<Manhattan>(&query_point, NonZero::new(5).unwrap(), item);
+            assert!(result.iter().all(|n| n.item != item));
+            let dists: Vec<Fxd> = result.iter().map(|n| n.distance).collect();
+            assert_eq!(dists, expected[..5]);
+        }
+    }
+
+    #[test]
+    fn nearest_excluding_the_only_item_is_none() {
+        let tree: ImmutableKdTree<Fxd, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&[[n(0.5); 3]]);
+
+        assert!(tree
+            .nearest_one_excluding::<Manhattan>(&[n(0.5); 3], 0)
+            .is_none());
+        assert!(tree
+            .nearest_n_excluding::<Manhattan>(&[n(0.5); 3], NonZero::new(3).unwrap(), 0)
+            .is_empty());
+    }
+}
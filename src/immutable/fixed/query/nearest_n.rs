@@ -0,0 +1,94 @@
+use std::num::NonZero;
+
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_nearest_n;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_nearest_n!(
+        (r#"Finds the nearest `qty` elements to `query`, using the specified
+distance metric function.
+
+# Examples
+
+```rust
+use std::num::NonZero;
+use fixed::FixedU16;
+use fixed::types::extra::U0;
+use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+use kiddo::fixed::distance::SquaredEuclidean;
+
+type Fxd = FixedU16<U0>;
+
+let content: Vec<[Fxd; 3]> = vec!(
+    [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+);
+let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+let nearest: Vec<_> = tree.nearest_n::<SquaredEuclidean>(
+    &[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    NonZero::new(1).unwrap(),
+);
+
+assert_eq!(nearest.len(), 1);
+assert_eq!(nearest[0].distance, Fxd::from_num(0));
+assert_eq!(nearest[0].item, 0);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+    use rand::{Rng, SeedableRng};
+    use std::num::NonZero;
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn can_query_nearest_n_items() {
+        const TREE_SIZE: usize = 50_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(37);
+        let mut rand_point =
+            || -> [Fxd; 4] { array_init::array_init(|_| n(rng.gen_range(0f32..1f32))) };
+
+        let content_to_add: Vec<[Fxd; 4]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Fxd; 4]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Fxd, u32, 4, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        for query_point in query_points {
+            for max_qty in [1, 10, 100] {
+                let mut expected: Vec<Fxd> = content_to_add
+                    .iter()
+                    .map(|p| Manhattan::dist(&query_point, p))
+                    .collect();
+                expected.sort_unstable();
+                expected.truncate(max_qty);
+
+                let result =
+                    tree.nearest_n::<Manhattan>(&query_point, NonZero::new(max_qty).unwrap());
+
+                assert_eq!(
+                    result.iter().map(|nn| nn.distance).collect::<Vec<_>>(),
+                    expected
+                );
+            }
+        }
+    }
+}
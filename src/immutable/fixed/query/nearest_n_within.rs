@@ -0,0 +1,220 @@
+use std::num::NonZero;
+use std::ops::Rem;
+
+use crate::float::result_collection::{
+    BinaryHeapArray, BinaryHeapRef, ResultCollection, SortedArrayRef, SortedVecRef,
+};
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_nearest_n_within;
+
+const MAX_VEC_RESULT_SIZE: usize = 20;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_nearest_n_within!(
+        (r#"Finds up to n elements within `dist` of `query`, using the specified
+distance metric function.
+
+# Examples
+
+```rust
+use std::num::NonZero;
+use std::ops::Rem;
+use fixed::FixedU16;
+use fixed::types::extra::U0;
+use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+use kiddo::fixed::distance::SquaredEuclidean;
+
+type Fxd = FixedU16<U0>;
+
+let content: Vec<[Fxd; 3]> = vec!(
+    [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+    [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+);
+let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+let within = tree.nearest_n_within::<SquaredEuclidean>(
+    &[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    Fxd::from_num(10),
+    NonZero::new(2).unwrap(),
+    true,
+);
+
+assert_eq!(within.len(), 2);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::{Manhattan, SquaredEuclidean};
+    use crate::fixed::kdtree::Axis;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::{U0, U14};
+    use fixed::{FixedI32, FixedU16};
+    use rand::{Rng, SeedableRng};
+    use std::num::NonZero;
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn can_query_items_within_radius() {
+        let content_to_add: [[Fxd; 4]; 16] = [
+            [n(0.9), n(0.0), n(0.9), n(0.0)],
+            [n(0.4), n(0.5), n(0.4), n(0.51)],
+            [n(0.12), n(0.3), n(0.12), n(0.3)],
+            [n(0.7), n(0.2), n(0.7), n(0.22)],
+            [n(0.13), n(0.4), n(0.13), n(0.4)],
+            [n(0.6), n(0.3), n(0.6), n(0.33)],
+            [n(0.2), n(0.7), n(0.2), n(0.7)],
+            [n(0.14), n(0.5), n(0.14), n(0.5)],
+            [n(0.3), n(0.6), n(0.3), n(0.6)],
+            [n(0.10), n(0.1), n(0.10), n(0.1)],
+            [n(0.16), n(0.7), n(0.16), n(0.7)],
+            [n(0.1), n(0.8), n(0.1), n(0.8)],
+            [n(0.15), n(0.6), n(0.15), n(0.6)],
+            [n(0.5), n(0.4), n(0.5), n(0.44)],
+            [n(0.8), n(0.1), n(0.8), n(0.15)],
+            [n(0.11), n(0.2), n(0.11), n(0.2)],
+        ];
+
+        let tree: ImmutableKdTree<Fxd, u32, 4, 4> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        let mut rng = rand::thread_rng();
+        for _i in 0..1000 {
+            let query_point = [
+                n(rng.gen_range(0f32..1f32)),
+                n(rng.gen_range(0f32..1f32)),
+                n(rng.gen_range(0f32..1f32)),
+                n(rng.gen_range(0f32..1f32)),
+            ];
+            let radius = n(rng.gen_range(0.2f32..2f32));
+            let max_qty = NonZero::new(3).unwrap();
+
+            let expected: Vec<_> =
+                linear_search::<_, Manhattan, 4>(&content_to_add, &query_point, radius)
+                    .into_iter()
+                    .take(max_qty.into())
+                    .map(|(distance, _)| distance)
+                    .collect();
+
+            let result = tree.nearest_n_within::<Manhattan>(&query_point, radius, max_qty, true);
+
+            // items at equal distances may be returned in either order
+            assert_eq!(
+                result.iter().map(|nn| nn.distance).collect::<Vec<_>>(),
+                expected
+            );
+            for nn in &result {
+                assert_eq!(
+                    Manhattan::dist(&query_point, &content_to_add[nn.item as usize]),
+                    nn.distance
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn can_query_items_within_radius_large_scale() {
+        type Int = FixedI32<U0>;
+
+        const TREE_SIZE: usize = 100_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(41);
+        let mut rand_point = || -> [Int; 3] {
+            array_init::array_init(|_| Int::from_num(rng.gen_range(-4096..4096)))
+        };
+
+        let content_to_add: Vec<[Int; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Int; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Int, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+        let radius = Int::from_num(400 * 400);
+
+        let mut buf = Vec::new();
+        for query_point in query_points {
+            for max_qty in [1, 5, 50, usize::MAX] {
+                let expected: Vec<_> =
+                    linear_search::<_, SquaredEuclidean, 3>(&content_to_add, &query_point, radius)
+                        .into_iter()
+                        .take(max_qty)
+                        .map(|(distance, _)| distance)
+                        .collect();
+
+                tree.collect_nearest_n_within::<SquaredEuclidean>(
+                    &query_point,
+                    radius,
+                    NonZero::new(max_qty).unwrap(),
+                    true,
+                    &mut buf,
+                );
+
+                // items at equal distances may be returned in either order
+                let result: Vec<_> = buf.iter().map(|nn| nn.distance).collect();
+                assert_eq!(result, expected);
+                for nn in &buf {
+                    assert_eq!(
+                        SquaredEuclidean::dist(&query_point, &content_to_add[nn.item as usize]),
+                        nn.distance
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unsorted_results_include_the_nearest_items() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(43);
+        let content_to_add: Vec<[Fxd; 2]> = (0..5_000)
+            .map(|_| [n(rng.gen_range(0f32..1f32)), n(rng.gen_range(0f32..1f32))])
+            .collect();
+        let tree: ImmutableKdTree<Fxd, u32, 2, 16> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        let query_point = [n(0.5), n(0.5)];
+        let max_qty = NonZero::new(20).unwrap();
+
+        let sorted = tree.nearest_n_within::<Manhattan>(&query_point, n(0.3), max_qty, true);
+        let mut unsorted = tree.nearest_n_within::<Manhattan>(&query_point, n(0.3), max_qty, false);
+        unsorted.sort();
+
+        assert_eq!(sorted.len(), 20);
+        assert_eq!(
+            sorted.iter().map(|nn| nn.distance).collect::<Vec<_>>(),
+            unsorted
+                .iter()
+                .take(20)
+                .map(|nn| nn.distance)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn linear_search<A: Axis, D: DistanceMetric<A, K>, const K: usize>(
+        content: &[[A; K]],
+        query_point: &[A; K],
+        radius: A,
+    ) -> Vec<(A, u32)> {
+        let mut matching_items: Vec<_> = content
+            .iter()
+            .enumerate()
+            .map(|(idx, p)| (D::dist(query_point, p), idx as u32))
+            .filter(|&(dist, _)| dist < radius)
+            .collect();
+
+        matching_items.sort_unstable();
+
+        matching_items
+    }
+}
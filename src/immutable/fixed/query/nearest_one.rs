@@ -0,0 +1,157 @@
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_nearest_one;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_nearest_one!(
+        (r#"Queries the tree to find the nearest item to the `query` point.
+
+Faster than querying for nearest_n(point, 1, ...) due
+to not needing to allocate memory or maintain sorted results.
+
+# Examples
+
+```rust
+use fixed::FixedU16;
+use fixed::types::extra::U0;
+use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+use kiddo::fixed::distance::SquaredEuclidean;
+
+type Fxd = FixedU16<U0>;
+
+let content: Vec<[Fxd; 3]> = vec!(
+    [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+);
+let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+let nearest = tree.nearest_one::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(6)]);
+
+assert_eq!(nearest.distance, Fxd::from_num(1));
+assert_eq!(nearest.item, 0);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::{Manhattan, SquaredEuclidean};
+    use crate::fixed::kdtree::Axis;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::nearest_neighbour::NearestNeighbour;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::{U0, U14};
+    use fixed::{FixedI32, FixedU16};
+    use rand::{Rng, SeedableRng};
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn can_query_nearest_one_item() {
+        let content_to_add: [[Fxd; 4]; 16] = [
+            [n(0.9), n(0.0), n(0.9), n(0.0)],
+            [n(0.4), n(0.5), n(0.4), n(0.5)],
+            [n(0.12), n(0.3), n(0.12), n(0.3)],
+            [n(0.7), n(0.2), n(0.7), n(0.2)],
+            [n(0.13), n(0.4), n(0.13), n(0.4)],
+            [n(0.6), n(0.3), n(0.6), n(0.3)],
+            [n(0.2), n(0.7), n(0.2), n(0.7)],
+            [n(0.14), n(0.5), n(0.14), n(0.5)],
+            [n(0.3), n(0.6), n(0.3), n(0.6)],
+            [n(0.10), n(0.1), n(0.10), n(0.1)],
+            [n(0.16), n(0.7), n(0.16), n(0.7)],
+            [n(0.1), n(0.8), n(0.1), n(0.8)],
+            [n(0.15), n(0.6), n(0.15), n(0.6)],
+            [n(0.5), n(0.4), n(0.5), n(0.4)],
+            [n(0.8), n(0.1), n(0.8), n(0.1)],
+            [n(0.11), n(0.2), n(0.11), n(0.2)],
+        ];
+
+        let tree: ImmutableKdTree<Fxd, u32, 4, 4> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        assert_eq!(tree.size(), 16);
+
+        let query_point = [n(0.78), n(0.55), n(0.78), n(0.55)];
+        let result = tree.nearest_one::<Manhattan>(&query_point);
+
+        // items 3 and 13 are both at this distance
+        assert_eq!(result.distance, n(0.86));
+        assert!(result.item == 3 || result.item == 13);
+
+        let mut rng = rand::thread_rng();
+        for _i in 0..1000 {
+            let query_point = [
+                n(rng.gen_range(0f32..1f32)),
+                n(rng.gen_range(0f32..1f32)),
+                n(rng.gen_range(0f32..1f32)),
+                n(rng.gen_range(0f32..1f32)),
+            ];
+            let expected = linear_search::<_, Manhattan, 4>(&content_to_add, &query_point);
+
+            let result = tree.nearest_one::<Manhattan>(&query_point);
+
+            assert_eq!(result.distance, expected.distance);
+        }
+    }
+
+    #[test]
+    fn can_query_nearest_one_item_large_scale() {
+        type Int = FixedI32<U0>;
+
+        const TREE_SIZE: usize = 100_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(23);
+        let mut rand_point = || -> [Int; 3] {
+            array_init::array_init(|_| Int::from_num(rng.gen_range(-4096..4096)))
+        };
+
+        let content_to_add: Vec<[Int; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Int; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Int, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+        assert_eq!(tree.size(), TREE_SIZE);
+
+        for query_point in query_points {
+            let expected = linear_search::<_, SquaredEuclidean, 3>(&content_to_add, &query_point);
+
+            let result = tree.nearest_one::<SquaredEuclidean>(&query_point);
+
+            assert_eq!(result.distance, expected.distance);
+            assert_eq!(
+                SquaredEuclidean::dist(&query_point, &content_to_add[result.item as usize]),
+                expected.distance
+            );
+        }
+    }
+
+    fn linear_search<A: Axis, D: DistanceMetric<A, K>, const K: usize>(
+        content: &[[A; K]],
+        query_point: &[A; K],
+    ) -> NearestNeighbour<A, u32> {
+        let mut best_dist: A = A::MAX;
+        let mut best_item: u32 = u32::MAX;
+
+        for (idx, p) in content.iter().enumerate() {
+            let dist = D::dist(query_point, p);
+            if dist < best_dist {
+                best_item = idx as u32;
+                best_dist = dist;
+            }
+        }
+
+        NearestNeighbour {
+            distance: best_dist,
+            item: best_item,
+        }
+    }
+}
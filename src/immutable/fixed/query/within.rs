@@ -0,0 +1,91 @@
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_within;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_within!(
+        (r#"Finds all elements within `dist` of `query`, using the specified
+distance metric function.
+
+Results are returned sorted nearest-first
+
+# Examples
+
+```rust
+use fixed::FixedU16;
+use fixed::types::extra::U0;
+use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+use kiddo::fixed::distance::SquaredEuclidean;
+
+type Fxd = FixedU16<U0>;
+
+let content: Vec<[Fxd; 3]> = vec!(
+    [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+    [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+);
+let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+let within = tree.within::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], Fxd::from_num(10));
+
+assert_eq!(within.len(), 2);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::Manhattan;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+    use rand::{Rng, SeedableRng};
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn can_query_items_within_radius() {
+        const TREE_SIZE: usize = 20_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(47);
+        let mut rand_point =
+            || -> [Fxd; 3] { array_init::array_init(|_| n(rng.gen_range(0f32..1f32))) };
+
+        let content_to_add: Vec<[Fxd; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Fxd; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Fxd, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+        let radius = n(0.1);
+
+        let mut buf = Vec::new();
+        for query_point in query_points {
+            let mut expected: Vec<(Fxd, u32)> = content_to_add
+                .iter()
+                .enumerate()
+                .map(|(idx, p)| (Manhattan::dist(&query_point, p), idx as u32))
+                .filter(|&(dist, _)| dist < radius)
+                .collect();
+            expected.sort_unstable();
+
+            let result = tree.within::<Manhattan>(&query_point, radius);
+            tree.collect_within::<Manhattan>(&query_point, radius, &mut buf);
+            assert_eq!(buf, result);
+
+            // items at equal distances may be returned in either order
+            let mut result: Vec<(Fxd, u32)> = result.into_iter().map(Into::into).collect();
+            assert!(result.windows(2).all(|w| w[0].0 <= w[1].0));
+            result.sort_unstable();
+            assert_eq!(result, expected);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::ops::Rem;
+
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::immutable::fixed::leaf_slice::LeafSlice;
+use crate::traits::Content;
+
+use crate::generate_immutable_within_box;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_within_box!(
+        (r#"Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let within = tree.within_box(&[Fxd::from_num(0), Fxd::from_num(0), Fxd::from_num(0)], &[Fxd::from_num(2), Fxd::from_num(2), Fxd::from_num(5)]);
+
+    assert_eq!(within, vec![0]);
+```"#),
+        (r#"Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Returns an `Iterator`. Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let min = [Fxd::from_num(0), Fxd::from_num(0), Fxd::from_num(0)];
+    let max = [Fxd::from_num(10), Fxd::from_num(10), Fxd::from_num(10)];
+    let within = tree.within_box_iter(&min, &max).collect::<Vec<_>>();
+
+    assert_eq!(within.len(), 2);
+```"#),
+        (r#"Counts the elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Faster than `within_box(min, max).len()`, since leaves that lie entirely within the box
+are counted without looking at the points that they hold.
+
+# Examples
+
+```rust
+    use fixed::FixedU16;
+    use fixed::types::extra::U0;
+    use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+
+    type Fxd = FixedU16<U0>;
+
+    let content: Vec<[Fxd; 3]> = vec!(
+        [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+        [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+        [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+    );
+    let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+    let count = tree.count_in_box(&[Fxd::from_num(0), Fxd::from_num(0), Fxd::from_num(0)], &[Fxd::from_num(10), Fxd::from_num(10), Fxd::from_num(10)]);
+
+    assert_eq!(count, 2);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use fixed::types::extra::U14;
+    use fixed::FixedU16;
+    use rand::{Rng, SeedableRng};
+
+    type Fxd = FixedU16<U14>;
+
+    fn n(num: f32) -> Fxd {
+        Fxd::from_num(num)
+    }
+
+    #[test]
+    fn can_query_items_within_box() {
+        const TREE_SIZE: usize = 20_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(71);
+        let mut rand_point =
+            || -> [Fxd; 3] { array_init::array_init(|_| n(rng.gen_range(0f32..1f32))) };
+
+        let content_to_add: Vec<[Fxd; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Fxd; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Fxd, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        for query_point in query_points {
+            let min: [Fxd; 3] =
+                array_init::array_init(|dim| query_point[dim].saturating_sub(n(0.1)));
+            let max: [Fxd; 3] =
+                array_init::array_init(|dim| query_point[dim].saturating_add(n(0.1)));
+
+            let mut expected: Vec<u32> = content_to_add
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| (0..3).all(|dim| p[dim] >= min[dim] && p[dim] <= max[dim]))
+                .map(|(idx, _)| idx as u32)
+                .collect();
+            expected.sort_unstable();
+
+            let mut result = tree.within_box(&min, &max);
+            result.sort_unstable();
+            assert_eq!(result, expected);
+
+            let mut result: Vec<u32> = tree.within_box_iter(&min, &max).collect();
+            result.sort_unstable();
+            assert_eq!(result, expected);
+
+            assert_eq!(tree.count_in_box(&min, &max), expected.len());
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::immutable::fixed::kdtree::{Axis, ImmutableKdTree};
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_within_unsorted;
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B> {
+    generate_immutable_within_unsorted!(
+        (r#"Finds all elements within `dist` of `query`, using the specified
+distance metric function.
+
+Results are returned in arbitrary order. Faster than `within`.
+
+# Examples
+
+```rust
+use fixed::FixedU16;
+use fixed::types::extra::U0;
+use kiddo::immutable::fixed::kdtree::ImmutableKdTree;
+use kiddo::fixed::distance::SquaredEuclidean;
+
+type Fxd = FixedU16<U0>;
+
+let content: Vec<[Fxd; 3]> = vec!(
+    [Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)],
+    [Fxd::from_num(2), Fxd::from_num(3), Fxd::from_num(6)],
+    [Fxd::from_num(20), Fxd::from_num(30), Fxd::from_num(60)],
+);
+let tree: ImmutableKdTree<Fxd, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+let within = tree.within_unsorted::<SquaredEuclidean>(&[Fxd::from_num(1), Fxd::from_num(2), Fxd::from_num(5)], Fxd::from_num(10));
+
+assert_eq!(within.len(), 2);
+```"#)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::distance::SquaredEuclidean;
+    use crate::immutable::fixed::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use fixed::types::extra::U0;
+    use fixed::FixedI32;
+    use rand::{Rng, SeedableRng};
+
+    type Int = FixedI32<U0>;
+
+    #[test]
+    fn can_query_items_within_radius() {
+        const TREE_SIZE: usize = 20_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(53);
+        let mut rand_point = || -> [Int; 3] {
+            array_init::array_init(|_| Int::from_num(rng.gen_range(-1024..1024)))
+        };
+
+        let content_to_add: Vec<[Int; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[Int; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<Int, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+        let radius = Int::from_num(150 * 150);
+
+        for query_point in query_points {
+            let mut expected: Vec<(Int, u32)> = content_to_add
+                .iter()
+                .enumerate()
+                .map(|(idx, p)| (SquaredEuclidean::dist(&query_point, p), idx as u32))
+                .filter(|&(dist, _)| dist < radius)
+                .collect();
+            expected.sort_unstable();
+
+            let mut result: Vec<(Int, u32)> = tree
+                .within_unsorted::<SquaredEuclidean>(&query_point, radius)
+                .into_iter()
+                .map(Into::into)
+                .collect();
+            result.sort_unstable();
+
+            assert_eq!(result, expected);
+        }
+    }
+}
@@ -0,0 +1,582 @@
+//! Builder for [`ImmutableKdTree`], for tuning how the tree is split during construction.
+//!
+//! The default [`ImmutableKdTree::new_from_slice`] splits each stem at the median point,
+//! cycling through the axes level by level. [`ImmutableKdTreeBuilder`] allows the split
+//! value, the split axis, and the number of items per leaf to be chosen instead, and can
+//! store a bounding box per leaf for tighter pruning during queries. The options are
+//! recorded in the tree, and are re-used whenever it is rebuilt.
+//!
+//! The ordering of the stems in memory is selected at compile time by the
+//! `modified_van_emde_boas` feature, and so is not configurable here. Every query walks the
+//! stems using the child index arithmetic of that one ordering, so choosing it per tree would
+//! mean checking the layout at each step of every query, which the default build avoids.
+
+use az::Cast;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::float_leaf_slice::leaf_slice::{LeafSliceFloat, LeafSliceFloatChunk};
+use crate::immutable::float::kdtree::{Axis, ImmutableKdTree};
+pub use crate::split_axis::SplitAxis;
+use crate::traits::Content;
+
+/// How the value that each stem splits its points at is chosen
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PivotStrategy {
+    /// Split at the median point, so that both children hold the same number of points.
+    /// Produces the most balanced tree.
+    #[default]
+    Median,
+
+    /// Split halfway between the smallest and largest value on the split axis of the
+    /// points being split, sliding the split up to the largest value if they are so
+    /// close together that the midpoint rounds down to the smallest.
+    /// Produces cells with a more even aspect ratio on clustered data.
+    SlidingMidpoint,
+
+    /// Split at whichever of a set of evenly spaced candidate planes minimises the
+    /// number of points in each child multiplied by the surface area of the child's
+    /// bounding box. Slower to build, but tends to separate clusters from empty space.
+    SurfaceAreaHeuristic,
+}
+
+/// Construction options, recorded in an [`ImmutableKdTree`] so that rebuilds use them too
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct BuildOptions {
+    pub(crate) pivot_strategy: PivotStrategy,
+    pub(crate) split_axis: SplitAxis,
+
+    /// target number of items per leaf, or zero to use `B`
+    pub(crate) leaf_fill_target: usize,
+
+    /// whether to store a bounding box for each leaf, for queries to prune against
+    pub(crate) leaf_bounding_boxes: bool,
+}
+
+impl BuildOptions {
+    /// Returns the target number of items per leaf
+    #[inline]
+    pub(crate) fn leaf_fill_target<const B: usize>(&self) -> usize {
+        if self.leaf_fill_target == 0 {
+            B
+        } else {
+            self.leaf_fill_target
+        }
+    }
+}
+
+/// Builds an [`ImmutableKdTree`] with a configurable split strategy.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::immutable::float::builder::{ImmutableKdTreeBuilder, PivotStrategy, SplitAxis};
+/// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+/// use kiddo::SquaredEuclidean;
+///
+/// let points: Vec<[f64; 3]> = (0..1000).map(|i| [i as f64, (i % 4) as f64, 0.0]).collect();
+///
+/// let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTreeBuilder::new()
+///     .pivot_strategy(PivotStrategy::SlidingMidpoint)
+///     .split_axis(SplitAxis::WidestSpread)
+///     .leaf_fill_target(16)
+///     .build_from_slice(&points);
+///
+/// assert_eq!(tree.size(), 1000);
+/// assert_eq!(tree.nearest_one::<SquaredEuclidean>(&[500.0, 0.0, 0.0]).item, 500);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImmutableKdTreeBuilder {
+    options: BuildOptions,
+}
+
+impl ImmutableKdTreeBuilder {
+    /// Creates a builder with the default options, which produce the same tree
+    /// as [`ImmutableKdTree::new_from_slice`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how the value that each stem splits its points at is chosen.
+    /// Defaults to [`PivotStrategy::Median`].
+    pub fn pivot_strategy(mut self, pivot_strategy: PivotStrategy) -> Self {
+        self.options.pivot_strategy = pivot_strategy;
+        self
+    }
+
+    /// Sets how the axis that each stem splits its points along is chosen.
+    /// Defaults to [`SplitAxis::RoundRobin`].
+    pub fn split_axis(mut self, split_axis: SplitAxis) -> Self {
+        self.options.split_axis = split_axis;
+        self
+    }
+
+    /// Sets the number of items that each leaf should hold. The tree is given enough leaves
+    /// for every leaf to hold this many items, although with pivot strategies other than
+    /// [`PivotStrategy::Median`] some leaves will hold more and some fewer.
+    /// Defaults to the tree's bucket size, `B`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaf_fill_target` is zero.
+    pub fn leaf_fill_target(mut self, leaf_fill_target: usize) -> Self {
+        assert!(leaf_fill_target > 0, "leaf_fill_target must be non-zero");
+        self.options.leaf_fill_target = leaf_fill_target;
+        self
+    }
+
+    /// Sets whether to store the axis-aligned bounding box of the points in each leaf.
+    ///
+    /// Queries normally decide whether a leaf needs searching from its distance to the
+    /// split planes above it, which on clustered data can be much less than its distance
+    /// to the closest point within it. With bounding boxes, leaves whose box is further away
+    /// than the current best result are skipped, at the cost of `2 * K` extra values per leaf.
+    /// Defaults to `false`.
+    pub fn leaf_bounding_boxes(mut self, enabled: bool) -> Self {
+        self.options.leaf_bounding_boxes = enabled;
+        self
+    }
+
+    /// Builds an [`ImmutableKdTree`] from `source`, in the same way as
+    /// [`ImmutableKdTree::new_from_slice`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`SplitAxis::WidestSpread`] is used with more than 256 dimensions.
+    pub fn build_from_slice<A, T, const K: usize, const B: usize>(
+        &self,
+        source: &[[A; K]],
+    ) -> ImmutableKdTree<A, T, K, B>
+    where
+        A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+        T: Content,
+        usize: Cast<T>,
+    {
+        let mut tree = self.empty_tree();
+        tree.rebuild_from_slice(source);
+        tree
+    }
+
+    /// Builds an [`ImmutableKdTree`] from `points` and `items`, in the same way as
+    /// [`ImmutableKdTree::new_from_points_and_items`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `items` have different lengths, or if
+    /// [`SplitAxis::WidestSpread`] is used with more than 256 dimensions.
+    pub fn build_from_points_and_items<A, T, const K: usize, const B: usize>(
+        &self,
+        points: &[[A; K]],
+        items: &[T],
+    ) -> ImmutableKdTree<A, T, K, B>
+    where
+        A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+        T: Content,
+        usize: Cast<T>,
+    {
+        let mut tree = self.empty_tree();
+        tree.rebuild_from_points_and_items(points, items);
+        tree
+    }
+
+    /// Parallel equivalent of [`ImmutableKdTreeBuilder::build_from_slice`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`SplitAxis::WidestSpread`] is used with more than 256 dimensions.
+    #[cfg(feature = "rayon")]
+    pub fn par_build_from_slice<A, T, const K: usize, const B: usize>(
+        &self,
+        source: &[[A; K]],
+    ) -> ImmutableKdTree<A, T, K, B>
+    where
+        A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+        T: Content,
+        usize: Cast<T>,
+    {
+        let mut tree = self.empty_tree();
+        tree.par_rebuild_from_slice(source);
+        tree
+    }
+
+    fn empty_tree<A, T, const K: usize, const B: usize>(&self) -> ImmutableKdTree<A, T, K, B>
+    where
+        A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+        T: Content,
+        usize: Cast<T>,
+    {
+        assert!(
+            self.options.split_axis == SplitAxis::RoundRobin || K <= u8::MAX as usize + 1,
+            "SplitAxis::WidestSpread supports at most 256 dimensions"
+        );
+
+        let mut tree = ImmutableKdTree::new_empty();
+        tree.options = self.options;
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImmutableKdTreeBuilder, PivotStrategy, SplitAxis};
+    use crate::immutable::float::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use crate::SquaredEuclidean;
+    use rand::{Rng, SeedableRng};
+    use std::num::NonZero;
+
+    const ALL_OPTIONS: [(PivotStrategy, SplitAxis); 6] = [
+        (PivotStrategy::Median, SplitAxis::RoundRobin),
+        (PivotStrategy::Median, SplitAxis::WidestSpread),
+        (PivotStrategy::SlidingMidpoint, SplitAxis::RoundRobin),
+        (PivotStrategy::SlidingMidpoint, SplitAxis::WidestSpread),
+        (PivotStrategy::SurfaceAreaHeuristic, SplitAxis::RoundRobin),
+        (PivotStrategy::SurfaceAreaHeuristic, SplitAxis::WidestSpread),
+    ];
+
+    /// flat terrain-like points: wide in x and z, but only a few units tall in y,
+    /// with some clusters of duplicate co-ordinates
+    fn anisotropic_points(count: usize, seed: u64) -> Vec<[f64; 3]> {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+        (0..count)
+            .map(|i| {
+                if i % 10 == 0 {
+                    [100.0, rng.gen_range(0..4) as f64, 250.0]
+                } else {
+                    [
+                        rng.gen_range(0.0..2000.0),
+                        rng.gen_range(0.0..4.0),
+                        rng.gen_range(0.0..2000.0),
+                    ]
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn default_builder_matches_new_from_slice() {
+        let points = anisotropic_points(5_000, 3);
+
+        let tree: ImmutableKdTree<f64, u32, 3, 32> =
+            ImmutableKdTreeBuilder::new().build_from_slice(&points);
+
+        assert_eq!(tree, ImmutableKdTree::new_from_slice(&points));
+        assert!(tree.split_dims.is_empty());
+    }
+
+    #[test]
+    fn widest_spread_splits_along_widest_axis() {
+        let points = anisotropic_points(5_000, 5);
+
+        let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTreeBuilder::new()
+            .split_axis(SplitAxis::WidestSpread)
+            .build_from_slice(&points);
+
+        assert_eq!(tree.split_axis(), SplitAxis::WidestSpread);
+        assert_eq!(tree.split_dims.len(), tree.stems.len());
+
+        // the tall, thin y axis should never be the widest near the root
+        #[cfg(not(feature = "modified_van_emde_boas"))]
+        let root_idx = 1;
+        #[cfg(feature = "modified_van_emde_boas")]
+        let root_idx = 0;
+        assert_ne!(tree.split_dims[root_idx], 1);
+    }
+
+    #[test]
+    fn widest_spread_treats_infinite_extents_as_no_spread() {
+        // every point is at infinity on the second axis, so its spread is inf - inf
+        let points: Vec<[f64; 2]> = (0..1_000).map(|i| [i as f64, f64::INFINITY]).collect();
+
+        let tree: ImmutableKdTree<f64, u32, 2, 32> = ImmutableKdTreeBuilder::new()
+            .split_axis(SplitAxis::WidestSpread)
+            .build_from_slice(&points);
+
+        assert_eq!(tree.size(), 1_000);
+        assert!(tree.split_dims.iter().all(|&dim| dim == 0));
+    }
+
+    #[test]
+    fn leaf_fill_target_sets_leaf_count() {
+        let points = anisotropic_points(10_000, 7);
+
+        let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTreeBuilder::new()
+            .leaf_fill_target(8)
+            .build_from_slice(&points);
+
+        assert_eq!(tree.leaf_fill_target(), 8);
+        assert_eq!(
+            tree.leaf_extents.len(),
+            10_000usize.div_ceil(8).next_power_of_two()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "leaf_fill_target must be non-zero")]
+    fn leaf_fill_target_of_zero_panics() {
+        let _ = ImmutableKdTreeBuilder::new().leaf_fill_target(0);
+    }
+
+    #[test]
+    fn every_strategy_gives_correct_query_results() {
+        let points = anisotropic_points(20_000, 11);
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(13);
+        let query_points: Vec<[f64; 3]> = (0..100)
+            .map(|_| {
+                [
+                    rng.gen_range(0.0..2000.0),
+                    rng.gen_range(0.0..4.0),
+                    rng.gen_range(0.0..2000.0),
+                ]
+            })
+            .collect();
+        let radius = 400.0;
+
+        for (pivot_strategy, split_axis) in ALL_OPTIONS {
+            let tree: ImmutableKdTree<f64, u32, 3, 16> = ImmutableKdTreeBuilder::new()
+                .pivot_strategy(pivot_strategy)
+                .split_axis(split_axis)
+                .build_from_slice(&points);
+
+            assert_eq!(tree.size(), points.len());
+            assert_eq!(tree.pivot_strategy(), pivot_strategy);
+
+            for query_point in &query_points {
+                let mut expected: Vec<f64> = points
+                    .iter()
+                    .map(|p| SquaredEuclidean::dist(query_point, p))
+                    .collect();
+                expected.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+                let expected_within: Vec<f64> = expected
+                    .iter()
+                    .copied()
+                    .take_while(|&d| d < radius)
+                    .collect();
+
+                assert_eq!(
+                    tree.nearest_one::<SquaredEuclidean>(query_point).distance,
+                    expected[0],
+                    "{pivot_strategy:?} / {split_axis:?}"
+                );
+
+                let nearest_n: Vec<f64> = tree
+                    .nearest_n_within::<SquaredEuclidean>(
+                        query_point,
+                        radius,
+                        NonZero::new(10).unwrap(),
+                        true,
+                    )
+                    .iter()
+                    .map(|nn| nn.distance)
+                    .collect();
+                assert_eq!(
+                    nearest_n,
+                    expected_within.iter().copied().take(10).collect::<Vec<_>>(),
+                    "{pivot_strategy:?} / {split_axis:?}"
+                );
+
+                let within: Vec<f64> = tree
+                    .within::<SquaredEuclidean>(query_point, radius)
+                    .iter()
+                    .map(|nn| nn.distance)
+                    .collect();
+                assert_eq!(
+                    within, expected_within,
+                    "{pivot_strategy:?} / {split_axis:?}"
+                );
+
+                let best_n = tree.best_n_within::<SquaredEuclidean>(
+                    query_point,
+                    radius,
+                    NonZero::new(5).unwrap(),
+                );
+                assert_eq!(
+                    best_n.count(),
+                    expected_within.len().min(5),
+                    "{pivot_strategy:?} / {split_axis:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rebuilds_keep_the_builder_options() {
+        let mut points = anisotropic_points(10_000, 17);
+
+        let mut tree: ImmutableKdTree<f64, u32, 3, 16> = ImmutableKdTreeBuilder::new()
+            .pivot_strategy(PivotStrategy::SurfaceAreaHeuristic)
+            .split_axis(SplitAxis::WidestSpread)
+            .build_from_slice(&points);
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(19);
+        let changed: Vec<usize> = (0..200).map(|_| rng.gen_range(0..points.len())).collect();
+        for &idx in &changed {
+            points[idx] = [
+                rng.gen_range(0.0..2000.0),
+                rng.gen_range(0.0..4.0),
+                rng.gen_range(0.0..2000.0),
+            ];
+        }
+        tree.rebuild_incremental(&points, &changed);
+
+        assert_eq!(tree.pivot_strategy(), PivotStrategy::SurfaceAreaHeuristic);
+        assert_eq!(tree.split_axis(), SplitAxis::WidestSpread);
+        for _ in 0..100 {
+            let query_point = [
+                rng.gen_range(0.0..2000.0),
+                rng.gen_range(0.0..4.0),
+                rng.gen_range(0.0..2000.0),
+            ];
+            let expected = points
+                .iter()
+                .map(|p| SquaredEuclidean::dist(&query_point, p))
+                .fold(f64::INFINITY, f64::min);
+
+            assert_eq!(
+                tree.nearest_one::<SquaredEuclidean>(&query_point).distance,
+                expected
+            );
+        }
+
+        tree.rebuild_from_slice(&points);
+        assert_eq!(
+            tree,
+            ImmutableKdTreeBuilder::new()
+                .pivot_strategy(PivotStrategy::SurfaceAreaHeuristic)
+                .split_axis(SplitAxis::WidestSpread)
+                .build_from_slice(&points)
+        );
+    }
+
+    /// tight clusters of points separated by empty space
+    fn clustered_points(count: usize, seed: u64) -> Vec<[f64; 3]> {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+        let centres: Vec<[f64; 3]> = (0..20)
+            .map(|_| array_init::array_init(|_| rng.gen_range(0.0..1000.0)))
+            .collect();
+        (0..count)
+            .map(|i| {
+                let centre = centres[i % centres.len()];
+                array_init::array_init(|dim| centre[dim] + rng.gen_range(-5.0..5.0))
+            })
+            .collect()
+    }
+
+    fn assert_leaf_bounds_contain_leaf_points<const B: usize>(
+        tree: &ImmutableKdTree<f64, u32, 3, B>,
+    ) {
+        assert_eq!(tree.leaf_bounds.len(), tree.leaf_extents.len() * 2 * 3);
+        for (leaf_idx, &(start, end)) in tree.leaf_extents.iter().enumerate() {
+            let (mins, maxes) = tree.leaf_bounds[leaf_idx * 6..(leaf_idx + 1) * 6].split_at(3);
+            for dim in 0..3 {
+                let values = &tree.leaf_points[dim][start as usize..end as usize];
+                assert!(values.iter().all(|&v| mins[dim] <= v && v <= maxes[dim]));
+                if !values.is_empty() {
+                    assert!(values.contains(&mins[dim]) && values.contains(&maxes[dim]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn leaf_bounding_boxes_contain_each_leafs_points() {
+        for count in [0, 10, 10_000] {
+            let points = clustered_points(count, 29);
+
+            let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTreeBuilder::new()
+                .leaf_bounding_boxes(true)
+                .build_from_slice(&points);
+
+            assert!(tree.has_leaf_bounding_boxes());
+            assert_leaf_bounds_contain_leaf_points(&tree);
+        }
+
+        let tree: ImmutableKdTree<f64, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&clustered_points(1_000, 29));
+        assert!(!tree.has_leaf_bounding_boxes());
+        assert!(tree.leaf_bounds.is_empty());
+    }
+
+    #[test]
+    fn leaf_bounding_boxes_give_the_same_query_results() {
+        let points = clustered_points(20_000, 31);
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(37);
+        let query_points: Vec<[f64; 3]> = (0..200)
+            .map(|_| array_init::array_init(|_| rng.gen_range(0.0..1000.0)))
+            .collect();
+
+        let plain: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+        let bounded: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTreeBuilder::new()
+            .leaf_bounding_boxes(true)
+            .build_from_slice(&points);
+
+        for query_point in &query_points {
+            assert_eq!(
+                bounded
+                    .nearest_one::<SquaredEuclidean>(query_point)
+                    .distance,
+                plain.nearest_one::<SquaredEuclidean>(query_point).distance
+            );
+
+            let distances = |results: Vec<crate::NearestNeighbour<f64, u32>>| -> Vec<f64> {
+                results.iter().map(|nn| nn.distance).collect()
+            };
+            let max_qty = NonZero::new(10).unwrap();
+            assert_eq!(
+                distances(bounded.nearest_n::<SquaredEuclidean>(query_point, max_qty)),
+                distances(plain.nearest_n::<SquaredEuclidean>(query_point, max_qty))
+            );
+            assert_eq!(
+                distances(bounded.within::<SquaredEuclidean>(query_point, 2_500.0)),
+                distances(plain.within::<SquaredEuclidean>(query_point, 2_500.0))
+            );
+            assert_eq!(
+                bounded
+                    .best_n_within::<SquaredEuclidean>(query_point, 2_500.0, max_qty)
+                    .count(),
+                plain
+                    .best_n_within::<SquaredEuclidean>(query_point, 2_500.0, max_qty)
+                    .count()
+            );
+        }
+    }
+
+    #[test]
+    fn incremental_rebuilds_update_leaf_bounding_boxes() {
+        let mut points = clustered_points(10_000, 41);
+
+        let mut tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTreeBuilder::new()
+            .leaf_bounding_boxes(true)
+            .build_from_slice(&points);
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(43);
+        let changed: Vec<usize> = (0..200).map(|_| rng.gen_range(0..points.len())).collect();
+        for &idx in &changed {
+            points[idx] =
+                array_init::array_init(|dim| points[idx][dim] + rng.gen_range(-20.0..20.0));
+        }
+        tree.rebuild_incremental(&points, &changed);
+
+        assert_leaf_bounds_contain_leaf_points(&tree);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_build_is_identical_to_sequential_build() {
+        let points = anisotropic_points(50_000, 23);
+
+        for (pivot_strategy, split_axis) in ALL_OPTIONS {
+            let builder = ImmutableKdTreeBuilder::new()
+                .pivot_strategy(pivot_strategy)
+                .split_axis(split_axis)
+                .leaf_bounding_boxes(true);
+
+            let sequential: ImmutableKdTree<f64, u32, 3, 32> = builder.build_from_slice(&points);
+            let parallel: ImmutableKdTree<f64, u32, 3, 32> = builder.par_build_from_slice(&points);
+
+            assert_eq!(sequential, parallel);
+        }
+    }
+}
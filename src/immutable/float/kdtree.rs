@@ -13,9 +13,16 @@
 
 pub use crate::float::kdtree::Axis;
 use crate::float_leaf_slice::leaf_slice::{LeafSlice, LeafSliceFloat, LeafSliceFloatChunk};
+use crate::generate_immutable_visit_leaves;
+use crate::immutable::float::builder::{BuildOptions, PivotStrategy, SplitAxis};
+use crate::modified_van_emde_boas::log2_items_per_cache_line;
 #[cfg(feature = "modified_van_emde_boas")]
-use crate::modified_van_emde_boas::modified_van_emde_boas_get_child_idx_v2_branchless;
-use crate::traits::Content;
+use crate::modified_van_emde_boas::{
+    modified_van_emde_boas_get_child_idx_v2, modified_van_emde_boas_get_child_idx_v2_branchless,
+    modified_van_emde_boas_stem_count,
+};
+use crate::stats::{MemoryUsage, TreeStats, TreeStatsBuilder};
+use crate::traits::{Content, DistanceMetric};
 use aligned_vec::{avec, AVec, ConstAlign, CACHELINE_ALIGN};
 use array_init::array_init;
 use az::{Az, Cast};
@@ -27,6 +34,48 @@ use rkyv::vec::ArchivedVec;
 use serde::{Deserialize, Serialize};
 use std::cmp::PartialEq;
 use std::fmt::Debug;
+use std::mem::size_of;
+use std::ops::Range;
+
+/// Default maximum proportion of the points in a tree that may change before
+/// [`ImmutableKdTree::rebuild_incremental`] falls back to a full rebuild
+pub const DEFAULT_MAX_INCREMENTAL_CHURN: f64 = 0.1;
+
+/// Returns the minimum distance between `query` and any point within `bounds`, which
+/// holds the `K` minimum values of a bounding box followed by its `K` maximum values
+#[inline]
+pub(crate) fn leaf_bounds_dist<A: Axis, D: DistanceMetric<A, K>, const K: usize>(
+    query: &[A; K],
+    bounds: &[A],
+) -> A {
+    let (mins, maxes) = bounds.split_at(K);
+
+    (0..K).fold(A::zero(), |dist, dim| {
+        let nearest = query[dim].max(mins[dim]).min(maxes[dim]);
+        Axis::rd_update(dist, D::dist1(query[dim], nearest))
+    })
+}
+
+/// Summarises the leaves of an immutable tree. Every stem level is fully populated,
+/// so all of the leaves are at the same depth and there is one less stem than leaf.
+pub(crate) fn immutable_tree_stats<'a, A: Copy + PartialOrd + 'a, T: 'a, const K: usize>(
+    leaves: impl ExactSizeIterator<Item = ([&'a [A]; K], &'a [T])>,
+    max_stem_level: i32,
+    memory: MemoryUsage,
+) -> TreeStats {
+    let depth = (max_stem_level + 1).max(0) as usize;
+    let leaf_count = leaves.len();
+
+    let mut builder = TreeStatsBuilder::<A, K>::new();
+    for (points, items) in leaves {
+        builder.add_leaf(
+            depth,
+            (0..items.len()).map(|idx| array_init(|dim| points[dim][idx])),
+        );
+    }
+
+    builder.finish(leaf_count.saturating_sub(1), memory)
+}
 
 /// Immutable floating point k-d tree
 ///
@@ -61,6 +110,69 @@ pub struct ImmutableKdTree<A: Copy + Default, T: Copy + Default, const K: usize,
     pub(crate) leaf_items: Vec<T>,
     pub(crate) leaf_extents: Vec<(u32, u32)>,
     pub(crate) max_stem_level: i32,
+
+    /// split dimension of each stem, indexed in the same way as `stems`. Empty unless the
+    /// tree was built with [`SplitAxis::WidestSpread`], in which case the split dimensions
+    /// don't simply cycle through the axes level by level
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) split_dims: Vec<u8>,
+
+    /// bounding box of each leaf, stored as the `K` minimum values followed by the `K`
+    /// maximum values. Empty unless the tree was built with leaf bounding boxes enabled
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) leaf_bounds: Vec<A>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) options: BuildOptions,
+
+    /// true if the tree was last built from points and items, by
+    /// [`ImmutableKdTree::rebuild_from_points_and_items`] or its parallel equivalent, in
+    /// which case its items are not indices into a source slice
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) built_from_points_and_items: bool,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) scratch: RebuildScratch,
+}
+
+/// Scratch space that is retained between calls to [`ImmutableKdTree::rebuild_from_slice`],
+/// so that once capacity has stabilised, rebuilding a tree does not need to allocate.
+///
+/// This is not part of the logical content of the tree: it is ignored by `PartialEq`,
+/// not serialized, and not carried across by `Clone`.
+#[derive(Default)]
+pub(crate) struct RebuildScratch {
+    pub(crate) sort_index: Vec<usize>,
+
+    /// true if the tree was last built by [`ImmutableKdTree::rebuild_from_slice`], in which
+    /// case each item is its point's index within the source, and `sort_index` holds the
+    /// source indices in leaf order
+    pub(crate) indexed_by_source: bool,
+
+    /// position of each source index within the leaf arrays, used by
+    /// [`ImmutableKdTree::rebuild_incremental`]. Populated on first use after a full rebuild
+    pub(crate) source_positions: Vec<u32>,
+
+    /// (first leaf, leaf count) of each subtree that needs repartitioning
+    /// during an incremental rebuild
+    pub(crate) dirty_subtrees: Vec<(usize, usize)>,
+}
+
+impl Clone for RebuildScratch {
+    fn clone(&self) -> Self {
+        RebuildScratch::default()
+    }
+}
+
+impl PartialEq for RebuildScratch {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Debug for RebuildScratch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RebuildScratch").finish_non_exhaustive()
+    }
 }
 
 /// rkyv-Archivable / Serializable version of an [`ImmutableKdTree`].
@@ -76,6 +188,8 @@ pub struct ImmutableKdTreeRK<A: Copy + Default, T: Copy + Default, const K: usiz
     pub(crate) leaf_items: Vec<T>,
     pub(crate) leaf_extents: Vec<(u32, u32)>,
     pub(crate) max_stem_level: i32,
+    pub(crate) split_dims: Vec<u8>,
+    pub(crate) leaf_bounds: Vec<A>,
 }
 
 #[cfg(feature = "rkyv")]
@@ -113,6 +227,9 @@ where
             leaf_items,
             leaf_extents,
             max_stem_level,
+            split_dims,
+            leaf_bounds,
+            ..
         } = orig;
 
         let (ptr, _, length, capacity) = stems.into_raw_parts();
@@ -124,6 +241,8 @@ where
             leaf_items,
             leaf_extents,
             max_stem_level,
+            split_dims,
+            leaf_bounds,
         }
     }
 }
@@ -148,6 +267,8 @@ pub struct AlignedArchivedImmutableKdTree<
     pub(crate) leaf_items: &'a ArchivedVec<T>,
     pub(crate) leaf_extents: &'a ArchivedVec<(u32, u32)>,
     pub(crate) max_stem_level: i32,
+    pub(crate) split_dims: &'a ArchivedVec<u8>,
+    pub(crate) leaf_bounds: &'a ArchivedVec<A>,
 }
 
 #[cfg(feature = "rkyv")]
@@ -168,6 +289,8 @@ impl<
             leaf_extents: &value.leaf_extents,
             leaf_items: &value.leaf_items,
             max_stem_level: value.max_stem_level,
+            split_dims: &value.split_dims,
+            leaf_bounds: &value.leaf_bounds,
         }
     }
 
@@ -196,9 +319,47 @@ where
         self.leaf_items.len()
     }
 
+    /// Iterate over all `(item, point)` tuples, in the order that they are stored in the tree.
+    /// See [`ImmutableKdTree::iter`].
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (T, [A; K])> + '_ {
+        self.leaf_items
+            .iter()
+            .enumerate()
+            .map(|(idx, &item)| (item, array_init(|dim| self.leaf_points[dim][idx])))
+    }
+
+    /// Iterate over the leaves of the tree, in order. See [`ImmutableKdTree::leaves`].
+    pub fn leaves(&self) -> impl ExactSizeIterator<Item = ([&[A]; K], &[T])> + '_ {
+        self.leaf_extents.iter().map(|&(start, end)| {
+            let (start, end) = (start as usize, end as usize);
+            (
+                array_init(|dim| &self.leaf_points[dim][start..end]),
+                &self.leaf_items[start..end],
+            )
+        })
+    }
+
+    /// Returns statistics describing the shape, fill level and memory usage of the tree.
+    /// See [`ImmutableKdTree::stats`].
+    pub fn stats(&self) -> TreeStats {
+        immutable_tree_stats(
+            self.leaves(),
+            self.max_stem_level,
+            MemoryUsage {
+                stems: self.stems.len() * size_of::<A>(),
+                leaf_points: self.leaf_points.iter().map(|vec| vec.len()).sum::<usize>()
+                    * size_of::<A>(),
+                leaf_items: self.leaf_items.len() * size_of::<T>(),
+                other: self.leaf_extents.len() * size_of::<(u32, u32)>()
+                    + self.split_dims.len()
+                    + self.leaf_bounds.len() * size_of::<A>(),
+            },
+        )
+    }
+
     /// Returns a LeafSlice for a given leaf index
     #[inline]
-    pub(crate) fn get_leaf_slice(&self, leaf_idx: usize) -> LeafSlice<A, T, K> {
+    pub(crate) fn get_leaf_slice(&self, leaf_idx: usize) -> LeafSlice<'_, A, T, K> {
         let (start, end) = unsafe { *self.leaf_extents.get_unchecked(leaf_idx) };
 
         // Artificially extend size to be at least chunk length for faster processing
@@ -210,6 +371,44 @@ where
             &self.leaf_items[start as usize..end as usize],
         )
     }
+
+    /// Returns true if every point in the leaf is further than `dist` from `query`,
+    /// according to the leaf's bounding box. Always false if the tree has no leaf bounding boxes.
+    #[inline]
+    pub(crate) fn leaf_is_beyond<D>(&self, query: &[A; K], leaf_idx: usize, dist: A) -> bool
+    where
+        D: DistanceMetric<A, K>,
+    {
+        !self.leaf_bounds.is_empty()
+            && leaf_bounds_dist::<A, D, K>(
+                query,
+                &self.leaf_bounds[leaf_idx * 2 * K..(leaf_idx + 1) * 2 * K],
+            ) > dist
+    }
+
+    /// Returns the dimension that the stem at `stem_idx` splits on. `split_dim` is the
+    /// dimension that it splits on if the tree cycles through the dimensions in turn.
+    #[inline]
+    pub(crate) fn stem_split_dim(&self, stem_idx: usize, split_dim: usize) -> usize {
+        if self.split_dims.is_empty() {
+            split_dim
+        } else {
+            *unsafe { self.split_dims.get_unchecked(stem_idx) } as usize
+        }
+    }
+
+    /// Returns the minimum and maximum corners of the bounding box of the leaf
+    /// at `leaf_idx`, or `None` if the tree has no leaf bounding boxes.
+    #[inline]
+    pub(crate) fn leaf_bounding_box(&self, leaf_idx: usize) -> Option<(&[A], &[A])> {
+        if self.leaf_bounds.is_empty() {
+            None
+        } else {
+            Some(self.leaf_bounds[leaf_idx * 2 * K..(leaf_idx + 1) * 2 * K].split_at(K))
+        }
+    }
+
+    generate_immutable_visit_leaves!();
 }
 
 impl<A: Axis, T: Content, const K: usize, const B: usize> From<&[[A; K]]>
@@ -240,6 +439,32 @@ where
     }
 }
 
+impl<A: Axis, T: Content, const K: usize, const B: usize> FromIterator<([A; K], T)>
+    for ImmutableKdTree<A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+    T: Content,
+    usize: Cast<T>,
+{
+    /// Creates an `ImmutableKdTree`, balanced and optimized, populated
+    /// with the `(point, item)` pairs from `iter`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    ///
+    /// let entries = vec!(([1.0f64, 2.0f64, 3.0f64], 1001u64), ([4.0f64, 5.0f64, 6.0f64], 2002u64));
+    /// let tree: ImmutableKdTree<f64, u64, 3, 32> = entries.into_iter().collect();
+    ///
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = ([A; K], T)>>(iter: I) -> Self {
+        let (points, items): (Vec<[A; K]>, Vec<T>) = iter.into_iter().unzip();
+        ImmutableKdTree::new_from_points_and_items(&points, &items)
+    }
+}
+
 // prevent clippy complaining that the feature unreliable_select_nth_unstable
 // is not defined (I don't want to explicitly define it as if I do then
 // passing --all-features in CI will enable it, which I don't want to do
@@ -250,7 +475,6 @@ where
     T: Content,
     usize: Cast<T>,
 {
-
     /// Creates an `ImmutableKdTree`, balanced and optimized, populated
     /// with items from `source`.
     ///
@@ -272,186 +496,408 @@ where
     where
         usize: Cast<T>,
     {
-        let mut result = Self {
+        let mut result = Self::new_empty();
+        result.rebuild_from_slice(source);
+        result
+    }
+
+    pub(crate) fn new_empty() -> Self {
+        Self {
             stems: avec![],
             leaf_points: array_init(|_| Vec::new()),
             leaf_items: Vec::new(),
             leaf_extents: Vec::new(),
             max_stem_level: 0,
-        };
+            split_dims: Vec::new(),
+            leaf_bounds: Vec::new(),
+            options: BuildOptions::default(),
+            built_from_points_and_items: false,
+            scratch: RebuildScratch::default(),
+        }
+    }
 
-        result.rebuild_from_slice(source);
-        result
+    /// Returns how the value that each stem splits its points at was chosen
+    /// when this tree was built. See [`ImmutableKdTreeBuilder`](`crate::immutable::float::builder::ImmutableKdTreeBuilder`).
+    #[inline]
+    pub fn pivot_strategy(&self) -> PivotStrategy {
+        self.options.pivot_strategy
+    }
+
+    /// Returns how the axis that each stem splits its points along was chosen
+    /// when this tree was built. See [`ImmutableKdTreeBuilder`](`crate::immutable::float::builder::ImmutableKdTreeBuilder`).
+    #[inline]
+    pub fn split_axis(&self) -> SplitAxis {
+        self.options.split_axis
+    }
+
+    /// Returns the number of items that each leaf of this tree was sized to hold
+    #[inline]
+    pub fn leaf_fill_target(&self) -> usize {
+        self.options.leaf_fill_target::<B>()
+    }
+
+    /// Returns true if this tree stores a bounding box for each leaf
+    #[inline]
+    pub fn has_leaf_bounding_boxes(&self) -> bool {
+        self.options.leaf_bounding_boxes
     }
 
     /// Helper function added by opencraft team that re-uses the existing buffer.
+    ///
+    /// All of the buffers used during construction, including the sort index, are retained
+    /// by the tree. Once their capacity has grown to fit the largest `source` seen so far,
+    /// subsequent rebuilds perform no heap allocations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    ///
+    /// let mut points: Vec<[f64; 3]> = vec!([1.0f64, 2.0f64, 3.0f64]);
+    /// let mut tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// points.push([4.0f64, 5.0f64, 6.0f64]);
+    /// tree.rebuild_from_slice(&points);
+    ///
+    /// assert_eq!(tree.size(), 2);
+    /// ```
     #[inline]
     pub fn rebuild_from_slice(&mut self, source: &[[A; K]])
     where
         usize: Cast<T>,
     {
-        let item_count = source.len();
-        let leaf_node_count = item_count.div_ceil(B);
+        self.rebuild_with_items(source, |idx| idx.az::<T>());
+        self.scratch.indexed_by_source = true;
+        self.built_from_points_and_items = false;
+    }
+
+    /// Helper function added by opencraft team for updating a tree when only
+    /// a small proportion of the points in `source` have moved since the last build.
+    ///
+    /// `changed_indices` must contain the index of every point in `source` that has
+    /// changed since the tree was last built. The previous build's leaf assignment is
+    /// re-used, and only the subtrees that points have moved between are repartitioned.
+    ///
+    /// Falls back to a full [`ImmutableKdTree::rebuild_from_slice`] if the tree was not last
+    /// built from a slice of the same length, or if more than
+    /// [`DEFAULT_MAX_INCREMENTAL_CHURN`] of the points have changed. Use
+    /// [`ImmutableKdTree::rebuild_incremental_with_max_churn`] to configure this threshold.
+    ///
+    /// Query results are the same as those from a tree built with
+    /// [`ImmutableKdTree::new_from_slice`] over the same `source`, although the
+    /// internal layout of the two trees may differ.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of `changed_indices` is not an index into `source`, or if the tree was
+    /// last built from points and items, since the items of such a tree are not indices into
+    /// `source`, and neither an incremental nor a full rebuild from `source` could keep them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    /// use kiddo::SquaredEuclidean;
+    ///
+    /// let mut points: Vec<[f64; 2]> = (0..1000).map(|i| [i as f64, (i % 10) as f64]).collect();
+    /// let mut tree: ImmutableKdTree<f64, u32, 2, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// points[5] = [500.5, 5.0];
+    /// tree.rebuild_incremental(&points, &[5]);
+    ///
+    /// assert_eq!(tree.nearest_one::<SquaredEuclidean>(&[500.6, 5.0]).item, 5);
+    /// ```
+    #[inline]
+    pub fn rebuild_incremental(&mut self, source: &[[A; K]], changed_indices: &[usize]) {
+        self.rebuild_incremental_with_max_churn(
+            source,
+            changed_indices,
+            DEFAULT_MAX_INCREMENTAL_CHURN,
+        );
+    }
+
+    /// As [`ImmutableKdTree::rebuild_incremental`], but falls back to a full rebuild
+    /// if more than `max_churn` (a proportion between 0.0 and 1.0) of the points
+    /// in `source` have changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`ImmutableKdTree::rebuild_incremental`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    /// use kiddo::SquaredEuclidean;
+    ///
+    /// let mut points: Vec<[f64; 2]> = (0..1000).map(|i| [i as f64, (i % 10) as f64]).collect();
+    /// let mut tree: ImmutableKdTree<f64, u32, 2, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// let changed: Vec<usize> = (0..300).collect();
+    /// changed.iter().for_each(|&i| points[i][1] += 0.5);
+    /// tree.rebuild_incremental_with_max_churn(&points, &changed, 0.5);
+    ///
+    /// assert_eq!(tree.nearest_one::<SquaredEuclidean>(&[0.0, 0.5]).item, 0);
+    /// ```
+    pub fn rebuild_incremental_with_max_churn(
+        &mut self,
+        source: &[[A; K]],
+        changed_indices: &[usize],
+        max_churn: f64,
+    ) {
+        assert!(
+            !self.built_from_points_and_items,
+            "trees built from points and items can't be rebuilt from a slice of points"
+        );
+        if let Some(&idx) = changed_indices.iter().find(|&&idx| idx >= source.len()) {
+            panic!(
+                "changed index {idx} is out of range for a source of length {}",
+                source.len()
+            );
+        }
 
+        let can_rebuild_incrementally = self.scratch.indexed_by_source
+            && !self.stems.is_empty()
+            && self.size() == source.len()
+            && changed_indices.len() as f64 <= max_churn * source.len() as f64;
+
+        if !can_rebuild_incrementally {
+            self.rebuild_from_slice(source);
+            return;
+        }
+
+        if self.scratch.source_positions.len() != source.len() {
+            self.scratch.source_positions.clear();
+            self.scratch.source_positions.resize(source.len(), 0);
+            for (pos, &idx) in self.scratch.sort_index.iter().enumerate() {
+                self.scratch.source_positions[idx] = pos as u32;
+            }
+        }
+
+        // find the smallest subtree containing both the old and the new leaf
+        // of each point that has moved across a split plane
+        self.scratch.dirty_subtrees.clear();
+        for &idx in changed_indices {
+            let pos = self.scratch.source_positions[idx] as usize;
+            let old_leaf_idx = self
+                .leaf_extents
+                .partition_point(|&(_, end)| end as usize <= pos);
+            let new_leaf_idx = self.leaf_idx_for_point(&source[idx]);
+
+            if old_leaf_idx != new_leaf_idx {
+                let subtree_levels = (old_leaf_idx ^ new_leaf_idx).ilog2() + 1;
+                let first_leaf_idx = (old_leaf_idx >> subtree_levels) << subtree_levels;
+                self.scratch
+                    .dirty_subtrees
+                    .push((first_leaf_idx, 1 << subtree_levels));
+            }
+        }
+
+        // subtrees are either nested or disjoint, so after sorting by first leaf
+        // and then largest first, any subtree inside the previous one can be skipped
+        self.scratch
+            .dirty_subtrees
+            .sort_unstable_by_key(|&(first_leaf_idx, leaf_count)| {
+                (first_leaf_idx, usize::MAX - leaf_count)
+            });
+        let mut repartitioned_up_to = 0;
+        for subtree_idx in 0..self.scratch.dirty_subtrees.len() {
+            let (first_leaf_idx, leaf_count) = self.scratch.dirty_subtrees[subtree_idx];
+            if first_leaf_idx < repartitioned_up_to {
+                continue;
+            }
+            self.repartition_subtree(source, first_leaf_idx, leaf_count);
+            repartitioned_up_to = first_leaf_idx + leaf_count;
+        }
+
+        // points that stayed in the same leaf just need their co-ordinates updating
+        for &idx in changed_indices {
+            let pos = self.scratch.source_positions[idx] as usize;
+            (0..K).for_each(|dim| self.leaf_points[dim][pos] = source[idx][dim]);
+        }
+
+        if !self.leaf_bounds.is_empty() {
+            for subtree_idx in 0..self.scratch.dirty_subtrees.len() {
+                let (first_leaf_idx, leaf_count) = self.scratch.dirty_subtrees[subtree_idx];
+                self.update_leaf_bounds(first_leaf_idx..first_leaf_idx + leaf_count);
+            }
+            for &idx in changed_indices {
+                let pos = self.scratch.source_positions[idx] as usize;
+                let leaf_idx = self
+                    .leaf_extents
+                    .partition_point(|&(_, end)| end as usize <= pos);
+                self.update_leaf_bounds(leaf_idx..leaf_idx + 1);
+            }
+        }
+    }
+
+    /// Returns the index of the leaf that `point` falls within
+    fn leaf_idx_for_point(&self, point: &[A; K]) -> usize {
         #[cfg(not(feature = "modified_van_emde_boas"))]
-        let stem_node_count = if leaf_node_count < 2 {
-            0
-        } else {
-            leaf_node_count.next_power_of_two()
-        };
+        let mut stem_idx = 1;
+        #[cfg(feature = "modified_van_emde_boas")]
+        let mut stem_idx = 0;
+
+        let mut leaf_idx = 0;
+        for level in 0..=self.max_stem_level as usize {
+            let split_dim = if self.split_dims.is_empty() {
+                level % K
+            } else {
+                self.split_dims[stem_idx] as usize
+            };
+            let is_right_child = point[split_dim] >= self.stems[stem_idx];
+            leaf_idx = (leaf_idx << 1) + usize::from(is_right_child);
+            stem_idx = Self::child_stem_idx(stem_idx, is_right_child, level);
+        }
 
+        leaf_idx
+    }
+
+    fn child_stem_idx(stem_idx: usize, is_right_child: bool, level: usize) -> usize {
         #[cfg(feature = "modified_van_emde_boas")]
-        let stem_node_count = if leaf_node_count < 2 {
-            0
-        } else {
-            leaf_node_count.next_power_of_two() - 1
-        };
+        return modified_van_emde_boas_get_child_idx_v2::<A>(
+            stem_idx as u32,
+            is_right_child,
+            level as u32,
+        ) as usize;
 
-        let max_stem_level: i32 = leaf_node_count.next_power_of_two().ilog2() as i32 - 1;
+        #[cfg(not(feature = "modified_van_emde_boas"))]
+        {
+            let _ = level;
+            (stem_idx << 1) + usize::from(is_right_child)
+        }
+    }
+
+    /// Re-selects the pivots of the subtree whose leaves are the `leaf_count` leaves
+    /// starting at `first_leaf_idx`, and rewrites the contents of those leaves.
+    /// The subtree's points all sit in one contiguous range of the leaf arrays,
+    /// so none of the tree outside of the subtree is affected.
+    fn repartition_subtree(&mut self, source: &[[A; K]], first_leaf_idx: usize, leaf_count: usize) {
+        let leaf_levels = self.max_stem_level as u32 + 1;
+        let subtree_level = leaf_levels - leaf_count.ilog2();
+        let subtree_idx = first_leaf_idx >> leaf_count.ilog2();
 
-        // TODO: It would be nice to be able to determine the exact required length up-front.
-        //  Instead, we just trim the stems afterwards by traversing right-child non-inf nodes
-        //  till we hit max level to get the max used stem
+        #[cfg(not(feature = "modified_van_emde_boas"))]
+        let mut stem_idx = 1;
         #[cfg(feature = "modified_van_emde_boas")]
-        let stem_node_count = stem_node_count * 5;
+        let mut stem_idx = 0;
 
-        // Clear old data
-        self.stems.clear();
-        self.leaf_points.iter_mut().for_each(|vec| vec.clear());
-        self.leaf_items.clear();
-        self.leaf_extents.clear();
+        for level in 0..subtree_level {
+            let is_right_child = (subtree_idx >> (subtree_level - 1 - level)) & 1 == 1;
+            stem_idx = Self::child_stem_idx(stem_idx, is_right_child, level as usize);
+        }
 
-        // reserve enough space in advance for new data.
-        self.stems.resize(stem_node_count, A::infinity());
-        self.leaf_points.iter_mut().for_each(|vec| vec.reserve(item_count));
-        self.leaf_items.reserve(item_count);
-        self.leaf_extents.reserve(item_count);
-        self.max_stem_level = max_stem_level;
+        Self::clear_stems_recursive(
+            &mut self.stems,
+            stem_idx,
+            subtree_level as usize,
+            self.max_stem_level as usize,
+        );
 
-        let mut stems = &mut self.stems;
-        let mut leaf_points = &mut self.leaf_points;
-        let mut leaf_items = &mut self.leaf_items;
-        let mut leaf_extents = &mut self.leaf_extents;
+        let start = self.leaf_extents[first_leaf_idx].0 as usize;
+        let end = self.leaf_extents[first_leaf_idx + leaf_count - 1].1 as usize;
 
-        let mut sort_index = Vec::from_iter(0..item_count);
+        Self::partition_recursive(
+            &mut SlicePartitionWriter {
+                stems: &mut self.stems,
+                split_dims: &mut self.split_dims,
+                leaf_extents: &mut self.leaf_extents,
+            },
+            subtree_level as usize % K,
+            source,
+            &mut self.scratch.sort_index[start..end],
+            start,
+            stem_idx,
+            subtree_level as i32,
+            u64::from(subtree_level % log2_items_per_cache_line::<A>()),
+            self.max_stem_level,
+            subtree_idx,
+            self.options,
+        );
 
-        if stem_node_count == 0 {
-            // Write leaf and terminate recursion
-            leaf_extents.push((0u32, sort_index.len() as u32));
+        for pos in start..end {
+            let idx = self.scratch.sort_index[pos];
+            (0..K).for_each(|dim| self.leaf_points[dim][pos] = source[idx][dim]);
+            self.leaf_items[pos] = idx.az::<T>();
+            self.scratch.source_positions[idx] = pos as u32;
+        }
+    }
 
-            (0..sort_index.len()).for_each(|i| {
-                (0..K).for_each(|dim| leaf_points[dim].push(source[sort_index[i]][dim]));
-                leaf_items.push(sort_index[i].az::<T>())
-            });
-        } else {
-            #[cfg(not(feature = "modified_van_emde_boas"))]
-            let initial_stem_idx = 1;
-            #[cfg(feature = "modified_van_emde_boas")]
-            let initial_stem_idx = 0;
-
-            Self::populate_recursive(
-                &mut stems,
-                0,
-                source,
-                &mut sort_index,
-                initial_stem_idx,
-                0,
-                0,
+    fn clear_stems_recursive(
+        stems: &mut AVec<A, ConstAlign<{ CACHELINE_ALIGN }>>,
+        stem_idx: usize,
+        level: usize,
+        max_stem_level: usize,
+    ) {
+        if level > max_stem_level {
+            return;
+        }
+
+        stems[stem_idx] = A::infinity();
+
+        for is_right_child in [false, true] {
+            Self::clear_stems_recursive(
+                stems,
+                Self::child_stem_idx(stem_idx, is_right_child, level),
+                level + 1,
                 max_stem_level,
-                leaf_node_count * B,
-                &mut leaf_points,
-                &mut leaf_items,
-                &mut leaf_extents,
             );
-
-            // trim unneeded stems
-            #[cfg(feature = "modified_van_emde_boas")]
-            if !stems.is_empty() {
-                let mut level: usize = 0;
-                let mut minor_level: u64 = 0;
-                let mut stem_idx = 0;
-                loop {
-                    let val = stems[stem_idx];
-                    let is_right_child = val.is_finite();
-                    stem_idx = modified_van_emde_boas_get_child_idx_v2_branchless(
-                        stem_idx as u32,
-                        is_right_child,
-                        minor_level as u32,
-                    ) as usize;
-                    level += 1;
-                    minor_level += 1;
-                    minor_level.cmovnz(&0, u8::from(minor_level == 3));
-                    if level == max_stem_level as usize {
-                        break;
-                    }
-                }
-                stems.truncate(stem_idx + 1);
-            }
         }
     }
 
+    /// Partitions `sort_index` between the leaves below `stem_index`, choosing each
+    /// stem's split with [`Self::split`], and records each leaf's extent within it
+    /// without touching the leaf arrays. Used for full builds, parallel builds and
+    /// repartitioning subtrees in place, with `writer` determining whether sibling
+    /// subtrees are partitioned in parallel.
     #[allow(clippy::too_many_arguments)]
-    fn populate_recursive(
-        stems: &mut AVec<A, ConstAlign<{ CACHELINE_ALIGN }>>,
+    fn partition_recursive<W: PartitionWriter<A>>(
+        writer: &mut W,
         dim: usize,
         source: &[[A; K]],
         sort_index: &mut [usize],
+        sort_index_offset: usize,
         stem_index: usize,
         mut level: i32,
         mut minor_level: u64,
         max_stem_level: i32,
-        capacity: usize,
-        leaf_points: &mut [Vec<A>; K],
-        leaf_items: &mut Vec<T>,
-        leaf_extents: &mut Vec<(u32, u32)>,
+        leaf_idx: usize,
+        options: BuildOptions,
     ) {
         let chunk_length = sort_index.len();
 
         if level > max_stem_level {
-            // Write leaf and terminate recursion
-            leaf_extents.push((
-                leaf_items.len() as u32,
-                (leaf_items.len() + chunk_length) as u32,
-            ));
-
-            (0..chunk_length).for_each(|i| {
-                (0..K).for_each(|dim| leaf_points[dim].push(source[sort_index[i]][dim]));
-                leaf_items.push(sort_index[i].az::<T>())
-            });
-
+            // Record where this leaf's items sit within the sort index and terminate recursion
+            writer.write_leaf_extent(
+                leaf_idx,
+                (
+                    sort_index_offset as u32,
+                    (sort_index_offset + chunk_length) as u32,
+                ),
+            );
             return;
         }
 
-        let levels_below = max_stem_level - level;
-        let left_capacity = (2usize.pow(levels_below as u32) * B).min(capacity);
-        let right_capacity = capacity.saturating_sub(left_capacity);
-
-        let mut pivot = Self::calc_pivot(chunk_length, stem_index, right_capacity);
-
-        // only bother with this if we are putting at least one item in the right hand child
-        if pivot < chunk_length {
-            pivot = Self::update_pivot(source, sort_index, dim, pivot);
+        let (split_dim, pivot, split_val) = Self::split(source, sort_index, dim, options);
 
-            // if we end up with a pivot of 0, something has gone wrong,
-            // unless we only had a slice of len 1 anyway
-            debug_assert!(pivot > 0 || chunk_length == 1);
+        if let Some(split_val) = split_val {
             debug_assert!(
-                stems[stem_index].is_infinite(),
+                writer.stem(stem_index).is_infinite(),
                 "Wrote to stem #{:?} for a second time",
                 stem_index
             );
 
-            stems[stem_index] = source[sort_index[pivot]][dim];
+            writer.write_stem(stem_index, split_val, split_dim);
         }
 
         #[cfg(feature = "modified_van_emde_boas")]
-        let left_child_idx = modified_van_emde_boas_get_child_idx_v2_branchless(
+        let left_child_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(
             stem_index as u32,
             false,
             minor_level as u32,
         ) as usize;
         #[cfg(feature = "modified_van_emde_boas")]
-        let right_child_idx = modified_van_emde_boas_get_child_idx_v2_branchless(
+        let right_child_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(
             stem_index as u32,
             true,
             minor_level as u32,
@@ -466,119 +912,976 @@ where
 
         level += 1;
         minor_level += 1;
-        minor_level.cmovnz(&0, u8::from(minor_level == 3));
+        minor_level.cmovnz(
+            &0,
+            u8::from(minor_level == u64::from(log2_items_per_cache_line::<A>())),
+        );
 
         let next_dim = (dim + 1) % K;
 
-        Self::populate_recursive(
-            stems,
-            next_dim,
+        writer.join(
+            chunk_length,
+            |writer| {
+                Self::partition_recursive(
+                    writer,
+                    next_dim,
+                    source,
+                    lower_sort_index,
+                    sort_index_offset,
+                    left_child_idx,
+                    level,
+                    minor_level,
+                    max_stem_level,
+                    leaf_idx << 1,
+                    options,
+                )
+            },
+            |writer| {
+                Self::partition_recursive(
+                    writer,
+                    next_dim,
+                    source,
+                    upper_sort_index,
+                    sort_index_offset + pivot,
+                    right_child_idx,
+                    level,
+                    minor_level,
+                    max_stem_level,
+                    (leaf_idx << 1) + 1,
+                    options,
+                )
+            },
+        );
+    }
+
+    /// Creates an `ImmutableKdTree`, balanced and optimized, populated with the
+    /// points in `points`, storing `items[i]` as the item for `points[i]`.
+    ///
+    /// Unlike [`ImmutableKdTree::new_from_slice`], which stores each point's index
+    /// within the source slice, this allows arbitrary `Content` values to be stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `items` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    /// use kiddo::SquaredEuclidean;
+    ///
+    /// let points: Vec<[f64; 3]> = vec!([1.0f64, 2.0f64, 3.0f64], [4.0f64, 5.0f64, 6.0f64]);
+    /// let items: Vec<u64> = vec!(1001, 2002);
+    /// let tree: ImmutableKdTree<f64, u64, 3, 32> =
+    ///     ImmutableKdTree::new_from_points_and_items(&points, &items);
+    ///
+    /// assert_eq!(tree.size(), 2);
+    /// assert_eq!(tree.nearest_one::<SquaredEuclidean>(&[4.0, 5.0, 6.0]).item, 2002);
+    /// ```
+    #[inline]
+    pub fn new_from_points_and_items(points: &[[A; K]], items: &[T]) -> Self {
+        let mut result = Self::new_empty();
+        result.rebuild_from_points_and_items(points, items);
+        result
+    }
+
+    /// Rebuilds the tree from `points` and `items`, re-using the existing buffers
+    /// in the same way as [`ImmutableKdTree::rebuild_from_slice`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `items` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    ///
+    /// let mut points: Vec<[f64; 3]> = vec!([1.0f64, 2.0f64, 3.0f64]);
+    /// let mut items: Vec<u64> = vec!(1001);
+    /// let mut tree: ImmutableKdTree<f64, u64, 3, 32> =
+    ///     ImmutableKdTree::new_from_points_and_items(&points, &items);
+    ///
+    /// points.push([4.0f64, 5.0f64, 6.0f64]);
+    /// items.push(2002);
+    /// tree.rebuild_from_points_and_items(&points, &items);
+    ///
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    #[inline]
+    pub fn rebuild_from_points_and_items(&mut self, points: &[[A; K]], items: &[T]) {
+        assert_eq!(
+            points.len(),
+            items.len(),
+            "points and items must have the same length"
+        );
+        self.rebuild_with_items(points, |idx| items[idx]);
+        self.built_from_points_and_items = true;
+    }
+
+    /// Rebuilds the tree from `source`, storing `item_at(i)` as the item for `source[i]`.
+    fn rebuild_with_items<F: Fn(usize) -> T>(&mut self, source: &[[A; K]], item_at: F) {
+        let stem_node_count = self.reset_for_rebuild(source.len());
+
+        if stem_node_count == 0 {
+            self.populate_single_leaf(source, &item_at);
+            self.rebuild_leaf_bounds();
+            return;
+        }
+
+        #[cfg(not(feature = "modified_van_emde_boas"))]
+        let initial_stem_idx = 1;
+        #[cfg(feature = "modified_van_emde_boas")]
+        let initial_stem_idx = 0;
+
+        self.leaf_extents
+            .resize(1 << (self.max_stem_level + 1), (0, 0));
+
+        Self::partition_recursive(
+            &mut SlicePartitionWriter {
+                stems: &mut self.stems,
+                split_dims: &mut self.split_dims,
+                leaf_extents: &mut self.leaf_extents,
+            },
+            0,
             source,
-            lower_sort_index,
-            left_child_idx,
-            level,
-            minor_level,
-            max_stem_level,
-            left_capacity,
-            leaf_points,
-            leaf_items,
-            leaf_extents,
+            &mut self.scratch.sort_index,
+            0,
+            initial_stem_idx,
+            0,
+            0,
+            self.max_stem_level,
+            0,
+            self.options,
         );
 
-        Self::populate_recursive(
-            stems,
-            next_dim,
-            source,
-            upper_sort_index,
-            right_child_idx,
-            level,
-            minor_level,
-            max_stem_level,
-            right_capacity,
-            leaf_points,
-            leaf_items,
-            leaf_extents,
-        );
+        // Once partitioned, the contents of each leaf are contiguous within
+        // the sort index, with the leaves in order.
+        let sort_index = &self.scratch.sort_index;
+        (0..K).for_each(|dim| {
+            self.leaf_points[dim].extend(sort_index.iter().map(|&i| source[i][dim]))
+        });
+        self.leaf_items
+            .extend(sort_index.iter().map(|&i| item_at(i)));
+
+        self.rebuild_leaf_bounds();
+    }
+
+    /// Recalculates the bounding box of every leaf if the tree stores them
+    fn rebuild_leaf_bounds(&mut self) {
+        self.leaf_bounds.clear();
+        if self.options.leaf_bounding_boxes {
+            self.leaf_bounds
+                .resize(self.leaf_extents.len() * 2 * K, A::zero());
+            self.update_leaf_bounds(0..self.leaf_extents.len());
+        }
+    }
+
+    /// Recalculates the bounding boxes of the leaves in `leaf_range`. Empty leaves get a
+    /// box with infinite minimums and negative infinite maximums, so that they are never searched.
+    fn update_leaf_bounds(&mut self, leaf_range: Range<usize>) {
+        for leaf_idx in leaf_range {
+            let (start, end) = self.leaf_extents[leaf_idx];
+            let (mins, maxes) =
+                self.leaf_bounds[leaf_idx * 2 * K..(leaf_idx + 1) * 2 * K].split_at_mut(K);
+
+            for dim in 0..K {
+                let values = &self.leaf_points[dim][start as usize..end as usize];
+                mins[dim] = values.iter().copied().fold(A::infinity(), A::min);
+                maxes[dim] = values.iter().copied().fold(A::neg_infinity(), A::max);
+            }
+        }
+    }
+
+    /// Clears the tree's contents and sizes its buffers, ready for `item_count` items
+    /// to be added. Returns the number of stems to be populated, which will be zero
+    /// if all the items fit in a single leaf.
+    fn reset_for_rebuild(&mut self, item_count: usize) -> usize {
+        let leaf_node_count = item_count.div_ceil(self.options.leaf_fill_target::<B>());
+
+        #[cfg(not(feature = "modified_van_emde_boas"))]
+        let stem_node_count = if leaf_node_count < 2 {
+            0
+        } else {
+            leaf_node_count.next_power_of_two()
+        };
+
+        let max_stem_level: i32 = leaf_node_count.next_power_of_two().ilog2() as i32 - 1;
+
+        // every stem level is fully populated, so we always end up with a power-of-two
+        // number of leaves, some of which may be empty
+        let leaf_extent_count = leaf_node_count.next_power_of_two();
+
+        // the right-most stem on the deepest level has the highest index
+        // in the layout, so the stem count can be determined up-front
+        #[cfg(feature = "modified_van_emde_boas")]
+        let stem_node_count = if leaf_node_count < 2 {
+            0
+        } else {
+            modified_van_emde_boas_stem_count::<A>(max_stem_level as u32 + 1)
+        };
+
+        // Clear old data
+        self.stems.clear();
+        self.leaf_points.iter_mut().for_each(|vec| vec.clear());
+        self.leaf_items.clear();
+        self.leaf_extents.clear();
+        self.scratch.sort_index.clear();
+        self.scratch.indexed_by_source = false;
+        self.scratch.source_positions.clear();
+
+        // reserve enough space in advance for new data.
+        self.stems.reserve_exact(stem_node_count);
+        self.stems.resize(stem_node_count, A::infinity());
+        self.split_dims.clear();
+        if self.options.split_axis == SplitAxis::WidestSpread {
+            self.split_dims.reserve_exact(stem_node_count);
+            self.split_dims.resize(stem_node_count, 0);
+        }
+        self.leaf_points
+            .iter_mut()
+            .for_each(|vec| vec.reserve(item_count));
+        self.leaf_items.reserve(item_count);
+        self.leaf_extents.reserve(leaf_extent_count);
+        self.scratch.sort_index.extend(0..item_count);
+        self.max_stem_level = max_stem_level;
+
+        stem_node_count
+    }
+
+    /// Writes all of `source` into a single leaf, for trees that have no stems.
+    fn populate_single_leaf<F: Fn(usize) -> T>(&mut self, source: &[[A; K]], item_at: &F) {
+        self.leaf_extents.push((0u32, source.len() as u32));
+
+        self.scratch.sort_index.iter().for_each(|&idx| {
+            (0..K).for_each(|dim| self.leaf_points[dim].push(source[idx][dim]));
+            self.leaf_items.push(item_at(idx))
+        });
+    }
+
+    #[cfg(not(feature = "unreliable_select_nth_unstable"))]
+    #[inline]
+    /// Chooses the axis and value that a stem splits the points in `sort_index` at,
+    /// according to `options`, and partitions `sort_index` so that the points that
+    /// belong in the left child come first. `dim` is the axis to split along when
+    /// cycling through the axes round-robin.
+    ///
+    /// Returns the split axis, the index within `sort_index` of the first point in the
+    /// right child, and the split value, which is `None` if there are no points to split.
+    fn split(
+        source: &[[A; K]],
+        sort_index: &mut [usize],
+        dim: usize,
+        options: BuildOptions,
+    ) -> (usize, usize, Option<A>) {
+        let chunk_length = sort_index.len();
+
+        if chunk_length == 0 {
+            return (dim, 0, None);
+        }
+
+        let extents = if options.split_axis == SplitAxis::RoundRobin
+            && options.pivot_strategy == PivotStrategy::Median
+        {
+            None
+        } else {
+            Some(Self::calc_extents(source, sort_index))
+        };
+
+        let dim = match (options.split_axis, &extents) {
+            (SplitAxis::WidestSpread, Some((min, max))) => {
+                // an axis whose extents are both infinite has a NaN spread,
+                // which is treated as no spread at all
+                let spread = |dim: usize| {
+                    let spread = max[dim] - min[dim];
+                    if spread.is_nan() {
+                        A::zero()
+                    } else {
+                        spread
+                    }
+                };
+
+                (0..K)
+                    .max_by(|&a, &b| spread(a).partial_cmp(&spread(b)).unwrap().then(b.cmp(&a)))
+                    .unwrap()
+            }
+            _ => dim,
+        };
+
+        let split_val = match (options.pivot_strategy, &extents) {
+            (PivotStrategy::SlidingMidpoint, Some((min, max))) => {
+                Self::calc_sliding_midpoint(min[dim], max[dim])
+            }
+            (PivotStrategy::SurfaceAreaHeuristic, Some((min, max))) => {
+                Self::calc_surface_area_heuristic_split(source, sort_index, dim, min, max)
+            }
+            _ => None,
+        };
+
+        if let Some(split_val) = split_val {
+            let pivot = Self::partition_at(source, sort_index, dim, split_val);
+            if pivot > 0 && pivot < chunk_length {
+                return (dim, pivot, Some(split_val));
+            }
+        }
+
+        // median split, either requested or as a fallback when the
+        // chosen split value would leave one of the children empty
+        let pivot = Self::calc_pivot(chunk_length);
+        let pivot = Self::update_pivot(source, sort_index, dim, pivot);
+
+        // if we end up with a pivot of 0, something has gone wrong,
+        // unless we only had a slice of len 1 anyway
+        debug_assert!(pivot > 0 || chunk_length == 1);
+
+        (dim, pivot, Some(source[sort_index[pivot]][dim]))
+    }
+
+    /// Returns the minimum and maximum value on each axis of the points in `sort_index`
+    fn calc_extents(source: &[[A; K]], sort_index: &[usize]) -> ([A; K], [A; K]) {
+        let mut min = [A::infinity(); K];
+        let mut max = [A::neg_infinity(); K];
+
+        for &idx in sort_index {
+            for dim in 0..K {
+                min[dim] = min[dim].min(source[idx][dim]);
+                max[dim] = max[dim].max(source[idx][dim]);
+            }
+        }
+
+        (min, max)
+    }
+
+    fn calc_sliding_midpoint(min: A, max: A) -> Option<A> {
+        if min == max {
+            return None;
+        }
+
+        let mid = min + (max - min) / (A::one() + A::one());
+
+        // points with a value equal to the split go right, so a split
+        // at the minimum would leave the left child empty
+        Some(if mid > min { mid } else { max })
+    }
+
+    /// Evaluates the surface area heuristic at evenly spaced planes between `min[dim]`
+    /// and `max[dim]`, binning the points so that each point is only visited once.
+    fn calc_surface_area_heuristic_split(
+        source: &[[A; K]],
+        sort_index: &[usize],
+        dim: usize,
+        min: &[A; K],
+        max: &[A; K],
+    ) -> Option<A> {
+        const BINS: usize = 16;
+
+        if min[dim] == max[dim] {
+            return None;
+        }
+
+        let bin_width = (max[dim] - min[dim]) / A::from(BINS).unwrap();
+        let mut bin_counts = [0usize; BINS];
+        let mut bin_min = [[A::infinity(); K]; BINS];
+        let mut bin_max = [[A::neg_infinity(); K]; BINS];
+
+        for &idx in sort_index {
+            let point = &source[idx];
+            let bin = ((point[dim] - min[dim]) / bin_width)
+                .to_usize()
+                .unwrap_or(0)
+                .min(BINS - 1);
+
+            bin_counts[bin] += 1;
+            for d in 0..K {
+                bin_min[bin][d] = bin_min[bin][d].min(point[d]);
+                bin_max[bin][d] = bin_max[bin][d].max(point[d]);
+            }
+        }
+
+        // bounding boxes and counts of everything to the right of each plane
+        let mut right_counts = [0usize; BINS];
+        let mut right_min = [[A::infinity(); K]; BINS];
+        let mut right_max = [[A::neg_infinity(); K]; BINS];
+        for bin in (1..BINS).rev() {
+            let (next_count, next_min, next_max) = if bin + 1 < BINS {
+                (
+                    right_counts[bin + 1],
+                    right_min[bin + 1],
+                    right_max[bin + 1],
+                )
+            } else {
+                (0, [A::infinity(); K], [A::neg_infinity(); K])
+            };
+
+            right_counts[bin] = next_count + bin_counts[bin];
+            for d in 0..K {
+                right_min[bin][d] = next_min[d].min(bin_min[bin][d]);
+                right_max[bin][d] = next_max[d].max(bin_max[bin][d]);
+            }
+        }
+
+        let mut best: Option<(A, usize)> = None;
+        let mut left_count = 0usize;
+        let mut left_min = [A::infinity(); K];
+        let mut left_max = [A::neg_infinity(); K];
+        for bin in 0..BINS - 1 {
+            left_count += bin_counts[bin];
+            for d in 0..K {
+                left_min[d] = left_min[d].min(bin_min[bin][d]);
+                left_max[d] = left_max[d].max(bin_max[bin][d]);
+            }
+
+            let right_count = right_counts[bin + 1];
+            if left_count == 0 || right_count == 0 {
+                continue;
+            }
+
+            let cost = A::from(left_count).unwrap() * Self::surface_area(&left_min, &left_max)
+                + A::from(right_count).unwrap()
+                    * Self::surface_area(&right_min[bin + 1], &right_max[bin + 1]);
+
+            if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+                best = Some((cost, bin));
+            }
+        }
+
+        best.map(|(_, bin)| min[dim] + bin_width * A::from(bin + 1).unwrap())
+    }
+
+    /// Returns the K-1 dimensional surface area of a bounding box, up to a constant factor
+    fn surface_area(min: &[A; K], max: &[A; K]) -> A {
+        (0..K)
+            .map(|skipped| {
+                (0..K)
+                    .filter(|&d| d != skipped)
+                    .fold(A::one(), |area, d| area * (max[d] - min[d]))
+            })
+            .fold(A::zero(), |total, area| total + area)
+    }
+
+    /// Partitions `sort_index` so that the points whose value on `dim` is less than `split_val`
+    /// come first, and returns the number of them
+    fn partition_at(
+        source: &[[A; K]],
+        sort_index: &mut [usize],
+        dim: usize,
+        split_val: A,
+    ) -> usize {
+        let mut pivot = 0;
+        for i in 0..sort_index.len() {
+            if source[sort_index[i]][dim] < split_val {
+                sort_index.swap(i, pivot);
+                pivot += 1;
+            }
+        }
+
+        pivot
+    }
+
+    fn update_pivot(
+        source: &[[A; K]],
+        sort_index: &mut [usize],
+        dim: usize,
+        mut pivot: usize,
+    ) -> usize {
+        // TODO: this block might be faster by using a quickselect with a fat partition?
+        //       we could then run that quickselect and subtract (fat partition length - 1)
+        //       from the pivot, avoiding the need for the while loop.
+
+        // ensure the item whose index = pivot is in its correctly sorted position, and any
+        // items that are equal to it are adjacent, according to our assumptions about the
+        // behaviour of `select_nth_unstable_by` (See examples/check_select_nth_unstable.rs)
+        sort_index.select_nth_unstable_by_key(pivot, |&i| OrderedFloat(source[i][dim]));
+
+        if pivot == 0 {
+            return pivot;
+        }
+
+        // if the pivot straddles two values that are equal, keep nudging it left until they aren't
+        while source[sort_index[pivot]][dim] == source[sort_index[pivot - 1]][dim] && pivot > 1 {
+            pivot -= 1;
+        }
+
+        pivot
+    }
+
+    /// Returns the current number of elements stored in the tree
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    ///
+    /// let points: Vec<[f64; 3]> = vec!([1.0f64, 2.0f64, 3.0f64]);
+    /// let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// assert_eq!(tree.size(), 1);
+    /// ```
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.leaf_items.len()
+    }
+
+    /// Returns the theoretical max capacity of this tree
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.size()
+    }
+
+    /// Iterate over all `(item, point)` tuples, in the order that they are stored in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    ///
+    /// let points: Vec<[f64; 3]> = vec!([1.0, 2.0, 3.0], [4.0, 5.0, 6.0]);
+    /// let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// assert_eq!(tree.iter().len(), 2);
+    ///
+    /// let mut pairs: Vec<_> = tree.iter().collect();
+    /// pairs.sort_by_key(|&(item, _)| item);
+    /// assert_eq!(pairs, vec!((0, [1.0, 2.0, 3.0]), (1, [4.0, 5.0, 6.0])));
+    /// ```
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (T, [A; K])> + '_ {
+        self.leaf_items
+            .iter()
+            .enumerate()
+            .map(|(idx, &item)| (item, array_init(|dim| self.leaf_points[dim][idx])))
+    }
+
+    /// Iterate over the leaves of the tree, in order. Each leaf is yielded as a slice
+    /// of its points' co-ordinates for each axis, along with a slice of its items.
+    /// Some leaves may be empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    ///
+    /// let points: Vec<[f64; 2]> = (0..100).map(|i| [i as f64, (i * 2) as f64]).collect();
+    /// let tree: ImmutableKdTree<f64, u32, 2, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// for ([xs, ys], items) in tree.leaves() {
+    ///     assert!(items.len() <= 32);
+    ///     assert!(items.iter().zip(xs).all(|(&item, &x)| x == item as f64));
+    ///     assert!(xs.iter().zip(ys).all(|(&x, &y)| y == x * 2.0));
+    /// }
+    /// assert_eq!(tree.leaves().map(|(_, items)| items.len()).sum::<usize>(), 100);
+    /// ```
+    pub fn leaves(&self) -> impl ExactSizeIterator<Item = ([&[A]; K], &[T])> + '_ {
+        self.leaf_extents.iter().map(|&(start, end)| {
+            let (start, end) = (start as usize, end as usize);
+            (
+                array_init(|dim| &self.leaf_points[dim][start..end]),
+                &self.leaf_items[start..end],
+            )
+        })
+    }
+
+    /// Returns statistics describing the shape, fill level and memory usage of the tree,
+    /// which can help with choosing a suitable value for `B`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    /// use std::mem::size_of;
+    ///
+    /// let points: Vec<[f64; 2]> = (0..1000).map(|i| [i as f64, (i % 10) as f64]).collect();
+    /// let tree: ImmutableKdTree<f64, u32, 2, 32> = ImmutableKdTree::new_from_slice(&points);
+    ///
+    /// let stats = tree.stats();
+    ///
+    /// assert_eq!(stats.size, 1000);
+    /// assert_eq!(stats.min_depth, stats.max_depth);
+    /// assert_eq!(stats.leaf_count, stats.stem_count + 1);
+    /// assert_eq!(stats.memory.leaf_items, 1000 * size_of::<u32>());
+    /// ```
+    pub fn stats(&self) -> TreeStats {
+        immutable_tree_stats(
+            self.leaves(),
+            self.max_stem_level,
+            MemoryUsage {
+                stems: self.stems.len() * size_of::<A>(),
+                leaf_points: self.leaf_points.iter().map(Vec::len).sum::<usize>() * size_of::<A>(),
+                leaf_items: self.leaf_items.len() * size_of::<T>(),
+                other: self.leaf_extents.len() * size_of::<(u32, u32)>()
+                    + self.split_dims.len()
+                    + self.leaf_bounds.len() * size_of::<A>(),
+            },
+        )
+    }
+
+    fn calc_pivot(chunk_length: usize) -> usize {
+        chunk_length >> 1
+    }
+
+    /// Returns a LeafSlice for a given leaf index
+    #[inline]
+    pub(crate) fn get_leaf_slice(&self, leaf_idx: usize) -> LeafSlice<'_, A, T, K> {
+        let (start, end) = unsafe { *self.leaf_extents.get_unchecked(leaf_idx) };
+
+        // Artificially extend size to be at least chunk length for faster processing
+        // TODO: why does this slow things down?
+        // let end = end.max(start + 32).min(self.leaf_items.len() as u32);
+
+        LeafSlice::new(
+            array_init::array_init(|i| &self.leaf_points[i][start as usize..end as usize]),
+            &self.leaf_items[start as usize..end as usize],
+        )
+    }
+
+    /// Returns true if every point in the leaf is further than `dist` from `query`,
+    /// according to the leaf's bounding box. Always false if the tree has no leaf bounding boxes.
+    #[inline]
+    pub(crate) fn leaf_is_beyond<D>(&self, query: &[A; K], leaf_idx: usize, dist: A) -> bool
+    where
+        D: DistanceMetric<A, K>,
+    {
+        !self.leaf_bounds.is_empty()
+            && leaf_bounds_dist::<A, D, K>(
+                query,
+                &self.leaf_bounds[leaf_idx * 2 * K..(leaf_idx + 1) * 2 * K],
+            ) > dist
+    }
+
+    /// Returns the dimension that the stem at `stem_idx` splits on. `split_dim` is the
+    /// dimension that it splits on if the tree cycles through the dimensions in turn.
+    #[inline]
+    pub(crate) fn stem_split_dim(&self, stem_idx: usize, split_dim: usize) -> usize {
+        if self.split_dims.is_empty() {
+            split_dim
+        } else {
+            *unsafe { self.split_dims.get_unchecked(stem_idx) } as usize
+        }
+    }
+
+    /// Returns the minimum and maximum corners of the bounding box of the leaf
+    /// at `leaf_idx`, or `None` if the tree has no leaf bounding boxes.
+    #[inline]
+    pub(crate) fn leaf_bounding_box(&self, leaf_idx: usize) -> Option<(&[A], &[A])> {
+        if self.leaf_bounds.is_empty() {
+            None
+        } else {
+            Some(self.leaf_bounds[leaf_idx * 2 * K..(leaf_idx + 1) * 2 * K].split_at(K))
+        }
+    }
+
+    generate_immutable_visit_leaves!();
+}
+
+/// The buffers that `partition_recursive` writes the stems, split dimensions and
+/// leaf extents of a tree to, and how it descends into the two children of a stem.
+trait PartitionWriter<A>: Sized {
+    /// Returns the split value currently held by the stem at `stem_idx`
+    fn stem(&self, stem_idx: usize) -> A;
+
+    /// Writes the split value and, if the tree stores them, the split dimension of a stem
+    fn write_stem(&mut self, stem_idx: usize, split_val: A, split_dim: usize);
+
+    /// Writes the range of the sort index that holds the contents of a leaf
+    fn write_leaf_extent(&mut self, leaf_idx: usize, extent: (u32, u32));
+
+    /// Partitions the left and then the right child of a stem whose subtree
+    /// holds `chunk_length` points
+    fn join<L, R>(&mut self, chunk_length: usize, left: L, right: R)
+    where
+        L: FnOnce(&mut Self) + Send,
+        R: FnOnce(&mut Self) + Send;
+}
+
+/// Writes a build to the tree's own buffers on the current thread.
+struct SlicePartitionWriter<'a, A> {
+    stems: &'a mut [A],
+    split_dims: &'a mut [u8],
+    leaf_extents: &'a mut [(u32, u32)],
+}
+
+impl<A: Copy> PartitionWriter<A> for SlicePartitionWriter<'_, A> {
+    #[inline]
+    fn stem(&self, stem_idx: usize) -> A {
+        self.stems[stem_idx]
+    }
+
+    #[inline]
+    fn write_stem(&mut self, stem_idx: usize, split_val: A, split_dim: usize) {
+        self.stems[stem_idx] = split_val;
+        if !self.split_dims.is_empty() {
+            self.split_dims[stem_idx] = split_dim as u8;
+        }
+    }
+
+    #[inline]
+    fn write_leaf_extent(&mut self, leaf_idx: usize, extent: (u32, u32)) {
+        self.leaf_extents[leaf_idx] = extent;
+    }
+
+    #[inline]
+    fn join<L, R>(&mut self, _chunk_length: usize, left: L, right: R)
+    where
+        L: FnOnce(&mut Self) + Send,
+        R: FnOnce(&mut Self) + Send,
+    {
+        left(self);
+        right(self);
+    }
+}
+
+/// Sub-slices of the sort index shorter than this are partitioned on the current
+/// thread rather than being split across the rayon thread pool.
+#[cfg(feature = "rayon")]
+const PAR_BUILD_MIN_CHUNK_LENGTH: usize = 1 << 14;
+
+/// Shares a mutable buffer between the threads of a parallel build.
+///
+/// Sibling subtrees always write to disjoint stem indices and disjoint leaf
+/// indices, so no two threads ever write to the same element.
+#[cfg(feature = "rayon")]
+#[derive(Clone, Copy)]
+struct DisjointWriter<X: Copy> {
+    ptr: *mut X,
+    len: usize,
+}
+
+#[cfg(feature = "rayon")]
+unsafe impl<X: Copy + Send> Send for DisjointWriter<X> {}
+#[cfg(feature = "rayon")]
+unsafe impl<X: Copy + Send> Sync for DisjointWriter<X> {}
+
+#[cfg(feature = "rayon")]
+impl<X: Copy> DisjointWriter<X> {
+    fn new(buf: &mut [X]) -> Self {
+        DisjointWriter {
+            ptr: buf.as_mut_ptr(),
+            len: buf.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// # Safety
+    ///
+    /// No other thread may access the element at `idx` for as long as the
+    /// buffer is shared.
+    unsafe fn write(&self, idx: usize, val: X) {
+        assert!(idx < self.len);
+        self.ptr.add(idx).write(val);
     }
 
-    #[cfg(not(feature = "unreliable_select_nth_unstable"))]
-    #[inline]
-    fn update_pivot(
-        source: &[[A; K]],
-        sort_index: &mut [usize],
-        dim: usize,
-        mut pivot: usize,
-    ) -> usize {
-        // TODO: this block might be faster by using a quickselect with a fat partition?
-        //       we could then run that quickselect and subtract (fat partition length - 1)
-        //       from the pivot, avoiding the need for the while loop.
+    /// # Safety
+    ///
+    /// No other thread may write to the element at `idx` for as long as the
+    /// buffer is shared.
+    unsafe fn read(&self, idx: usize) -> X {
+        assert!(idx < self.len);
+        self.ptr.add(idx).read()
+    }
+}
 
-        // ensure the item whose index = pivot is in its correctly sorted position, and any
-        // items that are equal to it are adjacent, according to our assumptions about the
-        // behaviour of `select_nth_unstable_by` (See examples/check_select_nth_unstable.rs)
-        sort_index.select_nth_unstable_by_key(pivot, |&i| OrderedFloat(source[i][dim]));
+/// Writes a build to the tree's own buffers from the threads of the rayon thread
+/// pool, partitioning sibling subtrees in parallel.
+#[cfg(feature = "rayon")]
+#[derive(Clone, Copy)]
+struct DisjointPartitionWriter<A: Copy> {
+    stems: DisjointWriter<A>,
+    split_dims: DisjointWriter<u8>,
+    leaf_extents: DisjointWriter<(u32, u32)>,
+}
 
-        if pivot == 0 {
-            return pivot;
-        }
+#[cfg(feature = "rayon")]
+impl<A: Copy + Send> PartitionWriter<A> for DisjointPartitionWriter<A> {
+    #[inline]
+    fn stem(&self, stem_idx: usize) -> A {
+        // only the thread partitioning this stem's subtree accesses it
+        unsafe { self.stems.read(stem_idx) }
+    }
 
-        // if the pivot straddles two values that are equal, keep nudging it left until they aren't
-        while source[sort_index[pivot]][dim] == source[sort_index[pivot - 1]][dim] && pivot > 1 {
-            pivot -= 1;
+    #[inline]
+    fn write_stem(&mut self, stem_idx: usize, split_val: A, split_dim: usize) {
+        unsafe { self.stems.write(stem_idx, split_val) };
+        if !self.split_dims.is_empty() {
+            unsafe { self.split_dims.write(stem_idx, split_dim as u8) };
         }
+    }
 
-        pivot
+    #[inline]
+    fn write_leaf_extent(&mut self, leaf_idx: usize, extent: (u32, u32)) {
+        unsafe { self.leaf_extents.write(leaf_idx, extent) };
     }
 
-    /// Returns the current number of elements stored in the tree
+    #[inline]
+    fn join<L, R>(&mut self, chunk_length: usize, left: L, right: R)
+    where
+        L: FnOnce(&mut Self) + Send,
+        R: FnOnce(&mut Self) + Send,
+    {
+        if chunk_length >= PAR_BUILD_MIN_CHUNK_LENGTH {
+            let (mut left_writer, mut right_writer) = (*self, *self);
+            rayon::join(|| left(&mut left_writer), || right(&mut right_writer));
+        } else {
+            left(self);
+            right(self);
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<A, T, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+    T: Content,
+    usize: Cast<T>,
+{
+    /// Creates an `ImmutableKdTree`, balanced and optimized, populated
+    /// with items from `source`, using the rayon thread pool.
+    ///
+    /// The resulting tree is identical to one created by [`ImmutableKdTree::new_from_slice`].
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```rust
     /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
     ///
     /// let points: Vec<[f64; 3]> = vec!([1.0f64, 2.0f64, 3.0f64]);
-    /// let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+    /// let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTree::par_new_from_slice(&points);
     ///
-    /// assert_eq!(tree.size(), 1);
+    /// assert_eq!(tree, ImmutableKdTree::new_from_slice(&points));
     /// ```
     #[inline]
-    pub fn size(&self) -> usize {
-        self.leaf_items.len()
+    pub fn par_new_from_slice(source: &[[A; K]]) -> Self {
+        let mut result = Self::new_empty();
+        result.par_rebuild_from_slice(source);
+        result
     }
 
-    /// Returns the theoretical max capacity of this tree
+    /// Parallel equivalent of [`ImmutableKdTree::rebuild_from_slice`].
+    ///
+    /// Partitioning of subtrees that are large enough to be worth it is spread
+    /// across the rayon thread pool. The resulting tree is identical to one
+    /// produced by the sequential build.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    ///
+    /// let mut points: Vec<[f64; 3]> = vec!([1.0f64, 2.0f64, 3.0f64]);
+    /// let mut tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTree::par_new_from_slice(&points);
+    ///
+    /// points.push([4.0f64, 5.0f64, 6.0f64]);
+    /// tree.par_rebuild_from_slice(&points);
+    ///
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    pub fn par_rebuild_from_slice(&mut self, source: &[[A; K]]) {
+        self.par_rebuild_with_items(source, |idx| idx.az::<T>());
+        self.scratch.indexed_by_source = true;
+        self.built_from_points_and_items = false;
+    }
+
+    /// Parallel equivalent of [`ImmutableKdTree::new_from_points_and_items`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `items` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::immutable::float::kdtree::ImmutableKdTree;
+    ///
+    /// let points: Vec<[f64; 3]> = vec!([1.0f64, 2.0f64, 3.0f64]);
+    /// let items: Vec<u64> = vec!(1001);
+    /// let tree: ImmutableKdTree<f64, u64, 3, 32> =
+    ///     ImmutableKdTree::par_new_from_points_and_items(&points, &items);
+    ///
+    /// assert_eq!(tree, ImmutableKdTree::new_from_points_and_items(&points, &items));
+    /// ```
     #[inline]
-    pub fn capacity(&self) -> usize {
-        self.size()
+    pub fn par_new_from_points_and_items(points: &[[A; K]], items: &[T]) -> Self {
+        let mut result = Self::new_empty();
+        result.par_rebuild_from_points_and_items(points, items);
+        result
     }
 
-    fn calc_pivot(chunk_length: usize, _stem_index: usize, _right_capacity: usize) -> usize {
-        chunk_length >> 1
+    /// Parallel equivalent of [`ImmutableKdTree::rebuild_from_points_and_items`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `items` have different lengths.
+    pub fn par_rebuild_from_points_and_items(&mut self, points: &[[A; K]], items: &[T]) {
+        assert_eq!(
+            points.len(),
+            items.len(),
+            "points and items must have the same length"
+        );
+        self.par_rebuild_with_items(points, |idx| items[idx]);
+        self.built_from_points_and_items = true;
     }
 
-    /// Returns a LeafSlice for a given leaf index
-    #[inline]
-    pub(crate) fn get_leaf_slice(&self, leaf_idx: usize) -> LeafSlice<A, T, K> {
-        let (start, end) = unsafe { *self.leaf_extents.get_unchecked(leaf_idx) };
+    fn par_rebuild_with_items<F: Fn(usize) -> T>(&mut self, source: &[[A; K]], item_at: F) {
+        use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
 
-        // Artificially extend size to be at least chunk length for faster processing
-        // TODO: why does this slow things down?
-        // let end = end.max(start + 32).min(self.leaf_items.len() as u32);
+        let stem_node_count = self.reset_for_rebuild(source.len());
 
-        LeafSlice::new(
-            array_init::array_init(|i| &self.leaf_points[i][start as usize..end as usize]),
-            &self.leaf_items[start as usize..end as usize],
-        )
+        if stem_node_count == 0 {
+            self.populate_single_leaf(source, &item_at);
+            self.rebuild_leaf_bounds();
+            return;
+        }
+
+        #[cfg(not(feature = "modified_van_emde_boas"))]
+        let initial_stem_idx = 1;
+        #[cfg(feature = "modified_van_emde_boas")]
+        let initial_stem_idx = 0;
+
+        self.leaf_extents
+            .resize(1 << (self.max_stem_level + 1), (0, 0));
+
+        Self::partition_recursive(
+            &mut DisjointPartitionWriter {
+                stems: DisjointWriter::new(&mut self.stems),
+                split_dims: DisjointWriter::new(&mut self.split_dims),
+                leaf_extents: DisjointWriter::new(&mut self.leaf_extents),
+            },
+            0,
+            source,
+            &mut self.scratch.sort_index,
+            0,
+            initial_stem_idx,
+            0,
+            0,
+            self.max_stem_level,
+            0,
+            self.options,
+        );
+
+        // Once partitioned, the contents of each leaf are contiguous within
+        // the sort index, with the leaves in order.
+        let sort_index = &self.scratch.sort_index;
+        self.leaf_points
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(dim, points)| points.extend(sort_index.iter().map(|&i| source[i][dim])));
+        self.leaf_items
+            .extend(sort_index.iter().map(|&i| item_at(i)));
+
+        self.rebuild_leaf_bounds();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::immutable::float::kdtree::ImmutableKdTree;
+    use crate::float_leaf_slice::leaf_slice::{LeafSliceFloat, LeafSliceFloatChunk};
+    use crate::immutable::float::kdtree::{Axis, ImmutableKdTree};
     use crate::SquaredEuclidean;
     use ordered_float::OrderedFloat;
     use rand::{Rng, SeedableRng};
+    use std::mem::size_of;
 
     #[test]
     fn can_construct_an_empty_tree() {
@@ -586,6 +1889,233 @@ mod tests {
         let _result = tree.nearest_one::<SquaredEuclidean>(&[0.; 3]);
     }
 
+    #[test]
+    fn can_construct_with_caller_supplied_items() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+
+        for tree_size in [0, 1, 20, 1_000, 10_000] {
+            let points: Vec<[f32; 3]> = (0..tree_size).map(|_| rng.gen::<[f32; 3]>()).collect();
+            let items: Vec<u64> = (0..tree_size as u64).map(|i| i * 7 + 3).collect();
+
+            let by_index: ImmutableKdTree<f32, u64, 3, 32> =
+                ImmutableKdTree::new_from_slice(&points);
+            let tree: ImmutableKdTree<f32, u64, 3, 32> =
+                ImmutableKdTree::new_from_points_and_items(&points, &items);
+
+            assert_eq!(tree.size(), tree_size);
+            assert_eq!(tree.stems, by_index.stems);
+            assert_eq!(tree.leaf_points, by_index.leaf_points);
+            assert_eq!(tree.leaf_extents, by_index.leaf_extents);
+            let expected_items: Vec<u64> = by_index
+                .leaf_items
+                .iter()
+                .map(|&idx| items[idx as usize])
+                .collect();
+            assert_eq!(tree.leaf_items, expected_items);
+
+            let collected: ImmutableKdTree<f32, u64, 3, 32> =
+                points.iter().copied().zip(items.iter().copied()).collect();
+            assert_eq!(collected, tree);
+        }
+    }
+
+    #[test]
+    fn iter_yields_every_item_and_point() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+        for tree_size in [0, 1, 20, 10_000] {
+            let points: Vec<[f64; 3]> = (0..tree_size)
+                .map(|_| [rng.gen(), rng.gen(), rng.gen()])
+                .collect();
+            let items: Vec<u64> = (0..tree_size as u64).map(|idx| idx * 7).collect();
+
+            let tree: ImmutableKdTree<f64, u64, 3, 32> =
+                ImmutableKdTree::new_from_points_and_items(&points, &items);
+
+            let mut iter = tree.iter();
+            assert_eq!(iter.len(), tree_size);
+            iter.next();
+            assert_eq!(iter.len(), tree_size.saturating_sub(1));
+
+            let mut pairs: Vec<_> = tree.iter().collect();
+            pairs.sort_unstable_by_key(|&(item, _)| item);
+            let expected: Vec<_> = items.iter().copied().zip(points.iter().copied()).collect();
+            assert_eq!(pairs, expected);
+        }
+    }
+
+    #[test]
+    fn leaves_are_yielded_in_the_same_order_as_iter() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(5);
+        let points: Vec<[f32; 2]> = (0..5_000).map(|_| [rng.gen(), rng.gen()]).collect();
+        let tree: ImmutableKdTree<f32, u32, 2, 32> = ImmutableKdTree::new_from_slice(&points);
+
+        assert_eq!(tree.leaves().len(), tree.leaf_extents.len());
+
+        let mut from_leaves = Vec::new();
+        for ([xs, ys], items) in tree.leaves() {
+            assert!(items.len() <= 32);
+            assert_eq!(xs.len(), items.len());
+            assert_eq!(ys.len(), items.len());
+            from_leaves.extend((0..items.len()).map(|idx| (items[idx], [xs[idx], ys[idx]])));
+        }
+
+        assert_eq!(from_leaves, tree.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stats_describe_every_leaf() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+        let mut points: Vec<[f32; 2]> = (0..5_000).map(|_| [rng.gen(), rng.gen()]).collect();
+        points.extend([[0.5f32, 0.25f32]; 100]);
+        let tree: ImmutableKdTree<f32, u32, 2, 32> = ImmutableKdTree::new_from_slice(&points);
+
+        let stats = tree.stats();
+
+        let expected_duplicate_leaves = tree
+            .leaves()
+            .filter(|([xs, ys], _)| {
+                let mut seen = std::collections::HashSet::new();
+                !xs.iter()
+                    .zip(ys.iter())
+                    .all(|(x, y)| seen.insert((x.to_bits(), y.to_bits())))
+            })
+            .count();
+
+        assert_eq!(stats.size, 5_100);
+        assert_eq!(stats.leaf_count, tree.leaf_extents.len());
+        assert_eq!(stats.stem_count, stats.leaf_count - 1);
+        assert_eq!(stats.min_depth, (tree.max_stem_level + 1) as usize);
+        assert_eq!(stats.max_depth, stats.min_depth);
+        assert_eq!(stats.leaf_occupancy.iter().sum::<usize>(), stats.leaf_count);
+        assert!(expected_duplicate_leaves > 0);
+        assert_eq!(stats.duplicate_coordinate_leaves, expected_duplicate_leaves);
+        assert_eq!(stats.memory.leaf_points, 5_100 * 2 * size_of::<f32>());
+        assert_eq!(stats.memory.leaf_items, 5_100 * size_of::<u32>());
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn aligned_archived_tree_iterates_the_same_as_the_original() {
+        use crate::immutable::float::kdtree::{AlignedArchivedImmutableKdTree, ImmutableKdTreeRK};
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+        let points: Vec<[f64; 3]> = (0..2_000)
+            .map(|_| [rng.gen(), rng.gen(), rng.gen()])
+            .collect();
+        let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+
+        let tree_rk: ImmutableKdTreeRK<f64, u32, 3, 32> = tree.clone().into();
+        let bytes = rkyv::to_bytes::<_, 256>(&tree_rk).unwrap();
+        let archived: AlignedArchivedImmutableKdTree<f64, u32, 3, 32> =
+            AlignedArchivedImmutableKdTree::from_bytes(&bytes);
+
+        assert_eq!(archived.iter().len(), tree.iter().len());
+        assert!(archived.iter().eq(tree.iter()));
+        assert_eq!(archived.leaves().len(), tree.leaves().len());
+        assert!(archived.leaves().eq(tree.leaves()));
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn aligned_archived_tree_has_the_same_stats_as_the_original() {
+        use crate::immutable::float::kdtree::{AlignedArchivedImmutableKdTree, ImmutableKdTreeRK};
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(13);
+        let points: Vec<[f64; 3]> = (0..2_000)
+            .map(|_| [rng.gen(), rng.gen(), rng.gen()])
+            .collect();
+        let tree: ImmutableKdTree<f64, u32, 3, 32> = ImmutableKdTree::new_from_slice(&points);
+
+        let tree_rk: ImmutableKdTreeRK<f64, u32, 3, 32> = tree.clone().into();
+        let bytes = rkyv::to_bytes::<_, 256>(&tree_rk).unwrap();
+        let archived: AlignedArchivedImmutableKdTree<f64, u32, 3, 32> =
+            AlignedArchivedImmutableKdTree::from_bytes(&bytes);
+
+        assert_eq!(archived.stats(), tree.stats());
+    }
+
+    #[test]
+    fn nearest_one_returns_caller_supplied_item() {
+        let points = [[0.0f64, 0.0], [10.0, 10.0], [20.0, 0.0]];
+        let items = [900u32, 17, 4_000_000];
+
+        let tree: ImmutableKdTree<f64, u32, 2, 32> =
+            ImmutableKdTree::new_from_points_and_items(&points, &items);
+
+        for (point, item) in points.iter().zip(items) {
+            let nearest = tree.nearest_one::<SquaredEuclidean>(point);
+            assert_eq!(nearest.item, item);
+            assert_eq!(nearest.distance, 0.0);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "points and items must have the same length")]
+    fn new_from_points_and_items_panics_on_length_mismatch() {
+        let points = [[0.0f64, 0.0], [1.0, 1.0]];
+        let items = [1u32];
+
+        let _tree: ImmutableKdTree<f64, u32, 2, 32> =
+            ImmutableKdTree::new_from_points_and_items(&points, &items);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_build_is_identical_to_sequential_build() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(29);
+
+        for tree_size in [0, 1, 31, 32, 33, 1_000, 100_000, 300_001] {
+            let content_to_add: Vec<[f32; 3]> =
+                (0..tree_size).map(|_| rng.gen::<[f32; 3]>()).collect();
+
+            let sequential: ImmutableKdTree<f32, u32, 3, 32> =
+                ImmutableKdTree::new_from_slice(&content_to_add);
+            let parallel: ImmutableKdTree<f32, u32, 3, 32> =
+                ImmutableKdTree::par_new_from_slice(&content_to_add);
+
+            assert_eq!(parallel, sequential, "tree of {tree_size} items differs");
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_build_with_items_is_identical_to_sequential_build() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(31);
+
+        for tree_size in [0, 1, 33, 100_000] {
+            let points: Vec<[f32; 3]> = (0..tree_size).map(|_| rng.gen::<[f32; 3]>()).collect();
+            let items: Vec<u64> = (0..tree_size).map(|_| rng.gen::<u64>()).collect();
+
+            let sequential: ImmutableKdTree<f32, u64, 3, 32> =
+                ImmutableKdTree::new_from_points_and_items(&points, &items);
+            let parallel: ImmutableKdTree<f32, u64, 3, 32> =
+                ImmutableKdTree::par_new_from_points_and_items(&points, &items);
+
+            assert_eq!(parallel, sequential, "tree of {tree_size} items differs");
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_build_is_identical_to_sequential_build_with_many_dupes() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(31);
+
+        // points on a coarse integer grid, so that there are lots of dupes on every axis
+        let content_to_add: Vec<[f64; 2]> = (0..200_000)
+            .map(|_| [rng.gen_range(0..64) as f64, rng.gen_range(0..64) as f64])
+            .collect();
+
+        let mut sequential: ImmutableKdTree<f64, u32, 2, 64> =
+            ImmutableKdTree::new_from_slice(&content_to_add[..1_000]);
+        let mut parallel: ImmutableKdTree<f64, u32, 2, 64> =
+            ImmutableKdTree::par_new_from_slice(&content_to_add[..1_000]);
+
+        sequential.rebuild_from_slice(&content_to_add);
+        parallel.par_rebuild_from_slice(&content_to_add);
+
+        assert_eq!(parallel, sequential);
+    }
+
     #[test]
     fn can_construct_optimized_tree_with_straddled_split() {
         let content_to_add = vec![
@@ -914,4 +2444,332 @@ mod tests {
         let _tree: ImmutableKdTree<f32, usize, 4, 32> =
             ImmutableKdTree::new_from_slice(&content_to_add);
     }
+
+    #[test]
+    fn rebuild_incremental_gives_same_query_results_as_new_from_slice() {
+        use std::num::NonZero;
+
+        const TREE_SIZE: usize = 20_000;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(29);
+        let mut content_to_add: Vec<[f64; 3]> =
+            (0..TREE_SIZE).map(|_| rng.gen::<[f64; 3]>()).collect();
+
+        let mut tree: ImmutableKdTree<f64, u32, 3, 8> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        for tick in 0..20 {
+            // a mixture of small jitters that mostly stay within their leaf,
+            // and teleports that cross split planes high up the tree
+            let changed: Vec<usize> = (0..TREE_SIZE / 50)
+                .map(|_| rng.gen_range(0..TREE_SIZE))
+                .collect();
+            for &idx in &changed {
+                if tick % 2 == 0 {
+                    content_to_add[idx]
+                        .iter_mut()
+                        .for_each(|x| *x = (*x + rng.gen_range(-0.001..0.001)).clamp(0.0, 1.0));
+                } else {
+                    content_to_add[idx] = rng.gen::<[f64; 3]>();
+                }
+            }
+
+            tree.rebuild_incremental(&content_to_add, &changed);
+            let expected: ImmutableKdTree<f64, u32, 3, 8> =
+                ImmutableKdTree::new_from_slice(&content_to_add);
+
+            assert_eq!(tree.size(), TREE_SIZE);
+            for _ in 0..50 {
+                let query_point = rng.gen::<[f64; 3]>();
+
+                assert_eq!(
+                    tree.nearest_one::<SquaredEuclidean>(&query_point),
+                    expected.nearest_one::<SquaredEuclidean>(&query_point)
+                );
+                assert_eq!(
+                    tree.nearest_n_within::<SquaredEuclidean>(
+                        &query_point,
+                        0.01,
+                        NonZero::new(10).unwrap(),
+                        true
+                    ),
+                    expected.nearest_n_within::<SquaredEuclidean>(
+                        &query_point,
+                        0.01,
+                        NonZero::new(10).unwrap(),
+                        true
+                    )
+                );
+                assert_eq!(
+                    tree.within::<SquaredEuclidean>(&query_point, 0.005),
+                    expected.within::<SquaredEuclidean>(&query_point, 0.005)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rebuild_incremental_with_build_options_gives_same_query_results_as_a_new_build() {
+        use crate::immutable::float::builder::{ImmutableKdTreeBuilder, PivotStrategy, SplitAxis};
+        use crate::traits::DistanceMetric;
+        use std::num::NonZero;
+
+        const TREE_SIZE: usize = 5_000;
+
+        let builders = [
+            ImmutableKdTreeBuilder::new()
+                .pivot_strategy(PivotStrategy::SlidingMidpoint)
+                .split_axis(SplitAxis::WidestSpread),
+            ImmutableKdTreeBuilder::new()
+                .pivot_strategy(PivotStrategy::SurfaceAreaHeuristic)
+                .leaf_fill_target(4)
+                .leaf_bounding_boxes(true),
+            ImmutableKdTreeBuilder::new()
+                .split_axis(SplitAxis::WidestSpread)
+                .leaf_bounding_boxes(true),
+        ];
+
+        for builder in builders {
+            let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(37);
+            // stretched along the last axis, so that round-robin and widest-spread
+            // splitting choose different axes
+            let mut content_to_add: Vec<[f64; 3]> = (0..TREE_SIZE)
+                .map(|_| {
+                    let [x, y, z] = rng.gen::<[f64; 3]>();
+                    [x, y, z * 10.0]
+                })
+                .collect();
+
+            let mut tree: ImmutableKdTree<f64, u32, 3, 8> =
+                builder.build_from_slice(&content_to_add);
+
+            for tick in 0..10 {
+                let changed: Vec<usize> = (0..TREE_SIZE / 50)
+                    .map(|_| rng.gen_range(0..TREE_SIZE))
+                    .collect();
+                for &idx in &changed {
+                    if tick % 2 == 0 {
+                        content_to_add[idx]
+                            .iter_mut()
+                            .for_each(|x| *x += rng.gen_range(-0.001..0.001));
+                    } else {
+                        let [x, y, z] = rng.gen::<[f64; 3]>();
+                        content_to_add[idx] = [x, y, z * 10.0];
+                    }
+                }
+
+                tree.rebuild_incremental(&content_to_add, &changed);
+                let expected: ImmutableKdTree<f64, u32, 3, 8> =
+                    builder.build_from_slice(&content_to_add);
+
+                assert_eq!(tree.size(), TREE_SIZE);
+                assert_eq!(tree.pivot_strategy(), expected.pivot_strategy());
+                assert_eq!(tree.split_axis(), expected.split_axis());
+                assert_eq!(
+                    tree.has_leaf_bounding_boxes(),
+                    expected.has_leaf_bounding_boxes()
+                );
+
+                for _ in 0..50 {
+                    let query_point = {
+                        let [x, y, z] = rng.gen::<[f64; 3]>();
+                        [x, y, z * 10.0]
+                    };
+
+                    let nearest = tree.nearest_one::<SquaredEuclidean>(&query_point);
+                    let brute_force = content_to_add
+                        .iter()
+                        .map(|p| SquaredEuclidean::dist(p, &query_point))
+                        .fold(f64::INFINITY, f64::min);
+                    assert_eq!(nearest.distance, brute_force);
+                    assert_eq!(
+                        nearest,
+                        expected.nearest_one::<SquaredEuclidean>(&query_point)
+                    );
+                    assert_eq!(
+                        tree.nearest_n_within::<SquaredEuclidean>(
+                            &query_point,
+                            0.05,
+                            NonZero::new(10).unwrap(),
+                            true
+                        ),
+                        expected.nearest_n_within::<SquaredEuclidean>(
+                            &query_point,
+                            0.05,
+                            NonZero::new(10).unwrap(),
+                            true
+                        )
+                    );
+                    assert_eq!(
+                        tree.within::<SquaredEuclidean>(&query_point, 0.02),
+                        expected.within::<SquaredEuclidean>(&query_point, 0.02)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rebuild_incremental_falls_back_to_full_rebuild_above_max_churn() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(31);
+        let mut content_to_add: Vec<[f32; 2]> = (0..1_000).map(|_| rng.gen::<[f32; 2]>()).collect();
+
+        let mut tree: ImmutableKdTree<f32, u32, 2, 8> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        let changed: Vec<usize> = (0..500).collect();
+        for &idx in &changed {
+            content_to_add[idx] = rng.gen::<[f32; 2]>();
+        }
+        tree.rebuild_incremental_with_max_churn(&content_to_add, &changed, 0.25);
+
+        assert_eq!(tree, ImmutableKdTree::new_from_slice(&content_to_add));
+    }
+
+    #[test]
+    fn rebuild_incremental_falls_back_to_full_rebuild_when_size_changes() {
+        let mut content_to_add: Vec<[f32; 2]> = (0..100).map(|i| [i as f32, 0.0]).collect();
+        let mut tree: ImmutableKdTree<f32, u32, 2, 8> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        content_to_add.push([50.5, 0.0]);
+        tree.rebuild_incremental(&content_to_add, &[100]);
+
+        assert_eq!(tree, ImmutableKdTree::new_from_slice(&content_to_add));
+    }
+
+    #[test]
+    #[should_panic(expected = "changed index 100 is out of range for a source of length 100")]
+    fn rebuild_incremental_rejects_out_of_range_changed_indices() {
+        let content_to_add: Vec<[f32; 2]> = (0..100).map(|i| [i as f32, 0.0]).collect();
+        let mut tree: ImmutableKdTree<f32, u32, 2, 8> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        tree.rebuild_incremental(&content_to_add, &[3, 100]);
+    }
+
+    #[test]
+    #[should_panic(expected = "trees built from points and items can't be rebuilt")]
+    fn rebuild_incremental_rejects_trees_built_from_points_and_items() {
+        let content_to_add: Vec<[f32; 2]> = (0..100).map(|i| [i as f32, 0.0]).collect();
+        let items: Vec<u32> = (0..100).map(|i| i * 10).collect();
+        let tree: ImmutableKdTree<f32, u32, 2, 8> =
+            ImmutableKdTree::new_from_points_and_items(&content_to_add, &items);
+
+        // the way that the tree was built is kept by clones too
+        tree.clone().rebuild_incremental(&content_to_add, &[3]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    #[should_panic(expected = "trees built from points and items can't be rebuilt")]
+    fn rebuild_incremental_rejects_trees_built_from_points_and_items_after_serde() {
+        let content_to_add: Vec<[f32; 2]> = (0..100).map(|i| [i as f32, 0.0]).collect();
+        let items: Vec<u32> = (0..100).map(|i| i * 10).collect();
+        let tree: ImmutableKdTree<f32, u32, 2, 8> =
+            ImmutableKdTree::new_from_points_and_items(&content_to_add, &items);
+
+        // stems can be infinite, which JSON can't represent
+        let serialized = bincode::serialize(&tree).unwrap();
+        let mut deserialized: ImmutableKdTree<f32, u32, 2, 8> =
+            bincode::deserialize(&serialized).unwrap();
+        assert_eq!(tree, deserialized);
+
+        deserialized.rebuild_incremental(&content_to_add, &[3]);
+    }
+
+    #[test]
+    fn construction_sizes_the_stems_exactly() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(17);
+
+        for tree_size in [0usize, 3, 33, 1_000, 4_096, 20_000, 65_536] {
+            let content_to_add: Vec<[f32; 3]> =
+                (0..tree_size).map(|_| rng.gen::<[f32; 3]>()).collect();
+
+            let tree: ImmutableKdTree<f32, u32, 3, 4> =
+                ImmutableKdTree::new_from_slice(&content_to_add);
+
+            assert_eq!(tree.stems.capacity(), tree.stems.len());
+
+            // when every leaf is full, the last stem is the right-most stem on the deepest level
+            if tree_size > 4 && tree_size.is_power_of_two() {
+                assert!(tree.stems.last().unwrap().is_finite());
+            }
+        }
+    }
+
+    #[test]
+    fn queries_match_linear_search_across_cache_line_levels_f64() {
+        assert_queries_match_linear_search::<f64>(7);
+    }
+
+    #[test]
+    fn queries_match_linear_search_across_cache_line_levels_f32() {
+        assert_queries_match_linear_search::<f32>(11);
+    }
+
+    #[cfg(feature = "f16")]
+    #[test]
+    fn queries_match_linear_search_across_cache_line_levels_f16() {
+        assert_queries_match_linear_search::<half::f16>(13);
+    }
+
+    /// Builds a tree deep enough to span several cache lines of stems in the
+    /// modified van Emde Boas layout, including a partially filled final one,
+    /// and checks query results against a linear search. This runs under both
+    /// stem layouts, so it also checks that they agree with one another.
+    fn assert_queries_match_linear_search<A>(seed: u64)
+    where
+        A: Axis + LeafSliceFloat<u32> + LeafSliceFloatChunk<u32, 3>,
+    {
+        use crate::traits::DistanceMetric;
+        use num_traits::NumCast;
+        use std::num::NonZero;
+
+        const TREE_SIZE: usize = 20_000;
+        const NUM_QUERIES: usize = 100;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+        let mut rand_point =
+            || -> [A; 3] { array_init::array_init(|_| NumCast::from(rng.gen::<f64>()).unwrap()) };
+
+        let content_to_add: Vec<[A; 3]> = (0..TREE_SIZE).map(|_| rand_point()).collect();
+        let query_points: Vec<[A; 3]> = (0..NUM_QUERIES).map(|_| rand_point()).collect();
+
+        let tree: ImmutableKdTree<A, u32, 3, 4> = ImmutableKdTree::new_from_slice(&content_to_add);
+        let radius: A = NumCast::from(0.01f64).unwrap();
+
+        for query_point in &query_points {
+            let mut expected: Vec<A> = content_to_add
+                .iter()
+                .map(|p| SquaredEuclidean::dist(query_point, p))
+                .collect();
+            expected.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            let expected_within: Vec<A> = expected
+                .iter()
+                .copied()
+                .take_while(|&d| d < radius)
+                .collect();
+
+            let nearest = tree.nearest_one::<SquaredEuclidean>(query_point);
+            assert_eq!(nearest.distance, expected[0]);
+
+            let nearest_n = tree.nearest_n_within::<SquaredEuclidean>(
+                query_point,
+                radius,
+                NonZero::new(5).unwrap(),
+                true,
+            );
+            assert_eq!(
+                nearest_n.iter().map(|nn| nn.distance).collect::<Vec<_>>(),
+                expected_within.iter().copied().take(5).collect::<Vec<_>>()
+            );
+
+            let within = tree.within::<SquaredEuclidean>(query_point, radius);
+            assert_eq!(
+                within.iter().map(|nn| nn.distance).collect::<Vec<_>>(),
+                expected_within
+            );
+        }
+    }
 }
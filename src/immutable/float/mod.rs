@@ -21,6 +21,7 @@
 //! in the tree must be floats ([`f64`] or [`f32`],
 //! or [`f16`](https://docs.rs/half/latest/half/struct.f16.html) if the `f16` feature is enabled).
 
+pub mod builder;
 pub mod kdtree;
 #[doc(hidden)]
 pub mod query;
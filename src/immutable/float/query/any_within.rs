@@ -0,0 +1,137 @@
+use az::Cast;
+
+use crate::float::kdtree::Axis;
+use crate::float_leaf_slice::leaf_slice::{LeafSliceFloat, LeafSliceFloatChunk};
+use crate::immutable::float::kdtree::ImmutableKdTree;
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_any_within;
+
+macro_rules! generate_immutable_float_any_within {
+    ($doctest_build_tree:tt) => {
+        generate_immutable_any_within!((
+            "Finds an element within `dist` of `query`, using the specified
+distance metric function, or returns `None` if there are none.
+
+Stops searching as soon as an element is found, so the element returned is not
+necessarily the nearest one. Faster than checking whether `within_unsorted` is empty.
+
+# Examples
+
+```rust
+    use kiddo::ImmutableKdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+            $doctest_build_tree,
+            "
+
+    assert!(tree.any_within::<SquaredEuclidean>(&[1.0, 2.0, 5.5], 1f64).is_some());
+    assert!(tree.any_within::<SquaredEuclidean>(&[10.0, 20.0, 50.0], 1f64).is_none());
+```"
+        ));
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+    T: Content,
+    usize: Cast<T>,
+{
+    generate_immutable_float_any_within!(
+        "let content: Vec<[f64; 3]> = vec!(
+            [1.0, 2.0, 5.0],
+            [2.0, 3.0, 6.0]
+        );
+
+        let tree: ImmutableKdTree<f64, 3> = ImmutableKdTree::new_from_slice(&content);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+#[cfg(feature = "rkyv")]
+impl<A, T, const K: usize, const B: usize> AlignedArchivedImmutableKdTree<'_, A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K> + rkyv::Archive<Archived = A>,
+    T: Content + rkyv::Archive<Archived = T>,
+    usize: Cast<T>,
+{
+    generate_immutable_float_any_within!(
+        "use std::fs::File;
+    use memmap::MmapOptions;
+
+    use kiddo::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+
+    let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/immutable-doctest-tree.rkyv\").unwrap()).unwrap() };
+    let tree: AlignedArchivedImmutableKdTree<f64, u32, 3, 256> = AlignedArchivedImmutableKdTree::from_bytes(&mmap);"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::SquaredEuclidean;
+    use crate::immutable::float::builder::ImmutableKdTreeBuilder;
+    use crate::immutable::float::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f64;
+
+    #[test]
+    fn can_find_any_item_within_radius() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(83);
+        let content: Vec<[AX; 3]> = (0..5_000).map(|_| rng.gen::<[AX; 3]>()).collect();
+
+        for leaf_bounding_boxes in [false, true] {
+            let tree: ImmutableKdTree<AX, u32, 3, 128> = ImmutableKdTreeBuilder::new()
+                .leaf_bounding_boxes(leaf_bounding_boxes)
+                .build_from_slice(&content);
+
+            for _ in 0..1_000 {
+                let query_point = rng.gen::<[AX; 3]>();
+                let radius = rng.gen_range(0.0..0.002);
+
+                let any_expected = content
+                    .iter()
+                    .any(|point| SquaredEuclidean::dist(&query_point, point) < radius);
+
+                match tree.any_within::<SquaredEuclidean>(&query_point, radius) {
+                    Some(found) => {
+                        let point = content[found.item as usize];
+                        assert_eq!(found.distance, SquaredEuclidean::dist(&query_point, &point));
+                        assert!(found.distance < radius);
+                    }
+                    None => assert!(!any_expected),
+                }
+            }
+        }
+
+        let tree: ImmutableKdTree<AX, u32, 3, 32> = ImmutableKdTree::new_from_slice(&[]);
+        assert!(tree
+            .any_within::<SquaredEuclidean>(&[0.5; 3], 10.0)
+            .is_none());
+    }
+
+    #[test]
+    fn does_not_find_items_exactly_on_the_radius() {
+        // more than one chunk's worth of points in a single leaf, so the points
+        // are split between whole chunks and the remainder
+        let content: Vec<[AX; 2]> = [[1.0, 0.0], [0.0, 1.0], [-1.0, 0.0], [0.0, -1.0]]
+            .into_iter()
+            .cycle()
+            .take(40)
+            .collect();
+
+        let tree: ImmutableKdTree<AX, u32, 2, 64> = ImmutableKdTree::new_from_slice(&content);
+        assert!(tree
+            .any_within::<SquaredEuclidean>(&[0.0, 0.0], 1.0)
+            .is_none());
+        assert!(tree
+            .any_within::<SquaredEuclidean>(&[0.0, 0.0], 1.01)
+            .is_some());
+    }
+}
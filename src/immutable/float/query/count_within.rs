@@ -0,0 +1,180 @@
+use az::Cast;
+
+use crate::float::kdtree::Axis;
+use crate::float_leaf_slice::leaf_slice::{LeafSliceFloat, LeafSliceFloatChunk};
+use crate::immutable::float::kdtree::ImmutableKdTree;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_count_within;
+
+macro_rules! generate_immutable_float_count_within {
+    ($doctest_build_tree:tt) => {
+        generate_immutable_count_within!(
+            (
+                "Counts the elements within `dist` of `query`, using the specified
+distance metric function.
+
+Equivalent to `within_unsorted(query, dist).len()`, but without collecting the results.
+
+# Examples
+
+```rust
+    use kiddo::ImmutableKdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    assert_eq!(tree.count_within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64), 2);
+    assert_eq!(tree.count_within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 1f64), 1);
+```"
+            ),
+            (
+                "Counts the elements within `dist` of `query`, using the specified
+distance metric function, stopping once `limit` elements have been found.
+
+Returns the smaller of `limit` and the number of elements within `dist` of `query`.
+Useful for threshold checks, such as whether at least `limit` elements are nearby.
+
+# Examples
+
+```rust
+    use kiddo::ImmutableKdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    assert_eq!(tree.count_within_at_most::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64, 1), 1);
+    assert_eq!(tree.count_within_at_most::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10f64, 5), 2);
+```"
+            )
+        );
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+    T: Content,
+    usize: Cast<T>,
+{
+    generate_immutable_float_count_within!(
+        "let content: Vec<[f64; 3]> = vec!(
+            [1.0, 2.0, 5.0],
+            [2.0, 3.0, 6.0]
+        );
+
+        let tree: ImmutableKdTree<f64, 3> = ImmutableKdTree::new_from_slice(&content);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+#[cfg(feature = "rkyv")]
+impl<A, T, const K: usize, const B: usize> AlignedArchivedImmutableKdTree<'_, A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K> + rkyv::Archive<Archived = A>,
+    T: Content + rkyv::Archive<Archived = T>,
+    usize: Cast<T>,
+{
+    generate_immutable_float_count_within!(
+        "use std::fs::File;
+    use memmap::MmapOptions;
+
+    use kiddo::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+
+    let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/immutable-doctest-tree.rkyv\").unwrap()).unwrap() };
+    let tree: AlignedArchivedImmutableKdTree<f64, u32, 3, 256> = AlignedArchivedImmutableKdTree::from_bytes(&mmap);"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::SquaredEuclidean;
+    use crate::immutable::float::builder::ImmutableKdTreeBuilder;
+    use crate::immutable::float::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f64;
+
+    #[test]
+    fn can_count_items_within_radius() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(73);
+        let content: Vec<[AX; 3]> = (0..20_000).map(|_| rng.gen::<[AX; 3]>()).collect();
+
+        for leaf_bounding_boxes in [false, true] {
+            // leaves of up to 128 items are scanned mostly in whole chunks
+            let tree: ImmutableKdTree<AX, u32, 3, 128> = ImmutableKdTreeBuilder::new()
+                .leaf_bounding_boxes(leaf_bounding_boxes)
+                .build_from_slice(&content);
+
+            for _ in 0..100 {
+                let query_point = rng.gen::<[AX; 3]>();
+                let radius = rng.gen_range(0.0..0.05);
+
+                let expected = content
+                    .iter()
+                    .filter(|point| SquaredEuclidean::dist(&query_point, point) < radius)
+                    .count();
+
+                assert_eq!(
+                    tree.count_within::<SquaredEuclidean>(&query_point, radius),
+                    expected
+                );
+                assert_eq!(
+                    tree.count_within::<SquaredEuclidean>(&query_point, radius),
+                    tree.within_unsorted::<SquaredEuclidean>(&query_point, radius)
+                        .len()
+                );
+
+                for limit in [0, 1, 33, expected / 2, expected, expected + 1] {
+                    assert_eq!(
+                        tree.count_within_at_most::<SquaredEuclidean>(&query_point, radius, limit),
+                        expected.min(limit)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn can_count_in_single_bucket_and_empty_trees() {
+        let content: Vec<[AX; 3]> = vec![[1.0, 2.0, 5.0], [2.0, 3.0, 6.0]];
+        let tree: ImmutableKdTree<AX, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+        assert_eq!(
+            tree.count_within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10.0),
+            2
+        );
+
+        let tree: ImmutableKdTree<AX, u32, 3, 32> = ImmutableKdTree::new_from_slice(&[]);
+
+        assert_eq!(
+            tree.count_within::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 10.0),
+            0
+        );
+    }
+
+    #[test]
+    fn does_not_count_items_exactly_on_the_radius() {
+        // more than one chunk's worth of points in a single leaf, so the points
+        // are split between whole chunks and the remainder
+        let content: Vec<[AX; 2]> = [[1.0, 0.0], [0.0, 1.0], [-1.0, 0.0], [0.0, -1.0]]
+            .into_iter()
+            .cycle()
+            .take(40)
+            .collect();
+
+        let tree: ImmutableKdTree<AX, u32, 2, 64> = ImmutableKdTree::new_from_slice(&content);
+        assert_eq!(tree.count_within::<SquaredEuclidean>(&[0.0, 0.0], 1.0), 0);
+        assert_eq!(tree.count_within::<SquaredEuclidean>(&[0.0, 0.0], 1.01), 40);
+
+        let tree: ImmutableKdTree<AX, u32, 2, 64> = ImmutableKdTree::new_from_slice(&content[..8]);
+        assert_eq!(tree.count_within::<SquaredEuclidean>(&[0.0, 0.0], 1.0), 0);
+    }
+}
@@ -0,0 +1,229 @@
+use az::Cast;
+use sorted_vec::SortedVec;
+use std::collections::BinaryHeap;
+use std::num::NonZero;
+
+use crate::float::kdtree::Axis;
+use crate::float::result_collection::ResultCollection;
+use crate::float_leaf_slice::leaf_slice::{LeafSliceFloat, LeafSliceFloatChunk};
+use crate::immutable::float::kdtree::ImmutableKdTree;
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_filtered;
+
+const MAX_VEC_RESULT_SIZE: usize = 20;
+
+macro_rules! generate_immutable_float_filtered {
+    ($doctest_build_tree:tt) => {
+        generate_immutable_filtered!(
+            (
+                "Finds the nearest element to `query` for which `filter` returns `true`, using
+the specified distance metric function, or returns `None` if there are none.
+
+Items that are rejected by `filter` do not narrow the search, so this always finds
+the nearest accepted item, unlike filtering the results of `nearest_n`.
+
+# Examples
+
+```rust
+    use kiddo::ImmutableKdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let nearest = tree.nearest_one_filtered::<SquaredEuclidean, _>(&[1.0, 2.0, 5.1], |item| item != 0);
+
+    assert_eq!(nearest.unwrap().item, 1);
+```"
+            ),
+            (
+                "Finds the nearest `max_qty` elements to `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use std::num::NonZero;
+    use kiddo::ImmutableKdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let nearest = tree.nearest_n_filtered::<SquaredEuclidean, _>(&[1.0, 2.0, 5.1], NonZero::new(2).unwrap(), |item| item % 2 == 1);
+
+    assert_eq!(nearest.len(), 1);
+    assert_eq!(nearest[0].item, 1);
+```"
+            ),
+            (
+                "Finds all elements within `dist` of `query` for which `filter` returns `true`,
+using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use kiddo::ImmutableKdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let within = tree.within_filtered::<SquaredEuclidean, _>(&[1.0, 2.0, 5.0], 10f64, |item| item > 0);
+
+    assert_eq!(within.len(), 1);
+    assert_eq!(within[0].item, 1);
+```"
+            )
+        );
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+    T: Content,
+    usize: Cast<T>,
+{
+    generate_immutable_float_filtered!(
+        "let content: Vec<[f64; 3]> = vec!(
+            [1.0, 2.0, 5.0],
+            [2.0, 3.0, 6.0]
+        );
+
+        let tree: ImmutableKdTree<f64, 3> = ImmutableKdTree::new_from_slice(&content);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+#[cfg(feature = "rkyv")]
+impl<A, T, const K: usize, const B: usize> AlignedArchivedImmutableKdTree<'_, A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K> + rkyv::Archive<Archived = A>,
+    T: Content + rkyv::Archive<Archived = T>,
+    usize: Cast<T>,
+{
+    generate_immutable_float_filtered!(
+        "use std::fs::File;
+    use memmap::MmapOptions;
+
+    use kiddo::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+
+    let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/immutable-doctest-tree.rkyv\").unwrap()).unwrap() };
+    let tree: AlignedArchivedImmutableKdTree<f64, u32, 3, 256> = AlignedArchivedImmutableKdTree::from_bytes(&mmap);"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::SquaredEuclidean;
+    use crate::immutable::float::builder::ImmutableKdTreeBuilder;
+    use crate::immutable::float::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+    use std::num::NonZero;
+
+    type AX = f64;
+
+    fn linear_search(
+        content: &[[AX; 3]],
+        query_point: &[AX; 3],
+        radius: AX,
+        filter: impl Fn(u32) -> bool,
+    ) -> Vec<(AX, u32)> {
+        let mut matching_items: Vec<(AX, u32)> = content
+            .iter()
+            .enumerate()
+            .map(|(item, point)| (SquaredEuclidean::dist(query_point, point), item as u32))
+            .filter(|&(distance, item)| distance < radius && filter(item))
+            .collect();
+        matching_items.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        matching_items
+    }
+
+    #[test]
+    fn can_query_filtered_items() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(97);
+        let content: Vec<[AX; 3]> = (0..10_000).map(|_| rng.gen::<[AX; 3]>()).collect();
+
+        let filter = |item: u32| item % 97 == 3;
+
+        for leaf_bounding_boxes in [false, true] {
+            // leaves of up to 128 items are scanned mostly in whole chunks
+            let tree: ImmutableKdTree<AX, u32, 3, 128> = ImmutableKdTreeBuilder::new()
+                .leaf_bounding_boxes(leaf_bounding_boxes)
+                .build_from_slice(&content);
+
+            for _ in 0..100 {
+                let query_point = rng.gen::<[AX; 3]>();
+                let expected = linear_search(&content, &query_point, AX::INFINITY, filter);
+
+                let nearest = tree
+                    .nearest_one_filtered::<SquaredEuclidean, _>(&query_point, filter)
+                    .unwrap();
+                assert_eq!((nearest.distance, nearest.item), expected[0]);
+
+                for qty in [5, 30] {
+                    let nearest: Vec<_> = tree
+                        .nearest_n_filtered::<SquaredEuclidean, _>(
+                            &query_point,
+                            NonZero::new(qty).unwrap(),
+                            filter,
+                        )
+                        .into_iter()
+                        .map(|n| (n.distance, n.item))
+                        .collect();
+                    assert_eq!(nearest, expected[..qty]);
+                }
+
+                let radius = 0.05;
+                let within: Vec<_> = tree
+                    .within_filtered::<SquaredEuclidean, _>(&query_point, radius, filter)
+                    .into_iter()
+                    .map(|n| (n.distance, n.item))
+                    .collect();
+                assert_eq!(
+                    within,
+                    linear_search(&content, &query_point, radius, filter)
+                );
+            }
+
+            assert!(tree
+                .nearest_one_filtered::<SquaredEuclidean, _>(&[0.5; 3], |_| false)
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn does_not_return_filtered_items_exactly_on_the_radius() {
+        // more than one chunk's worth of points in a single leaf, so the points
+        // are split between whole chunks and the remainder
+        let content: Vec<[AX; 2]> = [[1.0, 0.0], [0.0, 1.0], [-1.0, 0.0], [0.0, -1.0]]
+            .into_iter()
+            .cycle()
+            .take(40)
+            .collect();
+
+        let tree: ImmutableKdTree<AX, u32, 2, 64> = ImmutableKdTree::new_from_slice(&content);
+        assert!(tree
+            .within_filtered::<SquaredEuclidean, _>(&[0.0, 0.0], 1.0, |_| true)
+            .is_empty());
+        assert_eq!(
+            tree.within_filtered::<SquaredEuclidean, _>(&[0.0, 0.0], 1.01, |_| true)
+                .len(),
+            40
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use az::Cast;
+use sorted_vec::SortedVec;
+use std::collections::BinaryHeap;
+use std::num::NonZero;
+
+use crate::float::kdtree::Axis;
+use crate::float::result_collection::ResultCollection;
+use crate::float_leaf_slice::leaf_slice::{LeafSliceFloat, LeafSliceFloatChunk};
+use crate::immutable::float::kdtree::ImmutableKdTree;
+use crate::nearest_neighbour::NearestNeighbour;
+use crate::traits::Content;
+use crate::traits::DistanceMetric;
+
+use crate::generate_immutable_nearest_excluding;
+
+const MAX_VEC_RESULT_SIZE: usize = 20;
+
+macro_rules! generate_immutable_float_nearest_excluding {
+    ($doctest_build_tree:tt) => {
+        generate_immutable_nearest_excluding!(
+            (
+                "Queries the tree to find the nearest item to the `query` point, ignoring
+any elements whose item is `excluded`.
+
+Intended for querying with a point that is itself stored in the tree, such as when
+building a neighbour graph. Returns `None` if the tree holds no other items.
+
+# Examples
+
+```rust
+    use kiddo::ImmutableKdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let nearest = tree.nearest_one_excluding::<SquaredEuclidean>(&[1.0, 2.0, 5.0], 0).unwrap();
+
+    assert_eq!(nearest.distance, 3.0);
+    assert_eq!(nearest.item, 1);
+```"
+            ),
+            (
+                "Finds the nearest `max_qty` elements to `query`, ignoring any elements whose
+item is `excluded`, using the specified distance metric function.
+
+Results are returned sorted nearest-first.
+
+# Examples
+
+```rust
+    use std::num::NonZero;
+    use kiddo::ImmutableKdTree;
+    use kiddo::SquaredEuclidean;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let nearest = tree.nearest_n_excluding::<SquaredEuclidean>(&[1.0, 2.0, 5.0], NonZero::new(2).unwrap(), 0);
+
+    assert_eq!(nearest.len(), 1);
+    assert_eq!(nearest[0].item, 1);
+```"
+            )
+        );
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+    T: Content,
+    usize: Cast<T>,
+{
+    generate_immutable_float_nearest_excluding!(
+        "let content: Vec<[f64; 3]> = vec!(
+            [1.0, 2.0, 5.0],
+            [2.0, 3.0, 6.0]
+        );
+
+        let tree: ImmutableKdTree<f64, 3> = ImmutableKdTree::new_from_slice(&content);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+#[cfg(feature = "rkyv")]
+impl<A, T, const K: usize, const B: usize> AlignedArchivedImmutableKdTree<'_, A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K> + rkyv::Archive<Archived = A>,
+    T: Content + rkyv::Archive<Archived = T>,
+    usize: Cast<T>,
+{
+    generate_immutable_float_nearest_excluding!(
+        "use std::fs::File;
+    use memmap::MmapOptions;
+
+    use kiddo::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+
+    let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/immutable-doctest-tree.rkyv\").unwrap()).unwrap() };
+    let tree: AlignedArchivedImmutableKdTree<f64, u32, 3, 256> = AlignedArchivedImmutableKdTree::from_bytes(&mmap);"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float::distance::SquaredEuclidean;
+    use crate::immutable::float::builder::ImmutableKdTreeBuilder;
+    use crate::immutable::float::kdtree::ImmutableKdTree;
+    use crate::traits::DistanceMetric;
+    use rand::{Rng, SeedableRng};
+    use std::num::NonZero;
+
+    type AX = f64;
+
+    fn linear_search(content: &[[AX; 3]], query_point: &[AX; 3], excluded: u32) -> Vec<(AX, u32)> {
+        let mut matching_items: Vec<(AX, u32)> = content
+            .iter()
+            .enumerate()
+            .filter(|&(item, _)| item as u32 != excluded)
+            .map(|(item, point)| (SquaredEuclidean::dist(query_point, point), item as u32))
+            .collect();
+        matching_items.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        matching_items
+    }
+
+    #[test]
+    fn can_query_nearest_items_excluding_the_query_item() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(103);
+        let content: Vec<[AX; 3]> = (0..10_000).map(|_| rng.gen::<[AX; 3]>()).collect();
+
+        for leaf_bounding_boxes in [false, true] {
+            // leaves of up to 128 items are scanned mostly in whole chunks
+            let tree: ImmutableKdTree<AX, u32, 3, 128> = ImmutableKdTreeBuilder::new()
+                .leaf_bounding_boxes(leaf_bounding_boxes)
+                .build_from_slice(&content);
+
+            for (item, point) in content.iter().enumerate().step_by(100) {
+                let expected = linear_search(&content, point, item as u32);
+
+                let nearest = tree
+                    .nearest_one_excluding::<SquaredEuclidean>(point, item as u32)
+                    .unwrap();
+                assert_eq!((nearest.distance, nearest.item), expected[0]);
+
+                for qty in [5, 30] {
+                    let nearest: Vec<_> = tree
+                        .nearest_n_excluding::<SquaredEuclidean>(
+                            point,
+                            NonZero::new(qty).unwrap(),
+                            item as u32,
+                        )
+                        .into_iter()
+                        .map(|n| (n.distance, n.item))
+                        .collect();
+                    assert_eq!(nearest, expected[..qty]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn finds_nothing_when_only_the_excluded_item_is_present() {
+        let tree: ImmutableKdTree<AX, u32, 3, 32> = ImmutableKdTree::new_from_slice(&[[0.5; 3]]);
+
+        assert_eq!(
+            tree.nearest_one_excluding::<SquaredEuclidean>(&[0.5; 3], 0),
+            None
+        );
+        assert!(tree
+            .nearest_n_excluding::<SquaredEuclidean>(&[0.5; 3], NonZero::new(3).unwrap(), 0)
+            .is_empty());
+    }
+
+    #[test]
+    fn finds_nothing_in_an_empty_tree() {
+        let tree: ImmutableKdTree<AX, u32, 3, 32> = ImmutableKdTree::new_from_slice(&[]);
+
+        assert_eq!(
+            tree.nearest_one_excluding::<SquaredEuclidean>(&[0.5; 3], 0),
+            None
+        );
+        assert!(tree
+            .nearest_n_excluding::<SquaredEuclidean>(&[0.5; 3], NonZero::new(3).unwrap(), 0)
+            .is_empty());
+    }
+}
@@ -60,6 +60,10 @@ impl<
         const K: usize,
         const B: usize,
     > AlignedArchivedImmutableKdTree<'_, A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+    T: Content,
+    usize: Cast<T>,
 {
     generate_immutable_float_nearest_n!(
         "use std::fs::File;
@@ -1,6 +1,4 @@
 use az::Cast;
-use sorted_vec::SortedVec;
-use std::collections::BinaryHeap;
 use std::num::NonZero;
 use std::ops::Rem;
 
@@ -197,6 +195,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_query_items_within_radius_in_leaves_larger_than_a_chunk() {
+        const TREE_SIZE: usize = 10_000;
+        const NUM_QUERIES: usize = 100;
+        const RADIUS: f32 = 0.2;
+
+        let max_qty: NonZero<usize> = NonZero::new(10).unwrap();
+
+        let content_to_add: Vec<[f32; 4]> =
+            (0..TREE_SIZE).map(|_| rand::random::<[f32; 4]>()).collect();
+
+        // leaves of up to 128 items hold points after their last whole chunk
+        let tree: ImmutableKdTree<AX, u32, 4, 128> =
+            ImmutableKdTree::new_from_slice(&content_to_add);
+
+        for _ in 0..NUM_QUERIES {
+            let query_point = rand::random::<[f32; 4]>();
+            let expected = linear_search(&content_to_add, &query_point, RADIUS)
+                .into_iter()
+                .take(max_qty.into())
+                .collect::<Vec<_>>();
+
+            let mut result: Vec<_> = tree
+                .nearest_n_within::<SquaredEuclidean>(&query_point, RADIUS, max_qty, true)
+                .into_iter()
+                .map(|n| (n.distance, n.item))
+                .collect();
+
+            stabilize_sort(&mut result);
+
+            assert_eq!(result, expected);
+        }
+    }
+
     fn linear_search<A: Axis, const K: usize>(
         content: &[[A; K]],
         query_point: &[A; K],
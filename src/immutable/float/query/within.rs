@@ -57,6 +57,10 @@ impl<
         const K: usize,
         const B: usize,
     > AlignedArchivedImmutableKdTree<'_, A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+    T: Content,
+    usize: Cast<T>,
 {
     generate_immutable_float_within!(
         "use std::fs::File;
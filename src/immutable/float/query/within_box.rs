@@ -0,0 +1,203 @@
+use az::Cast;
+use std::ops::Rem;
+
+use crate::float::kdtree::Axis;
+use crate::float_leaf_slice::leaf_slice::{LeafSlice, LeafSliceFloat, LeafSliceFloatChunk};
+use crate::immutable::float::kdtree::ImmutableKdTree;
+use crate::traits::Content;
+
+use crate::generate_immutable_within_box;
+
+macro_rules! generate_immutable_float_within_box {
+    ($doctest_build_tree:tt) => {
+        generate_immutable_within_box!(
+            (
+                "Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use kiddo::ImmutableKdTree;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let within = tree.within_box(&[0.0, 0.0, 0.0], &[1.5, 2.5, 5.5]);
+
+    assert_eq!(within, vec![0]);
+```"
+            ),
+            (
+                "Finds all elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Returns an `Iterator`. Results are returned in arbitrary order.
+
+# Examples
+
+```rust
+    use kiddo::ImmutableKdTree;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    let within = tree.within_box_iter(&[0.0, 0.0, 0.0], &[10.0, 10.0, 10.0]).collect::<Vec<_>>();
+
+    assert_eq!(within.len(), 2);
+```"
+            ),
+            (
+                "Counts the elements whose position lies within the axis-aligned box from `min`
+to `max`, inclusive.
+
+Faster than `within_box(min, max).len()`, since leaves that lie entirely within the box
+are counted without looking at the points that they hold.
+
+# Examples
+
+```rust
+    use kiddo::ImmutableKdTree;
+
+    ",
+                $doctest_build_tree,
+                "
+
+    assert_eq!(tree.count_in_box(&[0.0, 0.0, 0.0], &[10.0, 10.0, 10.0]), 2);
+    assert_eq!(tree.count_in_box(&[1.5, 2.5, 5.5], &[10.0, 10.0, 10.0]), 1);
+```"
+            )
+        );
+    };
+}
+
+impl<A: Axis, T: Content, const K: usize, const B: usize> ImmutableKdTree<A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K>,
+    T: Content,
+    usize: Cast<T>,
+{
+    generate_immutable_float_within_box!(
+        "let content: Vec<[f64; 3]> = vec!(
+            [1.0, 2.0, 5.0],
+            [2.0, 3.0, 6.0]
+        );
+
+        let tree: ImmutableKdTree<f64, 3> = ImmutableKdTree::new_from_slice(&content);"
+    );
+}
+
+#[cfg(feature = "rkyv")]
+use crate::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+#[cfg(feature = "rkyv")]
+impl<A, T, const K: usize, const B: usize> AlignedArchivedImmutableKdTree<'_, A, T, K, B>
+where
+    A: Axis + LeafSliceFloat<T> + LeafSliceFloatChunk<T, K> + rkyv::Archive<Archived = A>,
+    T: Content + rkyv::Archive<Archived = T>,
+    usize: Cast<T>,
+{
+    generate_immutable_float_within_box!(
+        "use std::fs::File;
+    use memmap::MmapOptions;
+
+    use kiddo::immutable::float::kdtree::AlignedArchivedImmutableKdTree;
+
+    let mmap = unsafe { MmapOptions::new().map(&File::open(\"./examples/immutable-doctest-tree.rkyv\").unwrap()).unwrap() };
+    let tree: AlignedArchivedImmutableKdTree<f64, u32, 3, 256> = AlignedArchivedImmutableKdTree::from_bytes(&mmap);"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::immutable::float::builder::{ImmutableKdTreeBuilder, PivotStrategy, SplitAxis};
+    use crate::immutable::float::kdtree::ImmutableKdTree;
+    use rand::{Rng, SeedableRng};
+
+    type AX = f64;
+
+    fn linear_search(content: &[[AX; 3]], min: &[AX; 3], max: &[AX; 3]) -> Vec<u32> {
+        content
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| (0..3).all(|dim| point[dim] >= min[dim] && point[dim] <= max[dim]))
+            .map(|(item, _)| item as u32)
+            .collect()
+    }
+
+    #[test]
+    fn can_query_items_within_box() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(67);
+        // every other point is snapped to a coarse grid, so that many points share
+        // values with each other and with the stems' split values
+        let content: Vec<[AX; 3]> = (0..5_000)
+            .map(|idx| {
+                let point: [AX; 3] = [rng.gen(), rng.gen::<AX>() * 10.0, rng.gen()];
+                if idx % 2 == 0 {
+                    point.map(|val| (val * 4.0).round() / 4.0)
+                } else {
+                    point
+                }
+            })
+            .collect();
+
+        for split_axis in [SplitAxis::RoundRobin, SplitAxis::WidestSpread] {
+            for pivot_strategy in [PivotStrategy::Median, PivotStrategy::SlidingMidpoint] {
+                for leaf_bounding_boxes in [false, true] {
+                    let tree: ImmutableKdTree<AX, u32, 3, 32> = ImmutableKdTreeBuilder::new()
+                        .split_axis(split_axis)
+                        .pivot_strategy(pivot_strategy)
+                        .leaf_bounding_boxes(leaf_bounding_boxes)
+                        .build_from_slice(&content);
+
+                    for _ in 0..100 {
+                        let corners: [[AX; 3]; 2] = [
+                            [rng.gen(), rng.gen::<AX>() * 10.0, rng.gen()],
+                            [rng.gen(), rng.gen::<AX>() * 10.0, rng.gen()],
+                        ];
+                        let min = [0, 1, 2].map(|dim| corners[0][dim].min(corners[1][dim]));
+                        let max = [0, 1, 2].map(|dim| corners[0][dim].max(corners[1][dim]));
+                        let expected = linear_search(&content, &min, &max);
+
+                        let mut result = tree.within_box(&min, &max);
+                        result.sort_unstable();
+                        assert_eq!(result, expected);
+
+                        let mut result: Vec<_> = tree.within_box_iter(&min, &max).collect();
+                        result.sort_unstable();
+                        assert_eq!(result, expected);
+
+                        assert_eq!(tree.count_in_box(&min, &max), expected.len());
+                    }
+
+                    let grid_point = [0.25, 2.5, 0.75];
+                    assert_eq!(
+                        tree.count_in_box(&grid_point, &grid_point),
+                        linear_search(&content, &grid_point, &grid_point).len()
+                    );
+                    assert_eq!(
+                        tree.count_in_box(&[AX::NEG_INFINITY; 3], &[AX::INFINITY; 3]),
+                        content.len()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn can_query_single_bucket_and_empty_trees() {
+        let content: Vec<[AX; 3]> = vec![[1.0, 2.0, 5.0], [2.0, 3.0, 6.0]];
+        let tree: ImmutableKdTree<AX, u32, 3, 32> = ImmutableKdTree::new_from_slice(&content);
+
+        assert_eq!(tree.within_box(&[1.0, 2.0, 5.0], &[1.0, 2.0, 5.0]), vec![0]);
+        assert_eq!(tree.count_in_box(&[0.0; 3], &[10.0; 3]), 2);
+
+        let tree: ImmutableKdTree<AX, u32, 3, 32> = ImmutableKdTree::new_from_slice(&[]);
+
+        assert!(tree.within_box(&[0.0; 3], &[10.0; 3]).is_empty());
+        assert_eq!(tree.count_in_box(&[0.0; 3], &[10.0; 3]), 0);
+    }
+}
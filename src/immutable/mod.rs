@@ -18,8 +18,10 @@
 //!    NB construction time improved massively in Kiddo v5 compared to earlier versions.
 //!
 //! As per the other Kiddo float-type trees, points being stored
-//! in the tree must be floats ([`f64`] or [`f32`],
+//! in a [`float::kdtree::ImmutableKdTree`] must be floats ([`f64`] or [`f32`],
 //! or [`f16`](https://docs.rs/half/latest/half/struct.f16.html) if the `f16` feature is enabled).
+//! Fixed point or integer co-ordinates can be stored in a [`fixed::kdtree::ImmutableKdTree`].
 #[doc(hidden)]
 pub(crate) mod common;
+pub mod fixed;
 pub mod float;
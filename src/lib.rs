@@ -18,6 +18,8 @@
 //!   points to the tree after the initial construction / deserialization
 //! - An [`ImmutableKdTree`](`immutable::float::kdtree::ImmutableKdTree`) with performance space and advantages over the standard
 //!   k-d tree, for situations where the tree does not need to be modified after creation
+//! - An experimental [hybrid k-d tree](`hybrid::kdtree::KdTree`) that can be created already optimized
+//!   like the [`ImmutableKdTree`](`immutable::float::kdtree::ImmutableKdTree`), but still accepts inserts
 //! - **integer / fixed point support** via the [`fixed`](https://docs.rs/fixed/latest/fixed/) crate;
 //! - **`f16` support** via the [`half`](https://docs.rs/half/latest/half/) crate;
 //! - **instant zero-copy deserialization** and serialization via [`Rkyv`](https://docs.rs/rkyv/latest/rkyv/) ([`Serde`](https://docs.rs/serde/latest/serde/) still available).
@@ -78,6 +80,8 @@
 //! * **rkyv** - zero-copy serialization / deserialization via [`Rkyv`](https://docs.rs/rkyv/latest/rkyv/)
 //! * `simd` **(NIGHTLY)** - enables some hand written SIMD and pre-fetch intrinsics code within [`ImmutableKdTree`](`immutable::float::kdtree::ImmutableKdTree`) that may improve performance (currently only on nearest_one with `f64`)
 //! * `f16` - enables usage of `f16` from the `half` crate for float trees.
+//! * `rayon` - enables parallel construction of [`ImmutableKdTree`](`immutable::float::kdtree::ImmutableKdTree`) via
+//!   `par_new_from_slice` and `par_rebuild_from_slice`.
 
 #[macro_use]
 extern crate doc_comment;
@@ -90,12 +94,17 @@ pub(crate) mod common;
 #[cfg(feature = "serde")]
 #[doc(hidden)]
 mod custom_serde;
+pub mod error;
 pub mod fixed;
 pub mod float;
+pub mod hybrid;
 pub mod immutable;
 mod mirror_select_nth_unstable_by;
 #[doc(hidden)]
 pub mod nearest_neighbour;
+mod packed_leaves;
+pub mod split_axis;
+pub mod stats;
 #[doc(hidden)]
 #[cfg(feature = "test_utils")]
 pub mod test_utils;
@@ -131,9 +140,12 @@ pub type ImmutableKdTree<A, const K: usize> =
     immutable::float::kdtree::ImmutableKdTree<A, u64, K, 32>;
 
 pub use best_neighbour::BestNeighbour;
+pub use error::KiddoError;
 pub use float::distance::Manhattan;
 pub use float::distance::SquaredEuclidean;
 pub use nearest_neighbour::NearestNeighbour;
+pub use split_axis::SplitAxis;
+pub use stats::TreeStats;
 
 #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub use within_unsorted_iter::WithinUnsortedIter;
@@ -1,60 +1,81 @@
 use cmov::Cmov;
+use std::mem::size_of;
 
 const CACHE_LINE_WIDTH: u32 = 64; // Intel and AMD x86-64 have 64 byte cache lines. Apple M2 has 128
-const FLOAT_WIDTH: u32 = 8; // f64 = 8 bytes; f32 = 4 bytes
-const ITEMS_PER_CACHE_LINE: u32 = CACHE_LINE_WIDTH / FLOAT_WIDTH; // f64 = 8 items; f32 = 16 items
-const ITEMS_PER_CACHE_LINE_MASK: u32 = ITEMS_PER_CACHE_LINE - 1;
-const ITEMS_PER_CACHE_LINE_MASK_INV: u32 = !ITEMS_PER_CACHE_LINE_MASK;
-const LOG2_ITEMS_PER_CACHE_LINE: u32 = ITEMS_PER_CACHE_LINE.ilog2(); // f64 = 3 levels; f32 = 4 levels
+
+/// Number of stems of type `A` that fit in a single cache line.
+/// f64 = 8 items; f32 = 16 items; f16 = 32 items
+#[inline(always)]
+pub(crate) const fn items_per_cache_line<A>() -> u32 {
+    CACHE_LINE_WIDTH / size_of::<A>() as u32
+}
+
+/// Number of tree levels held in each cache line's minor triangle.
+/// f64 = 3 levels; f32 = 4 levels; f16 = 5 levels
+#[inline(always)]
+pub(crate) const fn log2_items_per_cache_line<A>() -> u32 {
+    items_per_cache_line::<A>().ilog2()
+}
 
 #[allow(dead_code)]
 #[inline]
-pub(crate) fn modified_van_emde_boas_get_child_idx_v2(
+pub(crate) fn modified_van_emde_boas_get_child_idx_v2<A>(
     curr_idx: u32,
     is_right_child: bool,
     level: u32,
 ) -> u32 {
-    let minor_level = level % LOG2_ITEMS_PER_CACHE_LINE;
-    let maj_idx = curr_idx >> LOG2_ITEMS_PER_CACHE_LINE;
-    let min_idx = curr_idx & ITEMS_PER_CACHE_LINE_MASK;
+    let log2_items_per_cache_line = log2_items_per_cache_line::<A>();
+    let items_per_cache_line_mask = items_per_cache_line::<A>() - 1;
+
+    let minor_level = level % log2_items_per_cache_line;
+    let maj_idx = curr_idx >> log2_items_per_cache_line;
+    let min_idx = curr_idx & items_per_cache_line_mask;
 
     let is_right_child = u32::from(is_right_child);
 
-    if (minor_level + 1) == LOG2_ITEMS_PER_CACHE_LINE {
-        // next level is in new cacheline / minor triangle
-        let min_row_idx = min_idx - minor_level - 1;
-        ((maj_idx << LOG2_ITEMS_PER_CACHE_LINE) + (min_row_idx << 1) + 1 + is_right_child)
-            << LOG2_ITEMS_PER_CACHE_LINE
+    if (minor_level + 1) == log2_items_per_cache_line {
+        // next level is in new cacheline / minor triangle.
+        // The bottom row of the minor triangle starts at (2^minor_level - 1)
+        let min_row_idx = min_idx + 1 - (1 << minor_level);
+        ((maj_idx << log2_items_per_cache_line) + (min_row_idx << 1) + 1 + is_right_child)
+            << log2_items_per_cache_line
     } else {
         // next level is in same cacheline / minor triangle
-        (maj_idx << LOG2_ITEMS_PER_CACHE_LINE) + (min_idx << 1) + 1 + is_right_child
+        (maj_idx << log2_items_per_cache_line) + (min_idx << 1) + 1 + is_right_child
     }
 }
 
 #[allow(dead_code)]
 #[inline]
-pub(crate) fn modified_van_emde_boas_get_child_idx_v2_branchless(
+pub(crate) fn modified_van_emde_boas_get_child_idx_v2_branchless<A>(
     curr_idx: u32,
     is_right_child: bool,
     minor_level: u32,
 ) -> u32 {
-    let min_idx = curr_idx & ITEMS_PER_CACHE_LINE_MASK;
+    let log2_items_per_cache_line = log2_items_per_cache_line::<A>();
+    let items_per_cache_line_mask = items_per_cache_line::<A>() - 1;
+
+    let min_idx = curr_idx & items_per_cache_line_mask;
 
-    let min_row_idx = min_idx.overflowing_sub(minor_level).0.overflowing_sub(1).0;
+    // only meaningful when on the bottom row of the minor triangle,
+    // which starts at (2^minor_level - 1)
+    let min_row_idx = min_idx
+        .overflowing_add(1)
+        .0
+        .overflowing_sub(1u32.overflowing_shl(minor_level).0)
+        .0;
 
     let is_right_child = u32::from(is_right_child);
-    let inc_major_level = u8::from((minor_level.overflowing_add(1).0) == LOG2_ITEMS_PER_CACHE_LINE);
+    let inc_major_level = u8::from((minor_level.overflowing_add(1).0) == log2_items_per_cache_line);
 
-    let mut result: u32 = ((curr_idx & ITEMS_PER_CACHE_LINE_MASK_INV)
-        .overflowing_add(1)
-        .0)
+    let mut result: u32 = ((curr_idx & !items_per_cache_line_mask).overflowing_add(1).0)
         .overflowing_add(is_right_child)
         .0;
     result.cmovnz(
         &result
             .overflowing_add(min_row_idx.overflowing_shl(1).0)
             .0
-            .overflowing_shl(LOG2_ITEMS_PER_CACHE_LINE)
+            .overflowing_shl(log2_items_per_cache_line)
             .0,
         inc_major_level,
     );
@@ -66,6 +87,22 @@ pub(crate) fn modified_van_emde_boas_get_child_idx_v2_branchless(
     result
 }
 
+/// Number of stems required to hold `stem_levels` fully-populated levels of stems
+/// in the modified van Emde Boas layout. The right-most stem on the deepest level
+/// has the highest index of any stem, so this is one more than its index.
+#[allow(dead_code)]
+pub(crate) fn modified_van_emde_boas_stem_count<A>(stem_levels: u32) -> usize {
+    if stem_levels == 0 {
+        return 0;
+    }
+
+    let max_stem_idx = (0..stem_levels - 1).fold(0, |stem_idx, level| {
+        modified_van_emde_boas_get_child_idx_v2::<A>(stem_idx, true, level)
+    });
+
+    max_stem_idx as usize + 1
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,7 +157,8 @@ mod tests {
     ) {
         let (curr_idx, level, is_right_child) = input;
 
-        let next_idx = modified_van_emde_boas_get_child_idx_v2(curr_idx, is_right_child, level);
+        let next_idx =
+            modified_van_emde_boas_get_child_idx_v2::<f64>(curr_idx, is_right_child, level);
 
         assert_eq!(next_idx, expected);
     }
@@ -174,7 +212,7 @@ mod tests {
     ) {
         let (curr_idx, minor_level, is_right_child) = input;
 
-        let next_idx = modified_van_emde_boas_get_child_idx_v2_branchless(
+        let next_idx = modified_van_emde_boas_get_child_idx_v2_branchless::<f64>(
             curr_idx,
             is_right_child,
             minor_level,
@@ -182,4 +220,138 @@ mod tests {
 
         assert_eq!(next_idx, expected);
     }
+
+    #[rstest]
+    #[case((0, 0, false), 1)]
+    #[case((0, 0, true), 2)]
+    #[case((6, 2, true), 14)]
+    #[case((7, 3, false), 16)]
+    #[case((7, 3, true), 32)]
+    #[case((14, 3, false), 240)]
+    #[case((14, 3, true), 256)]
+    #[case((16, 4, false), 17)]
+    #[case((16, 4, true), 18)]
+    #[case((23, 7, false), 272)]
+    fn mod_v_e_b_get_child_idx_produces_correct_values_f32(
+        #[case] input: (u32, u32, bool),
+        #[case] expected: u32,
+    ) {
+        let (curr_idx, level, is_right_child) = input;
+
+        let next_idx =
+            modified_van_emde_boas_get_child_idx_v2::<f32>(curr_idx, is_right_child, level);
+
+        assert_eq!(next_idx, expected);
+    }
+
+    #[test]
+    fn minor_triangles_fill_cache_line_f64() {
+        assert_eq!(log2_items_per_cache_line::<f64>(), 3);
+        assert_minor_triangles_fill_cache_lines::<f64>();
+    }
+
+    #[test]
+    fn minor_triangles_fill_cache_line_f32() {
+        assert_eq!(log2_items_per_cache_line::<f32>(), 4);
+        assert_minor_triangles_fill_cache_lines::<f32>();
+    }
+
+    #[cfg(feature = "f16")]
+    #[test]
+    fn minor_triangles_fill_cache_line_f16() {
+        assert_eq!(log2_items_per_cache_line::<half::f16>(), 5);
+        assert_minor_triangles_fill_cache_lines::<half::f16>();
+    }
+
+    /// Lays out two full cache-line levels of stems and checks that every
+    /// minor triangle occupies all but the last slot of exactly one cache line,
+    /// and that the branchless child index agrees with the branching one.
+    fn assert_minor_triangles_fill_cache_lines<A>() {
+        let items_per_cache_line = items_per_cache_line::<A>();
+        let log2_items_per_cache_line = log2_items_per_cache_line::<A>();
+        let max_level = log2_items_per_cache_line * 2;
+
+        let mut nodes_by_cache_line: std::collections::BTreeMap<u32, Vec<u32>> = Default::default();
+        let mut frontier = vec![(0u32, 0u32)];
+        while let Some((idx, level)) = frontier.pop() {
+            nodes_by_cache_line
+                .entry(idx >> log2_items_per_cache_line)
+                .or_default()
+                .push(idx & (items_per_cache_line - 1));
+
+            if level + 1 == max_level {
+                continue;
+            }
+
+            for is_right_child in [false, true] {
+                let child_idx =
+                    modified_van_emde_boas_get_child_idx_v2::<A>(idx, is_right_child, level);
+                let child_idx_branchless = modified_van_emde_boas_get_child_idx_v2_branchless::<A>(
+                    idx,
+                    is_right_child,
+                    level % log2_items_per_cache_line,
+                );
+                assert_eq!(child_idx, child_idx_branchless);
+                frontier.push((child_idx, level + 1));
+            }
+        }
+
+        assert_eq!(nodes_by_cache_line.len() as u32, 1 + items_per_cache_line);
+        for (_, mut slots) in nodes_by_cache_line {
+            slots.sort_unstable();
+            assert_eq!(slots, (0..items_per_cache_line - 1).collect::<Vec<_>>());
+        }
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(1, 1)]
+    #[case(2, 3)]
+    #[case(3, 7)]
+    #[case(4, 65)]
+    #[case(6, 71)]
+    #[case(7, 577)]
+    fn mod_v_e_b_stem_count_produces_correct_values(
+        #[case] stem_levels: u32,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(
+            modified_van_emde_boas_stem_count::<f64>(stem_levels),
+            expected
+        );
+    }
+
+    #[test]
+    fn stem_count_covers_every_stem_index() {
+        assert_stem_count_covers_every_stem_index::<f64>();
+        assert_stem_count_covers_every_stem_index::<f32>();
+        #[cfg(feature = "f16")]
+        assert_stem_count_covers_every_stem_index::<half::f16>();
+    }
+
+    fn assert_stem_count_covers_every_stem_index<A>() {
+        let mut level_indices = vec![0u32];
+        let mut max_stem_idx = 0;
+
+        for stem_levels in 1..=14 {
+            max_stem_idx = max_stem_idx.max(*level_indices.iter().max().unwrap());
+            assert_eq!(
+                modified_van_emde_boas_stem_count::<A>(stem_levels),
+                max_stem_idx as usize + 1
+            );
+
+            level_indices = level_indices
+                .iter()
+                .flat_map(|&idx| {
+                    [false, true].map(|is_right_child| {
+                        modified_van_emde_boas_get_child_idx_v2::<A>(
+                            idx,
+                            is_right_child,
+                            stem_levels - 1,
+                        )
+                    })
+                })
+                .collect();
+        }
+    }
 }
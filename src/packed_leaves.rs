@@ -0,0 +1,255 @@
+//! Selection and partitioning of the contents of a mutable tree whilst they are packed
+//! `B` to a leaf into the tree's own leaf storage, so that balanced trees can be
+//! rebalanced without copying their contents out into separate buffers.
+
+use crate::mirror_select_nth_unstable_by::mirror_select_nth_unstable_by;
+use std::cmp::Ordering;
+use std::ops::Range;
+
+/// Storage that holds the points and items of a tree at positions `0..len`, whose points
+/// can be read, and whose points and items can be swapped, by position.
+pub(crate) trait PackedContent<A, const K: usize> {
+    /// Returns the point at position `idx`
+    fn point(&self, idx: usize) -> &[A; K];
+
+    /// Swaps the points, and the items, at positions `a` and `b`
+    fn swap_content(&mut self, a: usize, b: usize);
+
+    /// Reorders the contents at the positions in `range` so that the point at `nth` is the
+    /// one that would be there if they were sorted by `compare`, with no greater point
+    /// before it and no lesser point after it.
+    fn select_nth_by<F>(&mut self, range: Range<usize>, nth: usize, compare: F)
+    where
+        F: FnMut(&[A; K], &[A; K]) -> Ordering;
+
+    /// Records that the `leaf_idx`th leaf of the balanced tree holds `size` items
+    fn set_leaf_size(&mut self, leaf_idx: usize, size: usize);
+}
+
+/// The points and items that a balanced tree is being built from, held in separate slices
+/// that get reordered together, along with the size of each leaf that they end up in.
+pub(crate) struct MirroredContent<'a, A, T, const K: usize> {
+    pub(crate) points: &'a mut [[A; K]],
+    pub(crate) items: &'a mut [T],
+    pub(crate) leaf_sizes: Vec<usize>,
+}
+
+impl<A, T, const K: usize> PackedContent<A, K> for MirroredContent<'_, A, T, K> {
+    fn point(&self, idx: usize) -> &[A; K] {
+        &self.points[idx]
+    }
+
+    fn swap_content(&mut self, a: usize, b: usize) {
+        self.points.swap(a, b);
+        self.items.swap(a, b);
+    }
+
+    fn select_nth_by<F>(&mut self, range: Range<usize>, nth: usize, compare: F)
+    where
+        F: FnMut(&[A; K], &[A; K]) -> Ordering,
+    {
+        mirror_select_nth_unstable_by(
+            &mut self.points[range.clone()],
+            &mut self.items[range.clone()],
+            nth - range.start,
+            compare,
+        );
+    }
+
+    fn set_leaf_size(&mut self, leaf_idx: usize, size: usize) {
+        debug_assert_eq!(leaf_idx, self.leaf_sizes.len());
+        self.leaf_sizes.push(size);
+    }
+}
+
+/// Reorders the contents of `storage` at the positions in `range` so that the point at `nth`
+/// is the one that would be there if they were sorted by `compare`, with no greater point
+/// before it and no lesser point after it.
+pub(crate) fn select_nth_by<A: Copy, const K: usize, S, F>(
+    storage: &mut S,
+    range: Range<usize>,
+    nth: usize,
+    mut compare: F,
+) where
+    S: PackedContent<A, K> + ?Sized,
+    F: FnMut(&[A; K], &[A; K]) -> Ordering,
+{
+    let Range {
+        start: mut lo,
+        end: mut hi,
+    } = range;
+
+    while hi - lo > 1 {
+        let pivot = *storage.point(median_of_three(storage, lo, hi, &mut compare));
+
+        // three-way partition around the pivot, so that ranges holding many
+        // equal points still shrink on every pass
+        let (mut lt, mut idx, mut gt) = (lo, lo, hi);
+        while idx < gt {
+            match compare(storage.point(idx), &pivot) {
+                Ordering::Less => {
+                    storage.swap_content(lt, idx);
+                    lt += 1;
+                    idx += 1;
+                }
+                Ordering::Greater => {
+                    gt -= 1;
+                    storage.swap_content(idx, gt);
+                }
+                Ordering::Equal => idx += 1,
+            }
+        }
+
+        if nth < lt {
+            hi = lt;
+        } else if nth >= gt {
+            lo = gt;
+        } else {
+            return;
+        }
+    }
+}
+
+/// Moves the contents of `storage` at the positions in `range` whose point satisfies `pred`
+/// to the front of `range`, returning the position of the first one that doesn't.
+pub(crate) fn partition_by<A, const K: usize, S, F>(
+    storage: &mut S,
+    range: Range<usize>,
+    mut pred: F,
+) -> usize
+where
+    S: PackedContent<A, K> + ?Sized,
+    F: FnMut(&[A; K]) -> bool,
+{
+    let mut split = range.start;
+    for idx in range {
+        if pred(storage.point(idx)) {
+            storage.swap_content(split, idx);
+            split += 1;
+        }
+    }
+
+    split
+}
+
+/// Returns whichever of the first, middle and last positions of `lo..hi` holds the
+/// median of the points at those positions
+fn median_of_three<A, const K: usize, S, F>(
+    storage: &S,
+    lo: usize,
+    hi: usize,
+    compare: &mut F,
+) -> usize
+where
+    S: PackedContent<A, K> + ?Sized,
+    F: FnMut(&[A; K], &[A; K]) -> Ordering,
+{
+    let (a, b, c) = (lo, lo + (hi - lo) / 2, hi - 1);
+    let a_lt_b = compare(storage.point(a), storage.point(b)) == Ordering::Less;
+    let b_lt_c = compare(storage.point(b), storage.point(c)) == Ordering::Less;
+    let a_lt_c = compare(storage.point(a), storage.point(c)) == Ordering::Less;
+
+    if a_lt_b == b_lt_c {
+        b
+    } else if a_lt_b == a_lt_c {
+        c
+    } else {
+        a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    impl PackedContent<u32, 1> for Vec<([u32; 1], usize)> {
+        fn point(&self, idx: usize) -> &[u32; 1] {
+            &self[idx].0
+        }
+
+        fn swap_content(&mut self, a: usize, b: usize) {
+            <[_]>::swap(self, a, b);
+        }
+
+        fn select_nth_by<F>(&mut self, range: Range<usize>, nth: usize, compare: F)
+        where
+            F: FnMut(&[u32; 1], &[u32; 1]) -> Ordering,
+        {
+            super::select_nth_by(self, range, nth, compare);
+        }
+
+        fn set_leaf_size(&mut self, _leaf_idx: usize, _size: usize) {}
+    }
+
+    #[test]
+    fn select_nth_by_places_the_nth_point_and_keeps_items_alongside() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+
+        for len in [1, 2, 3, 10, 257] {
+            for max_val in [1, 4, 1_000] {
+                let content: Vec<([u32; 1], usize)> = (0..len)
+                    .map(|idx| ([rng.gen_range(0..max_val)], idx))
+                    .collect();
+                let mut sorted: Vec<u32> = content.iter().map(|(point, _)| point[0]).collect();
+                sorted.sort_unstable();
+
+                for nth in [0, len / 3, len - 1] {
+                    let mut storage = content.clone();
+                    select_nth_by(&mut storage, 0..len, nth, |a, b| a.cmp(b));
+
+                    assert_eq!(storage[nth].0[0], sorted[nth]);
+                    assert!(storage[..nth]
+                        .iter()
+                        .all(|(point, _)| point[0] <= sorted[nth]));
+                    assert!(storage[nth..]
+                        .iter()
+                        .all(|(point, _)| point[0] >= sorted[nth]));
+                    assert!(storage
+                        .iter()
+                        .all(|&(point, item)| content[item].0 == point));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn partition_by_only_moves_positions_within_the_range() {
+        let mut storage: Vec<([u32; 1], usize)> = [5, 1, 4, 2, 3, 0]
+            .iter()
+            .map(|&val| ([val], val as usize))
+            .collect();
+
+        let split = partition_by(&mut storage, 1..5, |point| point[0] < 3);
+
+        assert_eq!(split, 3);
+        assert_eq!(storage[0].0, [5]);
+        assert_eq!(storage[5].0, [0]);
+        assert!(storage[1..3].iter().all(|(point, _)| point[0] < 3));
+        assert!(storage[3..5].iter().all(|(point, _)| point[0] >= 3));
+    }
+
+    #[test]
+    fn mirrored_content_selects_within_the_range_and_keeps_items_alongside() {
+        let mut points: Vec<[u32; 1]> = [9, 5, 1, 4, 2, 3, 0].iter().map(|&val| [val]).collect();
+        let mut items: Vec<u32> = points.iter().map(|point| point[0] * 10).collect();
+        let mut content = MirroredContent {
+            points: &mut points,
+            items: &mut items,
+            leaf_sizes: Vec::new(),
+        };
+
+        content.select_nth_by(1..6, 3, |a, b| a.cmp(b));
+
+        assert_eq!(content.points[3], [3]);
+        assert_eq!(content.points[0], [9]);
+        assert_eq!(content.points[6], [0]);
+        assert!(content.points[1..3].iter().all(|point| point[0] < 3));
+        assert!(content.points[4..6].iter().all(|point| point[0] > 3));
+        assert!(content
+            .points
+            .iter()
+            .zip(content.items.iter())
+            .all(|(point, &item)| item == point[0] * 10));
+    }
+}
@@ -0,0 +1,25 @@
+//! The choice of axis that each stem of a tree splits its points along, shared by the
+//! mutable trees and the [`ImmutableKdTreeBuilder`](crate::immutable::float::builder::ImmutableKdTreeBuilder).
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How the axis that each stem splits its points along is chosen.
+///
+/// Accepted by the mutable trees, e.g. [`KdTree::with_capacity_and_split_axis`](crate::float::kdtree::KdTree::with_capacity_and_split_axis),
+/// and by [`ImmutableKdTreeBuilder::split_axis`](crate::immutable::float::builder::ImmutableKdTreeBuilder::split_axis).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SplitAxis {
+    /// Cycle through the axes in order, one level of the tree at a time
+    #[default]
+    RoundRobin,
+
+    /// Split along whichever axis the points being split are most spread out on.
+    /// The axis chosen for each stem is stored in the tree, using an extra byte per stem.
+    WidestSpread,
+}
@@ -0,0 +1,153 @@
+//! Statistics describing the shape of a tree, returned by the `stats()` method
+//! of each tree type.
+//!
+//! These are intended to help with choosing the bucket size (`B`) and index type (`IDX`)
+//! generic parameters based on real data: a leaf occupancy histogram that is bunched up
+//! at `B`, for example, or many leaves that contain points at the exact same position,
+//! suggests that a larger bucket size would be a better fit.
+use std::cmp::Ordering;
+
+/// Describes the shape, fill level and memory usage of a tree
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreeStats {
+    /// number of items stored in the tree
+    pub size: usize,
+    /// number of leaf nodes, including any that are empty
+    pub leaf_count: usize,
+    /// number of stem nodes
+    pub stem_count: usize,
+    /// number of stems between the root and the shallowest leaf
+    pub min_depth: usize,
+    /// number of stems between the root and the deepest leaf
+    pub max_depth: usize,
+    /// mean number of stems between the root and each leaf
+    pub mean_depth: f64,
+    /// `leaf_occupancy[n]` is the number of leaves that contain exactly `n` items
+    pub leaf_occupancy: Vec<usize>,
+    /// number of leaves that contain two or more items at exactly the same position
+    pub duplicate_coordinate_leaves: usize,
+    /// bytes used by each component of the tree
+    pub memory: MemoryUsage,
+}
+
+/// Bytes used by each component of a tree. Storage that has been allocated
+/// to a node but not yet filled, such as the unused slots in a leaf of one
+/// of the mutable trees, is included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// bytes used by stem nodes, including their split values and child indices
+    pub stems: usize,
+    /// bytes used by the co-ordinates of the points stored in leaves
+    pub leaf_points: usize,
+    /// bytes used by the items stored in leaves
+    pub leaf_items: usize,
+    /// bytes used by anything else, such as leaf sizes or extents, split dimensions,
+    /// leaf bounding boxes and padding
+    pub other: usize,
+}
+
+impl MemoryUsage {
+    /// Returns the total number of bytes used by the tree
+    pub fn total(&self) -> usize {
+        self.stems + self.leaf_points + self.leaf_items + self.other
+    }
+}
+
+/// Accumulates [`TreeStats`] one leaf at a time
+pub(crate) struct TreeStatsBuilder<A, const K: usize> {
+    stats: TreeStats,
+    depth_sum: usize,
+    points: Vec<[A; K]>,
+}
+
+impl<A: Copy + PartialOrd, const K: usize> TreeStatsBuilder<A, K> {
+    pub(crate) fn new() -> Self {
+        Self {
+            stats: TreeStats {
+                min_depth: usize::MAX,
+                ..Default::default()
+            },
+            depth_sum: 0,
+            points: Vec::new(),
+        }
+    }
+
+    /// Records a leaf at `depth` that contains `points`
+    pub(crate) fn add_leaf<I: IntoIterator<Item = [A; K]>>(&mut self, depth: usize, points: I) {
+        self.points.clear();
+        self.points.extend(points);
+        let size = self.points.len();
+
+        self.stats.size += size;
+        self.stats.leaf_count += 1;
+        self.stats.min_depth = self.stats.min_depth.min(depth);
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        self.depth_sum += depth;
+
+        if self.stats.leaf_occupancy.len() <= size {
+            self.stats.leaf_occupancy.resize(size + 1, 0);
+        }
+        self.stats.leaf_occupancy[size] += 1;
+
+        if self.has_duplicate_points() {
+            self.stats.duplicate_coordinate_leaves += 1;
+        }
+    }
+
+    fn has_duplicate_points(&mut self) -> bool {
+        self.points.sort_unstable_by(|a, b| {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .find(|&ord| ord != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        self.points.windows(2).any(|pair| pair[0] == pair[1])
+    }
+
+    pub(crate) fn finish(mut self, stem_count: usize, memory: MemoryUsage) -> TreeStats {
+        if self.stats.leaf_count == 0 {
+            self.stats.min_depth = 0;
+        } else {
+            self.stats.mean_depth = self.depth_sum as f64 / self.stats.leaf_count as f64;
+        }
+        self.stats.stem_count = stem_count;
+        self.stats.memory = memory;
+
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::{MemoryUsage, TreeStatsBuilder};
+
+    #[test]
+    fn builder_summarises_leaves() {
+        let mut builder = TreeStatsBuilder::<f32, 2>::new();
+        builder.add_leaf(1, [[1.0, 2.0], [3.0, 4.0], [1.0, 2.0]]);
+        builder.add_leaf(2, [[1.0, 2.0], [2.0, 1.0]]);
+        builder.add_leaf(3, []);
+
+        let stats = builder.finish(2, MemoryUsage::default());
+
+        assert_eq!(stats.size, 5);
+        assert_eq!(stats.leaf_count, 3);
+        assert_eq!(stats.stem_count, 2);
+        assert_eq!(stats.min_depth, 1);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(stats.mean_depth, 2.0);
+        assert_eq!(stats.leaf_occupancy, vec![1, 0, 1, 1]);
+        assert_eq!(stats.duplicate_coordinate_leaves, 1);
+    }
+
+    #[test]
+    fn builder_handles_no_leaves() {
+        let stats = TreeStatsBuilder::<f32, 2>::new().finish(0, MemoryUsage::default());
+
+        assert_eq!(stats.leaf_count, 0);
+        assert_eq!(stats.min_depth, 0);
+        assert_eq!(stats.mean_depth, 0.0);
+    }
+}
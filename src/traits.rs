@@ -123,6 +123,64 @@ pub trait DistanceMetric<A, const K: usize> {
     fn dist1(a: A, b: A) -> A;
 }
 
+/// Implemented by the types that can be used as the co-ordinates of points in a tree,
+/// to give the distance used by queries that place no limit on how far away the items
+/// that they return can be.
+///
+/// This is infinity for floats, and the largest representable value for fixed point types.
+pub trait Unbounded {
+    /// returns a distance that no other distance is greater than
+    fn unbounded() -> Self;
+}
+
+impl Unbounded for f32 {
+    #[inline]
+    fn unbounded() -> f32 {
+        f32::INFINITY
+    }
+}
+
+impl Unbounded for f64 {
+    #[inline]
+    fn unbounded() -> f64 {
+        f64::INFINITY
+    }
+}
+
+#[cfg(feature = "f16")]
+impl Unbounded for half::f16 {
+    #[inline]
+    fn unbounded() -> half::f16 {
+        half::f16::INFINITY
+    }
+}
+
+macro_rules! impl_unbounded_for_fixed {
+    ($($fixed:ident: $frac_bound:ident),*) => {
+        $(
+            impl<Frac: fixed::types::extra::$frac_bound> Unbounded for fixed::$fixed<Frac> {
+                #[inline]
+                fn unbounded() -> Self {
+                    Self::MAX
+                }
+            }
+        )*
+    };
+}
+
+impl_unbounded_for_fixed!(
+    FixedI8: LeEqU8,
+    FixedI16: LeEqU16,
+    FixedI32: LeEqU32,
+    FixedI64: LeEqU64,
+    FixedI128: LeEqU128,
+    FixedU8: LeEqU8,
+    FixedU16: LeEqU16,
+    FixedU32: LeEqU32,
+    FixedU64: LeEqU64,
+    FixedU128: LeEqU128
+);
+
 #[cfg(test)]
 mod tests {
 
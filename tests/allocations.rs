@@ -0,0 +1,208 @@
+//! Checks how much the trees allocate during construction, rebuilds and updates.
+//!
+//! These live in their own test binary, as they replace the global allocator with one
+//! that counts allocations, which would otherwise apply to every one of the crate's tests.
+
+use kiddo::immutable::float::kdtree::ImmutableKdTree;
+use kiddo::KdTree;
+use rand::{Rng, SeedableRng};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::mem::size_of;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+
+    // signed, as this thread may free memory that another thread allocated
+    static ALLOCATED_BYTES: Cell<isize> = const { Cell::new(0) };
+    static PEAK_ALLOCATED_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+/// Counts the allocations made by the current thread, and the bytes that it has allocated
+/// and not yet freed, so that tests running concurrently on other threads don't interfere.
+struct CountingAllocator;
+
+fn record_alloc(size: usize) {
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+    let _ = ALLOCATED_BYTES.try_with(|bytes| {
+        bytes.set(bytes.get() + size as isize);
+        let _ = PEAK_ALLOCATED_BYTES.try_with(|peak| peak.set(peak.get().max(bytes.get())));
+    });
+}
+
+fn record_dealloc(size: usize) {
+    let _ = ALLOCATED_BYTES.try_with(|bytes| bytes.set(bytes.get() - size as isize));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_alloc(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_alloc(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_alloc(new_size);
+        record_dealloc(layout.size());
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record_dealloc(layout.size());
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations_during<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+/// Returns the value returned by `f`, along with the most bytes that were allocated
+/// at any one time while it ran and the bytes that were still allocated when it returned
+fn peak_and_retained_bytes_during<R, F: FnOnce() -> R>(f: F) -> (R, usize, usize) {
+    let before = ALLOCATED_BYTES.with(Cell::get);
+    PEAK_ALLOCATED_BYTES.with(|peak| peak.set(before));
+
+    let result = f();
+
+    let retained = ALLOCATED_BYTES.with(Cell::get) - before;
+    let peak = PEAK_ALLOCATED_BYTES.with(Cell::get) - before;
+    (result, peak as usize, retained as usize)
+}
+
+#[test]
+fn rebuild_from_slice_does_not_allocate_once_capacity_has_stabilised() {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+
+    for tree_size in [0, 20, 1_000, 10_000] {
+        let sources: Vec<Vec<[f32; 3]>> = (0..5)
+            .map(|_| (0..tree_size).map(|_| rng.gen::<[f32; 3]>()).collect())
+            .collect();
+
+        let mut tree: ImmutableKdTree<f32, u32, 3, 32> =
+            ImmutableKdTree::new_from_slice(&sources[0]);
+
+        for source in &sources[1..] {
+            let allocations = allocations_during(|| tree.rebuild_from_slice(source));
+            assert_eq!(allocations, 0, "rebuild of {tree_size} items allocated");
+            assert_eq!(tree, ImmutableKdTree::new_from_slice(source));
+        }
+    }
+}
+
+#[test]
+fn rebuild_from_slice_does_not_allocate_when_shrinking() {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+    let source: Vec<[f64; 2]> = (0..5_000).map(|_| rng.gen::<[f64; 2]>()).collect();
+
+    let mut tree: ImmutableKdTree<f64, u32, 2, 32> = ImmutableKdTree::new_from_slice(&source);
+
+    for len in [4_000, 2_500, 100, 5_000] {
+        let allocations = allocations_during(|| tree.rebuild_from_slice(&source[..len]));
+        assert_eq!(allocations, 0, "rebuild of {len} items allocated");
+        assert_eq!(tree.size(), len);
+    }
+}
+
+#[test]
+fn construction_allocates_exactly_the_stems_needed() {
+    // allows for small fixed-size allocations that the tree's stats do not count
+    const SLACK: usize = 64;
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(17);
+
+    for tree_size in [0usize, 3, 33, 1_000, 4_096, 20_000, 65_536] {
+        let content_to_add: Vec<[f32; 3]> = (0..tree_size).map(|_| rng.gen::<[f32; 3]>()).collect();
+
+        let (tree, peak, retained) = peak_and_retained_bytes_during(|| {
+            ImmutableKdTree::<f32, u32, 3, 4>::new_from_slice(&content_to_add)
+        });
+
+        // the tree keeps the sort index used during construction, for re-use by rebuilds
+        let expected = tree.stats().memory.total() + tree_size * size_of::<usize>();
+
+        assert_eq!(
+            peak, retained,
+            "{tree_size} items: construction allocated more than the tree kept"
+        );
+        assert!(
+            peak <= expected + SLACK,
+            "{tree_size} items: peak allocation of {peak} bytes exceeds {expected}"
+        );
+    }
+}
+
+#[test]
+fn finding_items_to_remove_or_update_does_not_allocate() {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(19);
+    let points: Vec<[f64; 3]> = (0..10_000).map(|_| rng.gen::<[f64; 3]>()).collect();
+
+    let mut tree: KdTree<f64, 3> = KdTree::with_capacity(points.len());
+    for (idx, point) in points.iter().enumerate() {
+        tree.add(point, idx as u64);
+    }
+
+    for (idx, point) in points.iter().enumerate().step_by(100) {
+        let allocations = allocations_during(|| {
+            assert_eq!(tree.remove(point, points.len() as u64), 0);
+            assert!(tree.update(point, point, idx as u64));
+            assert_eq!(tree.update_many([(*point, *point, idx as u64)]), 1);
+        });
+        assert_eq!(allocations, 0, "finding item {idx} allocated");
+    }
+
+    // swaps the positions of pairs of items, which will mostly be in different leaves.
+    // The first round of swaps may grow the free lists, after which nothing should allocate
+    let points = &points;
+    let swaps = |forward: bool| {
+        (0..50).flat_map(move |idx| {
+            let (a, b) = (points[idx], points[idx + 5_000]);
+            let (from, to) = if forward { (a, b) } else { (b, a) };
+            [(from, to, idx as u64), (to, from, (idx + 5_000) as u64)]
+        })
+    };
+    assert_eq!(tree.update_many(swaps(true)), 100);
+    assert_eq!(tree.update_many(swaps(false)), 100);
+    for round in 0..10 {
+        let allocations = allocations_during(|| {
+            assert_eq!(tree.update_many(swaps(round % 2 == 0)), 100);
+        });
+        assert_eq!(allocations, 0, "swap round {round} allocated");
+    }
+
+    assert_eq!(tree.size(), points.len() as u64);
+}
+
+#[test]
+fn rebalance_does_not_allocate_once_capacity_has_stabilised() {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(23);
+    let mut points: Vec<[f64; 3]> = (0..10_000).map(|_| rng.gen::<[f64; 3]>()).collect();
+
+    let mut tree: KdTree<f64, 3> = KdTree::with_capacity(points.len());
+    for (idx, point) in points.iter().enumerate() {
+        tree.add(point, idx as u64);
+    }
+    tree.rebalance();
+
+    for round in 0..5 {
+        for (idx, point) in points.iter_mut().enumerate().step_by(7) {
+            let moved = rng.gen::<[f64; 3]>();
+            assert!(tree.update(point, &moved, idx as u64));
+            *point = moved;
+        }
+
+        let allocations = allocations_during(|| tree.rebalance());
+        assert_eq!(allocations, 0, "rebalance {round} allocated");
+    }
+
+    assert_eq!(tree.size(), points.len() as u64);
+}